@@ -0,0 +1,14 @@
+-- Named settings presets (subset of gh_settings fields, same shape as PATCH /api/settings)
+CREATE TABLE IF NOT EXISTS gh_settings_profiles (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    settings JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO gh_settings_profiles (name, settings)
+VALUES
+    ('flash-fast', '{"default_model": "gemini-3.1-flash-preview", "response_style": "concise", "thinking_level": "minimal", "max_iterations": 10}'),
+    ('pro-deep', '{"default_model": "gemini-3.1-pro-preview", "response_style": "detailed", "thinking_level": "high", "max_iterations": 25}')
+ON CONFLICT (name) DO NOTHING;
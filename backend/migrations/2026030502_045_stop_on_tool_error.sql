@@ -0,0 +1,2 @@
+-- Abort the tool-call loop on the first TOOL_ERROR (off by default)
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS stop_on_tool_error BOOLEAN NOT NULL DEFAULT FALSE;
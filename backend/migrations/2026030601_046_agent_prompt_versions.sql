@@ -0,0 +1,13 @@
+-- Per-agent system prompt history: each update_agent that changes the prompt
+-- records the previous value so it can be restored later
+CREATE TABLE IF NOT EXISTS gh_agent_prompt_versions (
+    id BIGSERIAL PRIMARY KEY,
+    agent_id TEXT NOT NULL REFERENCES gh_agents(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    system_prompt TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (agent_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_gh_agent_prompt_versions_agent
+    ON gh_agent_prompt_versions (agent_id, version DESC);
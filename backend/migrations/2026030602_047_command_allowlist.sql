@@ -0,0 +1,2 @@
+-- execute_command allowlist: non-empty array = only these executables may run
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS command_allowlist TEXT[] NOT NULL DEFAULT '{}';
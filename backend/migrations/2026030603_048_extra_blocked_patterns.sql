@@ -0,0 +1,2 @@
+-- Site-specific execute_command bans, checked alongside the built-in BLOCKED_PATTERNS
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS extra_blocked_patterns TEXT[] NOT NULL DEFAULT '{}';
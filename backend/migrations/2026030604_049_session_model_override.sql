@@ -0,0 +1,2 @@
+-- Per-session model pin (NULL = follow global settings / pins)
+ALTER TABLE gh_sessions ADD COLUMN IF NOT EXISTS model_override TEXT;
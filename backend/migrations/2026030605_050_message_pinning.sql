@@ -0,0 +1,4 @@
+-- User-pinned messages are always replayed into the model context
+ALTER TABLE gh_chat_messages ADD COLUMN IF NOT EXISTS pinned BOOLEAN NOT NULL DEFAULT FALSE;
+CREATE INDEX IF NOT EXISTS idx_gh_chat_messages_pinned
+    ON gh_chat_messages (session_id, created_at) WHERE pinned;
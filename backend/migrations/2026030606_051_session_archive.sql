@@ -0,0 +1,4 @@
+-- Soft delete: DELETE /api/sessions/{id} sets archived_at; ?hard=true purges.
+ALTER TABLE gh_sessions ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS idx_gh_sessions_archived
+    ON gh_sessions (archived_at) WHERE archived_at IS NOT NULL;
@@ -0,0 +1,8 @@
+-- Free-form tags for organizing sessions (filter with GET /api/sessions?tag=)
+CREATE TABLE IF NOT EXISTS gh_session_tags (
+    session_id UUID NOT NULL REFERENCES gh_sessions(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (session_id, tag)
+);
+CREATE INDEX IF NOT EXISTS idx_gh_session_tags_tag ON gh_session_tags (tag);
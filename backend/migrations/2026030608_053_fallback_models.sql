@@ -0,0 +1,2 @@
+-- Ordered models tried when the primary model fails (empty = the flash tier model)
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS fallback_models TEXT[] NOT NULL DEFAULT '{}';
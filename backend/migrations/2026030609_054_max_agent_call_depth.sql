@@ -0,0 +1,2 @@
+-- How many levels deep `call_agent` delegation may nest (1-6)
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS max_agent_call_depth INTEGER NOT NULL DEFAULT 3;
@@ -0,0 +1,4 @@
+-- Session history replayed into each request: base window (scaled per model tier)
+-- and how many recent messages are kept untruncated
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS history_window INTEGER NOT NULL DEFAULT 20;
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS history_truncate_keep INTEGER NOT NULL DEFAULT 6;
@@ -0,0 +1,7 @@
+-- Roster order for GET /api/agents and the system-prompt agent roster,
+-- set via POST /api/agents/reorder. Existing agents keep their creation order.
+ALTER TABLE gh_agents ADD COLUMN IF NOT EXISTS display_order INTEGER NOT NULL DEFAULT 0;
+
+UPDATE gh_agents a SET display_order = o.pos
+FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY created_at, id) AS pos FROM gh_agents) o
+WHERE a.id = o.id AND NOT EXISTS (SELECT 1 FROM gh_agents WHERE display_order <> 0);
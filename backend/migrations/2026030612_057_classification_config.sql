@@ -0,0 +1,15 @@
+-- Keyword classification tuning (single row, id = 1). Defaults match the
+-- values previously hard-coded in classify.rs.
+CREATE TABLE IF NOT EXISTS gh_classification_config (
+    id INTEGER PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    base_confidence DOUBLE PRECISION NOT NULL DEFAULT 0.6,
+    fallback_threshold DOUBLE PRECISION NOT NULL DEFAULT 0.65,
+    long_keyword_len INTEGER NOT NULL DEFAULT 8,
+    long_keyword_weight DOUBLE PRECISION NOT NULL DEFAULT 2.0,
+    medium_keyword_len INTEGER NOT NULL DEFAULT 5,
+    medium_keyword_weight DOUBLE PRECISION NOT NULL DEFAULT 1.5,
+    short_keyword_weight DOUBLE PRECISION NOT NULL DEFAULT 1.0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO gh_classification_config (id) VALUES (1) ON CONFLICT (id) DO NOTHING;
@@ -0,0 +1,8 @@
+-- AI-generated one-paragraph abstract per session (POST /api/sessions/{id}/summarize).
+CREATE TABLE IF NOT EXISTS gh_session_summaries (
+    session_id UUID PRIMARY KEY REFERENCES gh_sessions(id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    model TEXT NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
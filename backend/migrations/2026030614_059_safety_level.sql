@@ -0,0 +1,2 @@
+-- Gemini safetySettings preset: 'default' (omit), 'relaxed' or 'strict'.
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS safety_level TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,4 @@
+-- #50 quality alert: fires when the average of an agent's recent ratings drops
+-- below the threshold, once at least min_count ratings exist.
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS rating_alert_threshold DOUBLE PRECISION NOT NULL DEFAULT 3.0;
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS rating_alert_min_count INTEGER NOT NULL DEFAULT 5;
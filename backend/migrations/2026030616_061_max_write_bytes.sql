@@ -0,0 +1,2 @@
+-- Cap on content written by the write_file / edit_file tools (default 5 MB).
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS max_write_bytes INTEGER NOT NULL DEFAULT 5242880;
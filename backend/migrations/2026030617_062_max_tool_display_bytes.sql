@@ -0,0 +1,3 @@
+-- Cap on tool output streamed into the chat (default 16 KB). The model's copy
+-- and the saved message keep their own limits.
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS max_tool_display_bytes INTEGER NOT NULL DEFAULT 16384;
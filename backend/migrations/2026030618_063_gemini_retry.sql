@@ -0,0 +1,5 @@
+-- Retry schedule for transient Gemini errors (429, 503, timeouts) on the
+-- streaming path: 3 retries, 1s base doubling per attempt, up to 500ms jitter.
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS gemini_max_retries INTEGER NOT NULL DEFAULT 3;
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS gemini_backoff_base_ms INTEGER NOT NULL DEFAULT 1000;
+ALTER TABLE gh_settings ADD COLUMN IF NOT EXISTS gemini_backoff_jitter_ms INTEGER NOT NULL DEFAULT 500;
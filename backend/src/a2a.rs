@@ -14,6 +14,7 @@ use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 use crate::state::AppState;
+use crate::tools::ToolOutput;
 
 // ---------------------------------------------------------------------------
 // A2A v0.3 Types
@@ -165,7 +166,7 @@ pub async fn message_send(
     }
 
     // Execute
-    match execute_a2a_task(&state, &task_id, &prompt, agent_override, 0).await {
+    match execute_a2a_task(&state, &task_id, &prompt, agent_override, 0, Vec::new()).await {
         Ok((agent_id, result)) => {
             // Save messages
             save_message(&state, &task_id, "user", &prompt, None).await;
@@ -230,7 +231,16 @@ pub async fn message_stream(
             )
             .await;
 
-        match execute_a2a_task(&state, &task_id_clone, &prompt, agent_override, 0).await {
+        match execute_a2a_task(
+            &state,
+            &task_id_clone,
+            &prompt,
+            agent_override,
+            0,
+            Vec::new(),
+        )
+        .await
+        {
             Ok((agent_id, result)) => {
                 save_message(&state, &task_id_clone, "user", &prompt, None).await;
                 save_message(&state, &task_id_clone, "agent", &result, Some(&agent_id)).await;
@@ -330,6 +340,7 @@ async fn execute_a2a_task(
     prompt: &str,
     agent_override: Option<(String, f64, String)>,
     call_depth: u32,
+    call_chain: Vec<String>,
 ) -> Result<(String, String), String> {
     // Update status to working
     let _ =
@@ -338,8 +349,11 @@ async fn execute_a2a_task(
             .execute(&state.db)
             .await;
 
-    let mut ctx = crate::context::prepare_execution(state, prompt, None, agent_override, "").await;
+    let mut ctx =
+        crate::context::prepare_execution(state, prompt, None, None, agent_override, "", false)
+            .await;
     ctx.call_depth = call_depth;
+    ctx.call_chain = call_chain;
     let agent_id = ctx.agent_id.clone();
 
     // Update task with resolved agent
@@ -373,17 +387,15 @@ async fn execute_a2a_task(
     const MAX_TOOL_ERRORS: usize = 5;
 
     for _iter in 0..max_iter {
-        let body = json!({
+        let mut body = json!({
             "systemInstruction": { "parts": [{ "text": ctx.system_prompt }] },
             "contents": contents,
             "tools": tools,
             "generationConfig": gen_config
         });
+        crate::prompt::apply_safety_settings(&mut body, &ctx.safety_level);
 
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
-            ctx.model
-        );
+        let url = state.providers.gemini_model(&ctx.model, "generateContent");
 
         let resp =
             crate::oauth::apply_google_auth(state.client.post(&url), &ctx.api_key, ctx.is_oauth)
@@ -391,7 +403,12 @@ async fn execute_a2a_task(
                 .timeout(Duration::from_secs(120))
                 .send()
                 .await
-                .map_err(|e| format!("Gemini request failed: {}", e))?;
+                .map_err(|e| {
+                    format!(
+                        "Gemini request failed: {}",
+                        crate::redact::redact_secrets(&e.to_string())
+                    )
+                })?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -400,7 +417,7 @@ async fn execute_a2a_task(
             return Err(format!(
                 "Gemini API error {}: {}",
                 status,
-                &text[..text.len().min(500)]
+                crate::redact::redact_secrets(&text[..text.len().min(500)])
             ));
         }
 
@@ -449,48 +466,45 @@ async fn execute_a2a_task(
             let args = &fc["args"];
 
             let output = if name == "call_agent" {
-                match Box::pin(execute_agent_call(state, args, ctx.call_depth)).await {
-                    Ok(text) => text,
+                let mut chain = ctx.call_chain.clone();
+                chain.push(ctx.agent_id.clone());
+                match Box::pin(execute_agent_call(state, args, ctx.call_depth, chain)).await {
+                    Ok(text) => ToolOutput::text(text),
                     Err(e) => {
                         tracing::error!("A2A agent call '{}' failed: {}", name, e);
-                        format!("AGENT_CALL_ERROR: {}", e)
+                        ToolOutput {
+                            success: false,
+                            ..ToolOutput::text(format!("AGENT_CALL_ERROR: {}", e))
+                        }
                     }
                 }
             } else {
-                match tokio::time::timeout(
+                let result = tokio::time::timeout(
                     Duration::from_secs(60),
-                    crate::tools::execute_tool(name, args, state, &ctx.working_directory),
+                    crate::tools::execute_tool(
+                        name,
+                        args,
+                        state,
+                        &ctx.working_directory,
+                        &crate::tools::ToolCaller {
+                            agent_id: Some(ctx.agent_id.clone()),
+                            session_id: None,
+                        },
+                    ),
                 )
-                .await
-                {
-                    Ok(Ok(out)) => {
-                        // Truncate tool output for context window
-                        let text = out.text;
-                        if text.len() > 15000 {
-                            let truncated: String = text.chars().take(15000).collect();
-                            format!(
-                                "{}...\n[TRUNCATED: {} → 15000 chars]",
-                                truncated,
-                                text.len()
-                            )
-                        } else {
-                            text
-                        }
-                    }
-                    Ok(Err(e)) => format!("TOOL_ERROR: {}", e),
-                    Err(_) => format!("TOOL_ERROR: {} timed out after 60s", name),
-                }
+                .await;
+                tool_call_output(name, result.ok())
             };
 
             // Track tool errors and abort if too many consecutive failures
-            if output.starts_with("TOOL_ERROR:") || output.starts_with("AGENT_CALL_ERROR:") {
+            if !output.success {
                 tool_error_count += 1;
                 tracing::warn!(
                     "A2A tool error ({}/{}): {} — {}",
                     tool_error_count,
                     MAX_TOOL_ERRORS,
                     name,
-                    &output[..output.len().min(200)]
+                    output.text.chars().take(200).collect::<String>()
                 );
                 if tool_error_count >= MAX_TOOL_ERRORS {
                     tracing::error!(
@@ -500,7 +514,7 @@ async fn execute_a2a_task(
                     result_parts.push(json!({
                         "functionResponse": {
                             "name": name,
-                            "response": { "result": format!("FATAL: Too many tool errors ({}). Stopping execution. Last error: {}", tool_error_count, output) }
+                            "response": { "result": format!("FATAL: Too many tool errors ({}). Stopping execution. Last error: {}", tool_error_count, output.text) }
                         }
                     }));
                     break;
@@ -510,7 +524,7 @@ async fn execute_a2a_task(
             result_parts.push(json!({
                 "functionResponse": {
                     "name": name,
-                    "response": { "result": output }
+                    "response": { "result": output.text }
                 }
             }));
         }
@@ -547,22 +561,75 @@ async fn execute_a2a_task(
 // Inter-agent delegation (call_agent tool)
 // ---------------------------------------------------------------------------
 
-const MAX_CALL_DEPTH: u32 = 3;
+/// Default for the `max_agent_call_depth` setting.
+/// Turn a tool call result (`None` when it timed out) into the output sent
+/// back to the model, truncated for the context window. Failure is carried in
+/// `success`, never inferred from the text.
+fn tool_call_output(name: &str, result: Option<Result<ToolOutput, String>>) -> ToolOutput {
+    match result {
+        Some(Ok(mut out)) => {
+            if out.text.len() > 15000 {
+                let truncated: String = out.text.chars().take(15000).collect();
+                out.text = format!(
+                    "{}...\n[TRUNCATED: {} → 15000 chars]",
+                    truncated,
+                    out.text.len()
+                );
+            }
+            out
+        }
+        Some(Err(e)) => ToolOutput::error(e),
+        None => ToolOutput::error(format!("{} timed out after 60s", name)),
+    }
+}
+
+const DEFAULT_CALL_DEPTH: u32 = 3;
+/// Upper bound for the `max_agent_call_depth` setting.
+pub(crate) const MAX_AGENT_CALL_DEPTH_CAP: u32 = 6;
+
+/// Depth of a delegation made at `parent_depth`, or an error once it would
+/// go past `max_depth`.
+fn next_call_depth(parent_depth: u32, max_depth: u32) -> Result<u32, String> {
+    let depth = parent_depth + 1;
+    if depth > max_depth {
+        return Err(format!("max delegation depth reached ({})", max_depth));
+    }
+    Ok(depth)
+}
+
+/// Refuse to delegate to an agent already in `chain` (the callers, outermost
+/// first), naming the cycle — e.g. `eskel → lambert → eskel`.
+fn check_delegation_cycle(chain: &[String], target: &str) -> Result<(), String> {
+    match chain.iter().position(|a| a == target) {
+        Some(start) => {
+            let mut cycle: Vec<&str> = chain[start..].iter().map(String::as_str).collect();
+            cycle.push(target);
+            Err(format!("delegation cycle detected: {}", cycle.join(" → ")))
+        }
+        None => Ok(()),
+    }
+}
+
+/// The `max_agent_call_depth` setting, clamped to `1..=MAX_AGENT_CALL_DEPTH_CAP`.
+async fn max_call_depth(state: &AppState) -> u32 {
+    sqlx::query_scalar::<_, i32>("SELECT max_agent_call_depth FROM gh_settings WHERE id = 1")
+        .fetch_one(&state.db)
+        .await
+        .map(|d| (d.max(1) as u32).min(MAX_AGENT_CALL_DEPTH_CAP))
+        .unwrap_or(DEFAULT_CALL_DEPTH)
+}
 
 /// Execute an agent-to-agent call (used by the `call_agent` tool).
 /// Runs a full Gemini multi-turn execution with tools for the target agent.
+/// `call_chain` holds the calling agents, outermost first, ending with the
+/// direct caller.
 pub(crate) async fn execute_agent_call(
     state: &AppState,
     args: &Value,
     parent_depth: u32,
+    call_chain: Vec<String>,
 ) -> Result<String, String> {
-    let depth = parent_depth + 1;
-    if depth > MAX_CALL_DEPTH {
-        return Err(format!(
-            "Agent call depth limit ({}) reached — cannot delegate further",
-            MAX_CALL_DEPTH
-        ));
-    }
+    let depth = next_call_depth(parent_depth, max_call_depth(state).await)?;
 
     let agent_id = args["agent_id"]
         .as_str()
@@ -570,6 +637,7 @@ pub(crate) async fn execute_agent_call(
     let task_prompt = args["task"]
         .as_str()
         .ok_or("Missing required argument: task")?;
+    check_delegation_cycle(&call_chain, agent_id)?;
 
     // Validate agent exists
     {
@@ -593,7 +661,7 @@ pub(crate) async fn execute_agent_call(
     )
     .bind(&task_id)
     .bind(agent_id)
-    .bind("parent") // caller context
+    .bind(call_chain.last().map(String::as_str).unwrap_or("parent"))
     .bind(task_prompt)
     .execute(&state.db)
     .await;
@@ -604,7 +672,16 @@ pub(crate) async fn execute_agent_call(
         "A2A call_agent delegation".to_string(),
     ));
 
-    match execute_a2a_task(state, &task_id, task_prompt, override_tuple, depth).await {
+    match execute_a2a_task(
+        state,
+        &task_id,
+        task_prompt,
+        override_tuple,
+        depth,
+        call_chain,
+    )
+    .await
+    {
         Ok((_agent, result)) => Ok(result),
         Err(e) => Err(e),
     }
@@ -691,3 +768,60 @@ async fn build_task_response(state: &AppState, task_id: &str) -> Option<A2aTask>
         updated_at: row.7.to_rfc3339(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegation_cycles_are_named() {
+        let chain = vec!["eskel".to_string(), "lambert".to_string()];
+        assert_eq!(
+            check_delegation_cycle(&chain, "eskel").unwrap_err(),
+            "delegation cycle detected: eskel → lambert → eskel"
+        );
+        assert_eq!(
+            check_delegation_cycle(&chain, "lambert").unwrap_err(),
+            "delegation cycle detected: lambert → lambert"
+        );
+        assert!(check_delegation_cycle(&chain, "yennefer").is_ok());
+        assert!(check_delegation_cycle(&[], "eskel").is_ok());
+    }
+
+    #[test]
+    fn delegation_chain_stops_at_configured_depth() {
+        for max_depth in 1..=MAX_AGENT_CALL_DEPTH_CAP {
+            let mut depth = 0;
+            while let Ok(next) = next_call_depth(depth, max_depth) {
+                depth = next;
+                assert!(depth <= max_depth);
+            }
+            assert_eq!(depth, max_depth);
+            let err = next_call_depth(depth, max_depth).unwrap_err();
+            assert!(err.starts_with("max delegation depth reached"));
+        }
+    }
+
+    #[test]
+    fn tool_call_output_keeps_structured_success() {
+        // Successful output that merely looks like an error stays a success
+        let out = tool_call_output(
+            "read_file",
+            Some(Ok(ToolOutput::text("TOOL_ERROR: quoted from a log".into()))),
+        );
+        assert!(out.success);
+        assert_eq!(out.text, "TOOL_ERROR: quoted from a log");
+
+        let out = tool_call_output("read_file", Some(Err("no such file".into())));
+        assert!(!out.success);
+        assert_eq!(out.text, "TOOL_ERROR: no such file");
+
+        let out = tool_call_output("execute_command", None);
+        assert!(!out.success);
+        assert!(out.text.contains("timed out"));
+
+        let out = tool_call_output("read_file", Some(Ok(ToolOutput::text("x".repeat(20000)))));
+        assert!(out.success);
+        assert!(out.text.ends_with("[TRUNCATED: 20000 → 15000 chars]"));
+    }
+}
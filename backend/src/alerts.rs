@@ -0,0 +1,229 @@
+// Jaskier Shared Pattern — alerts
+// GeminiHydra v15 — Operator webhook alerts
+//
+// Best-effort POST to `ALERT_WEBHOOK_URL` when the watchdog detects a degraded
+// condition. Each condition is debounced (`ALERT_DEBOUNCE_SECS`, default 15 min)
+// so a sustained outage produces one alert per window, not one per check.
+// Delivery runs on a spawned task — failures are logged, never propagated.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+const DEFAULT_DEBOUNCE_SECS: u64 = 900;
+const DEFAULT_MEMORY_THRESHOLD_PCT: f64 = 90.0;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// JSON body POSTed to the webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertPayload {
+    pub condition: String,
+    pub severity: AlertSeverity,
+    pub message: String,
+    pub timestamp: String,
+    pub hostname: String,
+    pub service: &'static str,
+}
+
+impl AlertPayload {
+    pub fn new(condition: &str, severity: AlertSeverity, message: impl Into<String>) -> Self {
+        Self {
+            condition: condition.to_string(),
+            severity,
+            message: message.into(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            hostname: hostname(),
+            service: "geminihydra-v15",
+        }
+    }
+}
+
+pub struct AlertDispatcher {
+    webhook_url: Option<String>,
+    debounce: Duration,
+    /// Host memory usage (percent) above which the watchdog raises `memory_high`.
+    pub memory_threshold_pct: f64,
+    /// Last dispatch time per condition.
+    last_sent: Mutex<HashMap<String, Instant>>,
+}
+
+impl AlertDispatcher {
+    pub fn new(webhook_url: Option<String>, debounce: Duration, memory_threshold_pct: f64) -> Self {
+        Self {
+            webhook_url,
+            debounce,
+            memory_threshold_pct,
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Read `ALERT_WEBHOOK_URL`, `ALERT_DEBOUNCE_SECS` and `ALERT_MEMORY_THRESHOLD_PCT`.
+    pub fn from_env() -> Self {
+        let webhook_url = std::env::var("ALERT_WEBHOOK_URL")
+            .ok()
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty());
+        let debounce = std::env::var("ALERT_DEBOUNCE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_DEBOUNCE_SECS);
+        let memory_threshold_pct = std::env::var("ALERT_MEMORY_THRESHOLD_PCT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|pct: &f64| *pct > 0.0 && *pct <= 100.0)
+            .unwrap_or(DEFAULT_MEMORY_THRESHOLD_PCT);
+        Self::new(
+            webhook_url,
+            Duration::from_secs(debounce),
+            memory_threshold_pct,
+        )
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.webhook_url.is_some()
+    }
+
+    /// Dispatch an alert in the background unless the webhook is unset or the
+    /// same condition already fired within the debounce window.
+    /// Returns `true` if a delivery was started.
+    pub fn fire(
+        &self,
+        client: &reqwest::Client,
+        condition: &str,
+        severity: AlertSeverity,
+        message: impl Into<String>,
+    ) -> bool {
+        let Some(url) = self.webhook_url.clone() else {
+            return false;
+        };
+        if !self.should_send(condition) {
+            tracing::debug!("alerts: '{}' suppressed (debounced)", condition);
+            return false;
+        }
+
+        let payload = AlertPayload::new(condition, severity, message);
+        let client = client.clone();
+        tokio::spawn(async move {
+            match post(&client, &url, &payload).await {
+                Ok(status) => tracing::info!(
+                    "alerts: '{}' delivered to webhook (HTTP {})",
+                    payload.condition,
+                    status
+                ),
+                Err(e) => {
+                    tracing::warn!("alerts: failed to deliver '{}': {}", payload.condition, e)
+                }
+            }
+        });
+        true
+    }
+
+    /// Forget the last dispatch of `condition` so the next occurrence alerts
+    /// immediately. Called once the condition has cleared.
+    pub fn resolve(&self, condition: &str) {
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+        last_sent.remove(condition);
+    }
+
+    /// Deliver `payload` synchronously, bypassing the debounce.
+    /// Returns the webhook's HTTP status code.
+    pub async fn send_now(
+        &self,
+        client: &reqwest::Client,
+        payload: &AlertPayload,
+    ) -> Result<u16, String> {
+        let url = self
+            .webhook_url
+            .as_deref()
+            .ok_or_else(|| "ALERT_WEBHOOK_URL is not set".to_string())?;
+        post(client, url, payload).await
+    }
+
+    /// Record a dispatch of `condition` unless it was sent within the window.
+    fn should_send(&self, condition: &str) -> bool {
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match last_sent.get(condition) {
+            Some(at) if now.duration_since(*at) < self.debounce => false,
+            _ => {
+                last_sent.insert(condition.to_string(), now);
+                true
+            }
+        }
+    }
+}
+
+async fn post(client: &reqwest::Client, url: &str, payload: &AlertPayload) -> Result<u16, String> {
+    let resp = client
+        .post(url)
+        .json(payload)
+        .timeout(WEBHOOK_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+    let status = resp.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err(format!("webhook returned HTTP {}", status.as_u16()))
+    }
+}
+
+fn hostname() -> String {
+    sysinfo::System::host_name()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_are_debounced_independently() {
+        let alerts = AlertDispatcher::new(
+            Some("http://localhost/hook".into()),
+            Duration::from_secs(60),
+            90.0,
+        );
+        assert!(alerts.should_send("db_down"));
+        assert!(!alerts.should_send("db_down"));
+        assert!(alerts.should_send("memory_high"));
+
+        alerts.resolve("db_down");
+        assert!(alerts.should_send("db_down"));
+    }
+
+    #[test]
+    fn payload_serializes_expected_fields() {
+        let payload = AlertPayload::new("gemini_circuit_open", AlertSeverity::Critical, "tripped");
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value["condition"], "gemini_circuit_open");
+        assert_eq!(value["severity"], "critical");
+        assert!(value["timestamp"].as_str().is_some_and(|t| !t.is_empty()));
+        assert!(value["hostname"].as_str().is_some_and(|h| !h.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn unconfigured_dispatcher_is_a_no_op() {
+        let alerts = AlertDispatcher::new(None, Duration::from_secs(60), 90.0);
+        assert!(!alerts.is_configured());
+        assert!(!alerts.fire(
+            &reqwest::Client::new(),
+            "db_down",
+            AlertSeverity::Critical,
+            "down"
+        ));
+    }
+}
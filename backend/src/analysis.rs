@@ -38,25 +38,45 @@ pub struct FileStructure {
 /// Tree-sitter is a CPU-bound parser that should not run on the tokio
 /// async runtime. Use this from any `async` context instead of calling
 /// `analyze_file` directly.
-pub async fn analyze_file_async(path: String, content: String) -> Option<FileStructure> {
-    tokio::task::spawn_blocking(move || analyze_file(&path, &content))
+pub async fn analyze_file_async(
+    path: String,
+    extension: String,
+    content: String,
+) -> Option<FileStructure> {
+    tokio::task::spawn_blocking(move || analyze_file(&path, &extension, &content))
         .await
         .ok()
         .flatten()
 }
 
+/// File extensions [`analyze_file`] can outline.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "py", "go", "java", "cs", "cpp", "cc", "cxx", "hpp", "hh", "h",
+];
+
+/// Lowercased extension of `path` (without dot), as expected by
+/// [`is_supported_extension`] and [`analyze_file`].
+pub fn file_extension(path: &str) -> String {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Whether [`analyze_file`] supports the given file extension (without dot).
+pub fn is_supported_extension(extension: &str) -> bool {
+    SUPPORTED_EXTENSIONS.contains(&extension)
+}
+
 /// Analyze a source file and extract its code structure (functions, classes, etc.).
+/// `extension` comes from [`file_extension`], so `Main.JAVA` is outlined too.
 ///
 /// Uses tree-sitter AST analysis first; falls back to regex-based extraction
 /// when tree-sitter fails (e.g., for files using newer language syntax).
-pub fn analyze_file(path: &str, content: &str) -> Option<FileStructure> {
-    let extension = std::path::Path::new(path)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
-
+pub fn analyze_file(path: &str, extension: &str, content: &str) -> Option<FileStructure> {
     // Check if we support this extension at all
-    if !matches!(extension, "rs" | "ts" | "tsx" | "js" | "jsx" | "py" | "go") {
+    if !is_supported_extension(extension) {
         return None;
     }
 
@@ -134,6 +154,64 @@ fn go_queries() -> &'static [&'static str] {
         "#]
 }
 
+fn java_queries() -> &'static [&'static str] {
+    &[
+        r#"
+            (class_declaration name: (identifier) @name) @class
+            (interface_declaration name: (identifier) @name) @interface
+            (enum_declaration name: (identifier) @name) @enum
+            (record_declaration name: (identifier) @name) @record
+            (method_declaration name: (identifier) @name) @method
+            (constructor_declaration name: (identifier) @name) @constructor
+        "#,
+        // Fallback: grammars without record support
+        r#"
+            (class_declaration name: (identifier) @name) @class
+            (interface_declaration name: (identifier) @name) @interface
+            (enum_declaration name: (identifier) @name) @enum
+            (method_declaration name: (identifier) @name) @method
+        "#,
+    ]
+}
+
+fn csharp_queries() -> &'static [&'static str] {
+    &[
+        r#"
+            (namespace_declaration name: (_) @name) @namespace
+            (file_scoped_namespace_declaration name: (_) @name) @namespace
+            (class_declaration name: (identifier) @name) @class
+            (interface_declaration name: (identifier) @name) @interface
+            (struct_declaration name: (identifier) @name) @struct
+            (enum_declaration name: (identifier) @name) @enum
+            (record_declaration name: (identifier) @name) @record
+            (method_declaration name: (identifier) @name) @method
+            (constructor_declaration name: (identifier) @name) @constructor
+        "#,
+        r#"
+            (namespace_declaration name: (_) @name) @namespace
+            (class_declaration name: (identifier) @name) @class
+            (interface_declaration name: (identifier) @name) @interface
+            (method_declaration name: (identifier) @name) @method
+        "#,
+    ]
+}
+
+fn cpp_queries() -> &'static [&'static str] {
+    &[
+        r#"
+            (namespace_definition name: (_) @name) @namespace
+            (class_specifier name: (type_identifier) @name body: (_)) @class
+            (struct_specifier name: (type_identifier) @name body: (_)) @struct
+            (enum_specifier name: (type_identifier) @name body: (_)) @enum
+            (function_definition declarator: (function_declarator declarator: (_) @name)) @func
+        "#,
+        r#"
+            (class_specifier name: (type_identifier) @name) @class
+            (function_definition declarator: (function_declarator declarator: (_) @name)) @func
+        "#,
+    ]
+}
+
 fn analyze_treesitter(path: &str, content: &str, extension: &str) -> Option<FileStructure> {
     let mut parser = Parser::new();
     let language = match extension {
@@ -142,6 +220,9 @@ fn analyze_treesitter(path: &str, content: &str, extension: &str) -> Option<File
         "js" | "jsx" => tree_sitter_javascript::LANGUAGE.into(),
         "py" => tree_sitter_python::LANGUAGE.into(),
         "go" => tree_sitter_go::LANGUAGE.into(),
+        "java" => tree_sitter_java::LANGUAGE.into(),
+        "cs" => tree_sitter_c_sharp::LANGUAGE.into(),
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "h" => tree_sitter_cpp::LANGUAGE.into(),
         _ => return None,
     };
 
@@ -167,6 +248,9 @@ fn analyze_treesitter(path: &str, content: &str, extension: &str) -> Option<File
         "ts" | "tsx" | "js" | "jsx" => ts_queries(),
         "py" => py_queries(),
         "go" => go_queries(),
+        "java" => java_queries(),
+        "cs" => csharp_queries(),
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "h" => cpp_queries(),
         _ => return None,
     };
 
@@ -289,6 +373,71 @@ fn analyze_regex(path: &str, content: &str, extension: &str) -> FileStructure {
             (r"(?m)^type\s+(\w+)\s+struct", "struct"),
             (r"(?m)^type\s+(\w+)\s+interface", "interface"),
         ],
+        "java" => &[
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|static|final|abstract|sealed)\s+)*class\s+(\w+)",
+                "class",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|static|sealed)\s+)*interface\s+(\w+)",
+                "interface",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|static)\s+)*enum\s+(\w+)",
+                "enum",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|static|final)\s+)*record\s+(\w+)",
+                "record",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|static|final|abstract|synchronized|default)\s+)+(?:<[^>]+>\s+)?[\w<>\[\],.?]+\s+(\w+)\s*\(",
+                "method",
+            ),
+        ],
+        "cs" => &[
+            (r"(?m)^\s*namespace\s+([\w.]+)", "namespace"),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|internal|static|sealed|abstract|partial)\s+)*class\s+(\w+)",
+                "class",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|internal|partial)\s+)*interface\s+(\w+)",
+                "interface",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|internal|readonly|partial)\s+)*struct\s+(\w+)",
+                "struct",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|internal)\s+)*enum\s+(\w+)",
+                "enum",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|internal|sealed|abstract|partial)\s+)*record\s+(?:class\s+|struct\s+)?(\w+)",
+                "record",
+            ),
+            (
+                r"(?m)^\s*(?:(?:public|protected|private|internal|static|virtual|override|abstract|sealed|async|extern|new)\s+)+[\w<>\[\],.?]+\s+(\w+)\s*(?:<[^>]+>)?\s*\(",
+                "method",
+            ),
+        ],
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "h" => &[
+            (r"(?m)^\s*namespace\s+([\w:]+)\s*\{", "namespace"),
+            (
+                r"(?m)^\s*(?:template\s*<[^>]*>\s*)?class\s+(\w+)[^;]*$",
+                "class",
+            ),
+            (
+                r"(?m)^\s*(?:template\s*<[^>]*>\s*)?struct\s+(\w+)[^;]*$",
+                "struct",
+            ),
+            (r"(?m)^\s*enum\s+(?:class\s+)?(\w+)[^;]*$", "enum"),
+            (
+                r"(?m)^(?:[\w:<>,*&]+\s+)+[*&]*([\w:~]+)\s*\([^;]*\)\s*(?:const\s*)?(?:override\s*)?(?:noexcept\s*)?\{?\s*$",
+                "function",
+            ),
+        ],
         _ => &[],
     };
 
@@ -335,3 +484,59 @@ fn analyze_regex(path: &str, content: &str, extension: &str) -> FileStructure {
         symbols,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(structure: &FileStructure) -> Vec<&str> {
+        structure.symbols.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn java_query_compiles_and_finds_symbols() {
+        let language = tree_sitter_java::LANGUAGE.into();
+        assert!(Query::new(&language, java_queries()[0]).is_ok());
+        let src = "class Greeter {\n    Greeter() {}\n    void hello() {}\n}\nrecord Point(int x, int y) {}\n";
+        let structure = analyze_treesitter("Greeter.java", src, "java").unwrap();
+        let names = names(&structure);
+        for name in ["Greeter", "hello", "Point"] {
+            assert!(names.contains(&name), "{:?}", names);
+        }
+    }
+
+    #[test]
+    fn csharp_query_compiles_and_finds_symbols() {
+        let language = tree_sitter_c_sharp::LANGUAGE.into();
+        assert!(Query::new(&language, csharp_queries()[0]).is_ok());
+        let src = "namespace App;\npublic struct Size {}\npublic class Greeter {\n    public void Hello() {}\n}\n";
+        let structure = analyze_treesitter("Greeter.cs", src, "cs").unwrap();
+        let names = names(&structure);
+        for name in ["App", "Size", "Greeter", "Hello"] {
+            assert!(names.contains(&name), "{:?}", names);
+        }
+    }
+
+    #[test]
+    fn cpp_query_compiles_and_finds_symbols() {
+        let language = tree_sitter_cpp::LANGUAGE.into();
+        assert!(Query::new(&language, cpp_queries()[0]).is_ok());
+        let src = "namespace app {\nclass Greeter {};\nstruct Point { int x; };\nint add(int a, int b) { return a + b; }\n}\n";
+        let structure = analyze_treesitter("greeter.cpp", src, "cpp").unwrap();
+        let names = names(&structure);
+        for name in ["app", "Greeter", "Point", "add"] {
+            assert!(names.contains(&name), "{:?}", names);
+        }
+    }
+
+    #[test]
+    fn extension_is_normalised_before_analysis() {
+        assert_eq!(file_extension("src/Main.JAVA"), "java");
+        let structure = analyze_file(
+            "src/Main.JAVA",
+            &file_extension("src/Main.JAVA"),
+            "class Main {}",
+        );
+        assert!(structure.is_some_and(|s| names(&s) == ["Main"]));
+    }
+}
@@ -27,3 +27,121 @@ pub async fn log_audit(pool: &PgPool, action: &str, details: Value, ip: Option<&
         tracing::warn!(action = %action, "audit log insert failed: {}", e);
     }
 }
+
+/// Audit action recorded for every file-modifying or command-running tool call.
+pub const TOOL_CALL_ACTION: &str = "tool_call";
+
+/// Tools whose every invocation is written to the audit log.
+pub const AUDITED_TOOLS: &[&str] = &[
+    "write_file",
+    "edit_file",
+    "delete_file",
+    "copy_file",
+    "create_directory",
+    "execute_command",
+    "run_tests",
+];
+
+/// One audited tool call. The row is inserted with status `started` before
+/// the tool runs, because callers wrap tool execution in a timeout and a
+/// dropped future must still leave a trace. `finish` records the outcome;
+/// dropping the guard unfinished marks the row `cancelled`.
+pub struct ToolCallAudit {
+    pool: PgPool,
+    id: i32,
+    started: std::time::Instant,
+    finished: bool,
+}
+
+impl ToolCallAudit {
+    /// Insert the `started` row. Only the target (path / copy source and
+    /// destination / command / working directory / test framework) is kept
+    /// from `args` — file contents are never logged. Returns `None` if the insert failed.
+    pub async fn start(
+        pool: &PgPool,
+        tool: &str,
+        args: &Value,
+        agent_id: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Option<Self> {
+        let target: serde_json::Map<String, Value> = [
+            "path",
+            "src",
+            "dst",
+            "command",
+            "working_directory",
+            "framework",
+        ]
+        .iter()
+        .filter_map(|key| args.get(*key).map(|v| (key.to_string(), v.clone())))
+        .collect();
+        let details = serde_json::json!({
+            "tool": tool,
+            "args": target,
+            "agent_id": agent_id,
+            "session_id": session_id,
+            "status": "started",
+        });
+        let id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO gh_audit_log (action, details) VALUES ($1, $2) RETURNING id",
+        )
+        .bind(TOOL_CALL_ACTION)
+        .bind(&details)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| tracing::warn!(tool = %tool, "audit log insert failed: {}", e))
+        .ok()?;
+        Some(Self {
+            pool: pool.clone(),
+            id,
+            started: std::time::Instant::now(),
+            finished: false,
+        })
+    }
+
+    /// Record the outcome of the call.
+    pub async fn finish(mut self, success: bool, duration_ms: u64) {
+        self.finished = true;
+        let status = if success { "succeeded" } else { "failed" };
+        update_tool_call(&self.pool, self.id, status, Some(success), duration_ms).await;
+    }
+}
+
+impl Drop for ToolCallAudit {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = self.pool.clone();
+        let id = self.id;
+        let duration_ms = self.started.elapsed().as_millis() as u64;
+        handle.spawn(async move {
+            update_tool_call(&pool, id, "cancelled", None, duration_ms).await;
+        });
+    }
+}
+
+async fn update_tool_call(
+    pool: &PgPool,
+    id: i32,
+    status: &str,
+    success: Option<bool>,
+    duration_ms: u64,
+) {
+    let outcome = serde_json::json!({
+        "status": status,
+        "success": success,
+        "duration_ms": duration_ms,
+    });
+    if let Err(e) = sqlx::query("UPDATE gh_audit_log SET details = details || $2 WHERE id = $1")
+        .bind(id)
+        .bind(&outcome)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(id, "audit log update failed: {}", e);
+    }
+}
@@ -3,13 +3,17 @@
 // If AUTH_SECRET env is set, all protected routes require
 // `Authorization: Bearer <secret>`. If not set, auth is disabled (dev mode).
 
+use std::net::IpAddr;
+
 use axum::{
     extract::{Request, State},
     http::StatusCode,
     middleware::Next,
     response::Response,
 };
+use sha2::{Digest, Sha256};
 use subtle::ConstantTimeEq;
+use tower_governor::{GovernorError, key_extractor::KeyExtractor};
 
 use crate::state::AppState;
 
@@ -17,7 +21,7 @@ use crate::state::AppState;
 /// Public routes (health, readiness, auth/*) should NOT use this middleware.
 pub async fn require_auth(
     State(state): State<AppState>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let secret = match state.auth_secret.as_deref() {
@@ -34,6 +38,8 @@ pub async fn require_auth(
         Some(header) if header.starts_with("Bearer ") => {
             let token = &header[7..];
             if bool::from(token.as_bytes().ct_eq(secret.as_bytes())) {
+                let identity = AuthIdentity::from_token(token);
+                request.extensions_mut().insert(identity);
                 Ok(next.run(request).await)
             } else {
                 tracing::warn!("Auth failed: invalid token");
@@ -63,6 +69,78 @@ pub fn validate_ws_token(query: &str, auth_secret: Option<&str>) -> bool {
         .any(|(key, value)| key == "token" && bool::from(value.as_bytes().ct_eq(secret.as_bytes())))
 }
 
+/// Middleware for the WebSocket upgrade route: resolves the principal from
+/// `?token=` and stores it in request extensions so the rate limiter can key
+/// on it. Rejection of invalid tokens is left to the handler.
+pub async fn identify_ws(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(secret) = state.auth_secret.as_deref() {
+        let token = request
+            .uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, value)| {
+                *key == "token" && bool::from(value.as_bytes().ct_eq(secret.as_bytes()))
+            })
+            .map(|(_, value)| AuthIdentity::from_token(value));
+        if let Some(identity) = token {
+            request.extensions_mut().insert(identity);
+        }
+    }
+    next.run(request).await
+}
+
+/// Authenticated principal resolved by the auth middleware and stored in
+/// request extensions. Derived from a hash of the credential, never the
+/// credential itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthIdentity(pub String);
+
+impl AuthIdentity {
+    pub fn from_token(token: &str) -> Self {
+        let digest = Sha256::digest(token.as_bytes());
+        let short: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        Self(format!("bearer:{}", short))
+    }
+}
+
+/// Rate-limit bucket key: the authenticated principal paired with the peer IP
+/// when known, else the peer IP alone.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Identity(String, IpAddr),
+    Ip(IpAddr),
+}
+
+/// Governor key extractor that limits per auth identity (set by
+/// `require_auth` / `identify_ws`) and peer IP. `AUTH_SECRET` is a single
+/// shared credential, so the identity alone would put every client in one
+/// global bucket; pairing it with the IP keeps clients apart until real
+/// per-user credentials exist.
+#[derive(Debug, Clone, Copy)]
+pub struct IdentityKeyExtractor;
+
+impl KeyExtractor for IdentityKeyExtractor {
+    type Key = RateLimitKey;
+
+    fn extract<T>(&self, req: &axum::http::Request<T>) -> Result<Self::Key, GovernorError> {
+        let ip = req
+            .extensions()
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+            .map(|addr| addr.ip())
+            .ok_or(GovernorError::UnableToExtractKey)?;
+        Ok(match req.extensions().get::<AuthIdentity>() {
+            Some(identity) => RateLimitKey::Identity(identity.0.clone(), ip),
+            None => RateLimitKey::Ip(ip),
+        })
+    }
+}
+
 /// Pure function: extract and validate a Bearer token from an Authorization header value.
 /// Returns true if the token matches the expected secret.
 /// Used internally by `require_auth` middleware.
@@ -133,6 +211,82 @@ mod tests {
         assert!(!validate_ws_token("token=MySecret", Some("mysecret")));
     }
 
+    // ── IdentityKeyExtractor ─────────────────────────────────────────────
+
+    #[test]
+    fn identity_is_hashed_and_stable() {
+        let a = AuthIdentity::from_token("mysecret");
+        assert_eq!(a, AuthIdentity::from_token("mysecret"));
+        assert_ne!(a, AuthIdentity::from_token("other"));
+        assert!(!a.0.contains("mysecret"));
+    }
+
+    #[test]
+    fn key_pairs_identity_with_ip() {
+        let addr: std::net::SocketAddr = "10.0.0.1:4000".parse().unwrap();
+        let mut req = axum::http::Request::new(());
+        req.extensions_mut()
+            .insert(axum::extract::ConnectInfo(addr));
+        assert_eq!(
+            IdentityKeyExtractor.extract(&req).unwrap(),
+            RateLimitKey::Ip(addr.ip())
+        );
+
+        let identity = AuthIdentity::from_token("mysecret");
+        req.extensions_mut().insert(identity.clone());
+        assert_eq!(
+            IdentityKeyExtractor.extract(&req).unwrap(),
+            RateLimitKey::Identity(identity.0.clone(), addr.ip())
+        );
+    }
+
+    #[test]
+    fn key_without_ip_fails() {
+        let mut req = axum::http::Request::new(());
+        assert!(IdentityKeyExtractor.extract(&req).is_err());
+        req.extensions_mut()
+            .insert(AuthIdentity::from_token("mysecret"));
+        assert!(IdentityKeyExtractor.extract(&req).is_err());
+    }
+
+    #[tokio::test]
+    async fn shared_secret_clients_on_different_ips_get_separate_bursts() {
+        use axum::{Router, body::Body, routing::get};
+        use tower::ServiceExt;
+        use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
+
+        let config = GovernorConfigBuilder::default()
+            .per_second(60)
+            .burst_size(3)
+            .key_extractor(IdentityKeyExtractor)
+            .finish()
+            .unwrap();
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(GovernorLayer::new(config));
+
+        let send = |peer: &'static str| {
+            let addr: std::net::SocketAddr = peer.parse().unwrap();
+            let mut req = axum::http::Request::get("/").body(Body::empty()).unwrap();
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(addr));
+            // Every client holds the one AUTH_SECRET
+            req.extensions_mut()
+                .insert(AuthIdentity::from_token("mysecret"));
+            app.clone().oneshot(req)
+        };
+
+        for peer in ["10.0.0.1:4000", "10.0.0.2:4000"] {
+            for _ in 0..3 {
+                assert_eq!(send(peer).await.unwrap().status(), StatusCode::OK);
+            }
+        }
+        assert_eq!(
+            send("10.0.0.1:4000").await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
     // ── check_bearer_token ───────────────────────────────────────────────
 
     #[test]
@@ -2,8 +2,102 @@
 // classify.rs — Agent classification logic (extracted from handlers/mod.rs)
 // ---------------------------------------------------------------------------
 
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::handlers::streaming::{gemini_backoff, is_retryable};
 use crate::models::WitcherAgent;
 
+/// Per-attempt timeout for the classification call. Two attempts plus one
+/// backoff stay inside the 8s budget `prepare_execution` gives classification.
+const CLASSIFY_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Retries after a transient (429/503/timeout/connect) classification failure.
+const CLASSIFY_RETRIES: u32 = 1;
+
+/// Most confidence keyword matches can add on top of `base_confidence`.
+const MAX_KEYWORD_BONUS: f64 = 0.35;
+/// Keyword score that earns the full bonus.
+const FULL_BONUS_SCORE: f64 = 8.0;
+/// Keyword classification never claims more than this.
+const MAX_KEYWORD_CONFIDENCE: f64 = 0.95;
+
+/// Keyword-classification tuning — row id=1 of `gh_classification_config`,
+/// loaded once at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, sqlx::FromRow)]
+pub struct ClassificationConfig {
+    /// Confidence of an agent with any keyword match, before the score bonus.
+    pub base_confidence: f64,
+    /// Keyword confidence below which `prepare_execution` asks Gemini Flash.
+    pub fallback_threshold: f64,
+    /// Keywords at least this many bytes long weigh `long_keyword_weight`.
+    pub long_keyword_len: i32,
+    pub long_keyword_weight: f64,
+    /// Keywords at least this many bytes long weigh `medium_keyword_weight`.
+    pub medium_keyword_len: i32,
+    pub medium_keyword_weight: f64,
+    pub short_keyword_weight: f64,
+}
+
+impl Default for ClassificationConfig {
+    fn default() -> Self {
+        Self {
+            base_confidence: 0.6,
+            fallback_threshold: 0.65,
+            long_keyword_len: 8,
+            long_keyword_weight: 2.0,
+            medium_keyword_len: 5,
+            medium_keyword_weight: 1.5,
+            short_keyword_weight: 1.0,
+        }
+    }
+}
+
+impl ClassificationConfig {
+    /// Read the config row, falling back to the defaults if it is missing.
+    pub async fn load(db: &sqlx::PgPool) -> Self {
+        match sqlx::query_as::<_, Self>(
+            "SELECT base_confidence, fallback_threshold, long_keyword_len, long_keyword_weight, \
+                    medium_keyword_len, medium_keyword_weight, short_keyword_weight \
+             FROM gh_classification_config WHERE id = 1",
+        )
+        .fetch_optional(db)
+        .await
+        {
+            Ok(Some(config)) => config,
+            Ok(None) => Self::default(),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load classification config, using defaults: {}",
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    fn keyword_weight(&self, keyword: &str) -> f64 {
+        let len = keyword.len() as i32;
+        if len >= self.long_keyword_len {
+            self.long_keyword_weight
+        } else if len >= self.medium_keyword_len {
+            self.medium_keyword_weight
+        } else {
+            self.short_keyword_weight
+        }
+    }
+
+    fn confidence(&self, score: f64) -> f64 {
+        (self.base_confidence + (score / FULL_BONUS_SCORE).min(MAX_KEYWORD_BONUS))
+            .min(MAX_KEYWORD_CONFIDENCE)
+    }
+
+    /// Whether a keyword result this confident should be re-checked by Gemini Flash.
+    pub fn wants_gemini_fallback(&self, confidence: f64) -> bool {
+        confidence < self.fallback_threshold
+    }
+}
+
 /// Remove Polish diacritics for keyword matching.
 pub fn strip_diacritics(s: &str) -> String {
     s.chars()
@@ -33,50 +127,46 @@ pub fn keyword_match(text: &str, keyword: &str) -> bool {
 }
 
 /// Compute the raw keyword confidence score for a single agent against a prompt.
-pub fn classify_agent_score(lower_prompt: &str, agent: &WitcherAgent) -> f64 {
-    let mut score = 0.0_f64;
-    for keyword in &agent.keywords {
-        if keyword_match(lower_prompt, keyword) {
-            let weight = if keyword.len() >= 8 {
-                2.0
-            } else if keyword.len() >= 5 {
-                1.5
-            } else {
-                1.0
-            };
-            score += weight;
-        }
-    }
+pub fn classify_agent_score(
+    lower_prompt: &str,
+    agent: &WitcherAgent,
+    config: &ClassificationConfig,
+) -> f64 {
+    let score: f64 = matched_keywords(lower_prompt, agent)
+        .into_iter()
+        .map(|k| config.keyword_weight(k))
+        .sum();
     if score > 0.0 {
-        (0.6 + (score / 8.0).min(0.35)).min(0.95)
+        config.confidence(score)
     } else {
         0.0
     }
 }
 
+/// Keywords of `agent` found in an already lowercased, diacritic-free prompt.
+pub fn matched_keywords<'a>(lower_prompt: &str, agent: &'a WitcherAgent) -> Vec<&'a str> {
+    agent
+        .keywords
+        .iter()
+        .filter(|k| keyword_match(lower_prompt, k))
+        .map(String::as_str)
+        .collect()
+}
+
 /// Expert agent classification based on prompt analysis and agent keywords.
-pub fn classify_prompt(prompt: &str, agents: &[WitcherAgent]) -> (String, f64, String) {
+pub fn classify_prompt(
+    prompt: &str,
+    agents: &[WitcherAgent],
+    config: &ClassificationConfig,
+) -> (String, f64, String) {
     let lower = strip_diacritics(&prompt.to_lowercase());
     let mut best: Option<(String, f64, f64, String)> = None;
 
     for agent in agents {
-        let mut score = 0.0_f64;
-        let mut matched: Vec<&str> = Vec::new();
-        for keyword in &agent.keywords {
-            if keyword_match(&lower, keyword) {
-                let weight = if keyword.len() >= 8 {
-                    2.0
-                } else if keyword.len() >= 5 {
-                    1.5
-                } else {
-                    1.0
-                };
-                score += weight;
-                matched.push(keyword);
-            }
-        }
+        let matched = matched_keywords(&lower, agent);
+        let score: f64 = matched.iter().map(|k| config.keyword_weight(k)).sum();
         if score > 0.0 {
-            let confidence = (0.6 + (score / 8.0).min(0.35)).min(0.95);
+            let confidence = config.confidence(score);
             let reasoning = format!(
                 "Matched [{}] for {} (score: {:.1})",
                 matched.join(", "),
@@ -100,9 +190,11 @@ pub fn classify_prompt(prompt: &str, agents: &[WitcherAgent]) -> (String, f64, S
 }
 
 /// Semantic classification fallback via Gemini Flash.
-/// Called when keyword-based classification gives low confidence (<0.65).
+/// Called when keyword-based classification gives low confidence
+/// (below `ClassificationConfig::fallback_threshold`).
 pub async fn classify_with_gemini(
     client: &reqwest::Client,
+    endpoints: &crate::state::ProviderEndpoints,
     api_key: &str,
     is_oauth: bool,
     prompt: &str,
@@ -134,19 +226,44 @@ pub async fn classify_with_gemini(
         truncated_prompt, agent_list
     );
 
-    let url =
-        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent";
+    let url = endpoints.gemini_model("gemini-2.5-flash", "generateContent");
     let body = serde_json::json!({
         "contents": [{"parts": [{"text": classification_prompt}]}],
         "generationConfig": {"temperature": 1.0, "maxOutputTokens": 256}
     });
 
-    let resp = crate::oauth::apply_google_auth(client.post(url), api_key, is_oauth)
-        .json(&body)
-        .timeout(std::time::Duration::from_secs(5))
-        .send()
-        .await
-        .ok()?;
+    let mut attempt = 0;
+    let resp = loop {
+        let result = crate::oauth::apply_google_auth(client.post(&url), api_key, is_oauth)
+            .json(&body)
+            .timeout(CLASSIFY_ATTEMPT_TIMEOUT)
+            .send()
+            .await;
+        let retryable = is_retryable(&result);
+        match result {
+            Ok(resp) if resp.status().is_success() => break resp,
+            Ok(resp) => tracing::debug!(
+                "classify_with_gemini: Gemini unavailable (HTTP {}) on attempt {}",
+                resp.status(),
+                attempt + 1
+            ),
+            Err(e) if e.is_timeout() => tracing::debug!(
+                "classify_with_gemini: timed out after {:?} on attempt {}",
+                CLASSIFY_ATTEMPT_TIMEOUT,
+                attempt + 1
+            ),
+            Err(e) => tracing::debug!(
+                "classify_with_gemini: Gemini unavailable ({}) on attempt {}",
+                crate::redact::redact_secrets(&e.to_string()),
+                attempt + 1
+            ),
+        }
+        if !retryable || attempt >= CLASSIFY_RETRIES {
+            return None;
+        }
+        attempt += 1;
+        tokio::time::sleep(gemini_backoff(attempt)).await;
+    };
 
     let j: serde_json::Value = resp.json().await.ok()?;
     let text = j
@@ -5,6 +5,7 @@
 use crate::classify::{
     classify_agent_score, classify_prompt, classify_with_gemini, strip_diacritics,
 };
+use crate::models::{ExecutePlan, ExecutionPreview};
 use crate::prompt::{build_system_prompt, fetch_knowledge_context};
 use crate::state::AppState;
 
@@ -24,12 +25,214 @@ pub fn tier_token_budget(model: &str) -> i32 {
     }
 }
 
+/// Token budget of a mid-tier model, the reference for `history_window`.
+const HISTORY_REFERENCE_BUDGET: i32 = 32768;
+
+/// Session messages to replay for `model`: the `history_window` setting scaled
+/// by the tier's token budget relative to a mid-tier model, but never below
+/// half or above double the setting (flash halves it, pro doubles it).
+pub fn history_window(base: i32, model: &str) -> i64 {
+    let base = base.max(2) as i64;
+    let scaled = base * tier_token_budget(model) as i64 / HISTORY_REFERENCE_BUDGET as i64;
+    scaled.clamp(base / 2, base * 2)
+}
+
 /// Whether an HTTP status code is retryable (transient failure).
 #[allow(dead_code)]
 pub fn is_retryable_status(code: u16) -> bool {
     matches!(code, 429 | 502 | 503)
 }
 
+/// Starting model for requests in a session, before per-request/per-agent
+/// overrides and auto-tier routing: global `force_model`, then the session
+/// pin, then the global `default_model`. Returns `(model, source)`.
+pub async fn resolve_session_model(
+    state: &AppState,
+    session_model: Option<&str>,
+) -> (String, &'static str) {
+    let (force_model, default_model) = state
+        .settings()
+        .await
+        .map(|s| (s.force_model, s.default_model))
+        .unwrap_or_else(|_| (None, "gemini-3.1-pro-preview-customtools".to_string()));
+
+    match (force_model, session_model) {
+        (Some(fm), _) => (fm, "force_model"),
+        (None, Some(sm)) => (sm.to_string(), "session"),
+        (None, None) => (default_model, "default"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Context Budget
+// ---------------------------------------------------------------------------
+
+/// Rough bytes-per-token ratio for serialized Gemini `contents`.
+const BYTES_PER_TOKEN: usize = 4;
+/// Context window size as a multiple of the tier's output token budget.
+const CONTEXT_WINDOW_MULTIPLIER: usize = 12;
+/// Trailing messages that are never dropped (latest model call + its tool results).
+const KEEP_RECENT_MESSAGES: usize = 2;
+/// Cap for tool results in surviving turns when dropping whole turns is not enough.
+const SHRUNK_TOOL_RESULT_CHARS: usize = 4000;
+/// Text part prepended to the first remaining turn after a trim.
+const OMITTED_NOTE: &str = "[SYSTEM: Earlier messages were omitted to fit the context window.]";
+
+/// Max serialized size of `contents` sent to Gemini for `model`.
+pub fn context_byte_budget(model: &str) -> usize {
+    tier_token_budget(model) as usize * CONTEXT_WINDOW_MULTIPLIER * BYTES_PER_TOKEN
+}
+
+/// What `fit_contents_to_budget` removed to make the request fit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextTrim {
+    pub dropped_messages: usize,
+    pub shrunk_results: usize,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+fn content_bytes(content: &serde_json::Value) -> usize {
+    serde_json::to_string(content).map(|s| s.len()).unwrap_or(0)
+}
+
+fn is_function_response_turn(content: &serde_json::Value) -> bool {
+    content["role"] == "user"
+        && content["parts"]
+            .as_array()
+            .is_some_and(|parts| parts.iter().any(|p| p.get("functionResponse").is_some()))
+}
+
+/// Trim Gemini `contents` until its serialized size is within `budget` bytes.
+///
+/// Oldest turns go first; a model turn is always dropped together with the
+/// tool results answering it, so every remaining `functionResponse` still
+/// follows its `functionCall`. `contents[*pinned]` (the current user prompt)
+/// and the last `KEEP_RECENT_MESSAGES` entries are kept, and `pinned` is
+/// updated to the prompt's new index. If that is still too large, tool results
+/// outside the newest turn are shortened. Returns `None` when nothing had to
+/// change.
+pub fn fit_contents_to_budget(
+    contents: &mut Vec<serde_json::Value>,
+    pinned: &mut usize,
+    budget: usize,
+) -> Option<ContextTrim> {
+    let sizes: Vec<usize> = contents.iter().map(content_bytes).collect();
+    let bytes_before: usize = sizes.iter().sum();
+    if bytes_before <= budget {
+        return None;
+    }
+
+    let len = contents.len();
+    let pinned_at = *pinned;
+    let protected = |i: usize| i == pinned_at || i + KEEP_RECENT_MESSAGES >= len;
+    let mut drop = vec![false; len];
+    let mut total = bytes_before;
+    let mut i = 0;
+    while i < len && total > budget {
+        if protected(i) {
+            i += 1;
+            continue;
+        }
+        let pair_end = if contents[i]["role"] == "model"
+            && i + 1 < len
+            && is_function_response_turn(&contents[i + 1])
+        {
+            if protected(i + 1) {
+                i += 1;
+                continue;
+            }
+            i + 1
+        } else {
+            i
+        };
+        for j in i..=pair_end {
+            drop[j] = true;
+            total -= sizes[j];
+        }
+        i = pair_end + 1;
+    }
+    // The conversation must open with a plain user turn
+    for (j, content) in contents.iter().enumerate() {
+        if drop[j] {
+            continue;
+        }
+        if content["role"] == "user" && !is_function_response_turn(content) {
+            break;
+        }
+        if protected(j) {
+            break;
+        }
+        drop[j] = true;
+        total -= sizes[j];
+    }
+
+    let dropped_messages = drop.iter().filter(|d| **d).count();
+    *pinned -= drop[..pinned_at].iter().filter(|d| **d).count();
+    let mut keep = drop.iter().map(|d| !d);
+    contents.retain(|_| keep.next().unwrap_or(true));
+
+    let mut shrunk_results = 0;
+    if total > budget {
+        let newest = contents.len().saturating_sub(1);
+        for content in contents.iter_mut().take(newest) {
+            let Some(parts) = content["parts"].as_array_mut() else {
+                continue;
+            };
+            for part in parts {
+                let result = &mut part["functionResponse"]["response"]["result"];
+                if let Some(text) = result.as_str()
+                    && text.len() > SHRUNK_TOOL_RESULT_CHARS
+                {
+                    let mut end = SHRUNK_TOOL_RESULT_CHARS;
+                    while !text.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    *result = serde_json::Value::String(format!(
+                        "{}\n\n[Result shortened from {} chars to fit the context window]",
+                        &text[..end],
+                        text.len()
+                    ));
+                    shrunk_results += 1;
+                }
+            }
+        }
+    }
+
+    if dropped_messages > 0
+        && let Some(parts) = contents.first_mut().and_then(|c| c["parts"].as_array_mut())
+        && parts.first().and_then(|p| p["text"].as_str()) != Some(OMITTED_NOTE)
+    {
+        parts.insert(0, serde_json::json!({ "text": OMITTED_NOTE }));
+    }
+
+    Some(ContextTrim {
+        dropped_messages,
+        shrunk_results,
+        bytes_before,
+        bytes_after: contents.iter().map(content_bytes).sum(),
+    })
+}
+
+/// How many of an agent's most recent ratings the #50 quality alert averages,
+/// so a run of good ratings clears an old alert quickly.
+pub const RATING_ALERT_WINDOW: usize = 20;
+
+/// Average of the newest `RATING_ALERT_WINDOW` ratings (given newest first)
+/// when it falls below `threshold` over at least `min_count` ratings.
+pub fn rating_alert(recent: &[i32], threshold: f64, min_count: i32) -> Option<f64> {
+    let recent = &recent[..recent.len().min(RATING_ALERT_WINDOW)];
+    if recent.is_empty() || (recent.len() as i32) < min_count {
+        return None;
+    }
+    let avg = recent.iter().map(|&r| r as f64).sum::<f64>() / recent.len() as f64;
+    (avg < threshold).then_some(avg)
+}
+
+/// Execute `mode` that stops after `prepare_execution` and returns an
+/// `ExecutionPreview` instead of calling Gemini.
+pub const PLAN_ONLY_MODE: &str = "plan-only";
+
 #[derive(Clone)]
 pub struct ExecuteContext {
     pub agent_id: String,
@@ -54,18 +257,110 @@ pub struct ExecuteContext {
     pub max_iterations: i32,
     /// Gemini 3 thinking level: 'none', 'minimal', 'low', 'medium', 'high'
     pub thinking_level: String,
-    /// A2A — current agent call depth (0 = user-initiated, max 3)
+    /// A2A — current agent call depth (0 = user-initiated, capped by `max_agent_call_depth`)
     pub call_depth: u32,
+    /// A2A — agents that delegated down to this one, outermost first
+    pub call_chain: Vec<String>,
     /// Working directory for filesystem tools (empty = absolute paths only)
     pub working_directory: String,
+    /// Stop the tool-call loop on the first TOOL_ERROR result
+    pub stop_on_tool_error: bool,
+    /// Models tried in order when `model` keeps failing (empty = flash tier model)
+    pub fallback_models: Vec<String>,
+    /// Session messages replayed into the request (`history_window` scaled for `model`)
+    pub history_window: i64,
+    /// Most recent history messages kept untruncated
+    pub history_truncate_keep: usize,
+    /// Gemini safetySettings preset: 'default', 'relaxed', 'strict'
+    pub safety_level: String,
+    /// Byte cap on each tool result streamed into the chat
+    pub max_tool_display_bytes: usize,
+    /// Retry schedule for transient Gemini errors
+    pub retry: crate::handlers::streaming::RetryPolicy,
+}
+
+/// Per-request tuning from an execute message, layered over the settings and
+/// agent values for that one execution only.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOverrides {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub thinking_level: Option<String>,
+    pub stop_on_tool_error: Option<bool>,
+}
+
+impl ExecuteOverrides {
+    /// Reject non-finite numbers and unknown thinking levels, and clamp to
+    /// the same ranges as the matching settings.
+    pub fn validate(&mut self) -> Result<(), String> {
+        for (field, value) in [("temperature", self.temperature), ("top_p", self.top_p)] {
+            if value.is_some_and(|v| !v.is_finite()) {
+                return Err(format!("{} must be a finite number", field));
+            }
+        }
+        if let Some(level) = self.thinking_level.as_deref()
+            && !crate::sessions::THINKING_LEVELS.contains(&level)
+        {
+            return Err(format!(
+                "Invalid thinking_level '{}'. Allowed: {}",
+                level,
+                crate::sessions::THINKING_LEVELS.join(", ")
+            ));
+        }
+        self.temperature = self.temperature.map(|v| v.clamp(0.0, 2.0));
+        self.top_p = self.top_p.map(|v| v.clamp(0.0, 1.0));
+        Ok(())
+    }
 }
 
+impl ExecuteContext {
+    /// Apply validated per-request overrides. Every Gemini call of the
+    /// execution — tool loop, forced edit phase, synthesis — reads these fields.
+    pub fn apply_overrides(&mut self, overrides: &ExecuteOverrides) {
+        if let Some(temperature) = overrides.temperature {
+            self.temperature = temperature;
+        }
+        if let Some(top_p) = overrides.top_p {
+            self.top_p = top_p;
+        }
+        if let Some(level) = &overrides.thinking_level {
+            self.thinking_level = level.clone();
+        }
+        if let Some(stop) = overrides.stop_on_tool_error {
+            self.stop_on_tool_error = stop;
+        }
+    }
+
+    /// What executing this context would do — the `plan-only` response.
+    pub fn preview(&self) -> ExecutionPreview {
+        ExecutionPreview {
+            plan: ExecutePlan {
+                agent: Some(self.agent_id.clone()),
+                steps: self.steps.clone(),
+                estimated_time: None,
+            },
+            confidence: self.confidence,
+            reasoning: self.reasoning.clone(),
+            model: self.model.clone(),
+            thinking_level: self.thinking_level.clone(),
+            max_tokens: self.max_tokens,
+            files_loaded: self.files_loaded.clone(),
+            system_prompt_chars: self.system_prompt.chars().count(),
+            prompt_chars: self.final_user_prompt.chars().count(),
+        }
+    }
+}
+
+/// Resolve agent, model, credentials and prompts for one execution.
+/// `plan_only` previews classify with keywords alone — no Gemini call.
 pub async fn prepare_execution(
     state: &AppState,
     prompt: &str,
     model_override: Option<String>,
+    session_model: Option<String>,
     agent_override: Option<(String, f64, String)>,
     session_wd: &str,
+    plan_only: bool,
 ) -> ExecuteContext {
     let agents_lock = state.agents.read().await;
 
@@ -102,14 +397,16 @@ pub async fn prepare_execution(
     } else if let Some(prefix_ov) = agent_override_from_prefix {
         prefix_ov
     } else {
-        let (kw_agent, kw_conf, kw_reason) = classify_prompt(&prompt_clean, &agents_lock);
+        let (kw_agent, kw_conf, kw_reason) =
+            classify_prompt(&prompt_clean, &agents_lock, &state.classification);
         // #28 — If keyword confidence is low, try Gemini Flash as fallback (with timeout)
-        if kw_conf < 0.65 {
+        if !plan_only && state.classification.wants_gemini_fallback(kw_conf) {
             let gemini_result = tokio::time::timeout(std::time::Duration::from_secs(8), async {
                 let classify_cred = crate::oauth::get_google_credential(state).await;
                 if let Some((classify_key, classify_is_oauth)) = classify_cred {
                     classify_with_gemini(
                         &state.client,
+                        &state.providers,
                         &classify_key,
                         classify_is_oauth,
                         &prompt_clean,
@@ -149,7 +446,7 @@ pub async fn prepare_execution(
     let mut top_agents: Vec<_> = agents_lock
         .iter()
         .map(|a| {
-            let score = classify_agent_score(&lower_prompt, a);
+            let score = classify_agent_score(&lower_prompt, a, &state.classification);
             (a.id.clone(), a.name.clone(), score)
         })
         .filter(|(id, _, s)| *s > 0.65 && *id != agent_id)
@@ -165,16 +462,79 @@ pub async fn prepare_execution(
         String::new()
     };
 
-    let (force_model_setting, def_model, lang, temperature, max_tokens, top_p, response_style, max_iterations, thinking_level, settings_wd) =
-        sqlx::query_as::<_, (Option<String>, String, String, f64, i32, f64, String, i32, String, String)>(
-            "SELECT force_model, default_model, language, temperature, max_tokens, top_p, response_style, max_iterations, thinking_level, working_directory \
-             FROM gh_settings WHERE id = 1",
-        )
-        .fetch_one(&state.db)
+    let (
+        force_model_setting,
+        def_model,
+        lang,
+        temperature,
+        max_tokens,
+        top_p,
+        response_style,
+        max_iterations,
+        thinking_level,
+        settings_wd,
+        stop_on_tool_error,
+        fallback_models,
+        base_history_window,
+        history_truncate_keep,
+        safety_level,
+        rating_alert_threshold,
+        rating_alert_min_count,
+        max_tool_display_bytes,
+        retry,
+    ) = state
+        .settings()
         .await
-        .unwrap_or_else(|_| (
-            None, "gemini-3.1-pro-preview-customtools".to_string(), "en".to_string(), 1.0, 65536, 0.95, "balanced".to_string(), 10, "medium".to_string(), String::new()
-        ));
+        .map(|s| {
+            (
+                s.force_model,
+                s.default_model,
+                s.language,
+                s.temperature,
+                s.max_tokens,
+                s.top_p,
+                s.response_style,
+                s.max_iterations,
+                s.thinking_level,
+                s.working_directory,
+                s.stop_on_tool_error,
+                s.fallback_models,
+                s.history_window,
+                s.history_truncate_keep,
+                s.safety_level,
+                s.rating_alert_threshold,
+                s.rating_alert_min_count,
+                s.max_tool_display_bytes,
+                crate::handlers::streaming::RetryPolicy::from_settings(
+                    s.gemini_max_retries,
+                    s.gemini_backoff_base_ms,
+                    s.gemini_backoff_jitter_ms,
+                ),
+            )
+        })
+        .unwrap_or_else(|_| {
+            (
+                None,
+                "gemini-3.1-pro-preview-customtools".to_string(),
+                "en".to_string(),
+                1.0,
+                65536,
+                0.95,
+                "balanced".to_string(),
+                10,
+                "medium".to_string(),
+                String::new(),
+                false,
+                Vec::new(),
+                20,
+                6,
+                "default".to_string(),
+                3.0,
+                5,
+                crate::handlers::streaming::DEFAULT_TOOL_DISPLAY_BYTES as i32,
+                crate::handlers::streaming::RetryPolicy::default(),
+            )
+        });
 
     // Session WD takes priority over global settings WD
     let working_directory = if !session_wd.is_empty() {
@@ -192,12 +552,16 @@ pub async fn prepare_execution(
     let agent_thinking = matched_agent.and_then(|a| a.thinking_level.clone());
     let effective_thinking = agent_thinking.unwrap_or(thinking_level);
 
-    // Model priority: 0) global force_model → 1) user request override → 2) per-agent DB override → 3) auto-tier → 4) global default
+    // Model priority: 0) global force_model → 1) user request override → 2) session pin
+    // → 3) per-agent DB override → 4) auto-tier → 5) global default
     let agent_model = matched_agent.and_then(|a| a.model_override.clone());
+    let session_pinned = force_model_setting.is_none() && session_model.is_some();
     let model = if let Some(fm) = force_model_setting {
         fm
     } else if let Some(ov) = model_override {
         ov
+    } else if let Some(sm) = session_model {
+        sm
     } else if let Some(am) = agent_model {
         am
     } else {
@@ -210,8 +574,10 @@ pub async fn prepare_execution(
         }
     };
 
-    // A/B testing: per-agent model_b with ab_split probability
-    let model = if let Some(agent) = matched_agent {
+    // A/B testing: per-agent model_b with ab_split probability (never overrides a session pin)
+    let model = if session_pinned {
+        model
+    } else if let Some(agent) = matched_agent {
         if let (Some(model_b), Some(split)) = (&agent.model_b, agent.ab_split) {
             if rand::random::<f64>() < split {
                 tracing::info!(
@@ -243,26 +609,33 @@ pub async fn prepare_execution(
 
     // Cached system prompt — byte-identical across requests enables Gemini implicit caching
     let prompt_cache_key = format!("{}:{}:{}:{}", agent_id, language, model, working_directory);
-    let system_prompt = {
-        let cache = state.prompt_cache.read().await;
-        cache.get(&prompt_cache_key).cloned()
-    }
-    .unwrap_or_else(|| {
-        let prompt = build_system_prompt(
-            &agent_id,
-            &agents_lock,
-            language,
-            &model,
-            &working_directory,
-        );
-        let cache_clone = prompt.clone();
-        let state_clone = state.prompt_cache.clone();
-        let key_clone = prompt_cache_key.clone();
-        tokio::spawn(async move {
-            state_clone.write().await.insert(key_clone, cache_clone);
-        });
-        prompt
-    });
+    let cached_prompt = state
+        .prompt_cache
+        .read()
+        .await
+        .get(&prompt_cache_key)
+        .cloned();
+    let system_prompt = match cached_prompt {
+        Some(prompt) => prompt,
+        None => {
+            let prompt = build_system_prompt(
+                &agent_id,
+                &agents_lock,
+                language,
+                &model,
+                &working_directory,
+            );
+            // Insert while `agents_lock` is still held: refresh_agents swaps the roster
+            // before clearing the cache, so a prompt built from the old roster can never
+            // land in the cache after the invalidation.
+            state
+                .prompt_cache
+                .write()
+                .await
+                .insert(prompt_cache_key, prompt.clone());
+            prompt
+        }
+    };
 
     // Jaskier Knowledge API — enrich system prompt with project context (optional, non-blocking)
     let system_prompt = if state.knowledge_api_url.is_some() {
@@ -333,8 +706,10 @@ pub async fn prepare_execution(
     });
 
     // #21 — Capture file context errors instead of discarding them
+    // Auto-load limits scale with the model tier; small models lean on tools instead
+    let file_budget = crate::files::FileContextBudget::for_token_budget(tier_token_budget(&model));
     let (file_context, context_errors) = if !sorted_paths.is_empty() {
-        crate::files::build_file_context(&sorted_paths).await
+        crate::files::build_file_context(&sorted_paths, file_budget).await
     } else {
         (String::new(), Vec::new())
     };
@@ -358,9 +733,12 @@ pub async fn prepare_execution(
     let context_summary = if !files_loaded.is_empty() {
         let total_size = file_context.len();
         format!(
-            "\n[AUTO-LOADED: {} file(s), ~{}KB total: {}]\n",
+            "\n[AUTO-LOADED: {} file(s), ~{}KB total (budget {}KB/file, {}KB total, {} items): {}]\n",
             files_loaded.len(),
             total_size / 1024,
+            file_budget.max_file_bytes / 1024,
+            file_budget.max_total_bytes / 1024,
+            file_budget.max_files,
             files_loaded.join(", ")
         )
     } else {
@@ -396,23 +774,32 @@ pub async fn prepare_execution(
         _ => "", // "balanced" = default, no override
     };
 
-    // #50 — Rating-based quality warning (fire-and-forget, don't block on failure)
-    let rating_warning = match sqlx::query_as::<_, (f64, i64)>(
-        "SELECT COALESCE(avg_rating, 5.0)::FLOAT8, COALESCE(total_ratings, 0)::BIGINT \
-         FROM gh_agent_rating_stats WHERE agent_id = $1",
+    // #50 — Rating-based quality warning over the agent's most recent ratings
+    // (fire-and-forget, don't block on failure)
+    let recent_ratings = sqlx::query_scalar::<_, i32>(
+        "SELECT r.rating::int FROM gh_ratings r \
+         LEFT JOIN gh_chat_messages m ON m.id = r.message_id \
+         WHERE COALESCE(r.agent_id, m.agent) = $1 \
+         ORDER BY r.created_at DESC LIMIT $2",
     )
     .bind(&agent_id)
-    .fetch_optional(&state.db)
+    .bind(RATING_ALERT_WINDOW as i64)
+    .fetch_all(&state.db)
     .await
-    {
-        Ok(Some((avg, total))) if avg < 3.0 && total >= 5 => {
+    .unwrap_or_default();
+    let rating_warning = match rating_alert(
+        &recent_ratings,
+        rating_alert_threshold,
+        rating_alert_min_count,
+    ) {
+        Some(avg) => {
             format!(
                 "\n[QUALITY ALERT: Your recent responses received low ratings (avg {:.1}/5). \
                  Focus on: being concise, using tables, providing actionable insights instead of generic commentary.]\n",
                 avg
             )
         }
-        _ => String::new(),
+        None => String::new(),
     };
 
     let final_user_prompt = format!(
@@ -451,6 +838,154 @@ pub async fn prepare_execution(
         max_iterations,
         thinking_level: effective_thinking,
         call_depth: 0,
+        call_chain: Vec::new(),
         working_directory,
+        stop_on_tool_error,
+        fallback_models,
+        history_window: history_window(base_history_window, &model),
+        history_truncate_keep: history_truncate_keep.max(0) as usize,
+        safety_level,
+        max_tool_display_bytes: max_tool_display_bytes.max(0) as usize,
+        retry,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn execute_overrides_are_validated_and_clamped() {
+        let mut overrides = ExecuteOverrides {
+            temperature: Some(3.5),
+            top_p: Some(-0.2),
+            thinking_level: Some("low".into()),
+            stop_on_tool_error: None,
+        };
+        overrides.validate().unwrap();
+        assert_eq!(overrides.temperature, Some(2.0));
+        assert_eq!(overrides.top_p, Some(0.0));
+
+        let mut bad_level = ExecuteOverrides {
+            thinking_level: Some("maximum".into()),
+            ..Default::default()
+        };
+        assert!(bad_level.validate().unwrap_err().contains("thinking_level"));
+
+        let mut nan = ExecuteOverrides {
+            temperature: Some(f64::NAN),
+            ..Default::default()
+        };
+        assert!(nan.validate().is_err());
+        assert!(ExecuteOverrides::default().validate().is_ok());
+    }
+
+    #[test]
+    fn recent_good_ratings_clear_quality_alert() {
+        // Newest first: a long run of 1-star ratings triggers the alert.
+        let bad = vec![1; 10];
+        assert_eq!(rating_alert(&bad, 3.0, 5), Some(1.0));
+        // Fewer than min_count ratings never alert.
+        assert_eq!(rating_alert(&bad[..4], 3.0, 5), None);
+
+        // After the agent improves, the old ratings fall out of the window.
+        let mut recovered = vec![5; RATING_ALERT_WINDOW];
+        recovered.extend(&bad);
+        assert_eq!(rating_alert(&recovered, 3.0, 5), None);
+
+        // A stricter threshold still flags a mediocre recent average.
+        let mixed = [4, 3, 3, 4, 3];
+        assert!(rating_alert(&mixed, 4.0, 5).is_some());
+        assert_eq!(rating_alert(&mixed, 3.0, 5), None);
+    }
+
+    #[test]
+    fn history_window_scales_with_model_tier() {
+        assert_eq!(history_window(20, "gemini-2.5-flash"), 10);
+        assert_eq!(history_window(20, "gemini-experimental"), 20);
+        assert_eq!(history_window(20, "gemini-3.1-pro-preview"), 40);
+        // A zero setting still replays the latest message.
+        assert_eq!(history_window(0, "gemini-2.5-flash"), 1);
+    }
+
+    fn user(text: &str) -> serde_json::Value {
+        json!({ "role": "user", "parts": [{ "text": text }] })
+    }
+
+    fn call(name: &str) -> serde_json::Value {
+        json!({ "role": "model", "parts": [{ "functionCall": { "name": name, "args": {} } }] })
+    }
+
+    fn result(name: &str, body: &str) -> serde_json::Value {
+        json!({ "role": "user", "parts": [{ "functionResponse": { "name": name, "response": { "result": body } } }] })
+    }
+
+    #[test]
+    fn contents_within_budget_are_untouched() {
+        let mut contents = vec![user("hi"), call("a"), result("a", "ok")];
+        let mut pinned = 0;
+        assert_eq!(
+            fit_contents_to_budget(&mut contents, &mut pinned, 10_000),
+            None
+        );
+        assert_eq!(contents.len(), 3);
+    }
+
+    #[test]
+    fn oldest_turns_are_dropped_in_call_result_pairs() {
+        let big = "x".repeat(2000);
+        let mut contents = vec![
+            user("old question"),
+            json!({ "role": "model", "parts": [{ "text": big }] }),
+            user("current task"),
+            call("read_file"),
+            result("read_file", &big),
+            call("search_files"),
+            result("search_files", &big),
+            call("list_directory"),
+            result("list_directory", "small"),
+        ];
+        let mut pinned = 2;
+        let trim = fit_contents_to_budget(&mut contents, &mut pinned, 1000).unwrap();
+
+        assert_eq!(trim.dropped_messages, 6);
+        assert_eq!(pinned, 0);
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[0]["parts"][0]["text"], OMITTED_NOTE);
+        assert_eq!(contents[0]["parts"][1]["text"], "current task");
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["name"],
+            "list_directory"
+        );
+        assert_eq!(
+            contents[2]["parts"][0]["functionResponse"]["name"],
+            "list_directory"
+        );
+        assert!(trim.bytes_after < trim.bytes_before);
+    }
+
+    #[test]
+    fn older_tool_results_are_shortened_when_dropping_is_not_enough() {
+        let big = "y".repeat(20_000);
+        let mut contents = vec![
+            user("task"),
+            call("a"),
+            result("a", &big),
+            call("b"),
+            result("b", &big),
+        ];
+        let mut pinned = 0;
+        let trim = fit_contents_to_budget(&mut contents, &mut pinned, 30_000).unwrap();
+
+        assert_eq!(trim.dropped_messages, 2);
+        assert_eq!(trim.shrunk_results, 0);
+        assert_eq!(contents.len(), 3);
+
+        let mut contents = vec![user("task"), call("a"), result("a", &big), call("b")];
+        let trim = fit_contents_to_budget(&mut contents, &mut pinned, 10_000).unwrap();
+        assert_eq!(trim.dropped_messages, 0);
+        assert_eq!(trim.shrunk_results, 1);
+        assert!(trim.bytes_after < 10_000);
     }
 }
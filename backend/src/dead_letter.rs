@@ -0,0 +1,212 @@
+// GeminiHydra v15 — Dead-letter queue for chat messages
+//
+// When `store_messages` can't write a turn to `gh_chat_messages` (Postgres
+// hiccup mid-stream), the unsaved rows are parked here instead of being lost.
+// A background task retries them with exponential backoff; inserts use
+// `ON CONFLICT (id) DO NOTHING`, so a retry after a partial success is safe.
+// The queue is bounded — when full, the oldest message is dropped (and logged).
+// Its depth is reported by `/api/health/detailed`.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Unsaved messages kept in memory at most.
+const MAX_DEAD_LETTERS: usize = 500;
+/// Poll interval while the queue is empty, and the first retry delay.
+const RETRY_BASE: Duration = Duration::from_secs(5);
+/// Backoff ceiling while the database keeps failing.
+const RETRY_MAX: Duration = Duration::from_secs(300);
+
+/// A `gh_chat_messages` row that could not be written.
+#[derive(Debug, Clone)]
+pub struct PendingMessage {
+    pub id: Uuid,
+    pub role: &'static str,
+    pub content: String,
+    pub model: Option<String>,
+    pub agent: Option<String>,
+    pub session_id: Option<Uuid>,
+    /// When the turn happened — kept so retried rows sort where they belong.
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub attempts: u32,
+}
+
+pub struct DeadLetterQueue {
+    messages: Mutex<VecDeque<PendingMessage>>,
+    capacity: usize,
+}
+
+impl Default for DeadLetterQueue {
+    fn default() -> Self {
+        Self::new(MAX_DEAD_LETTERS)
+    }
+}
+
+impl DeadLetterQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: Mutex::new(VecDeque::new()),
+            capacity,
+        }
+    }
+
+    /// Park a message for retry, evicting the oldest one if the queue is full.
+    pub fn push(&self, message: PendingMessage) {
+        let mut queue = self.messages.lock().unwrap_or_else(|p| p.into_inner());
+        if queue.len() >= self.capacity
+            && let Some(dropped) = queue.pop_front()
+        {
+            tracing::error!(
+                "dead_letter: queue full — dropping unsaved {} message {} (session {:?})",
+                dropped.role,
+                dropped.id,
+                dropped.session_id
+            );
+        }
+        queue.push_back(message);
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take every parked message, oldest first.
+    fn take_all(&self) -> Vec<PendingMessage> {
+        let mut queue = self.messages.lock().unwrap_or_else(|p| p.into_inner());
+        queue.drain(..).collect()
+    }
+
+    /// Put back messages that failed again, ahead of anything parked since.
+    /// Over capacity, the oldest are dropped — same as `push`.
+    fn requeue(&self, failed: Vec<PendingMessage>) {
+        let mut queue = self.messages.lock().unwrap_or_else(|p| p.into_inner());
+        for message in failed.into_iter().rev() {
+            queue.push_front(message);
+        }
+        while queue.len() > self.capacity {
+            if let Some(dropped) = queue.pop_front() {
+                tracing::error!(
+                    "dead_letter: queue full — dropping unsaved {} message {} (session {:?})",
+                    dropped.role,
+                    dropped.id,
+                    dropped.session_id
+                );
+            }
+        }
+    }
+}
+
+/// Insert one chat message; safe to repeat for the same `id`.
+pub async fn insert_message(db: &PgPool, message: &PendingMessage) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO gh_chat_messages (id, role, content, model, agent, session_id, created_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (id) DO NOTHING",
+    )
+    .bind(message.id)
+    .bind(message.role)
+    .bind(&message.content)
+    .bind(&message.model)
+    .bind(&message.agent)
+    .bind(message.session_id)
+    .bind(message.created_at)
+    .execute(db)
+    .await
+    .map(|_| ())
+}
+
+/// Retry parked messages in the background until the process exits.
+pub fn spawn(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut delay = RETRY_BASE;
+        loop {
+            tokio::time::sleep(delay).await;
+            if state.dead_letters.is_empty() {
+                delay = RETRY_BASE;
+                continue;
+            }
+
+            let pending = state.dead_letters.take_all();
+            let total = pending.len();
+            let mut failed = Vec::new();
+            for mut message in pending {
+                if let Err(e) = insert_message(&state.db, &message).await {
+                    message.attempts += 1;
+                    tracing::warn!(
+                        "dead_letter: retry {} of message {} (session {:?}) failed: {}",
+                        message.attempts,
+                        message.id,
+                        message.session_id,
+                        e
+                    );
+                    failed.push(message);
+                }
+            }
+
+            if failed.is_empty() {
+                tracing::info!("dead_letter: flushed {} unsaved message(s)", total);
+                delay = RETRY_BASE;
+            } else {
+                delay = (delay * 2).min(RETRY_MAX);
+                state.dead_letters.requeue(failed);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &str) -> PendingMessage {
+        PendingMessage {
+            id: Uuid::new_v4(),
+            role: "user",
+            content: content.to_string(),
+            model: None,
+            agent: None,
+            session_id: None,
+            created_at: chrono::Utc::now(),
+            attempts: 0,
+        }
+    }
+
+    fn contents(queue: &DeadLetterQueue) -> Vec<String> {
+        queue.take_all().into_iter().map(|m| m.content).collect()
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_message() {
+        let queue = DeadLetterQueue::new(2);
+        queue.push(message("a"));
+        queue.push(message("b"));
+        queue.push(message("c"));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(contents(&queue), ["b", "c"]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn failed_retries_go_back_ahead_of_newer_messages_within_capacity() {
+        let queue = DeadLetterQueue::new(3);
+        queue.push(message("a"));
+        queue.push(message("b"));
+        let failed = queue.take_all();
+        queue.push(message("c"));
+        queue.push(message("d"));
+        queue.requeue(failed);
+        assert_eq!(contents(&queue), ["b", "c", "d"]);
+    }
+}
@@ -8,6 +8,11 @@ use axum::http::StatusCode;
 use serde_json::{Value, json};
 use uuid::Uuid;
 
+tokio::task_local! {
+    /// Correlation ID of the request being served, set by `request_id_middleware`.
+    pub static REQUEST_ID: String;
+}
+
 /// Centralized API error type for all handlers.
 /// Logs full details server-side, returns sanitized JSON to the client.
 ///
@@ -45,8 +50,22 @@ pub enum ApiError {
     #[error("Tool timeout: {0}")]
     ToolTimeout(String),
 
-    #[error("Rate limited: {0}")]
-    RateLimited(String),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Unprocessable: {0}")]
+    Unprocessable(String),
+
+    /// 429 — `retry_after_secs` becomes the `Retry-After` header and
+    /// `details.retry_after_secs` when known.
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
 }
 
 /// Structured error response body — serialized inside `{ "error": ... }`.
@@ -74,7 +93,10 @@ impl ApiError {
             ApiError::Unauthorized(_) => "UNAUTHORIZED",
             ApiError::Unavailable(_) => "SERVICE_UNAVAILABLE",
             ApiError::ToolTimeout(_) => "TOOL_TIMEOUT",
-            ApiError::RateLimited(_) => "RATE_LIMITED",
+            ApiError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            ApiError::Conflict(_) => "CONFLICT",
+            ApiError::Unprocessable(_) => "UNPROCESSABLE_ENTITY",
+            ApiError::RateLimited { .. } => "RATE_LIMITED",
         }
     }
 
@@ -88,7 +110,10 @@ impl ApiError {
             ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
             ApiError::ToolTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
-            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 
@@ -102,7 +127,28 @@ impl ApiError {
             ApiError::Unauthorized(m) => m.clone(),
             ApiError::Unavailable(m) => m.clone(),
             ApiError::ToolTimeout(m) => m.clone(),
-            ApiError::RateLimited(m) => m.clone(),
+            ApiError::PayloadTooLarge(m) => m.clone(),
+            ApiError::Conflict(m) => m.clone(),
+            ApiError::Unprocessable(m) => m.clone(),
+            ApiError::RateLimited { message, .. } => message.clone(),
+        }
+    }
+
+    /// Rate-limit rejection telling the client how long to back off.
+    pub fn rate_limited(message: impl Into<String>, retry_after_secs: u64) -> Self {
+        ApiError::RateLimited {
+            message: message.into(),
+            retry_after_secs: Some(retry_after_secs),
+        }
+    }
+
+    /// Seconds the client should wait before retrying, if known.
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            ApiError::RateLimited {
+                retry_after_secs, ..
+            } => *retry_after_secs,
+            _ => None,
         }
     }
 
@@ -114,9 +160,23 @@ impl ApiError {
         }
     }
 
-    /// Extract the request_id from the current tracing span (set by request_id_middleware).
+    /// Status and JSON body without building a response — see
+    /// [`ApiErrorWithDetails::into_parts`].
+    pub fn into_parts(self) -> (StatusCode, Value) {
+        ApiErrorWithDetails {
+            error: self,
+            details: None,
+        }
+        .into_parts()
+    }
+
+    /// Correlation ID of the current request (set by request_id_middleware),
+    /// so error bodies match the `X-Request-Id` header and log lines. Falls
+    /// back to a fresh UUID outside a request (e.g. background tasks).
     pub fn current_request_id() -> String {
-        Uuid::new_v4().to_string()
+        REQUEST_ID
+            .try_with(Clone::clone)
+            .unwrap_or_else(|_| Uuid::new_v4().to_string())
     }
 }
 
@@ -133,15 +193,17 @@ impl axum::response::IntoResponse for ApiError {
             self
         );
 
+        let retry_after = self.retry_after_secs();
+        let details = retry_after.map(|secs| json!({ "retry_after_secs": secs }));
         let body = json!({
             "error": {
                 "code": self.error_code(),
                 "message": self.sanitized_message(),
                 "request_id": request_id,
-                "details": null,
+                "details": details,
             }
         });
-        (status, Json(body)).into_response()
+        with_retry_after((status, Json(body)).into_response(), retry_after)
     }
 }
 
@@ -151,8 +213,10 @@ pub struct ApiErrorWithDetails {
     pub details: Option<Value>,
 }
 
-impl axum::response::IntoResponse for ApiErrorWithDetails {
-    fn into_response(self) -> axum::response::Response {
+impl ApiErrorWithDetails {
+    /// Status and JSON body without building a response — for handlers that
+    /// return `(StatusCode, Json<Value>)` (e.g. to cache the body).
+    pub fn into_parts(self) -> (StatusCode, Value) {
         let status = self.error.status_code();
         let request_id = ApiError::current_request_id();
 
@@ -164,14 +228,203 @@ impl axum::response::IntoResponse for ApiErrorWithDetails {
             self.error
         );
 
+        let retry_after = self.error.retry_after_secs();
+        let mut details = self.details;
+        if let Some(secs) = retry_after {
+            match details {
+                Some(Value::Object(ref mut map)) => {
+                    map.entry("retry_after_secs").or_insert(json!(secs));
+                }
+                None => details = Some(json!({ "retry_after_secs": secs })),
+                Some(_) => {}
+            }
+        }
         let body = json!({
             "error": {
                 "code": self.error.error_code(),
                 "message": self.error.sanitized_message(),
                 "request_id": request_id,
-                "details": self.details,
+                "details": details,
+            }
+        });
+        (status, body)
+    }
+}
+
+impl axum::response::IntoResponse for ApiErrorWithDetails {
+    fn into_response(self) -> axum::response::Response {
+        let retry_after = self.error.retry_after_secs();
+        let (status, body) = self.into_parts();
+        with_retry_after((status, Json(body)).into_response(), retry_after)
+    }
+}
+
+/// `map_response` hook for body-limited routes: replaces the plain-text 413
+/// from `RequestBodyLimitLayer` (or axum's body extractors) with an `ApiError`
+/// body naming the limit.
+pub async fn payload_too_large_json(
+    response: axum::response::Response,
+    limit_bytes: usize,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let is_json = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE || is_json {
+        return response;
+    }
+    ApiError::PayloadTooLarge(format!(
+        "Request body exceeds the {} MB limit",
+        limit_bytes / (1024 * 1024)
+    ))
+    .into_response()
+}
+
+/// Set the `Retry-After` header (seconds) when a back-off is known.
+fn with_retry_after(
+    mut response: axum::response::Response,
+    retry_after_secs: Option<u64>,
+) -> axum::response::Response {
+    if let Some(secs) = retry_after_secs {
+        response
+            .headers_mut()
+            .insert(axum::http::header::RETRY_AFTER, secs.into());
+    }
+    response
+}
+
+/// `GovernorLayer` error handler: rejections become structured
+/// `RATE_LIMITED` errors with `Retry-After`, keeping governor's
+/// `x-ratelimit-*` headers.
+pub fn governor_error_response(err: tower_governor::GovernorError) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    use tower_governor::GovernorError;
+
+    match err {
+        GovernorError::TooManyRequests { wait_time, headers } => {
+            let mut response = ApiError::rate_limited(
+                format!("Too many requests — retry in {}s", wait_time),
+                wait_time,
+            )
+            .into_response();
+            if let Some(headers) = headers {
+                for (name, value) in headers.iter() {
+                    if !response.headers().contains_key(name) {
+                        response.headers_mut().insert(name.clone(), value.clone());
+                    }
+                }
             }
+            response
+        }
+        GovernorError::UnableToExtractKey => {
+            ApiError::Internal("rate limiter could not extract a client key".into()).into_response()
+        }
+        other => other.into_response().map(axum::body::Body::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn rate_limited_sets_retry_after_header_and_details() {
+        let response = ApiError::rate_limited("slow down", 7).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "7");
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "RATE_LIMITED");
+        assert_eq!(body["error"]["details"]["retry_after_secs"], 7);
+    }
+
+    #[tokio::test]
+    async fn over_limit_body_gets_json_error_and_limit_raises_axum_default() {
+        use tower::ServiceExt;
+
+        let router = || {
+            axum::Router::new().route(
+                "/echo",
+                axum::routing::post(|body: axum::Json<Value>| async move { body }),
+            )
+        };
+        let mb = 1024 * 1024;
+        let request = |len: usize| {
+            let body = format!("\"{}\"", "a".repeat(len - 2));
+            axum::http::Request::post("/echo")
+                .header("content-type", "application/json")
+                .header("content-length", body.len())
+                .body(axum::body::Body::from(body))
+                .unwrap()
+        };
+
+        let small = crate::with_body_limit(router(), mb);
+        let response = small.oneshot(request(2 * mb)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(
+            body["error"]["message"],
+            "Request body exceeds the 1 MB limit"
+        );
+
+        // 3 MB is over axum's 2 MB extractor default but under this limit
+        let large = crate::with_body_limit(router(), 4 * mb);
+        let response = large.oneshot(request(3 * mb)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn error_request_id_matches_response_header() {
+        use tower::ServiceExt;
+
+        let app = axum::Router::new()
+            .route(
+                "/fail",
+                axum::routing::get(|| async { ApiError::NotFound("nothing here".into()) }),
+            )
+            .layer(axum::middleware::from_fn(crate::request_id_middleware));
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/fail")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let header = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["request_id"], header);
+    }
+
+    #[test]
+    fn governor_rejection_keeps_ratelimit_headers() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-ratelimit-after", 3u64.into());
+        headers.insert("retry-after", 3u64.into());
+        let response = governor_error_response(tower_governor::GovernorError::TooManyRequests {
+            wait_time: 3,
+            headers: Some(headers),
         });
-        (status, Json(body)).into_response()
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "3");
+        assert_eq!(response.headers()["x-ratelimit-after"], "3");
     }
 }
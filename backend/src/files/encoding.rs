@@ -0,0 +1,113 @@
+// backend/src/files/encoding.rs
+//! Text decoding for files that are not plain UTF-8.
+//!
+//! A BOM wins (UTF-8 / UTF-16LE / UTF-16BE, BOM stripped). Valid UTF-8 is
+//! passed through. Anything else goes through `chardetng` — biased toward
+//! Central European encodings, since most legacy files here are Windows-1250 —
+//! and is transcoded to UTF-8 with `encoding_rs`.
+
+use encoding_rs::{Encoding, UTF_8};
+
+/// Decoded file text plus the source encoding when it was not UTF-8.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedText {
+    pub text: String,
+    /// `Some("windows-1250")`, `Some("UTF-16LE")`, ... — `None` for UTF-8.
+    pub encoding: Option<&'static str>,
+}
+
+/// Whether `bytes` starts with a UTF-16 byte-order mark. Such files contain
+/// NUL bytes, so they must bypass the binary sniff.
+pub fn has_utf16_bom(bytes: &[u8]) -> bool {
+    matches!(Encoding::for_bom(bytes), Some((enc, _)) if enc != UTF_8)
+}
+
+/// Decode `bytes` to UTF-8. `complete` is false when `bytes` is only the head
+/// of a larger file, so a multi-byte character cut at the end is not an error.
+pub fn decode_to_utf8(bytes: &[u8], complete: bool) -> DecodedText {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return DecodedText {
+            text: text.into_owned(),
+            encoding: (encoding != UTF_8).then(|| encoding.name()),
+        };
+    }
+
+    let is_utf8 = match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => !complete && e.error_len().is_none(),
+    };
+    if is_utf8 {
+        return DecodedText {
+            text: String::from_utf8_lossy(bytes).into_owned(),
+            encoding: None,
+        };
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, complete);
+    let encoding = detector.guess(Some(b"pl"), false);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    DecodedText {
+        text: text.into_owned(),
+        encoding: Some(encoding.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLISH: &str = "Zażółć gęślą jaźń — ĄĆĘŁŃÓŚŹŻ";
+
+    #[test]
+    fn utf8_passes_through_and_bom_is_stripped() {
+        let plain = decode_to_utf8(POLISH.as_bytes(), true);
+        assert_eq!(plain.text, POLISH);
+        assert_eq!(plain.encoding, None);
+
+        let mut with_bom = vec![0xEF, 0xBB, 0xBF];
+        with_bom.extend_from_slice(POLISH.as_bytes());
+        let decoded = decode_to_utf8(&with_bom, true);
+        assert_eq!(decoded.text, POLISH);
+        assert_eq!(decoded.encoding, None);
+    }
+
+    #[test]
+    fn utf16_with_bom_round_trips_polish_diacritics() {
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(POLISH.encode_utf16().flat_map(u16::to_le_bytes));
+        assert!(has_utf16_bom(&le));
+        let decoded = decode_to_utf8(&le, true);
+        assert_eq!(decoded.text, POLISH);
+        assert_eq!(decoded.encoding, Some("UTF-16LE"));
+
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(POLISH.encode_utf16().flat_map(u16::to_be_bytes));
+        let decoded = decode_to_utf8(&be, true);
+        assert_eq!(decoded.text, POLISH);
+        assert_eq!(decoded.encoding, Some("UTF-16BE"));
+    }
+
+    #[test]
+    fn windows_1250_is_detected_and_transcoded() {
+        let source = "// Obsługa błędów połączenia z bazą danych\n\
+                      fn połącz() { println!(\"Nie można nawiązać połączenia, spróbuj ponownie\"); }\n";
+        let (bytes, _, unmappable) = encoding_rs::WINDOWS_1250.encode(source);
+        assert!(!unmappable);
+        assert!(std::str::from_utf8(&bytes).is_err());
+
+        let decoded = decode_to_utf8(&bytes, true);
+        assert_eq!(decoded.encoding, Some("windows-1250"));
+        assert_eq!(decoded.text, source);
+    }
+
+    #[test]
+    fn utf8_cut_mid_character_is_not_reencoded() {
+        let bytes = POLISH.as_bytes();
+        let cut = &bytes[..POLISH.find('ż').unwrap() + 1];
+        let decoded = decode_to_utf8(cut, false);
+        assert_eq!(decoded.encoding, None);
+        assert!(decoded.text.starts_with("Za"));
+    }
+}
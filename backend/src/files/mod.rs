@@ -2,12 +2,16 @@
 //! File system access module for GeminiHydra v15.
 //!
 //! Sub-modules:
-//! - `validator` — path sanitization, text-file detection, security checks
+//! - `validator` — path sanitization, text-file and binary detection, security checks
+//! - `encoding` — BOM stripping, charset detection and transcoding to UTF-8
 //!
 //! This file: context building, file reading, directory listing, file writing.
 
+pub mod encoding;
 pub mod validator;
-pub use validator::{is_text_extension, is_text_file, validate_and_canonicalize};
+pub use validator::{
+    guess_binary_type, is_text_extension, is_text_file, looks_binary, validate_and_canonicalize,
+};
 
 use regex::Regex;
 use std::path::Path;
@@ -19,7 +23,7 @@ use tokio::io::AsyncReadExt;
 // Constants
 // ---------------------------------------------------------------------------
 
-/// Max bytes per single file (100 KB).
+/// Max bytes per single file (100 KB) — pro-tier default, see `FileContextBudget`.
 const MAX_FILE_SIZE: u64 = 100 * 1024;
 
 /// Max total context bytes across all files (500 KB).
@@ -28,6 +32,16 @@ const MAX_TOTAL_SIZE: usize = 500 * 1024;
 /// Max number of files to include in context.
 const MAX_FILES: usize = 10;
 
+/// Token budget the `MAX_*` limits above are sized for (a pro-tier model).
+const REFERENCE_TOKEN_BUDGET: i32 = 65536;
+
+/// Leading bytes inspected by the binary sniff.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Share of non-text bytes above which a non-UTF-8 file counts as binary
+/// (override with `BINARY_NON_TEXT_RATIO`).
+const DEFAULT_BINARY_NON_TEXT_RATIO: f64 = 0.30;
+
 /// Path prefixes that are blocked for reading (sensitive system directories).
 const BLOCKED_READ_PREFIXES: &[&str] = &[
     "/etc/shadow",
@@ -49,6 +63,8 @@ pub struct FileContext {
     pub size_bytes: u64,
     pub truncated: bool,
     pub extension: String,
+    /// Source encoding when the file was transcoded (e.g. `windows-1250`).
+    pub encoding: Option<&'static str>,
 }
 
 /// Errors that can occur when reading a file.
@@ -135,12 +151,57 @@ pub fn extract_file_paths(prompt: &str) -> Vec<String> {
     paths
 }
 
+// ---------------------------------------------------------------------------
+// Auto-load budget
+// ---------------------------------------------------------------------------
+
+/// Size limits for auto-loaded file context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileContextBudget {
+    pub max_file_bytes: u64,
+    pub max_total_bytes: usize,
+    pub max_files: usize,
+}
+
+impl Default for FileContextBudget {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: MAX_FILE_SIZE,
+            max_total_bytes: MAX_TOTAL_SIZE,
+            max_files: MAX_FILES,
+        }
+    }
+}
+
+impl FileContextBudget {
+    /// Limits scaled to a model's token budget (`tier_token_budget`): a pro
+    /// model gets the full defaults, a flash model an eighth of the bytes
+    /// and fewer files, leaving the rest to `read_file` and friends.
+    pub fn for_token_budget(token_budget: i32) -> Self {
+        let full = Self::default();
+        let ratio = (token_budget.max(1) as f64 / REFERENCE_TOKEN_BUDGET as f64).min(1.0);
+        Self {
+            max_file_bytes: ((full.max_file_bytes as f64 * ratio) as u64).max(8 * 1024),
+            max_total_bytes: ((full.max_total_bytes as f64 * ratio) as usize).max(32 * 1024),
+            max_files: ((full.max_files as f64 * ratio).round() as usize).clamp(3, full.max_files),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // File reading
 // ---------------------------------------------------------------------------
 
 /// Read a single file for context injection.
 pub async fn read_file_for_context(path: &str) -> Result<FileContext, FileError> {
+    read_file_for_context_limited(path, MAX_FILE_SIZE).await
+}
+
+/// [`read_file_for_context`] with a custom per-file size cap.
+async fn read_file_for_context_limited(
+    path: &str,
+    max_file_bytes: u64,
+) -> Result<FileContext, FileError> {
     // Canonicalize path BEFORE any checks to prevent traversal attacks
     let canonical = validate_and_canonicalize(path, BLOCKED_READ_PREFIXES)?;
 
@@ -151,13 +212,6 @@ pub async fn read_file_for_context(path: &str) -> Result<FileContext, FileError>
         .unwrap_or("")
         .to_lowercase();
 
-    if !is_text_file(&canonical) {
-        return Err(FileError {
-            path: path.to_string(),
-            reason: format!("'{}' is not recognized as a text file", canonical.display()),
-        });
-    }
-
     // Check file exists and size
     let metadata = tokio::fs::metadata(&canonical)
         .await
@@ -180,7 +234,7 @@ pub async fn read_file_for_context(path: &str) -> Result<FileContext, FileError>
     })?;
 
     // Read full file (up to a generous limit for smart truncation)
-    let read_limit = (MAX_FILE_SIZE * 2) as usize; // read more so we can grab the tail
+    let read_limit = (max_file_bytes * 2) as usize; // read more so we can grab the tail
     let mut buffer = Vec::with_capacity(read_limit.min(file_size as usize) + 1);
     file.take(read_limit as u64)
         .read_to_end(&mut buffer)
@@ -190,11 +244,34 @@ pub async fn read_file_for_context(path: &str) -> Result<FileContext, FileError>
             reason: format!("Cannot read file: {}", e),
         })?;
 
-    let limit = MAX_FILE_SIZE as usize;
-    let truncated = file_size > MAX_FILE_SIZE;
+    // Binary sniff runs before the extension whitelist so images/PDFs get a
+    // pointer to the right tool instead of a bare rejection.
+    let sample = &buffer[..buffer.len().min(BINARY_SNIFF_BYTES)];
+    // UTF-16 text is full of NUL bytes — a BOM exempts it from the sniff.
+    if !encoding::has_utf16_bom(&buffer) && looks_binary(sample, binary_non_text_ratio()) {
+        return Ok(FileContext {
+            path: path.to_string(),
+            content: binary_file_notice(&canonical, file_size, sample),
+            size_bytes: file_size,
+            truncated: false,
+            extension: ext,
+            encoding: None,
+        });
+    }
+
+    if !is_text_file(&canonical) {
+        return Err(FileError {
+            path: path.to_string(),
+            reason: format!("'{}' is not recognized as a text file", canonical.display()),
+        });
+    }
+
+    let limit = max_file_bytes as usize;
+    let truncated = file_size > max_file_bytes;
 
-    // Convert to string (lossy to avoid UTF-8 errors on cut boundaries)
-    let raw = String::from_utf8_lossy(&buffer).to_string();
+    // Strip BOM and transcode legacy encodings (UTF-16, Windows-1250, ...) to UTF-8
+    let decoded = encoding::decode_to_utf8(&buffer, buffer.len() as u64 >= file_size);
+    let raw = decoded.text;
 
     let content = if truncated {
         // Smart truncation: keep first 30% + last 10% + middle marker
@@ -252,9 +329,44 @@ pub async fn read_file_for_context(path: &str) -> Result<FileContext, FileError>
         size_bytes: file_size,
         truncated,
         extension: ext,
+        encoding: decoded.encoding,
     })
 }
 
+fn binary_non_text_ratio() -> f64 {
+    static RATIO: OnceLock<f64> = OnceLock::new();
+    *RATIO.get_or_init(|| {
+        std::env::var("BINARY_NON_TEXT_RATIO")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|r: &f64| *r > 0.0 && *r <= 1.0)
+            .unwrap_or(DEFAULT_BINARY_NON_TEXT_RATIO)
+    })
+}
+
+/// Short placeholder returned instead of a binary file's content.
+fn binary_file_notice(path: &Path, size_bytes: u64, sample: &[u8]) -> String {
+    let (kind, tool) = guess_binary_type(sample);
+    let size = if size_bytes < 1024 {
+        format!("{} B", size_bytes)
+    } else if size_bytes < 1024 * 1024 {
+        format!("{:.1} KB", size_bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", size_bytes as f64 / (1024.0 * 1024.0))
+    };
+    let hint = match tool {
+        Some(tool) => format!(" Use `{}` to inspect it.", tool),
+        None => String::new(),
+    };
+    format!(
+        "[Binary file '{}' — {}, {}. Content not shown.{}]",
+        path.display(),
+        kind,
+        size,
+        hint
+    )
+}
+
 /// Read a file and return its full content plus metadata (for the /api/files/read endpoint).
 pub async fn read_file_raw(path: &str) -> Result<FileContext, FileError> {
     read_file_for_context(path).await
@@ -284,6 +396,7 @@ const KEY_PROJECT_FILES: &[&str] = &[
 async fn build_directory_context(
     dir_path: &str,
     total_size: &mut usize,
+    budget: FileContextBudget,
 ) -> Result<(String, Vec<FileContext>), FileError> {
     let entries = list_directory(dir_path, false).await?;
 
@@ -303,7 +416,9 @@ async fn build_directory_context(
     for (idx, key_name) in KEY_PROJECT_FILES.iter().enumerate() {
         let full_path = format!("{}\\{}", dir_path.trim_end_matches('\\'), key_name);
         set.spawn(async move {
-            let result = read_file_for_context(&full_path).await.ok();
+            let result = read_file_for_context_limited(&full_path, budget.max_file_bytes)
+                .await
+                .ok();
             (idx, result)
         });
     }
@@ -318,7 +433,9 @@ async fn build_directory_context(
     // Apply budget filter sequentially (preserves priority ordering)
     let mut key_files: Vec<FileContext> = Vec::new();
     for (_idx, result) in file_results {
-        if let Some(fc) = result.filter(|fc| *total_size + fc.content.len() <= MAX_TOTAL_SIZE) {
+        if let Some(fc) =
+            result.filter(|fc| *total_size + fc.content.len() <= budget.max_total_bytes)
+        {
             *total_size += fc.content.len();
             key_files.push(fc);
         }
@@ -331,8 +448,11 @@ async fn build_directory_context(
 ///
 /// Returns `(context_string, errors)` where `context_string` is the formatted
 /// block ready to prepend to the user prompt, and `errors` lists any paths
-/// that could not be read.
-pub async fn build_file_context(paths: &[String]) -> (String, Vec<FileError>) {
+/// that could not be read. `budget` caps per-file, total and item counts.
+pub async fn build_file_context(
+    paths: &[String],
+    budget: FileContextBudget,
+) -> (String, Vec<FileError>) {
     let mut files: Vec<FileContext> = Vec::new();
     let mut dir_listings: Vec<String> = Vec::new();
     let mut errors: Vec<FileError> = Vec::new();
@@ -340,10 +460,10 @@ pub async fn build_file_context(paths: &[String]) -> (String, Vec<FileError>) {
     let mut item_count: usize = 0;
 
     for path in paths.iter() {
-        if item_count >= MAX_FILES {
+        if item_count >= budget.max_files {
             errors.push(FileError {
                 path: path.clone(),
-                reason: format!("Skipped — max {} items per request", MAX_FILES),
+                reason: format!("Skipped — max {} items per request", budget.max_files),
             });
             continue;
         }
@@ -352,7 +472,7 @@ pub async fn build_file_context(paths: &[String]) -> (String, Vec<FileError>) {
 
         if p.is_dir() {
             // Handle directory: listing + key files
-            match build_directory_context(path, &mut total_size).await {
+            match build_directory_context(path, &mut total_size, budget).await {
                 Ok((listing, key_files)) => {
                     dir_listings.push(listing);
                     item_count += 1;
@@ -365,15 +485,15 @@ pub async fn build_file_context(paths: &[String]) -> (String, Vec<FileError>) {
             }
         } else {
             // Handle file
-            match read_file_for_context(path).await {
+            match read_file_for_context_limited(path, budget.max_file_bytes).await {
                 Ok(fc) => {
                     let content_len = fc.content.len();
-                    if total_size + content_len > MAX_TOTAL_SIZE {
+                    if total_size + content_len > budget.max_total_bytes {
                         errors.push(FileError {
                             path: path.clone(),
                             reason: format!(
                                 "Skipped — would exceed total context limit of {}KB",
-                                MAX_TOTAL_SIZE / 1024
+                                budget.max_total_bytes / 1024
                             ),
                         });
                         continue;
@@ -428,10 +548,13 @@ pub async fn build_file_context(paths: &[String]) -> (String, Vec<FileError>) {
         if fc.truncated {
             ctx.push_str(&format!(
                 "_Truncated: showing first ~{}KB of {}KB_\n",
-                MAX_FILE_SIZE / 1024,
+                budget.max_file_bytes / 1024,
                 fc.size_bytes / 1024
             ));
         }
+        if let Some(encoding) = fc.encoding {
+            ctx.push_str(&format!("_Encoding: {} (converted to UTF-8)_\n", encoding));
+        }
         ctx.push_str(&format!("```{}\n{}\n```\n\n", lang_hint, fc.content));
     }
 
@@ -531,8 +654,12 @@ async fn list_directory_inner(path: &str, show_hidden: bool) -> Result<Vec<FileE
 // File writing (for tool calling)
 // ---------------------------------------------------------------------------
 
-/// Max content size for write_file (1 MB).
-const MAX_WRITE_SIZE: usize = 1024 * 1024;
+/// Default cap on content written by write_file/edit_file (5 MB); the
+/// `max_write_bytes` setting overrides it.
+pub const DEFAULT_MAX_WRITE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Upper bound accepted for the `max_write_bytes` setting (50 MB).
+pub const MAX_WRITE_BYTES_CAP: usize = 50 * 1024 * 1024;
 
 /// Path prefixes that are blocked for writing.
 const BLOCKED_WRITE_PREFIXES: &[&str] = &[
@@ -551,18 +678,24 @@ pub fn validate_write_path(path: &str) -> Result<std::path::PathBuf, FileError>
     validate_and_canonicalize(path, BLOCKED_WRITE_PREFIXES)
 }
 
-/// Write content to a file with safety checks.
-pub async fn write_file(path: &str, content: &str) -> Result<String, FileError> {
-    if content.len() > MAX_WRITE_SIZE {
+/// Reject content over the write cap before anything touches the disk.
+pub fn check_write_size(path: &str, len: usize, max_bytes: usize) -> Result<(), FileError> {
+    if len > max_bytes {
         return Err(FileError {
             path: path.to_string(),
             reason: format!(
-                "Content too large: {} bytes (max {} bytes)",
-                content.len(),
-                MAX_WRITE_SIZE
+                "Content too large: {} bytes exceeds the write limit of {} bytes \
+                 (max_write_bytes setting)",
+                len, max_bytes
             ),
         });
     }
+    Ok(())
+}
+
+/// Write content to a file with safety checks.
+pub async fn write_file(path: &str, content: &str, max_bytes: usize) -> Result<String, FileError> {
+    check_write_size(path, content.len(), max_bytes)?;
 
     // Ensure parent directory exists BEFORE canonicalization (so parent can be resolved)
     if let Some(parent) = Path::new(path)
@@ -603,6 +736,21 @@ pub async fn write_file(path: &str, content: &str) -> Result<String, FileError>
 mod tests {
     use super::*;
 
+    #[test]
+    fn file_context_budget_scales_with_model_tier() {
+        let pro = FileContextBudget::for_token_budget(65536);
+        assert_eq!(pro, FileContextBudget::default());
+
+        let flash = FileContextBudget::for_token_budget(8192);
+        assert_eq!(flash.max_file_bytes, MAX_FILE_SIZE / 8);
+        assert_eq!(flash.max_total_bytes, MAX_TOTAL_SIZE / 8);
+        assert_eq!(flash.max_files, 3);
+
+        let mid = FileContextBudget::for_token_budget(32768);
+        assert!(mid.max_total_bytes < pro.max_total_bytes);
+        assert!(mid.max_total_bytes > flash.max_total_bytes);
+    }
+
     #[test]
     fn test_extract_windows_path() {
         let prompt = r"Odczytaj plik C:\Users\BIURODOM\Desktop\GeminiHydra-v15\package.json";
@@ -741,4 +889,92 @@ mod tests {
             reason
         );
     }
+
+    #[test]
+    fn test_binary_sniff_detects_nul_and_garbage() {
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", 0.30));
+        let garbage: Vec<u8> = (0..2048u32).map(|i| (i * 97 % 251) as u8 | 0x80).collect();
+        assert!(looks_binary(&garbage, 0.30));
+        assert!(!looks_binary(b"", 0.30));
+    }
+
+    #[test]
+    fn test_binary_sniff_never_flags_utf8_text() {
+        let source = "fn main() {\n\tprintln!(\"Zażółć gęślą jaźń — 日本語 🦀\");\n}\n\x1b[0m\r\n";
+        assert!(!looks_binary(source.as_bytes(), 0.0));
+        // Multi-byte character cut at the end of the sniff window
+        let cut = &source.as_bytes()[..source.find('ż').unwrap() + 1];
+        assert!(!looks_binary(cut, 0.0));
+        // Legacy 8-bit text (CP1250 "Zażółć") stays under the default ratio
+        assert!(!looks_binary(
+            b"Za\xbf\xf3\xb3\xe6 gesla jazn, plain Polish text line\n",
+            0.30
+        ));
+    }
+
+    #[test]
+    fn test_guess_binary_type_suggests_tools() {
+        assert_eq!(
+            guess_binary_type(b"\x89PNG\r\n\x1a\n"),
+            ("PNG image", Some("analyze_image"))
+        );
+        assert_eq!(
+            guess_binary_type(b"%PDF-1.7"),
+            ("PDF document", Some("read_pdf"))
+        );
+        assert_eq!(guess_binary_type(b"\x7fELF\x02"), ("ELF executable", None));
+        assert_eq!(guess_binary_type(b"\x01\x02"), ("binary data", None));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_for_context_returns_notice_for_binary() {
+        let dir = std::env::temp_dir().join(format!("gh-binary-sniff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.log");
+        std::fs::write(&path, b"%PDF-1.7\n\0\0binary").unwrap();
+
+        let ctx = read_file_for_context(path.to_str().unwrap()).await.unwrap();
+        assert!(ctx.content.starts_with("[Binary file"));
+        assert!(ctx.content.contains("PDF document"));
+        assert!(ctx.content.contains("read_pdf"));
+        assert!(!ctx.content.contains("binary\u{0}"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_write_file_enforces_max_bytes() {
+        let dir = std::env::temp_dir().join(format!("gh-write-cap-{}", std::process::id()));
+        let path = dir.join("blob.txt");
+        let path_str = path.to_str().unwrap();
+
+        let err = write_file(path_str, &"x".repeat(2048), 1024)
+            .await
+            .unwrap_err();
+        assert!(err.reason.contains("2048 bytes"));
+        assert!(err.reason.contains("max_write_bytes"));
+        assert!(!path.exists());
+
+        write_file(path_str, &"x".repeat(1024), 1024).await.unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 1024);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_read_file_for_context_transcodes_utf16() {
+        let dir = std::env::temp_dir().join(format!("gh-encoding-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notatki.txt");
+        let text = "Zażółć gęślą jaźń\r\n";
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        std::fs::write(&path, bytes).unwrap();
+
+        let ctx = read_file_for_context(path.to_str().unwrap()).await.unwrap();
+        assert_eq!(ctx.content, text);
+        assert_eq!(ctx.encoding, Some("UTF-16LE"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -132,6 +132,71 @@ pub fn is_text_file(path: &Path) -> bool {
     false
 }
 
+/// Heuristic binary sniff over the first few KB of a file.
+///
+/// A NUL byte means binary. Otherwise valid UTF-8 is always text (a multi-byte
+/// character cut at the end of the sample is fine); for anything else the
+/// share of invalid-UTF-8 and control bytes must exceed `max_non_text_ratio`,
+/// so legacy 8-bit encodings (Latin-1, CP1250) still read as text.
+pub fn looks_binary(sample: &[u8], max_non_text_ratio: f64) -> bool {
+    if sample.is_empty() {
+        return false;
+    }
+    if sample.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => return false,
+        Err(e) if e.error_len().is_none() => return false,
+        Err(_) => {}
+    }
+
+    let non_text: usize = sample
+        .utf8_chunks()
+        .map(|chunk| {
+            let control = chunk
+                .valid()
+                .chars()
+                .filter(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+                .count();
+            chunk.invalid().len() + control
+        })
+        .sum();
+    non_text as f64 / sample.len() as f64 > max_non_text_ratio
+}
+
+/// Best-effort type guess from magic bytes: `(description, suggested tool)`.
+pub fn guess_binary_type(sample: &[u8]) -> (&'static str, Option<&'static str>) {
+    match sample {
+        [0x89, b'P', b'N', b'G', ..] => ("PNG image", Some("analyze_image")),
+        [0xFF, 0xD8, 0xFF, ..] => ("JPEG image", Some("analyze_image")),
+        [b'G', b'I', b'F', b'8', ..] => ("GIF image", Some("analyze_image")),
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => ("WebP image", Some("analyze_image")),
+        [b'%', b'P', b'D', b'F', ..] => ("PDF document", Some("read_pdf")),
+        [b'P', b'K', 0x03, 0x04, ..] => ("ZIP archive (or Office/JAR container)", None),
+        [0x1F, 0x8B, ..] => ("gzip archive", None),
+        [0x7F, b'E', b'L', b'F', ..] => ("ELF executable", None),
+        [b'M', b'Z', ..] => ("Windows executable", None),
+        [0x00, b'a', b's', b'm', ..] => ("WebAssembly module", None),
+        _ if sample.starts_with(b"SQLite format 3\0") => ("SQLite database", None),
+        _ => ("binary data", None),
+    }
+}
+
 pub fn validate_and_canonicalize(
     raw_path: &str,
     blocked_prefixes: &[&str],
@@ -1,31 +1,70 @@
 // ---------------------------------------------------------------------------
-// handlers/agents.rs — Agent CRUD + classification endpoints
+// handlers/agents.rs — Agent CRUD + classification + prompt history endpoints
 // ---------------------------------------------------------------------------
 
 use axum::Json;
 use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
 use axum::response::IntoResponse;
 use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
 
-use crate::models::{ClassifyRequest, ClassifyResponse, WitcherAgent};
+use crate::error::ApiError;
+use crate::models::{
+    AgentClassifyScore, AgentPromptVersion, AgentRatingBreakdown, ClassifyDebugResponse,
+    ClassifyRequest, ClassifyResponse, LowRatedMessage, RatingBucket, RatingTrendPoint,
+    ReorderAgentsRequest, WitcherAgent,
+};
 use crate::state::AppState;
 
-use crate::classify::classify_prompt;
+use crate::classify::{
+    ClassificationConfig, classify_agent_score, classify_prompt, matched_keywords, strip_diacritics,
+};
 
 // ---------------------------------------------------------------------------
 // Agent List & Classification
 // ---------------------------------------------------------------------------
 
 #[utoipa::path(get, path = "/api/agents", tag = "agents",
-    responses((status = 200, description = "List of configured agents", body = Value))
-)]
-pub async fn list_agents(State(state): State<AppState>) -> impl IntoResponse {
-    let agents = state.agents.read().await;
-    // #6 — Cache agent list for 60 seconds
-    (
-        [(axum::http::header::CACHE_CONTROL, "public, max-age=60")],
-        Json(json!({ "agents": *agents })),
+    responses(
+        (status = 200, description = "List of configured agents", body = Value),
+        (status = 304, description = "If-None-Match matches the current list")
     )
+)]
+pub async fn list_agents(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    let body = json!({ "agents": *state.agents.read().await });
+    let etag = agents_etag(&body);
+    // #6 — Cache agent list for 60 seconds, then revalidate with the ETag
+    let cache_headers = [
+        (header::CACHE_CONTROL, "public, max-age=60"),
+        (header::ETAG, etag.as_str()),
+    ];
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+    if etag_matches(if_none_match, &etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (cache_headers, Json(body)).into_response()
+}
+
+/// Weak ETag over the serialized roster, so any change — an edit, a reorder,
+/// a reload from the DB — yields a new tag.
+pub(crate) fn agents_etag(body: &Value) -> String {
+    let digest = Sha256::digest(body.to_string().as_bytes());
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("W/\"{}\"", hex)
+}
+
+/// `If-None-Match` check with weak comparison: `*` or any listed tag equal to
+/// `etag` once `W/` prefixes are ignored.
+pub(crate) fn etag_matches(if_none_match: Option<&str>, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match.is_some_and(|header| {
+        header
+            .split(',')
+            .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+    })
 }
 
 #[utoipa::path(post, path = "/api/agents/classify", tag = "agents",
@@ -37,7 +76,8 @@ pub async fn classify_agent(
     Json(body): Json<ClassifyRequest>,
 ) -> Json<ClassifyResponse> {
     let agents = state.agents.read().await;
-    let (agent_id, confidence, reasoning) = classify_prompt(&body.prompt, &agents);
+    let (agent_id, confidence, reasoning) =
+        classify_prompt(&body.prompt, &agents, &state.classification);
     Json(ClassifyResponse {
         agent: agent_id,
         confidence,
@@ -45,6 +85,49 @@ pub async fn classify_agent(
     })
 }
 
+/// Routing diagnostics: every agent's keyword score and matches, plus whether
+/// the Gemini Flash fallback would run. Does not call Gemini.
+#[utoipa::path(post, path = "/api/agents/classify/debug", tag = "agents",
+    request_body = ClassifyRequest,
+    responses((status = 200, description = "Per-agent classification scores", body = ClassifyDebugResponse))
+)]
+pub async fn classify_agent_debug(
+    State(state): State<AppState>,
+    Json(body): Json<ClassifyRequest>,
+) -> Json<ClassifyDebugResponse> {
+    let agents = state.agents.read().await;
+    Json(classify_debug(&body.prompt, &agents, &state.classification))
+}
+
+pub(crate) fn classify_debug(
+    prompt: &str,
+    agents: &[WitcherAgent],
+    config: &ClassificationConfig,
+) -> ClassifyDebugResponse {
+    let (agent, confidence, reasoning) = classify_prompt(prompt, agents, config);
+    let lower = strip_diacritics(&prompt.to_lowercase());
+    let mut scores: Vec<AgentClassifyScore> = agents
+        .iter()
+        .map(|a| AgentClassifyScore {
+            agent: a.id.clone(),
+            score: classify_agent_score(&lower, a, config),
+            matched_keywords: matched_keywords(&lower, a)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        })
+        .collect();
+    scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ClassifyDebugResponse {
+        agent,
+        confidence,
+        reasoning,
+        gemini_fallback: config.wants_gemini_fallback(confidence),
+        gemini_fallback_threshold: config.fallback_threshold,
+        scores,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Agent CRUD
 // ---------------------------------------------------------------------------
@@ -58,8 +141,8 @@ pub async fn create_agent(
     Json(agent): Json<WitcherAgent>,
 ) -> Json<Value> {
     let _ = sqlx::query(
-        "INSERT INTO gh_agents (id, name, role, tier, status, description, system_prompt, keywords, temperature) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        "INSERT INTO gh_agents (id, name, role, tier, status, description, system_prompt, keywords, temperature, display_order) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, (SELECT COALESCE(MAX(display_order), 0) + 1 FROM gh_agents))"
     )
     .bind(&agent.id)
     .bind(&agent.name)
@@ -80,14 +163,21 @@ pub async fn create_agent(
 #[utoipa::path(post, path = "/api/agents/{id}", tag = "agents",
     params(("id" = String, Path, description = "Agent ID")),
     request_body = WitcherAgent,
-    responses((status = 200, description = "Agent updated", body = Value))
+    responses(
+        (status = 200, description = "Agent updated", body = Value),
+        (status = 404, description = "Agent not found")
+    )
 )]
 pub async fn update_agent(
     State(state): State<AppState>,
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(agent): Json<WitcherAgent>,
-) -> Json<Value> {
-    let _ = sqlx::query(
+) -> Result<Json<Value>, ApiError> {
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    if !save_prompt_version(&mut tx, &id, agent.system_prompt.as_deref()).await? {
+        return Err(ApiError::NotFound(format!("Agent '{}' not found", id)));
+    }
+    sqlx::query(
         "UPDATE gh_agents SET name=$1, role=$2, tier=$3, status=$4, description=$5, system_prompt=$6, keywords=$7, temperature=$8, updated_at=NOW() \
          WHERE id=$9"
     )
@@ -99,12 +189,52 @@ pub async fn update_agent(
     .bind(&agent.system_prompt)
     .bind(&agent.keywords)
     .bind(agent.temperature)
-    .bind(id)
-    .execute(&state.db)
-    .await;
+    .bind(&id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
 
     state.refresh_agents().await;
-    Json(json!({ "success": true }))
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Lock the agent row and, when `new_prompt` differs from the stored prompt,
+/// save the stored one as the agent's next prompt version. The caller updates
+/// the row in the same transaction. Returns `false` if the agent doesn't exist.
+async fn save_prompt_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: &str,
+    new_prompt: Option<&str>,
+) -> Result<bool, ApiError> {
+    let Some(current) = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT system_prompt FROM gh_agents WHERE id=$1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(db_error)?
+    else {
+        return Ok(false);
+    };
+
+    if current.as_deref() != new_prompt {
+        sqlx::query(
+            "INSERT INTO gh_agent_prompt_versions (agent_id, version, system_prompt) \
+             SELECT $1, COALESCE(MAX(version), 0) + 1, $2 \
+             FROM gh_agent_prompt_versions WHERE agent_id=$1",
+        )
+        .bind(id)
+        .bind(&current)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_error)?;
+    }
+    Ok(true)
+}
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    ApiError::Internal(e.to_string())
 }
 
 #[utoipa::path(delete, path = "/api/agents/{id}", tag = "agents",
@@ -132,3 +262,242 @@ pub async fn delete_agent(
 
     Json(json!({ "success": true }))
 }
+
+#[utoipa::path(post, path = "/api/agents/reorder", tag = "agents",
+    request_body = ReorderAgentsRequest,
+    responses(
+        (status = 200, description = "Agent ids in the new roster order", body = Value),
+        (status = 400, description = "Unknown or duplicate agent id")
+    )
+)]
+pub async fn reorder_agents(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    Json(body): Json<ReorderAgentsRequest>,
+) -> Result<Json<Value>, ApiError> {
+    {
+        let agents = state.agents.read().await;
+        let mut seen = std::collections::HashSet::new();
+        for id in &body.ids {
+            if !agents.iter().any(|a| &a.id == id) {
+                return Err(ApiError::BadRequest(format!("Unknown agent '{}'", id)));
+            }
+            if !seen.insert(id.as_str()) {
+                return Err(ApiError::BadRequest(format!("Duplicate agent '{}'", id)));
+            }
+        }
+    }
+
+    sqlx::query(
+        "UPDATE gh_agents a SET display_order = o.pos \
+         FROM (SELECT id, ROW_NUMBER() OVER ( \
+             ORDER BY array_position($1::text[], id) NULLS LAST, display_order, created_at) AS pos \
+             FROM gh_agents) o \
+         WHERE a.id = o.id",
+    )
+    .bind(&body.ids)
+    .execute(&state.db)
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+    state.refresh_agents().await;
+
+    crate::audit::log_audit(
+        &state.db,
+        "reorder_agents",
+        json!({ "ids": body.ids }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
+
+    let order: Vec<String> = state
+        .agents
+        .read()
+        .await
+        .iter()
+        .map(|a| a.id.clone())
+        .collect();
+    Ok(Json(json!({ "success": true, "order": order })))
+}
+
+// ---------------------------------------------------------------------------
+// Agent system-prompt history
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(get, path = "/api/agents/{id}/prompt-versions", tag = "agents",
+    params(("id" = String, Path, description = "Agent ID")),
+    responses(
+        (status = 200, description = "Previous system prompts, newest first", body = Vec<AgentPromptVersion>),
+        (status = 404, description = "Agent not found")
+    )
+)]
+pub async fn list_prompt_versions(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<AgentPromptVersion>>, ApiError> {
+    if !state.agents.read().await.iter().any(|a| a.id == id) {
+        return Err(ApiError::NotFound(format!("Agent '{}' not found", id)));
+    }
+
+    let versions = sqlx::query_as::<_, AgentPromptVersion>(
+        "SELECT version, system_prompt, created_at FROM gh_agent_prompt_versions \
+         WHERE agent_id=$1 ORDER BY version DESC",
+    )
+    .bind(&id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(Json(versions))
+}
+
+#[utoipa::path(post, path = "/api/agents/{id}/prompt-versions/{version}/restore", tag = "agents",
+    params(
+        ("id" = String, Path, description = "Agent ID"),
+        ("version" = i32, Path, description = "Prompt version to restore")
+    ),
+    responses(
+        (status = 200, description = "Prompt restored", body = Value),
+        (status = 404, description = "Agent or version not found")
+    )
+)]
+pub async fn restore_prompt_version(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    axum::extract::Path((id, version)): axum::extract::Path<(String, i32)>,
+) -> Result<Json<Value>, ApiError> {
+    let target = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT system_prompt FROM gh_agent_prompt_versions WHERE agent_id=$1 AND version=$2",
+    )
+    .bind(&id)
+    .bind(version)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?
+    .ok_or_else(|| {
+        ApiError::NotFound(format!(
+            "Prompt version {} of agent '{}' not found",
+            version, id
+        ))
+    })?;
+
+    // The prompt being replaced is itself saved, so a restore can be undone.
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    if !save_prompt_version(&mut tx, &id, target.as_deref()).await? {
+        return Err(ApiError::NotFound(format!("Agent '{}' not found", id)));
+    }
+    sqlx::query("UPDATE gh_agents SET system_prompt=$2, updated_at=NOW() WHERE id=$1")
+        .bind(&id)
+        .bind(&target)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    if let Some(agent) = state.agents.write().await.iter_mut().find(|a| a.id == id) {
+        agent.system_prompt = target;
+    }
+    state.invalidate_agent_prompts(&id).await;
+
+    crate::audit::log_audit(
+        &state.db,
+        "restore_agent_prompt",
+        json!({ "agent_id": id, "version": version }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
+
+    Ok(Json(
+        json!({ "success": true, "agent_id": id, "restored_version": version }),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Agent rating breakdown
+// ---------------------------------------------------------------------------
+
+/// Ratings attributed to an agent — recorded on the rating row, or taken from
+/// the rated message for rows that predate `gh_ratings.agent_id`.
+const AGENT_RATINGS_FROM: &str = "FROM gh_ratings r \
+     LEFT JOIN gh_chat_messages m ON m.id = r.message_id \
+     WHERE COALESCE(r.agent_id, m.agent) = $1";
+
+/// Spreads `(stars, count)` rows over all five star levels, zero-filling
+/// the gaps so the histogram always has the same shape.
+pub(crate) fn rating_histogram(rows: &[(i32, i64)]) -> Vec<RatingBucket> {
+    (1..=5)
+        .map(|stars| RatingBucket {
+            stars,
+            count: rows
+                .iter()
+                .filter(|(s, _)| *s == stars)
+                .map(|(_, c)| c)
+                .sum(),
+        })
+        .collect()
+}
+
+#[utoipa::path(get, path = "/api/agents/{id}/ratings", tag = "agents",
+    params(("id" = String, Path, description = "Agent ID")),
+    responses(
+        (status = 200, description = "Rating histogram, weekly trend and recent low ratings", body = AgentRatingBreakdown),
+        (status = 404, description = "Agent not found")
+    )
+)]
+pub async fn agent_ratings(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<AgentRatingBreakdown>, ApiError> {
+    if !state.agents.read().await.iter().any(|a| a.id == id) {
+        return Err(ApiError::NotFound(format!("Agent '{}' not found", id)));
+    }
+    let db_err = |e: sqlx::Error| ApiError::Internal(e.to_string());
+
+    let counts = sqlx::query_as::<_, (i32, i64)>(&format!(
+        "SELECT r.rating::int, COUNT(*) {} GROUP BY r.rating",
+        AGENT_RATINGS_FROM
+    ))
+    .bind(&id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_err)?;
+
+    let weekly = sqlx::query_as::<_, RatingTrendPoint>(&format!(
+        "SELECT date_trunc('week', r.created_at) AS week_start, COUNT(*) AS count, \
+         AVG(r.rating)::float8 AS average {} \
+           AND r.created_at >= date_trunc('week', NOW()) - INTERVAL '11 weeks' \
+         GROUP BY week_start ORDER BY week_start",
+        AGENT_RATINGS_FROM
+    ))
+    .bind(&id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_err)?;
+
+    let recent_low = sqlx::query_as::<_, LowRatedMessage>(&format!(
+        "SELECT r.message_id::text AS message_id, r.session_id::text AS session_id, \
+         r.rating::int AS rating, r.feedback, LEFT(m.content, 200) AS content_preview, \
+         r.created_at {} AND r.rating <= 2 \
+         ORDER BY r.created_at DESC LIMIT 10",
+        AGENT_RATINGS_FROM
+    ))
+    .bind(&id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_err)?;
+
+    let histogram = rating_histogram(&counts);
+    let total: i64 = histogram.iter().map(|b| b.count).sum();
+    let average = (total > 0).then(|| {
+        let sum: i64 = histogram.iter().map(|b| b.stars as i64 * b.count).sum();
+        sum as f64 / total as f64
+    });
+
+    Ok(Json(AgentRatingBreakdown {
+        agent_id: id,
+        total,
+        average,
+        histogram,
+        weekly,
+        recent_low,
+    }))
+}
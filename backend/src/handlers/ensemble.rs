@@ -0,0 +1,307 @@
+// ---------------------------------------------------------------------------
+// handlers/ensemble.rs — Multi-agent ensemble execution
+// ---------------------------------------------------------------------------
+//
+// The same prompt is answered by several agents concurrently (each through
+// `prepare_execution` with an explicit agent override), optionally followed
+// by a "judge" call that synthesizes a consensus. Used by
+// `POST /api/execute/ensemble` and the `ensemble` WebSocket message.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use serde_json::{Value, json};
+use tokio::sync::{Semaphore, mpsc};
+use uuid::Uuid;
+
+use crate::context::prepare_execution;
+use crate::error::ApiError;
+use crate::models::{EnsembleAnswer, EnsembleRequest, EnsembleResponse};
+use crate::prompt::{apply_safety_settings, build_thinking_config};
+use crate::state::AppState;
+
+use super::execute::gemini_request_simple;
+use super::gemini_diagnose;
+
+/// Maximum number of agents in one ensemble.
+pub(crate) const MAX_ENSEMBLE_AGENTS: usize = 6;
+/// Branches talking to Gemini at the same time.
+const ENSEMBLE_CONCURRENCY: usize = 3;
+/// Deadline shared by all branches and the judge — same as a single execution.
+const ENSEMBLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Per-answer cap when feeding branch outputs to the judge.
+const JUDGE_ANSWER_CHARS: usize = 8_000;
+
+/// Result of an ensemble run, before it is shaped for HTTP or WebSocket.
+pub(crate) struct EnsembleOutcome {
+    pub answers: Vec<EnsembleAnswer>,
+    pub consensus: Option<String>,
+    pub timed_out: bool,
+}
+
+/// Resolve requested agent IDs/names to unique agent IDs, preserving order.
+pub(crate) async fn resolve_ensemble_agents(
+    state: &AppState,
+    requested: &[String],
+) -> Result<Vec<String>, String> {
+    let agents = state.agents.read().await;
+    let mut resolved: Vec<String> = Vec::new();
+    for wanted in requested {
+        let agent = agents
+            .iter()
+            .find(|a| a.id == *wanted || a.name.eq_ignore_ascii_case(wanted))
+            .ok_or_else(|| format!("Unknown agent '{}'", wanted))?;
+        if !resolved.contains(&agent.id) {
+            resolved.push(agent.id.clone());
+        }
+    }
+    if resolved.len() < 2 {
+        return Err("An ensemble needs at least 2 distinct agents".into());
+    }
+    if resolved.len() > MAX_ENSEMBLE_AGENTS {
+        return Err(format!(
+            "An ensemble is limited to {} agents (got {})",
+            MAX_ENSEMBLE_AGENTS,
+            resolved.len()
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Run `prompt` through every agent concurrently (at most
+/// `ENSEMBLE_CONCURRENCY` at a time) and optionally judge the answers.
+/// Each finished answer is also pushed to `progress` as soon as it is ready.
+pub(crate) async fn run_ensemble(
+    state: &AppState,
+    prompt: &str,
+    agent_ids: &[String],
+    model: Option<String>,
+    judge: bool,
+    progress: Option<mpsc::UnboundedSender<EnsembleAnswer>>,
+) -> EnsembleOutcome {
+    let deadline = tokio::time::Instant::now() + ENSEMBLE_TIMEOUT;
+    let permits = Arc::new(Semaphore::new(ENSEMBLE_CONCURRENCY));
+
+    let branches = agent_ids.iter().map(|agent_id| {
+        let permits = permits.clone();
+        let model = model.clone();
+        let progress = progress.clone();
+        async move {
+            let started = Instant::now();
+            let answer = match tokio::time::timeout_at(deadline, async {
+                let _permit = permits.acquire().await.map_err(|e| e.to_string())?;
+                answer_as_agent(state, prompt, agent_id, model).await
+            })
+            .await
+            {
+                Ok(Ok((model, content))) => EnsembleAnswer {
+                    agent: agent_id.clone(),
+                    model,
+                    content,
+                    success: true,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                },
+                Ok(Err(e)) => EnsembleAnswer {
+                    agent: agent_id.clone(),
+                    model: String::new(),
+                    content: e,
+                    success: false,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                },
+                Err(_) => EnsembleAnswer {
+                    agent: agent_id.clone(),
+                    model: String::new(),
+                    content: format!("Timed out after {}s", ENSEMBLE_TIMEOUT.as_secs()),
+                    success: false,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                },
+            };
+            if let Some(tx) = progress {
+                let _ = tx.send(answer.clone());
+            }
+            answer
+        }
+    });
+    let answers = futures_util::future::join_all(branches).await;
+
+    let mut timed_out = tokio::time::Instant::now() >= deadline;
+    let consensus = if judge && answers.iter().filter(|a| a.success).count() >= 2 {
+        match tokio::time::timeout_at(deadline, judge_answers(state, prompt, &answers, model)).await
+        {
+            Ok(Ok(text)) => Some(text),
+            Ok(Err(e)) => {
+                tracing::warn!("ensemble: judge failed: {}", e);
+                None
+            }
+            Err(_) => {
+                timed_out = true;
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    EnsembleOutcome {
+        answers,
+        consensus,
+        timed_out,
+    }
+}
+
+/// One branch: build the agent's context and ask Gemini once (text only).
+/// Returns `(model, text)`.
+async fn answer_as_agent(
+    state: &AppState,
+    prompt: &str,
+    agent_id: &str,
+    model: Option<String>,
+) -> Result<(String, String), String> {
+    let override_reason = "Selected for ensemble execution".to_string();
+    let ctx = prepare_execution(
+        state,
+        prompt,
+        model,
+        None,
+        Some((agent_id.to_string(), 0.99, override_reason)),
+        "",
+        false,
+    )
+    .await;
+    if ctx.api_key.is_empty() {
+        return Err("No API Key".into());
+    }
+
+    let mut generation_config = json!({
+        "temperature": ctx.temperature,
+        "topP": ctx.top_p,
+        "maxOutputTokens": ctx.max_tokens
+    });
+    if let Some(tc) = build_thinking_config(&ctx.model, &ctx.thinking_level) {
+        generation_config["thinkingConfig"] = tc;
+    }
+    let mut body = json!({
+        "systemInstruction": { "parts": [{ "text": format!("{}\n\nYou are running in text-only mode. Do NOT call any tools or functions.", ctx.system_prompt) }] },
+        "contents": [{ "role": "user", "parts": [{ "text": ctx.final_user_prompt }] }],
+        "generationConfig": generation_config
+    });
+    apply_safety_settings(&mut body, &ctx.safety_level);
+    let text = generate_text(state, &ctx.model, &ctx.api_key, ctx.is_oauth, &body).await?;
+    Ok((ctx.model, text))
+}
+
+/// Ask a neutral model to merge the successful answers into one response.
+async fn judge_answers(
+    state: &AppState,
+    prompt: &str,
+    answers: &[EnsembleAnswer],
+    model: Option<String>,
+) -> Result<String, String> {
+    let (api_key, is_oauth) = crate::oauth::get_google_credential(state)
+        .await
+        .ok_or("No API Key")?;
+    let model = match model {
+        Some(m) => m,
+        None => crate::model_registry::get_model_id(state, "chat").await,
+    };
+
+    let mut judge_prompt = format!("## Question\n{}\n\n", prompt);
+    for answer in answers.iter().filter(|a| a.success) {
+        let content: String = answer.content.chars().take(JUDGE_ANSWER_CHARS).collect();
+        judge_prompt.push_str(&format!("## Answer from {}\n{}\n\n", answer.agent, content));
+    }
+    let body = json!({
+        "systemInstruction": { "parts": [{ "text": "You are the judge of an expert panel. Compare the answers below, point out where they disagree, and write a single consensus answer to the question that keeps what is correct in each. Answer in the language of the question." }] },
+        "contents": [{ "role": "user", "parts": [{ "text": judge_prompt }] }],
+        "generationConfig": { "temperature": 0.2 }
+    });
+    generate_text(state, &model, &api_key, is_oauth, &body).await
+}
+
+/// Single `generateContent` call guarded by the Gemini circuit breaker.
+async fn generate_text(
+    state: &AppState,
+    model: &str,
+    api_key: &str,
+    is_oauth: bool,
+    body: &Value,
+) -> Result<String, String> {
+    state.gemini_circuit.check().await?;
+
+    let url = state.providers.gemini_model(model, "generateContent");
+    let parsed_url = reqwest::Url::parse(&url)
+        .ok()
+        .filter(|u| u.scheme() == "https")
+        .ok_or("API credentials require HTTPS")?;
+
+    let resp =
+        match gemini_request_simple(&state.client, &parsed_url, api_key, is_oauth, body).await {
+            Ok(r) => {
+                state.gemini_circuit.record_success().await;
+                r
+            }
+            Err(e) => {
+                state.gemini_circuit.record_failure().await;
+                return Err(e.message);
+            }
+        };
+
+    let j: Value = resp.json().await.unwrap_or_default();
+    let text: String = j["candidates"][0]["content"]["parts"]
+        .as_array()
+        .map(|parts| parts.iter().filter_map(|p| p["text"].as_str()).collect())
+        .unwrap_or_default();
+    if text.is_empty() {
+        Err(format!(
+            "Gemini API returned no text — {}",
+            gemini_diagnose(&j)
+        ))
+    } else {
+        Ok(text)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// HTTP handler
+// ---------------------------------------------------------------------------
+
+/// POST /api/execute/ensemble — answer one prompt with several agents
+#[utoipa::path(post, path = "/api/execute/ensemble", tag = "chat",
+    request_body = EnsembleRequest,
+    responses(
+        (status = 200, description = "Per-agent answers and optional consensus", body = EnsembleResponse),
+        (status = 400, description = "Empty prompt or invalid agent list")
+    )
+)]
+pub async fn execute_ensemble(
+    State(state): State<AppState>,
+    Json(body): Json<EnsembleRequest>,
+) -> Result<Json<EnsembleResponse>, ApiError> {
+    if body.prompt.trim().is_empty() {
+        return Err(ApiError::BadRequest("Prompt cannot be empty".into()));
+    }
+    let agent_ids = resolve_ensemble_agents(&state, &body.agents)
+        .await
+        .map_err(ApiError::BadRequest)?;
+
+    let start = Instant::now();
+    let outcome = run_ensemble(
+        &state,
+        &body.prompt,
+        &agent_ids,
+        body.model,
+        body.judge,
+        None,
+    )
+    .await;
+
+    Ok(Json(EnsembleResponse {
+        id: Uuid::new_v4().to_string(),
+        answers: outcome.answers,
+        consensus: outcome.consensus,
+        timed_out: outcome.timed_out,
+        duration_ms: start.elapsed().as_millis() as u64,
+    }))
+}
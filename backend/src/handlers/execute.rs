@@ -1,23 +1,30 @@
 // ---------------------------------------------------------------------------
-// handlers/execute.rs — Legacy HTTP execute + internal tool bridge
+// handlers/execute.rs — Legacy HTTP execute + tool execution endpoints
 // ---------------------------------------------------------------------------
 
 use std::time::Instant;
 
-use axum::Json;
-use axum::extract::State;
-use axum::http::StatusCode;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
 use serde_json::{Value, json};
 use uuid::Uuid;
 
-use crate::models::{ExecutePlan, ExecuteRequest, ExecuteResponse};
+use crate::auth::AuthIdentity;
+use crate::idempotency::{self, Claim, IdempotencyCache, MAX_KEY_LEN};
+use crate::models::{
+    ExecutePlan, ExecuteRequest, ExecuteResponse, ToolCatalogEntry, ToolExecuteRequest,
+    ToolExecuteResponse, ToolInlineData,
+};
 use crate::state::AppState;
 
-use crate::context::prepare_execution;
+use crate::context::{ExecuteOverrides, PLAN_ONLY_MODE, prepare_execution};
 use crate::error::ApiError;
-use crate::prompt::build_thinking_config;
+use crate::prompt::{apply_safety_settings, build_thinking_config};
+use crate::redact::redact_secrets;
 
-use super::gemini_diagnose;
+use super::streaming::{execute_with_tools, session_wd_and_model};
+use super::{gemini_diagnose, gemini_diagnose_details};
 
 // ---------------------------------------------------------------------------
 // ADK Internal Tool Bridge
@@ -40,7 +47,15 @@ pub async fn internal_tool_execute(
         .await
         .unwrap_or_default();
 
-    match crate::tools::execute_tool(name, &args, &state, &wd).await {
+    match crate::tools::execute_tool(
+        name,
+        &args,
+        &state,
+        &wd,
+        &crate::tools::ToolCaller::default(),
+    )
+    .await
+    {
         Ok(output) => Ok(Json(json!({
             "status": "success",
             "result": output.text
@@ -52,19 +67,119 @@ pub async fn internal_tool_execute(
     }
 }
 
+/// GET /api/tools — native tool catalog with argument schemas.
+#[utoipa::path(get, path = "/api/tools", tag = "chat",
+    responses((status = 200, description = "Native tools with descriptions and parameter schemas", body = Vec<ToolCatalogEntry>))
+)]
+pub async fn list_tools(State(state): State<AppState>) -> Json<Vec<ToolCatalogEntry>> {
+    Json(crate::tool_defs::tool_catalog(&state))
+}
+
+/// POST /api/tools/execute — run one native tool directly, outside the agent
+/// loop. Paths resolve against the configured working directory.
+#[utoipa::path(post, path = "/api/tools/execute", tag = "chat",
+    request_body = ToolExecuteRequest,
+    responses(
+        (status = 200, description = "Tool result (success=false when the tool failed)", body = ToolExecuteResponse),
+        (status = 400, description = "Arguments do not match the tool's parameter schema"),
+        (status = 404, description = "Unknown tool")
+    )
+)]
+pub async fn tool_execute(
+    State(state): State<AppState>,
+    Json(body): Json<ToolExecuteRequest>,
+) -> Result<Json<ToolExecuteResponse>, ApiError> {
+    if !crate::tools::list_available_tools()
+        .iter()
+        .any(|t| t.name == body.name)
+    {
+        return Err(ApiError::NotFound(format!("Unknown tool '{}'", body.name)));
+    }
+    // Same schema check the agent loop applies to model-issued calls
+    if let Some(schema) =
+        crate::tool_defs::find_tool_schema(&crate::tool_defs::build_tools(&state), &body.name)
+    {
+        crate::tool_defs::validate_tool_args(schema, &body.args).map_err(ApiError::BadRequest)?;
+    }
+    let wd = state
+        .settings()
+        .await
+        .map(|s| s.working_directory)
+        .map_err(|e| ApiError::Internal(format!("Failed to load settings: {}", e)))?;
+
+    let start = Instant::now();
+    let result = crate::tools::execute_tool(
+        &body.name,
+        &body.args,
+        &state,
+        &wd,
+        &crate::tools::ToolCaller::default(),
+    )
+    .await;
+
+    Ok(Json(match result {
+        Ok(output) => ToolExecuteResponse {
+            name: body.name,
+            success: output.success,
+            text: output.text,
+            error: None,
+            inline_data: output.inline_data.map(|d| ToolInlineData {
+                mime_type: d.mime_type,
+                data: d.data,
+            }),
+            exit_code: output.exit_code,
+            duration_ms: output.duration_ms,
+        },
+        Err(e) => ToolExecuteResponse {
+            name: body.name,
+            success: false,
+            text: String::new(),
+            error: Some(e),
+            inline_data: None,
+            exit_code: None,
+            duration_ms: start.elapsed().as_millis() as u64,
+        },
+    }))
+}
+
 // ---------------------------------------------------------------------------
 // HTTP Execute (Legacy)
 // ---------------------------------------------------------------------------
 
+/// Failed call from [`gemini_request_simple`].
+pub(super) struct GeminiHttpError {
+    /// Upstream HTTP status; `None` when no response was received.
+    pub status: Option<StatusCode>,
+    /// Gemini's `error.status` (e.g. `RESOURCE_EXHAUSTED`), when the body had one.
+    pub reason: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for GeminiHttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl GeminiHttpError {
+    /// `ApiError` details: lets clients tell quota errors from other failures.
+    pub(super) fn details(&self) -> Value {
+        json!({
+            "httpStatus": self.status.map(|s| s.as_u16()),
+            "status": self.reason,
+        })
+    }
+}
+
 /// Gemini retry helper — reuses the same backoff logic as streaming.
 /// This is a simplified version for the non-streaming execute endpoint.
-async fn gemini_request_simple(
+pub(super) async fn gemini_request_simple(
     client: &reqwest::Client,
     url: &reqwest::Url,
     api_key: &str,
     is_oauth: bool,
     body: &Value,
-) -> Result<reqwest::Response, String> {
+) -> Result<reqwest::Response, GeminiHttpError> {
     let result = crate::oauth::apply_google_auth(client.post(url.clone()), api_key, is_oauth)
         .json(body)
         .timeout(std::time::Duration::from_secs(300))
@@ -82,34 +197,143 @@ async fn gemini_request_simple(
                 .last()
                 .map(|(i, c)| i + c.len_utf8())
                 .unwrap_or(0);
-            Err(format!(
-                "Gemini API error ({}): {}",
-                status,
-                &err_body[..safe_len]
-            ))
+            let reason = serde_json::from_str::<Value>(&err_body)
+                .ok()
+                .and_then(|j| j.pointer("/error/status")?.as_str().map(str::to_string));
+            Err(GeminiHttpError {
+                status: Some(status),
+                reason,
+                message: format!(
+                    "Gemini API error ({}): {}",
+                    status,
+                    redact_secrets(&err_body[..safe_len])
+                ),
+            })
         }
-        Err(e) => Err(format!("Gemini API request failed: {:?}", e)),
+        Err(e) => Err(GeminiHttpError {
+            status: None,
+            reason: None,
+            message: format!(
+                "Gemini API request failed: {}",
+                redact_secrets(&format!("{:?}", e))
+            ),
+        }),
     }
 }
 
 #[utoipa::path(post, path = "/api/execute", tag = "chat",
     request_body = ExecuteRequest,
-    responses((status = 200, description = "Execution result", body = ExecuteResponse))
+    params(("Idempotency-Key" = Option<String>, Header,
+        description = "Replay the first response for a repeated key instead of re-running")),
+    responses(
+        (status = 200, description = "Execution result, or an ExecutionPreview for mode `plan-only`", body = ExecuteResponse),
+        (status = 502, description = "Gemini failed or returned no text; `error.details` carries blockReason/finishReason/safetyRatings or the upstream HTTP status"),
+        (status = 409, description = "A request with this Idempotency-Key is still running"),
+        (status = 422, description = "This Idempotency-Key was already used with a different request body")
+    )
 )]
 pub async fn execute(
     State(state): State<AppState>,
+    identity: Option<Extension<AuthIdentity>>,
+    peer: Option<Extension<ConnectInfo<std::net::SocketAddr>>>,
+    headers: HeaderMap,
     Json(body): Json<ExecuteRequest>,
 ) -> (StatusCode, Json<Value>) {
+    let key = match headers.get("idempotency-key").map(|v| v.to_str()) {
+        None => return run_execute(&state, body).await,
+        Some(Ok(k)) if !k.trim().is_empty() && k.len() <= MAX_KEY_LEN => k.trim(),
+        Some(_) => {
+            return error_parts(ApiError::BadRequest(format!(
+                "Idempotency-Key must be 1-{} visible ASCII characters",
+                MAX_KEY_LEN
+            )));
+        }
+    };
+    let key = IdempotencyCache::scoped_key(
+        identity.as_ref().map(|Extension(i)| i.0.as_str()),
+        peer.map(|Extension(ConnectInfo(addr))| addr.ip()),
+        key,
+    );
+    let fingerprint = idempotency::fingerprint(&body);
+
+    match state.idempotency.claim(&key, &fingerprint) {
+        Claim::Done(status, value) => {
+            tracing::info!("execute: replaying response for idempotency key");
+            (status, Json(value))
+        }
+        Claim::InFlight => error_parts(ApiError::Conflict(
+            "A request with this Idempotency-Key is still in progress".into(),
+        )),
+        Claim::Mismatch => error_parts(ApiError::Unprocessable(
+            "This Idempotency-Key was already used with a different request body".into(),
+        )),
+        Claim::New => {
+            // Run detached so a client that disconnects and retries picks up
+            // this result instead of finding the key stuck in flight. The
+            // request ID is re-scoped so error bodies still carry it.
+            let task_state = state.clone();
+            let task_key = key.clone();
+            let request_id = ApiError::current_request_id();
+            let run = tokio::spawn(crate::error::REQUEST_ID.scope(request_id, async move {
+                let (status, Json(value)) = run_execute(&task_state, body).await;
+                if status.is_server_error() {
+                    task_state.idempotency.release(&task_key);
+                } else {
+                    task_state
+                        .idempotency
+                        .complete(&task_key, &fingerprint, status, value.clone());
+                }
+                (status, Json(value))
+            }));
+            match run.await {
+                Ok(response) => response,
+                Err(e) => {
+                    state.idempotency.release(&key);
+                    error_parts(ApiError::Internal(format!("execute: task failed: {}", e)))
+                }
+            }
+        }
+    }
+}
+
+/// `ApiError` envelope for handlers that return `(StatusCode, Json<Value>)`.
+fn error_parts(error: ApiError) -> (StatusCode, Json<Value>) {
+    let (status, body) = error.into_parts();
+    (status, Json(body))
+}
+
+async fn run_execute(state: &AppState, body: ExecuteRequest) -> (StatusCode, Json<Value>) {
     if body.prompt.trim().is_empty() {
         return (
             StatusCode::BAD_REQUEST,
             Json(json!({ "error": "Prompt cannot be empty" })),
         );
     }
+    let mut overrides = ExecuteOverrides {
+        temperature: body.temperature,
+        top_p: body.top_p,
+        thinking_level: body.thinking_level.clone(),
+        stop_on_tool_error: None,
+    };
+    if let Err(e) = overrides.validate() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e })));
+    }
+    let sid = match body.session_id.as_deref().map(Uuid::parse_str) {
+        None => None,
+        Some(Ok(sid)) => Some(sid),
+        Some(Err(_)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Invalid session_id" })),
+            );
+        }
+    };
     let start = Instant::now();
 
+    let plan_only = body.mode == PLAN_ONLY_MODE;
+
     // Translate body.mode into agent_override so the user's explicit choice is respected.
-    let mode_override = if !body.mode.is_empty() && body.mode != "auto" {
+    let mode_override = if !body.mode.is_empty() && body.mode != "auto" && !plan_only {
         let agents = state.agents.read().await;
         agents
             .iter()
@@ -124,7 +348,21 @@ pub async fn execute(
     } else {
         None
     };
-    let ctx = prepare_execution(&state, &body.prompt, body.model.clone(), mode_override, "").await;
+    let (session_wd, session_model) = session_wd_and_model(state, sid.as_ref()).await;
+    let mut ctx = prepare_execution(
+        state,
+        &body.prompt,
+        body.model.clone(),
+        session_model,
+        mode_override,
+        &session_wd,
+        plan_only,
+    )
+    .await;
+    ctx.apply_overrides(&overrides);
+    if plan_only {
+        return (StatusCode::OK, Json(json!(ctx.preview())));
+    }
     if ctx.api_key.is_empty() {
         return (
             StatusCode::UNAUTHORIZED,
@@ -141,10 +379,50 @@ pub async fn execute(
         );
     }
 
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
-        ctx.model
-    );
+    // Upstream failures become a structured 502 with Gemini's diagnosis in `details`.
+    let upstream_error = |message: String, mut details: Value| -> (StatusCode, Json<Value>) {
+        details["safetyLevel"] = json!(ctx.safety_level);
+        let (status, body) = ApiError::Upstream(message)
+            .with_details(details)
+            .into_parts();
+        (status, Json(body))
+    };
+
+    let respond = |text: String, tools_used: Vec<String>| -> (StatusCode, Json<Value>) {
+        (
+            StatusCode::OK,
+            Json(json!(ExecuteResponse {
+                id: Uuid::new_v4().to_string(),
+                result: text,
+                plan: Some(ExecutePlan {
+                    agent: Some(ctx.agent_id.clone()),
+                    steps: ctx.steps.clone(),
+                    estimated_time: None
+                }),
+                duration_ms: start.elapsed().as_millis() as u64,
+                mode: body.mode.clone(),
+                files_loaded: ctx.files_loaded.clone(),
+                tools_used,
+            })),
+        )
+    };
+
+    // Tool-enabled: run the WebSocket tool loop to completion — same
+    // capabilities, no streaming.
+    if body.enable_tools {
+        let run = execute_with_tools(state, &ctx, sid).await;
+        if run.text.is_empty()
+            && let Some(message) = run.error
+        {
+            return upstream_error(
+                format!("execute (tools): {}", message),
+                json!({ "toolsUsed": run.tools_used }),
+            );
+        }
+        return respond(run.text, run.tools_used);
+    }
+
+    let url = state.providers.gemini_model(&ctx.model, "generateContent");
     let parsed_url = match reqwest::Url::parse(&url) {
         Ok(u) if u.scheme() == "https" => u,
         _ => {
@@ -162,11 +440,12 @@ pub async fn execute(
     if let Some(tc) = build_thinking_config(&ctx.model, &ctx.thinking_level) {
         gen_config_exec["thinkingConfig"] = tc;
     }
-    let gem_body = json!({
+    let mut gem_body = json!({
         "systemInstruction": { "parts": [{ "text": ctx.system_prompt }] },
         "contents": [{ "parts": [{ "text": ctx.final_user_prompt }] }],
         "generationConfig": gen_config_exec
     });
+    apply_safety_settings(&mut gem_body, &ctx.safety_level);
 
     // Helper to extract text from a Gemini generateContent response.
     let extract_text = |j: &Value| -> Option<String> {
@@ -209,11 +488,12 @@ pub async fn execute(
                 tracing::warn!(
                     "execute: MALFORMED_FUNCTION_CALL, retrying without tool references"
                 );
-                let retry_body = json!({
+                let mut retry_body = json!({
                     "systemInstruction": { "parts": [{ "text": format!("{}\n\nIMPORTANT: You are running in text-only mode. Do NOT attempt to call any tools or functions. Answer the user's question directly using your knowledge.", ctx.system_prompt) }] },
                     "contents": [{ "parts": [{ "text": ctx.final_user_prompt }] }],
                     "generationConfig": gen_config_exec
                 });
+                apply_safety_settings(&mut retry_body, &ctx.safety_level);
                 match gemini_request_simple(
                     &state.client,
                     &parsed_url,
@@ -225,42 +505,40 @@ pub async fn execute(
                 {
                     Ok(r2) => {
                         let j2: Value = r2.json().await.unwrap_or_default();
-                        extract_text(&j2).unwrap_or_else(|| {
-                            let diag = gemini_diagnose(&j2);
-                            format!("Gemini API returned no text — {}", diag)
-                        })
+                        match extract_text(&j2) {
+                            Some(text) => text,
+                            None => {
+                                return upstream_error(
+                                    format!(
+                                        "execute retry: Gemini response missing text ({}, safety_level={})",
+                                        gemini_diagnose(&j2),
+                                        ctx.safety_level
+                                    ),
+                                    gemini_diagnose_details(&j2),
+                                );
+                            }
+                        }
                     }
                     Err(e) => {
-                        tracing::error!("execute retry: {}", e);
-                        "API Error on retry".to_string()
+                        return upstream_error(format!("execute retry: {}", e), e.details());
                     }
                 }
             } else {
-                let diag = gemini_diagnose(&j);
-                tracing::error!("execute: Gemini response missing text ({})", diag);
-                format!("Gemini API returned no text — {}", diag)
+                return upstream_error(
+                    format!(
+                        "execute: Gemini response missing text ({}, safety_level={})",
+                        gemini_diagnose(&j),
+                        ctx.safety_level
+                    ),
+                    gemini_diagnose_details(&j),
+                );
             }
         }
         Err(e) => {
             state.gemini_circuit.record_failure().await;
-            tracing::error!("execute: {}", e);
-            "API Error".to_string()
+            return upstream_error(format!("execute: {}", e), e.details());
         }
     };
 
-    (
-        StatusCode::OK,
-        Json(json!(ExecuteResponse {
-            id: Uuid::new_v4().to_string(),
-            result: text,
-            plan: Some(ExecutePlan {
-                agent: Some(ctx.agent_id),
-                steps: ctx.steps,
-                estimated_time: None
-            }),
-            duration_ms: start.elapsed().as_millis() as u64,
-            mode: body.mode,
-            files_loaded: ctx.files_loaded,
-        })),
-    )
+    respond(text, Vec::new())
 }
@@ -25,7 +25,8 @@ pub async fn read_file(Json(body): Json<FileReadRequest>) -> Json<Value> {
             content: f.content,
             size_bytes: f.size_bytes,
             truncated: f.truncated,
-            extension: f.extension
+            extension: f.extension,
+            encoding: f.encoding.map(str::to_string),
         })),
         Err(e) => Json(json!({ "error": e.reason, "path": e.path })),
     }
@@ -12,6 +12,7 @@ use axum::{
 };
 
 pub(crate) mod agents;
+pub(crate) mod ensemble;
 pub(crate) mod execute;
 pub(crate) mod files_handlers;
 pub(crate) mod streaming;
@@ -28,10 +29,24 @@ pub fn agents_router(state: AppState) -> Router<AppState> {
             get(agents::list_agents).post(agents::create_agent),
         )
         .route("/api/agents/classify", post(agents::classify_agent))
+        .route(
+            "/api/agents/classify/debug",
+            post(agents::classify_agent_debug),
+        )
+        .route("/api/agents/reorder", post(agents::reorder_agents))
         .route(
             "/api/agents/{id}",
             post(agents::update_agent).delete(agents::delete_agent),
         )
+        .route(
+            "/api/agents/{id}/prompt-versions",
+            get(agents::list_prompt_versions),
+        )
+        .route("/api/agents/{id}/ratings", get(agents::agent_ratings))
+        .route(
+            "/api/agents/{id}/prompt-versions/{version}/restore",
+            post(agents::restore_prompt_version),
+        )
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth::require_auth,
@@ -42,6 +57,20 @@ pub fn system_router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/api/system/stats", get(system::system_stats))
         .route("/api/admin/rotate-key", post(system::rotate_key))
+        .route("/api/admin/test-alert", post(system::test_alert))
+        .route("/api/admin/cache/clear", post(system::clear_caches))
+        .route("/api/admin/circuit", get(system::circuit_status))
+        .route("/api/admin/circuit/reset", post(system::reset_circuits))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ))
+}
+
+pub fn tools_router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/tools", get(execute::list_tools))
+        .route("/api/tools/execute", post(execute::tool_execute))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth::require_auth,
@@ -61,21 +90,28 @@ pub fn files_router(state: AppState) -> Router<AppState> {
 
 // ── Re-exports (backward-compatible) ─────────────────────────────────────────
 
-pub use agents::{classify_agent, create_agent, delete_agent, list_agents, update_agent};
-pub use execute::{execute, internal_tool_execute};
+pub use agents::{
+    agent_ratings, classify_agent, classify_agent_debug, create_agent, delete_agent, list_agents,
+    list_prompt_versions, reorder_agents, restore_prompt_version, update_agent,
+};
+pub use ensemble::execute_ensemble;
+pub use execute::{execute, internal_tool_execute, list_tools, tool_execute};
 pub use files_handlers::{browse_directory, list_files, read_file};
 pub use streaming::ws_execute;
 pub use system::{
-    auth_mode, browser_proxy_history, gemini_models, health, health_detailed, readiness,
-    rotate_key, system_stats, ProxyHistoryResponse,
+    auth_mode, browser_proxy_history, circuit_status, clear_caches, gemini_models, health,
+    health_detailed, readiness, reset_circuits, rotate_key, system_stats, test_alert,
+    ProxyHistoryResponse,
 };
 
 // ── utoipa __path_* re-exports ───────────────────────────────────────────────
 pub use agents::{
-    __path_classify_agent, __path_create_agent, __path_delete_agent, __path_list_agents,
-    __path_update_agent,
+    __path_agent_ratings, __path_classify_agent, __path_classify_agent_debug, __path_create_agent,
+    __path_delete_agent, __path_list_agents, __path_list_prompt_versions, __path_reorder_agents,
+    __path_restore_prompt_version, __path_update_agent,
 };
-pub use execute::__path_execute;
+pub use ensemble::__path_execute_ensemble;
+pub use execute::{__path_execute, __path_list_tools, __path_tool_execute};
 pub use files_handlers::{__path_list_files, __path_read_file};
 pub use system::{
     __path_auth_mode, __path_browser_proxy_history, __path_gemini_models, __path_health,
@@ -131,6 +167,37 @@ pub(crate) fn gemini_diagnose(resp_json: &Value) -> String {
     }
 }
 
+/// Structured form of [`gemini_diagnose`] for `ApiError` details, so clients
+/// can tell a safety block from an empty or truncated answer.
+pub(crate) fn gemini_diagnose_details(resp_json: &Value) -> Value {
+    let feedback = resp_json.get("promptFeedback");
+    let safety: Vec<Value> = feedback
+        .and_then(|f| f.get("safetyRatings"))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|r| {
+            !matches!(
+                r.get("probability").and_then(|v| v.as_str()),
+                Some("NEGLIGIBLE" | "LOW") | None
+            )
+        })
+        .map(|r| {
+            serde_json::json!({
+                "category": r.get("category"),
+                "probability": r.get("probability"),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "blockReason": feedback.and_then(|f| f.get("blockReason")),
+        "finishReason": resp_json.pointer("/candidates/0/finishReason"),
+        "safetyRatings": safety,
+        "diagnosis": gemini_diagnose(resp_json),
+    })
+}
+
 pub(crate) fn build_providers(
     api_keys: &HashMap<String, String>,
     cached_google: &[crate::model_registry::ModelInfo],
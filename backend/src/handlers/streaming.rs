@@ -3,6 +3,7 @@
 // ---------------------------------------------------------------------------
 
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 
 use axum::extract::State;
@@ -11,15 +12,21 @@ use axum::response::IntoResponse;
 use futures_util::{SinkExt, StreamExt};
 use rand::Rng;
 use serde_json::{Value, json};
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use uuid::Uuid;
 
 use crate::models::{WsClientMessage, WsServerMessage};
 use crate::state::AppState;
 
-use crate::context::{ExecuteContext, prepare_execution};
-use crate::prompt::build_thinking_config;
-use crate::tool_defs::build_tools_with_mcp;
+use crate::context::{
+    ExecuteContext, ExecuteOverrides, PLAN_ONLY_MODE, context_byte_budget, fit_contents_to_budget,
+    prepare_execution, tier_token_budget,
+};
+use crate::prompt::{apply_safety_settings, build_thinking_config};
+use crate::redact::redact_secrets;
+use crate::tool_defs::{build_tools_with_mcp, find_tool_schema, validate_tool_args};
 
 // ---------------------------------------------------------------------------
 // SSE Parser
@@ -28,24 +35,29 @@ use crate::tool_defs::build_tools_with_mcp;
 #[derive(Debug, Clone)]
 enum SseParsedEvent {
     TextToken(String),
+    /// Thinking summary (a part flagged `thought: true`) — streamed to the UI
+    /// separately and never stored with the answer
+    Thought(String),
     FunctionCall {
         name: String,
         args: Value,
         raw_part: Value,
     },
-    /// Gemini returned MALFORMED_FUNCTION_CALL — tool schema issue, retry without tools
-    MalformedFunctionCall,
+    /// Gemini returned MALFORMED_FUNCTION_CALL — carries `finishMessage` (the
+    /// unparsed call) so the args can be repaired before retrying without tools
+    MalformedFunctionCall(Option<String>),
 }
 
+/// Incremental SSE parser. Buffers raw bytes and only decodes complete
+/// frames, so a multi-byte UTF-8 character split across network chunks
+/// survives intact.
 struct SseParser {
-    buffer: String,
+    buffer: Vec<u8>,
 }
 
 impl SseParser {
     fn new() -> Self {
-        Self {
-            buffer: String::new(),
-        }
+        Self { buffer: Vec::new() }
     }
 
     fn parse_parts(json_val: &Value) -> Vec<SseParsedEvent> {
@@ -59,7 +71,11 @@ impl SseParser {
         {
             for part in parts {
                 if let Some(text) = part["text"].as_str().filter(|t| !t.is_empty()) {
-                    events.push(SseParsedEvent::TextToken(text.to_string()));
+                    if part["thought"].as_bool() == Some(true) {
+                        events.push(SseParsedEvent::Thought(text.to_string()));
+                    } else {
+                        events.push(SseParsedEvent::TextToken(text.to_string()));
+                    }
                 }
                 if let Some(name) = part.get("functionCall").and_then(|fc| fc["name"].as_str()) {
                     events.push(SseParsedEvent::FunctionCall {
@@ -85,8 +101,11 @@ impl SseParser {
                 .and_then(|v| v.as_str())
             {
                 if reason == "MALFORMED_FUNCTION_CALL" {
-                    tracing::warn!("stream: MALFORMED_FUNCTION_CALL — will retry without tools");
-                    events.push(SseParsedEvent::MalformedFunctionCall);
+                    tracing::warn!("stream: MALFORMED_FUNCTION_CALL — will attempt arg repair");
+                    let message = json_val["candidates"][0]["finishMessage"]
+                        .as_str()
+                        .map(str::to_string);
+                    events.push(SseParsedEvent::MalformedFunctionCall(message));
                 } else if reason != "STOP" {
                     tracing::warn!(
                         "stream: Gemini chunk has no 'parts' (finishReason={})",
@@ -98,12 +117,12 @@ impl SseParser {
         events
     }
 
-    fn feed(&mut self, chunk: &str) -> Vec<SseParsedEvent> {
-        self.buffer.push_str(chunk);
+    fn feed(&mut self, chunk: &[u8]) -> Vec<SseParsedEvent> {
+        self.buffer.extend_from_slice(chunk);
         let mut events = Vec::new();
-        while let Some(pos) = self.buffer.find("\n\n") {
-            let block = self.buffer[..pos].to_string();
-            self.buffer = self.buffer[pos + 2..].to_string();
+        while let Some(pos) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let frame: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+            let block = String::from_utf8_lossy(&frame[..pos]);
             for line in block.lines() {
                 if let Some(jv) = line
                     .strip_prefix("data: ")
@@ -119,7 +138,7 @@ impl SseParser {
 
     fn flush(&mut self) -> Vec<SseParsedEvent> {
         let mut events = Vec::new();
-        for line in self.buffer.lines() {
+        for line in String::from_utf8_lossy(&self.buffer).lines() {
             if let Some(jv) = line
                 .strip_prefix("data: ")
                 .filter(|d| *d != "[DONE]" && !d.is_empty())
@@ -144,16 +163,148 @@ impl SseParser {
 #[allow(dead_code)]
 const MAX_TOOL_RESULT_FOR_CONTEXT: usize = 25000;
 
+/// Default cap on each tool result streamed into the chat (16 KB); the
+/// `max_tool_display_bytes` setting overrides it. Independent of the context
+/// limits above — the model may see more than the browser is sent.
+pub const DEFAULT_TOOL_DISPLAY_BYTES: usize = 16 * 1024;
+
+/// Upper bound accepted for the `max_tool_display_bytes` setting (1 MB).
+pub const MAX_TOOL_DISPLAY_BYTES_CAP: usize = 1024 * 1024;
+
 /// Per-tool execution timeout — prevents individual tool calls from hanging forever.
 const TOOL_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Tool calls from one iteration running at the same time — `TOOL_CONCURRENCY`
+/// env (1-16, default 4).
+fn tool_concurrency() -> usize {
+    static CONCURRENCY: OnceLock<usize> = OnceLock::new();
+    *CONCURRENCY.get_or_init(|| env_limit("TOOL_CONCURRENCY", 4, 16))
+}
+
+/// `call_agent` delegations running at the same time — each is a full agent
+/// run, so the limit is separate and smaller. `AGENT_CALL_CONCURRENCY` env
+/// (1-4, default 2).
+fn agent_call_concurrency() -> usize {
+    static CONCURRENCY: OnceLock<usize> = OnceLock::new();
+    *CONCURRENCY.get_or_init(|| env_limit("AGENT_CALL_CONCURRENCY", 2, 4))
+}
+
+/// Percent of a truncated tool result kept from the start; the rest comes
+/// from the end, where command and test failures usually are.
+/// `TOOL_RESULT_HEAD_PERCENT` env (1-99, default 60).
+fn tool_result_head_percent() -> usize {
+    static PERCENT: OnceLock<usize> = OnceLock::new();
+    *PERCENT.get_or_init(|| env_limit("TOOL_RESULT_HEAD_PERCENT", 60, 99))
+}
+
+fn env_limit(var: &str, default: usize, max: usize) -> usize {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(default)
+        .clamp(1, max)
+}
+
 // ── Retry with exponential backoff constants ────────────────────────────────
 /// Maximum number of retry attempts for transient Gemini API errors (429, 503, timeout).
-const GEMINI_MAX_RETRIES: u32 = 3;
+/// Default for the `gemini_max_retries` setting.
+pub(crate) const GEMINI_MAX_RETRIES: u32 = 3;
 /// Base delay for exponential backoff (doubles each attempt: 1s, 2s, 4s).
-const GEMINI_BACKOFF_BASE: Duration = Duration::from_secs(1);
+pub(crate) const GEMINI_BACKOFF_BASE: Duration = Duration::from_secs(1);
 /// Maximum random jitter added to each backoff delay.
-const GEMINI_BACKOFF_JITTER_MS: u64 = 500;
+pub(crate) const GEMINI_BACKOFF_JITTER_MS: u64 = 500;
+
+/// Bounds accepted for the retry settings.
+pub(crate) const MAX_GEMINI_RETRIES: u32 = 10;
+pub(crate) const MIN_BACKOFF_BASE_MS: u64 = 100;
+pub(crate) const MAX_BACKOFF_BASE_MS: u64 = 30_000;
+pub(crate) const MAX_BACKOFF_JITTER_MS: u64 = 10_000;
+/// No single backoff sleep exceeds this, however many retries are configured.
+const MAX_BACKOFF_DELAY: Duration = Duration::from_secs(120);
+
+/// Retry schedule of `gemini_request_with_retry`, from the
+/// `gemini_max_retries` / `gemini_backoff_base_ms` / `gemini_backoff_jitter_ms` settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff_base: Duration,
+    pub jitter_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: GEMINI_MAX_RETRIES,
+            backoff_base: GEMINI_BACKOFF_BASE,
+            jitter_ms: GEMINI_BACKOFF_JITTER_MS,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy from stored settings, clamped to the accepted bounds.
+    pub fn from_settings(max_retries: i32, backoff_base_ms: i32, jitter_ms: i32) -> Self {
+        Self {
+            max_retries: (max_retries.max(0) as u32).min(MAX_GEMINI_RETRIES),
+            backoff_base: Duration::from_millis(
+                (backoff_base_ms.max(0) as u64).clamp(MIN_BACKOFF_BASE_MS, MAX_BACKOFF_BASE_MS),
+            ),
+            jitter_ms: (jitter_ms.max(0) as u64).min(MAX_BACKOFF_JITTER_MS),
+        }
+    }
+
+    /// Delay before retry `attempt` (1-based): base * 2^(attempt-1) + random
+    /// jitter, capped at `MAX_BACKOFF_DELAY`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .backoff_base
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..=self.jitter_ms));
+        (backoff + jitter).min(MAX_BACKOFF_DELAY)
+    }
+}
+
+/// Gemini 3 thought signature of each function call, in call order.
+/// Parallel calls to the same tool can carry different signatures, and with
+/// mixed models only some calls may have one — so responses are matched by
+/// position, never by tool name.
+fn thought_signatures(fcs: &[(String, Value, Value)]) -> Vec<Option<Value>> {
+    let signatures: Vec<Option<Value>> = fcs
+        .iter()
+        .map(|(_, _, raw)| raw.get("thoughtSignature").cloned())
+        .collect();
+    let signed = signatures.iter().filter(|s| s.is_some()).count();
+    if signed > 0 && signed < signatures.len() {
+        tracing::warn!(
+            "stream: only {} of {} function calls carry a thoughtSignature — echoing on matching responses only",
+            signed,
+            signatures.len()
+        );
+    }
+    signatures
+}
+
+/// Signature to echo on the `index`-th function response, if its call had one
+/// and the response really belongs to that call.
+fn signature_for_response<'a>(
+    signatures: &'a [Option<Value>],
+    fcs: &[(String, Value, Value)],
+    index: usize,
+    name: &str,
+) -> Option<&'a Value> {
+    let sig = signatures.get(index)?.as_ref()?;
+    match fcs.get(index) {
+        Some((call_name, _, _)) if call_name == name => Some(sig),
+        _ => {
+            tracing::warn!(
+                "stream: function response #{} ('{}') does not match its call — thoughtSignature dropped",
+                index,
+                name
+            );
+            None
+        }
+    }
+}
 
 /// Wrapper using the default limit (kept for potential external usage).
 #[allow(dead_code)]
@@ -162,20 +313,62 @@ fn truncate_for_context(output: &str) -> String {
 }
 
 fn truncate_for_context_with_limit(output: &str, limit: usize) -> String {
+    truncate_head_tail(output, limit, tool_result_head_percent())
+}
+
+/// Byte offsets `(head_end, tail_start)` that keep `head_percent` of `limit`
+/// from the start of `output` and the rest from its end, on char boundaries.
+fn head_tail_cut(output: &str, limit: usize, head_percent: usize) -> (usize, usize) {
+    let mut head_end = limit * head_percent.min(100) / 100;
+    while !output.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = output.len() - (limit - head_end);
+    while !output.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    (head_end, tail_start)
+}
+
+/// Keep `head_percent` of `limit` from the start of `output` and the rest
+/// from its end, with a marker where the middle was cut.
+fn truncate_head_tail(output: &str, limit: usize, head_percent: usize) -> String {
     if output.len() <= limit {
         return output.to_string();
     }
-    let boundary = output
-        .char_indices()
-        .take_while(|(i, _)| *i < limit)
-        .last()
-        .map(|(i, c)| i + c.len_utf8())
-        .unwrap_or(0);
+    let (head_end, tail_start) = head_tail_cut(output, limit, head_percent);
+    format!(
+        "{}\n\n[... {} chars omitted ...]\n\n{}\n\n[Output truncated from {} to {} chars (start and end kept). Ask for specific sections if needed. ANALYZE what you see instead of reading more.]",
+        &output[..head_end],
+        tail_start - head_end,
+        &output[tail_start..],
+        output.len(),
+        head_end + output.len() - tail_start,
+    )
+}
+
+/// Markdown block for a tool result.
+fn tool_result_md(output: &str) -> String {
+    format!("```\n{}\n```\n---\n\n", output)
+}
+
+/// What the chat is sent for a tool result: output over `limit` bytes keeps
+/// its start and end, followed by a note pointing at the full output, which
+/// is always kept in the saved message (`tool_result_md`).
+fn tool_result_display_md(output: &str, limit: usize) -> String {
+    if output.len() <= limit {
+        return tool_result_md(output);
+    }
+    let (head_end, tail_start) = head_tail_cut(output, limit, tool_result_head_percent());
     format!(
-        "{}\n\n[Output truncated from {} to {} chars. Ask for specific sections if needed. ANALYZE what you see instead of reading more.]",
-        &output[..boundary],
+        "```\n{}\n\n[... {} bytes not shown ...]\n\n{}\n```\n\
+         > Showing {} of {} bytes (max_tool_display_bytes). \
+         The full output is saved with this message — reload the session to view it.\n\n---\n\n",
+        &output[..head_end],
+        tail_start - head_end,
+        &output[tail_start..],
+        head_end + output.len() - tail_start,
         output.len(),
-        boundary,
     )
 }
 
@@ -183,10 +376,15 @@ fn truncate_for_context_with_limit(output: &str, limit: usize) -> String {
 // WebSocket Helper
 // ---------------------------------------------------------------------------
 
-async fn ws_send(
-    sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
-    msg: &WsServerMessage,
-) -> bool {
+/// Longest a running execution may go without sending a frame — proxies and
+/// load balancers drop WebSockets that sit idle for ~30-60s.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Destination for server messages — the client WebSocket, or a
+/// `ToolLoopCollector` when `POST /api/execute` runs the tool loop.
+type WsSink = dyn futures_util::Sink<WsMessage, Error = axum::Error> + Send + Unpin;
+
+async fn ws_send(sender: &mut WsSink, msg: &WsServerMessage) -> bool {
     if let Ok(json) = serde_json::to_string(msg) {
         sender.send(WsMessage::Text(json.into())).await.is_ok()
     } else {
@@ -194,6 +392,44 @@ async fn ws_send(
     }
 }
 
+/// `gemini_request_with_retry` for a streaming run: sends a `Heartbeat` every
+/// `HEARTBEAT_INTERVAL` until it resolves (backoff between retries can be
+/// long) and forwards each `Retry` notice as the retry is scheduled.
+async fn gemini_request_streamed(
+    sender: &mut WsSink,
+    client: &reqwest::Client,
+    url: &reqwest::Url,
+    ctx: &ExecuteContext,
+    body: &Value,
+) -> Result<reqwest::Response, String> {
+    let (notice_tx, mut notices) = tokio::sync::mpsc::unbounded_channel();
+    let request = gemini_request_with_retry(
+        client,
+        url,
+        &ctx.api_key,
+        ctx.is_oauth,
+        body,
+        &ctx.retry,
+        Some(&notice_tx),
+    );
+    tokio::pin!(request);
+    let mut heartbeat = tokio::time::interval_at(
+        tokio::time::Instant::now() + HEARTBEAT_INTERVAL,
+        HEARTBEAT_INTERVAL,
+    );
+    loop {
+        tokio::select! {
+            out = &mut request => return out,
+            Some(notice) = notices.recv() => {
+                let _ = ws_send(sender, &notice).await;
+            }
+            _ = heartbeat.tick() => {
+                let _ = ws_send(sender, &WsServerMessage::Heartbeat).await;
+            }
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // WebSocket Handler
 // ---------------------------------------------------------------------------
@@ -214,16 +450,32 @@ pub async fn ws_execute(
         return (axum::http::StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     }
 
-    ws.on_upgrade(move |socket| handle_ws(socket, state))
+    // Same ID as the upgrade response's X-Request-Id header
+    let request_id = crate::error::ApiError::current_request_id();
+    ws.on_upgrade(move |socket| handle_ws(socket, state, request_id))
         .into_response()
 }
 
-async fn handle_ws(socket: WebSocket, state: AppState) {
+/// Runs the connection inside a `ws_session` span carrying its correlation
+/// ID, scoped in `error::REQUEST_ID` so `Start` messages can echo it.
+async fn handle_ws(socket: WebSocket, state: AppState, request_id: String) {
+    let span = tracing::info_span!("ws_session", request_id = %request_id);
+    crate::error::REQUEST_ID
+        .scope(request_id, ws_session(socket, state))
+        .instrument(span)
+        .await
+}
+
+async fn ws_session(socket: WebSocket, state: AppState) {
     let (mut sender, mut receiver) = socket.split();
-    let cancel = CancellationToken::new();
+    let cancel = state.shutdown.child_token();
 
     loop {
         tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                let _ = sender.send(WsMessage::Close(None)).await;
+                break;
+            }
             msg = receiver.next() => {
                 match msg {
                     Some(Ok(WsMessage::Text(text))) => {
@@ -236,12 +488,22 @@ async fn handle_ws(socket: WebSocket, state: AppState) {
                         };
                         match client_msg {
                             WsClientMessage::Ping => { let _ = ws_send(&mut sender, &WsServerMessage::Pong).await; }
-                            WsClientMessage::Cancel => { cancel.cancel(); }
-                            WsClientMessage::Execute { prompt, mode, model, session_id } => {
-                                execute_streaming(&mut sender, &state, &prompt, mode, model, session_id, cancel.child_token()).await;
+                            WsClientMessage::Cancel => {} // nothing running
+                            WsClientMessage::Execute { prompt, mode, model, session_id, stop_on_tool_error, temperature, top_p, thinking_level } => {
+                                let overrides = ExecuteOverrides { temperature, top_p, thinking_level, stop_on_tool_error };
+                                let run_cancel = cancel.child_token();
+                                let run = state.executions.track_future(execute_streaming(&mut sender, &state, &prompt, mode, model, session_id, overrides, run_cancel.clone()));
+                                if !run_cancellable(run, &mut receiver, &run_cancel).await { break; }
                             }
                             WsClientMessage::Orchestrate { prompt, pattern, agents, session_id } => {
-                                execute_orchestrated(&mut sender, &state, &prompt, &pattern, agents.as_deref(), session_id, cancel.child_token()).await;
+                                let run_cancel = cancel.child_token();
+                                let run = state.executions.track_future(execute_orchestrated(&mut sender, &state, &prompt, &pattern, agents.as_deref(), session_id, run_cancel.clone()));
+                                if !run_cancellable(run, &mut receiver, &run_cancel).await { break; }
+                            }
+                            WsClientMessage::Ensemble { prompt, agents, model, judge } => {
+                                let run_cancel = cancel.child_token();
+                                let run = state.executions.track_future(execute_ensemble_ws(&mut sender, &state, &prompt, &agents, model, judge, run_cancel.clone()));
+                                if !run_cancellable(run, &mut receiver, &run_cancel).await { break; }
                             }
                             WsClientMessage::ToolResponse { tool_name, response } => {
                                 tracing::info!("Received ToolResponse from client for {}: {}", tool_name, response);
@@ -260,6 +522,37 @@ async fn handle_ws(socket: WebSocket, state: AppState) {
     }
 }
 
+/// Drives `run` while still reading the socket, so a `cancel` sent mid-run
+/// reaches `cancel` (as does the client disconnecting). Other messages are
+/// dropped — the sender is busy with `run`. Returns `false` once the client
+/// has gone away.
+async fn run_cancellable<F: std::future::Future<Output = ()>>(
+    run: F,
+    receiver: &mut futures_util::stream::SplitStream<WebSocket>,
+    cancel: &CancellationToken,
+) -> bool {
+    tokio::pin!(run);
+    let mut connected = true;
+    loop {
+        tokio::select! {
+            _ = &mut run => return connected,
+            msg = receiver.next(), if connected => match msg {
+                Some(Ok(WsMessage::Text(text))) => {
+                    match serde_json::from_str::<WsClientMessage>(&text) {
+                        Ok(WsClientMessage::Cancel) => cancel.cancel(),
+                        _ => tracing::debug!("ws: ignoring message received mid-execution"),
+                    }
+                }
+                Some(Ok(_)) => {}
+                _ => {
+                    connected = false;
+                    cancel.cancel();
+                }
+            },
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ADK Orchestrated Execution (proxy to Python sidecar)
 // ---------------------------------------------------------------------------
@@ -316,6 +609,7 @@ async fn execute_orchestrated(
     {
         Ok(r) => r,
         Err(e) => {
+            let e = redact_secrets(&e.to_string()).into_owned();
             tracing::warn!(
                 "ADK sidecar unreachable ({}), falling back to direct execution",
                 e
@@ -355,7 +649,7 @@ async fn execute_orchestrated(
     let mut buffer = String::new();
     let mut last_author = String::new();
     let mut step_count: u32 = 0;
-    let mut heartbeat = tokio::time::interval(Duration::from_secs(15));
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
 
     loop {
         tokio::select! {
@@ -404,6 +698,114 @@ async fn execute_orchestrated(
     .await;
 }
 
+// ---------------------------------------------------------------------------
+// Ensemble Execution
+// ---------------------------------------------------------------------------
+
+/// Run an agent ensemble and stream each agent's answer as `AgentOutput` as
+/// soon as it is ready, followed by the judge's consensus (agent `"judge"`).
+async fn execute_ensemble_ws(
+    sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
+    state: &AppState,
+    prompt: &str,
+    agents: &[String],
+    model: Option<String>,
+    judge: bool,
+    cancel: CancellationToken,
+) {
+    let start = Instant::now();
+    let agent_ids = match super::ensemble::resolve_ensemble_agents(state, agents).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            let _ = ws_send(
+                sender,
+                &WsServerMessage::Error {
+                    message: e,
+                    code: Some("ENSEMBLE_INVALID".into()),
+                },
+            )
+            .await;
+            return;
+        }
+    };
+
+    let _ = ws_send(
+        sender,
+        &WsServerMessage::OrchestrationStart {
+            pattern: "ensemble".to_string(),
+            agents: agent_ids.clone(),
+        },
+    )
+    .await;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let run = super::ensemble::run_ensemble(state, prompt, &agent_ids, model, judge, Some(tx));
+    tokio::pin!(run);
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+    let outcome = loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                tracing::info!("Ensemble cancelled by client");
+                return;
+            }
+            _ = heartbeat.tick() => {
+                let _ = ws_send(sender, &WsServerMessage::Heartbeat).await;
+            }
+            Some(answer) = rx.recv() => {
+                let _ = ws_send(sender, &ensemble_output(answer)).await;
+            }
+            outcome = &mut run => break outcome,
+        }
+    };
+    // Answers that finished in the same poll as the last branch
+    while let Ok(answer) = rx.try_recv() {
+        let _ = ws_send(sender, &ensemble_output(answer)).await;
+    }
+
+    if let Some(consensus) = outcome.consensus {
+        let _ = ws_send(
+            sender,
+            &WsServerMessage::AgentOutput {
+                agent: "judge".to_string(),
+                content: consensus,
+                is_final: true,
+            },
+        )
+        .await;
+    }
+    if outcome.timed_out {
+        let _ = ws_send(
+            sender,
+            &WsServerMessage::Error {
+                message: "Ensemble deadline reached before all agents finished".into(),
+                code: Some("ENSEMBLE_TIMEOUT".into()),
+            },
+        )
+        .await;
+    }
+    let _ = ws_send(
+        sender,
+        &WsServerMessage::Complete {
+            duration_ms: start.elapsed().as_millis() as u64,
+        },
+    )
+    .await;
+}
+
+fn ensemble_output(answer: crate::models::EnsembleAnswer) -> WsServerMessage {
+    let content = if answer.success {
+        answer.content
+    } else {
+        format!("[error] {}", answer.content)
+    };
+    WsServerMessage::AgentOutput {
+        agent: answer.agent,
+        content,
+        is_final: true,
+    }
+}
+
 /// Translate a single ADK SSE event into WsServerMessage(s).
 async fn translate_adk_event(
     sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
@@ -515,6 +917,7 @@ async fn translate_adk_event(
 // Streaming Execution Engine
 // ---------------------------------------------------------------------------
 
+#[allow(clippy::too_many_arguments)]
 async fn execute_streaming(
     sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
     state: &AppState,
@@ -522,13 +925,28 @@ async fn execute_streaming(
     mode: String,
     model_override: Option<String>,
     session_id: Option<String>,
+    mut overrides: ExecuteOverrides,
     cancel: CancellationToken,
 ) {
     let start = Instant::now();
     let sid = session_id.as_deref().and_then(|s| Uuid::parse_str(s).ok());
 
+    if let Err(message) = overrides.validate() {
+        let _ = ws_send(
+            sender,
+            &WsServerMessage::Error {
+                message,
+                code: Some("INVALID_REQUEST".into()),
+            },
+        )
+        .await;
+        return;
+    }
+
+    let plan_only = mode == PLAN_ONLY_MODE;
+
     // Resolve agent: explicit mode > session lock > classify
-    let agent_info = if !mode.is_empty() && mode != "auto" {
+    let agent_info = if !mode.is_empty() && mode != "auto" && !plan_only {
         let agents = state.agents.read().await;
         agents
             .iter()
@@ -541,31 +959,32 @@ async fn execute_streaming(
                 )
             })
     } else if let Some(s) = &sid {
-        Some(resolve_session_agent(state, s, prompt).await)
+        Some(resolve_session_agent(state, s, prompt, !plan_only).await)
     } else {
         None
     };
 
     // Fetch session WD before prepare_execution so cache key includes correct WD
-    let session_wd: String = if let Some(ref s) = sid {
-        sqlx::query_scalar("SELECT working_directory FROM gh_sessions WHERE id = $1")
-            .bind(s)
-            .fetch_optional(&state.db)
-            .await
-            .ok()
-            .flatten()
-            .unwrap_or_default()
-    } else {
-        String::new()
-    };
-
-    let ctx = prepare_execution(state, prompt, model_override, agent_info, &session_wd).await;
+    let (session_wd, session_model) = session_wd_and_model(state, sid.as_ref()).await;
+
+    let mut ctx = prepare_execution(
+        state,
+        prompt,
+        model_override,
+        session_model,
+        agent_info,
+        &session_wd,
+        plan_only,
+    )
+    .await;
+    ctx.apply_overrides(&overrides);
     let resp_id = Uuid::new_v4();
 
     if !ws_send(
         sender,
         &WsServerMessage::Start {
             id: resp_id.to_string(),
+            request_id: crate::error::ApiError::current_request_id(),
             agent: ctx.agent_id.clone(),
             model: ctx.model.clone(),
             files_loaded: ctx.files_loaded.clone(),
@@ -586,28 +1005,81 @@ async fn execute_streaming(
     )
     .await;
 
-    // Dispatch to Gemini streaming (with fallback to flash on failure)
-    let full_text = execute_streaming_gemini(sender, state, &ctx, sid, cancel.clone()).await;
-    let (full_text, used_model) = if full_text.is_empty() && !ctx.model.contains("flash") {
-        let flash_model = crate::model_registry::get_model_id(state, "flash").await;
-        tracing::warn!(
-            "Model fallback: {} failed, retrying with {}",
-            ctx.model,
-            flash_model
-        );
-        let mut fallback_ctx = ctx.clone();
-        fallback_ctx.model = flash_model;
-        let fb_text = execute_streaming_gemini(sender, state, &fallback_ctx, sid, cancel).await;
-        (fb_text, fallback_ctx.model)
+    // Plan-only: report what would run and stop before any Gemini request
+    if plan_only {
+        let _ = ws_send(sender, &WsServerMessage::PlanPreview(ctx.preview())).await;
+        let _ = ws_send(
+            sender,
+            &WsServerMessage::Complete {
+                duration_ms: start.elapsed().as_millis() as u64,
+            },
+        )
+        .await;
+        return;
+    }
+
+    // Dispatch to Gemini streaming; walk the fallback chain if the primary model fails
+    let mut run = execute_streaming_gemini(sender, state, &ctx, sid, cancel.clone()).await;
+    let mut used_model = ctx.model.clone();
+    if let Some(primary_error) = run.request_error.clone() {
+        for fallback in fallback_chain(state, &ctx).await {
+            if cancel.is_cancelled() {
+                break;
+            }
+            if fallback == ctx.model {
+                continue;
+            }
+            if let Err(msg) = state.model_circuit(&fallback).await.check().await {
+                tracing::warn!("Model fallback: skipping {} — {}", fallback, msg);
+                continue;
+            }
+            tracing::warn!(
+                "Model fallback: {} failed, retrying with {}",
+                used_model,
+                fallback
+            );
+            let _ = ws_send(
+                sender,
+                &WsServerMessage::ModelFallback {
+                    from: ctx.model.clone(),
+                    to: fallback.clone(),
+                    reason: primary_error.clone(),
+                },
+            )
+            .await;
+            let mut fallback_ctx = ctx.clone();
+            fallback_ctx.max_tokens = ctx.max_tokens.min(tier_token_budget(&fallback));
+            fallback_ctx.model = fallback;
+            run = execute_streaming_gemini(sender, state, &fallback_ctx, sid, cancel.clone()).await;
+            used_model = fallback_ctx.model;
+            if run.request_error.is_none() {
+                break;
+            }
+        }
+        if run.request_error.is_some() && !cancel.is_cancelled() {
+            let _ = ws_send(
+                sender,
+                &WsServerMessage::Error {
+                    message: "AI service error".into(),
+                    code: Some("GEMINI_ERROR".into()),
+                },
+            )
+            .await;
+        }
+    }
+    // Keep whatever was produced before a cancel, marked so the history shows it
+    let cancelled = cancel.is_cancelled();
+    let success = !run.text.is_empty() && !cancelled;
+    let full_text = if cancelled {
+        mark_cancelled(&run.text)
     } else {
-        (full_text, ctx.model.clone())
+        run.text
     };
 
-    store_messages(&state.db, sid, resp_id, prompt, &full_text, &ctx).await;
+    store_messages(state, sid, resp_id, prompt, &full_text, &ctx).await;
 
     // Token usage tracking — fire-and-forget INSERT
     let latency = start.elapsed().as_millis() as i32;
-    let success = !full_text.is_empty();
     let input_est = (prompt.len() / 4) as i32;
     let output_est = (full_text.len() / 4) as i32;
     let db = state.db.clone();
@@ -633,20 +1105,138 @@ async fn execute_streaming(
         }
     });
 
-    let _ = ws_send(
-        sender,
-        &WsServerMessage::Complete {
-            duration_ms: start.elapsed().as_millis() as u64,
-        },
-    )
-    .await;
+    if state.shutdown.is_cancelled() {
+        let _ = ws_send(
+            sender,
+            &WsServerMessage::Error {
+                message: "Server is shutting down — the partial response was saved".into(),
+                code: Some("SERVER_SHUTDOWN".into()),
+            },
+        )
+        .await;
+    }
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let done = if cancelled {
+        WsServerMessage::Cancelled { duration_ms }
+    } else {
+        WsServerMessage::Complete { duration_ms }
+    };
+    let _ = ws_send(sender, &done).await;
+}
+
+/// Appended to an assistant message whose execution was cancelled part-way.
+const CANCELLED_MARKER: &str = "[cancelled]";
+
+fn mark_cancelled(partial: &str) -> String {
+    let partial = partial.trim_end();
+    if partial.is_empty() {
+        CANCELLED_MARKER.to_string()
+    } else {
+        format!("{}\n\n{}", partial, CANCELLED_MARKER)
+    }
+}
+
+// ── Non-streaming tool loop ─────────────────────────────────────────────────
+
+/// Stands in for the WebSocket when the tool loop runs without a client,
+/// keeping only what the HTTP response reports.
+#[derive(Default)]
+struct ToolLoopCollector {
+    tools_used: Vec<String>,
+    last_error: Option<String>,
+}
+
+impl futures_util::Sink<WsMessage> for ToolLoopCollector {
+    type Error = axum::Error;
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: std::pin::Pin<&mut Self>, item: WsMessage) -> Result<(), Self::Error> {
+        let WsMessage::Text(text) = item else {
+            return Ok(());
+        };
+        let this = self.get_mut();
+        match serde_json::from_str::<WsServerMessage>(text.as_str()) {
+            Ok(WsServerMessage::ToolCall { name, .. }) => this.tools_used.push(name),
+            Ok(WsServerMessage::Error { message, .. }) => this.last_error = Some(message),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+/// Result of `execute_with_tools`.
+pub(crate) struct ToolLoopOutcome {
+    pub text: String,
+    /// Tools called, in order
+    pub tools_used: Vec<String>,
+    /// Last error the loop reported (model failure, aborted tool chain)
+    pub error: Option<String>,
+}
+
+/// Runs the WebSocket tool loop to completion without a client, walking the
+/// fallback chain the same way `execute_streaming` does.
+pub(crate) async fn execute_with_tools(
+    state: &AppState,
+    ctx: &ExecuteContext,
+    sid: Option<Uuid>,
+) -> ToolLoopOutcome {
+    let mut collector = ToolLoopCollector::default();
+    let cancel = state.shutdown.child_token();
+    let mut run = execute_streaming_gemini(&mut collector, state, ctx, sid, cancel.clone()).await;
+    if run.request_error.is_some() {
+        for fallback in fallback_chain(state, ctx).await {
+            if fallback == ctx.model || state.model_circuit(&fallback).await.check().await.is_err()
+            {
+                continue;
+            }
+            tracing::warn!(
+                "execute_with_tools: {} failed, retrying with {}",
+                ctx.model,
+                fallback
+            );
+            let mut fallback_ctx = ctx.clone();
+            fallback_ctx.max_tokens = ctx.max_tokens.min(tier_token_budget(&fallback));
+            fallback_ctx.model = fallback;
+            run =
+                execute_streaming_gemini(&mut collector, state, &fallback_ctx, sid, cancel.clone())
+                    .await;
+            if run.request_error.is_none() {
+                break;
+            }
+        }
+    }
+    ToolLoopOutcome {
+        text: run.text,
+        tools_used: collector.tools_used,
+        error: run.request_error.or(collector.last_error),
+    }
 }
 
 // ── Gemini retry with exponential backoff ───────────────────────────────────
 // Jaskier Shared Pattern -- gemini_retry
 
 /// Whether a reqwest error or HTTP status is a transient failure worth retrying.
-fn is_retryable(result: &Result<reqwest::Response, reqwest::Error>) -> bool {
+pub(crate) fn is_retryable(result: &Result<reqwest::Response, reqwest::Error>) -> bool {
     match result {
         Ok(resp) => {
             let status = resp.status().as_u16();
@@ -656,7 +1246,14 @@ fn is_retryable(result: &Result<reqwest::Response, reqwest::Error>) -> bool {
     }
 }
 
+/// Delay before retry `attempt` (1-based) with the default retry policy.
+pub(crate) fn gemini_backoff(attempt: u32) -> Duration {
+    RetryPolicy::default().backoff(attempt)
+}
+
 /// Send a streaming Gemini API request with retry + exponential backoff.
+/// Each retry is announced on `notices` (as `WsServerMessage::Retry`) before
+/// its backoff sleep; pass `None` to only log them.
 /// Returns the successful response, or the last error after all retries are exhausted.
 async fn gemini_request_with_retry(
     client: &reqwest::Client,
@@ -664,22 +1261,27 @@ async fn gemini_request_with_retry(
     api_key: &str,
     is_oauth: bool,
     body: &Value,
+    policy: &RetryPolicy,
+    notices: Option<&tokio::sync::mpsc::UnboundedSender<WsServerMessage>>,
 ) -> Result<reqwest::Response, String> {
     let mut last_err = String::new();
 
-    for attempt in 0..=GEMINI_MAX_RETRIES {
+    for attempt in 0..=policy.max_retries {
         if attempt > 0 {
-            // Exponential backoff: base * 2^(attempt-1) + random jitter
-            let backoff = GEMINI_BACKOFF_BASE * 2u32.saturating_pow(attempt - 1);
-            let jitter =
-                Duration::from_millis(rand::thread_rng().gen_range(0..=GEMINI_BACKOFF_JITTER_MS));
-            let delay = backoff + jitter;
+            let delay = policy.backoff(attempt);
             tracing::warn!(
                 "gemini_retry: attempt {}/{} after {:?} backoff",
                 attempt + 1,
-                GEMINI_MAX_RETRIES + 1,
+                policy.max_retries + 1,
                 delay
             );
+            if let Some(tx) = notices {
+                let _ = tx.send(WsServerMessage::Retry {
+                    attempt: attempt + 1,
+                    max: policy.max_retries + 1,
+                    delay_ms: delay.as_millis() as u64,
+                });
+            }
             tokio::time::sleep(delay).await;
         }
 
@@ -705,17 +1307,20 @@ async fn gemini_request_with_retry(
                     Err(format!(
                         "Gemini API error ({}): {}",
                         status,
-                        &err_body[..safe_len]
+                        redact_secrets(&err_body[..safe_len])
                     ))
                 }
-                Err(e) => Err(format!("Gemini API request failed: {:?}", e)),
+                Err(e) => Err(format!(
+                    "Gemini API request failed: {}",
+                    redact_secrets(&format!("{:?}", e))
+                )),
             };
         }
 
         // Retryable — log and loop.
         last_err = match &result {
             Ok(resp) => format!("HTTP {}", resp.status()),
-            Err(e) => format!("{:?}", e),
+            Err(e) => redact_secrets(&format!("{:?}", e)).into_owned(),
         };
         tracing::warn!(
             "gemini_retry: transient error on attempt {}: {}",
@@ -726,20 +1331,47 @@ async fn gemini_request_with_retry(
 
     Err(format!(
         "Gemini API failed after {} attempts — last error: {}",
-        GEMINI_MAX_RETRIES + 1,
+        policy.max_retries + 1,
         last_err
     ))
 }
 
 // ── Gemini Implementation ──────────────────────────────────────────────────
 
+/// Outcome of one `execute_streaming_gemini` run.
+struct GeminiRun {
+    text: String,
+    /// Set when the model failed (or its circuit was open) before anything was
+    /// streamed — the caller may retry the whole run on a fallback model.
+    request_error: Option<String>,
+}
+
+impl GeminiRun {
+    fn done(text: String) -> Self {
+        Self {
+            text,
+            request_error: None,
+        }
+    }
+}
+
+/// Models to try after `ctx.model` fails: the `fallback_models` setting, or
+/// the flash tier model when it is empty.
+async fn fallback_chain(state: &AppState, ctx: &ExecuteContext) -> Vec<String> {
+    if ctx.fallback_models.is_empty() {
+        vec![crate::model_registry::get_model_id(state, "flash").await]
+    } else {
+        ctx.fallback_models.clone()
+    }
+}
+
 async fn execute_streaming_gemini(
-    sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
+    sender: &mut WsSink,
     state: &AppState,
     ctx: &ExecuteContext,
     sid: Option<Uuid>,
     cancel: CancellationToken,
-) -> String {
+) -> GeminiRun {
     if ctx.api_key.is_empty() {
         let _ = ws_send(
             sender,
@@ -749,7 +1381,7 @@ async fn execute_streaming_gemini(
             },
         )
         .await;
-        return String::new();
+        return GeminiRun::done(String::new());
     }
 
     // Circuit breaker — fail fast if the Gemini provider is tripped.
@@ -763,12 +1395,22 @@ async fn execute_streaming_gemini(
             },
         )
         .await;
-        return String::new();
+        return GeminiRun::done(String::new());
+    }
+    let model_circuit = state.model_circuit(&ctx.model).await;
+    if let Err(msg) = model_circuit.check().await {
+        tracing::warn!("execute_streaming_gemini: {}", msg);
+        return GeminiRun {
+            text: String::new(),
+            request_error: Some(msg),
+        };
     }
 
     let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse",
-        ctx.model
+        "{}?alt=sse",
+        state
+            .providers
+            .gemini_model(&ctx.model, "streamGenerateContent")
     );
     let parsed_url = match reqwest::Url::parse(&url) {
         Ok(u) if u.scheme() == "https" => u,
@@ -781,16 +1423,18 @@ async fn execute_streaming_gemini(
                 },
             )
             .await;
-            return String::new();
+            return GeminiRun::done(String::new());
         }
     };
     let tools = build_tools_with_mcp(state).await;
     let mut contents = if let Some(s) = &sid {
-        load_session_history(&state.db, s).await
+        load_session_history(&state.db, s, ctx.history_window, ctx.history_truncate_keep).await
     } else {
         Vec::new()
     };
     contents.push(json!({ "role": "user", "parts": [{ "text": ctx.final_user_prompt }] }));
+    let mut prompt_index = contents.len() - 1;
+    let context_budget = context_byte_budget(&ctx.model);
 
     // #36 — Dynamic max iterations based on prompt complexity
     // The dynamic floor ensures complex multi-step tasks get enough iterations even if the
@@ -810,6 +1454,7 @@ async fn execute_streaming_gemini(
     let mut has_written_file = false;
     let mut agent_text_len: usize = 0;
     let mut loop_ended_naturally = true; // true if we exhausted max_iterations without break
+    let mut failed_tool: Option<String> = None; // set when stop_on_tool_error aborts the loop
     let mut approx_context_bytes: usize = contents
         .iter()
         .map(|c| serde_json::to_string(c).map(|s| s.len()).unwrap_or(0))
@@ -820,6 +1465,10 @@ async fn execute_streaming_gemini(
     let execution_timeout = Duration::from_secs(300);
 
     for iter in 0..max_iterations {
+        if cancel.is_cancelled() {
+            loop_ended_naturally = false;
+            break;
+        }
         // #39 — Check elapsed time at the start of each iteration
         if execution_start.elapsed() >= execution_timeout {
             tracing::warn!(
@@ -849,38 +1498,70 @@ async fn execute_streaming_gemini(
         )
         .await;
 
+        // Drop the oldest turns before the request outgrows the context window
+        if approx_context_bytes > context_budget
+            && let Some(trim) =
+                fit_contents_to_budget(&mut contents, &mut prompt_index, context_budget)
+        {
+            tracing::warn!(
+                "execute_streaming_gemini: context trimmed on iter {} — {} messages dropped, {} results shortened ({}KB -> {}KB, budget {}KB)",
+                iter,
+                trim.dropped_messages,
+                trim.shrunk_results,
+                trim.bytes_before / 1024,
+                trim.bytes_after / 1024,
+                context_budget / 1024
+            );
+            approx_context_bytes = trim.bytes_after;
+            let _ = ws_send(
+                sender,
+                &WsServerMessage::ContextTrimmed {
+                    dropped_messages: trim.dropped_messages as u32,
+                    shrunk_results: trim.shrunk_results as u32,
+                    bytes_before: trim.bytes_before as u64,
+                    bytes_after: trim.bytes_after as u64,
+                    budget_bytes: context_budget as u64,
+                },
+            )
+            .await;
+        }
+
         let mut gen_config = json!({
             "temperature": ctx.temperature,
             "topP": ctx.top_p,
             "maxOutputTokens": ctx.max_tokens
         });
-        if let Some(tc) = build_thinking_config(&ctx.model, &ctx.thinking_level) {
+        if let Some(tc) = stream_thinking_config(&ctx.model, &ctx.thinking_level) {
             gen_config["thinkingConfig"] = tc;
         }
-        let body = json!({
+        let mut body = json!({
             "systemInstruction": { "parts": [{ "text": ctx.system_prompt }] },
             "contents": contents,
             "tools": tools,
             "generationConfig": gen_config
         });
+        apply_safety_settings(&mut body, &ctx.safety_level);
 
         // Use retry-with-backoff helper; circuit breaker is updated on success/failure.
-        let resp = match gemini_request_with_retry(
-            &state.client,
-            &parsed_url,
-            &ctx.api_key,
-            ctx.is_oauth,
-            &body,
-        )
-        .await
-        {
+        let request = gemini_request_streamed(sender, &state.client, &parsed_url, ctx, &body);
+        let resp = match request.await {
             Ok(r) => {
                 state.gemini_circuit.record_success().await;
+                model_circuit.record_success().await;
                 r
             }
             Err(e) => {
                 state.gemini_circuit.record_failure().await;
+                model_circuit.record_failure().await;
                 tracing::error!("{}", e);
+
+                // #38 — Nothing streamed yet: let the caller walk the fallback chain
+                if full_text.is_empty() {
+                    return GeminiRun {
+                        text: full_text,
+                        request_error: Some(e),
+                    };
+                }
                 let _ = ws_send(
                     sender,
                     &WsServerMessage::Error {
@@ -889,40 +1570,55 @@ async fn execute_streaming_gemini(
                     },
                 )
                 .await;
-
-                // #38 — Model fallback chain: try gemini-2.5-flash if primary model failed
-                if full_text.is_empty() && ctx.model != "gemini-2.5-flash" {
-                    tracing::warn!(
-                        "Primary model {} failed, trying gemini-2.5-flash fallback",
-                        ctx.model
-                    );
-                    let fallback_url_str = "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse";
-                    if let Ok(fallback_url) = reqwest::Url::parse(fallback_url_str)
-                        && let Ok(fallback_resp) = gemini_request_with_retry(
-                            &state.client,
-                            &fallback_url,
-                            &ctx.api_key,
-                            ctx.is_oauth,
-                            &body,
-                        )
-                        .await
-                    {
-                        state.gemini_circuit.record_success().await;
-                        let (fallback_text, _, _, _) =
-                            consume_gemini_stream(fallback_resp, sender, &cancel).await;
-                        full_text.push_str(&fallback_text);
-                    }
-                }
-
-                return full_text;
+                return GeminiRun::done(full_text);
             }
         };
 
-        let (text, fcs, aborted, malformed) = consume_gemini_stream(resp, sender, &cancel).await;
+        let (text, mut fcs, aborted, malformed) =
+            consume_gemini_stream(resp, sender, &cancel).await;
         full_text.push_str(&text);
+        if text.is_empty() && fcs.is_empty() && !aborted && malformed.is_none() {
+            // Usually a safety block (see the blockReason warning above)
+            tracing::warn!(
+                "stream: iter {} produced no output (safety_level={})",
+                iter,
+                ctx.safety_level
+            );
+        }
         agent_text_len += text.trim().len();
 
-        // Retry without tools if Gemini generated a malformed function call
+        // Repair args that don't match the declared schema before executing them
+        for (name, args, raw) in fcs.iter_mut() {
+            let Some(schema) = find_tool_schema(&tools, name) else {
+                continue;
+            };
+            if let Err(e) = validate_tool_args(schema, args) {
+                tracing::warn!("tool '{}' args failed schema check: {}", name, e);
+                if let Some(fixed) =
+                    repair_tool_args(state, ctx, name, schema, &args.to_string()).await
+                {
+                    raw["functionCall"]["args"] = fixed.clone();
+                    *args = fixed;
+                }
+            }
+        }
+
+        // A malformed call names its tool in `finishMessage` — try to recover
+        // the intended action before discarding the turn.
+        let malformed = match malformed {
+            Some(message) if fcs.is_empty() && !aborted => {
+                match recover_malformed_call(state, ctx, &tools, &message).await {
+                    Some(fc) => {
+                        fcs.push(fc);
+                        false
+                    }
+                    None => true,
+                }
+            }
+            other => other.is_some(),
+        };
+
+        // Retry without tools if the malformed function call couldn't be repaired
         if malformed && full_text.trim().is_empty() {
             tracing::warn!(
                 "MALFORMED_FUNCTION_CALL on iter {}, retrying without tools",
@@ -933,22 +1629,17 @@ async fn execute_streaming_gemini(
                 "topP": ctx.top_p,
                 "maxOutputTokens": ctx.max_tokens
             });
-            if let Some(tc) = build_thinking_config(&ctx.model, &ctx.thinking_level) {
+            if let Some(tc) = stream_thinking_config(&ctx.model, &ctx.thinking_level) {
                 gen_config_retry["thinkingConfig"] = tc;
             }
-            let retry_body = json!({
+            let mut retry_body = json!({
                 "systemInstruction": { "parts": [{ "text": format!("{}\n\nIMPORTANT: Answer this question directly using your knowledge. Do NOT attempt to call any tools or functions.", ctx.system_prompt) }] },
                 "contents": contents,
                 "generationConfig": gen_config_retry
             });
-            if let Ok(retry_resp) = gemini_request_with_retry(
-                &state.client,
-                &parsed_url,
-                &ctx.api_key,
-                ctx.is_oauth,
-                &retry_body,
-            )
-            .await
+            apply_safety_settings(&mut retry_body, &ctx.safety_level);
+            if let Ok(retry_resp) =
+                gemini_request_streamed(sender, &state.client, &parsed_url, ctx, &retry_body).await
             {
                 let (retry_text, _, _, _) =
                     consume_gemini_stream(retry_resp, sender, &cancel).await;
@@ -1021,12 +1712,25 @@ async fn execute_streaming_gemini(
             .await;
         }
 
-        // Execute all tool calls concurrently using tokio::join_all.
+        // Execute all tool calls concurrently using tokio::join_all, bounded by
+        // `tool_concurrency()` (and `agent_call_concurrency()` for call_agent).
         // Each call is wrapped in a per-tool timeout so one hanging tool
         // doesn't block the entire iteration.
         // Heartbeat messages are sent every 15s to prevent proxy/LB timeouts.
         let call_depth = ctx.call_depth;
+        let call_chain: Vec<String> = ctx
+            .call_chain
+            .iter()
+            .chain([&ctx.agent_id])
+            .cloned()
+            .collect();
         let wd = ctx.working_directory.clone();
+        let tool_permits = Arc::new(Semaphore::new(tool_concurrency()));
+        let agent_permits = Arc::new(Semaphore::new(agent_call_concurrency()));
+        let caller = crate::tools::ToolCaller {
+            agent_id: Some(ctx.agent_id.clone()),
+            session_id: sid.map(|s| s.to_string()),
+        };
         let tool_futures: Vec<_> = fcs
             .iter()
             .map(|(name, args, _)| {
@@ -1034,39 +1738,54 @@ async fn execute_streaming_gemini(
                 let args = args.clone();
                 let state = state.clone();
                 let wd = wd.clone();
+                let caller = caller.clone();
+                let call_chain = call_chain.clone();
+                let permits = if name == "call_agent" {
+                    agent_permits.clone()
+                } else {
+                    tool_permits.clone()
+                };
                 async move {
+                    // The timeout starts once a slot is free, not while queued
+                    let _permit = permits.acquire().await.ok();
                     if name == "call_agent" {
-                        // A2A agent delegation — longer timeout (120s), depth tracking
+                        // A2A agent delegation — longer timeout (120s), depth + cycle tracking
                         match tokio::time::timeout(
                             Duration::from_secs(120),
-                            crate::a2a::execute_agent_call(&state, &args, call_depth),
+                            crate::a2a::execute_agent_call(&state, &args, call_depth, call_chain),
                         )
                         .await
                         {
                             Ok(Ok(text)) => (name, crate::tools::ToolOutput::text(text)),
                             Ok(Err(e)) => (
                                 name,
-                                crate::tools::ToolOutput::text(format!("AGENT_CALL_ERROR: {}", e)),
+                                crate::tools::ToolOutput {
+                                    success: false,
+                                    ..crate::tools::ToolOutput::text(format!(
+                                        "AGENT_CALL_ERROR: {}",
+                                        e
+                                    ))
+                                },
                             ),
                             Err(_) => (
                                 name,
-                                crate::tools::ToolOutput::text(
-                                    "AGENT_CALL_ERROR: timed out after 120s".to_string(),
-                                ),
+                                crate::tools::ToolOutput {
+                                    success: false,
+                                    ..crate::tools::ToolOutput::text(
+                                        "AGENT_CALL_ERROR: timed out after 120s".to_string(),
+                                    )
+                                },
                             ),
                         }
                     } else {
                         match tokio::time::timeout(
                             TOOL_TIMEOUT,
-                            crate::tools::execute_tool(&name, &args, &state, &wd),
+                            crate::tools::execute_tool(&name, &args, &state, &wd, &caller),
                         )
                         .await
                         {
                             Ok(Ok(output)) => (name, output),
-                            Ok(Err(e)) => (
-                                name,
-                                crate::tools::ToolOutput::text(format!("TOOL_ERROR: {}", e)),
-                            ),
+                            Ok(Err(e)) => (name, crate::tools::ToolOutput::error(e)),
                             Err(_) => {
                                 tracing::warn!(
                                     "tool '{}' timed out after {}s",
@@ -1075,8 +1794,8 @@ async fn execute_streaming_gemini(
                                 );
                                 (
                                     name,
-                                    crate::tools::ToolOutput::text(format!(
-                                        "TOOL_ERROR: timed out after {}s",
+                                    crate::tools::ToolOutput::error(format!(
+                                        "timed out after {}s",
                                         TOOL_TIMEOUT.as_secs()
                                     )),
                                 )
@@ -1091,7 +1810,7 @@ async fn execute_streaming_gemini(
         // Heartbeat keeps the WS alive during long tool executions (prevents proxy timeouts).
         let mut tools_handle =
             tokio::spawn(async move { futures_util::future::join_all(tool_futures).await });
-        let mut heartbeat_interval = tokio::time::interval(Duration::from_secs(15));
+        let mut heartbeat_interval = tokio::time::interval(HEARTBEAT_INTERVAL);
         heartbeat_interval.tick().await; // consume immediate first tick
         let tool_results = loop {
             tokio::select! {
@@ -1104,34 +1823,33 @@ async fn execute_streaming_gemini(
             }
         };
 
-        // Gemini 3 Thought Signatures: build name→signature map from function call parts.
-        // raw_part (from SseParsedEvent::FunctionCall) is part.clone() which captures
-        // thoughtSignature if present. Must echo back on functionResponse parts (400 if missing).
-        let sig_map: std::collections::HashMap<&str, &Value> = fcs
-            .iter()
-            .filter_map(|(name, _, raw)| {
-                raw.get("thoughtSignature").map(|sig| (name.as_str(), sig))
-            })
-            .collect();
+        // Gemini 3 Thought Signatures: raw_part (from SseParsedEvent::FunctionCall) is
+        // part.clone() which captures thoughtSignature if present. Must echo back on
+        // functionResponse parts (400 if missing). Matched by position, not by name.
+        let signatures = thought_signatures(&fcs);
+        if tool_results.len() != fcs.len() {
+            tracing::warn!(
+                "stream: {} tool results for {} function calls — thought signatures not echoed",
+                tool_results.len(),
+                fcs.len()
+            );
+        }
 
         // Track file-modifying tool usage (write_file or edit_file) — only on success
         for (name, output) in &tool_results {
-            if (name == "write_file" || name == "edit_file")
-                && !output.text.starts_with("TOOL_ERROR:")
-            {
+            if (name == "write_file" || name == "edit_file") && output.success {
                 has_written_file = true;
             }
         }
 
         // Stream results to frontend + build Gemini context
         let mut res_parts = Vec::new();
-        for (name, output) in &tool_results {
-            let success = !output.text.starts_with("TOOL_ERROR:");
+        for (i, (name, output)) in tool_results.iter().enumerate() {
             let _ = ws_send(
                 sender,
                 &WsServerMessage::ToolResult {
                     name: name.clone(),
-                    success,
+                    success: output.success,
                     summary: output.text.chars().take(200).collect(),
                     iteration: iter as u32 + 1,
                 },
@@ -1142,8 +1860,8 @@ async fn execute_streaming_gemini(
             full_text.push_str(&header);
             let _ = ws_send(sender, &WsServerMessage::Token { content: header }).await;
 
-            let res_md = format!("```\n{}\n```\n---\n\n", output.text);
-            full_text.push_str(&res_md);
+            full_text.push_str(&tool_result_md(&output.text));
+            let res_md = tool_result_display_md(&output.text, ctx.max_tool_display_bytes);
             let _ = ws_send(sender, &WsServerMessage::Token { content: res_md }).await;
 
             // #26 — Dynamic context limit based on iteration (earlier = more generous)
@@ -1164,8 +1882,10 @@ async fn execute_streaming_gemini(
                 });
             }
             // Attach thought signature from corresponding function call (Gemini 3 requirement)
-            if let Some(sig) = sig_map.get(name.as_str()) {
-                fn_response["thoughtSignature"] = (*sig).clone();
+            if tool_results.len() == fcs.len()
+                && let Some(sig) = signature_for_response(&signatures, &fcs, i, name)
+            {
+                fn_response["thoughtSignature"] = sig.clone();
             }
             res_parts.push(fn_response);
         }
@@ -1229,6 +1949,28 @@ async fn execute_streaming_gemini(
         if let Some(last) = contents.last() {
             approx_context_bytes += serde_json::to_string(last).map(|s| s.len()).unwrap_or(0);
         }
+
+        // Abort on the first failing tool when later steps depend on it succeeding
+        if ctx.stop_on_tool_error
+            && let Some((name, _)) = tool_results.iter().find(|(_, output)| !output.success)
+        {
+            tracing::warn!(
+                "execute_streaming_gemini: tool '{}' failed on iter {} — stopping (stop_on_tool_error)",
+                name,
+                iter
+            );
+            let _ = ws_send(
+                sender,
+                &WsServerMessage::Error {
+                    message: format!("Tool '{}' failed — execution stopped", name),
+                    code: Some("TOOL_ERROR".into()),
+                },
+            )
+            .await;
+            failed_tool = Some(name.clone());
+            loop_ended_naturally = false;
+            break;
+        }
     }
 
     // #34a — Write-phase enforcement: if agent described a fix but never called edit_file/write_file,
@@ -1249,7 +1991,13 @@ async fn execute_streaming_gemini(
             || (lower.contains("applied") && lower.contains("fix"))
             || (lower.contains("updated") && lower.contains("code"))
     };
-    if !has_written_file && !full_text.is_empty() && agent_text_len > 50 && describes_fix {
+    // Skipped after a stop_on_tool_error abort — writing past a failed step is what it prevents.
+    if failed_tool.is_none()
+        && !has_written_file
+        && !full_text.is_empty()
+        && agent_text_len > 50
+        && describes_fix
+    {
         tracing::info!(
             "execute_streaming_gemini: agent described a fix but never applied it — forcing edit phase"
         );
@@ -1264,7 +2012,7 @@ async fn execute_streaming_gemini(
             "topP": ctx.top_p,
             "maxOutputTokens": ctx.max_tokens
         });
-        if let Some(tc) = build_thinking_config(&ctx.model, &ctx.thinking_level) {
+        if let Some(tc) = stream_thinking_config(&ctx.model, &ctx.thinking_level) {
             gen_config["thinkingConfig"] = tc;
         }
         let edit_only_tools = json!([{
@@ -1285,20 +2033,15 @@ async fn execute_streaming_gemini(
                 }, "required": ["path", "content"] }
             }]
         }]);
-        let body = json!({
+        let mut body = json!({
             "systemInstruction": { "parts": [{ "text": &ctx.system_prompt }] },
             "contents": contents,
             "tools": edit_only_tools,
             "generationConfig": gen_config
         });
-        if let Ok(resp) = gemini_request_with_retry(
-            &state.client,
-            &parsed_url,
-            &ctx.api_key,
-            ctx.is_oauth,
-            &body,
-        )
-        .await
+        apply_safety_settings(&mut body, &ctx.safety_level);
+        if let Ok(resp) =
+            gemini_request_streamed(sender, &state.client, &parsed_url, ctx, &body).await
         {
             state.gemini_circuit.record_success().await;
             let (write_text, write_fcs, _, _) = consume_gemini_stream(resp, sender, &cancel).await;
@@ -1312,7 +2055,16 @@ async fn execute_streaming_gemini(
                     );
                     match tokio::time::timeout(
                         TOOL_TIMEOUT,
-                        crate::tools::execute_tool(name, args, state, &ctx.working_directory),
+                        crate::tools::execute_tool(
+                            name,
+                            args,
+                            state,
+                            &ctx.working_directory,
+                            &crate::tools::ToolCaller {
+                                agent_id: Some(ctx.agent_id.clone()),
+                                session_id: sid.map(|s| s.to_string()),
+                            },
+                        ),
                     )
                     .await
                     {
@@ -1321,8 +2073,9 @@ async fn execute_streaming_gemini(
                             full_text.push_str(&header);
                             let _ =
                                 ws_send(sender, &WsServerMessage::Token { content: header }).await;
-                            let res_md = format!("```\n{}\n```\n---\n\n", output.text);
-                            full_text.push_str(&res_md);
+                            full_text.push_str(&tool_result_md(&output.text));
+                            let res_md =
+                                tool_result_display_md(&output.text, ctx.max_tool_display_bytes);
                             let _ =
                                 ws_send(sender, &WsServerMessage::Token { content: res_md }).await;
                         }
@@ -1342,7 +2095,11 @@ async fn execute_streaming_gemini(
     // (loop_ended_naturally=true), it never got to write a synthesis. Force one final Gemini call
     // WITHOUT tools so it summarizes all gathered data into a proper report.
     // Also trigger if agent produced minimal meaningful text (fallback for edge cases).
-    let needs_synthesis = if loop_ended_naturally && !full_text.is_empty() {
+    let needs_synthesis = if cancel.is_cancelled() {
+        false
+    } else if failed_tool.is_some() {
+        true
+    } else if loop_ended_naturally && !full_text.is_empty() {
         tracing::info!(
             "execute_streaming_gemini: loop exhausted max_iterations ({}) — agent was still calling tools, forcing synthesis",
             max_iterations
@@ -1374,34 +2131,32 @@ async fn execute_streaming_gemini(
         }
     };
     if needs_synthesis {
+        let synthesis_prompt = match &failed_tool {
+            Some(name) => format!(
+                "[SYSTEM: Tool `{}` returned an error and execution was stopped before any further steps ran. Do NOT call tools. Explain to the user what you were doing, which step failed and why, what was already changed before the failure, and what they should do next.]",
+                name
+            ),
+            None => "[SYSTEM: You called tools and gathered data but did NOT write any text response. Write your comprehensive structured report NOW. If you applied a fix with edit_file or write_file, explain: what the bug was, what you changed (before/after), and file paths with line numbers. If you did NOT apply a fix, explain what you found and what needs to be changed. Use headers (##), bullet points, tables, and code refs.]".to_string(),
+        };
         contents.push(json!({
             "role": "user",
-            "parts": [{
-                "text": "[SYSTEM: You called tools and gathered data but did NOT write any text response. Write your comprehensive structured report NOW. If you applied a fix with edit_file or write_file, explain: what the bug was, what you changed (before/after), and file paths with line numbers. If you did NOT apply a fix, explain what you found and what needs to be changed. Use headers (##), bullet points, tables, and code refs.]"
-            }]
+            "parts": [{ "text": synthesis_prompt }]
         }));
         let mut gen_config = json!({
             "temperature": ctx.temperature,
             "topP": ctx.top_p,
             "maxOutputTokens": ctx.max_tokens
         });
-        if let Some(tc) = build_thinking_config(&ctx.model, &ctx.thinking_level) {
+        if let Some(tc) = stream_thinking_config(&ctx.model, &ctx.thinking_level) {
             gen_config["thinkingConfig"] = tc;
         }
-        let body = json!({
+        let mut body = json!({
             "systemInstruction": { "parts": [{ "text": &ctx.system_prompt }] },
             "contents": contents,
             "generationConfig": gen_config
         });
-        match gemini_request_with_retry(
-            &state.client,
-            &parsed_url,
-            &ctx.api_key,
-            ctx.is_oauth,
-            &body,
-        )
-        .await
-        {
+        apply_safety_settings(&mut body, &ctx.safety_level);
+        match gemini_request_streamed(sender, &state.client, &parsed_url, ctx, &body).await {
             Ok(resp) => {
                 state.gemini_circuit.record_success().await;
                 let (synth_text, synth_fcs, _, _) =
@@ -1422,36 +2177,59 @@ async fn execute_streaming_gemini(
         }
     }
 
-    full_text
+    GeminiRun::done(full_text)
+}
+
+/// `build_thinking_config` plus `includeThoughts`, so the stream carries the
+/// model's thinking summaries as `thought: true` parts.
+fn stream_thinking_config(model: &str, thinking_level: &str) -> Option<Value> {
+    let mut tc = build_thinking_config(model, thinking_level)?;
+    tc["includeThoughts"] = json!(true);
+    Some(tc)
 }
 
 /// Returns (text, function_calls, aborted, malformed_tool_call)
 async fn consume_gemini_stream(
     resp: reqwest::Response,
-    sender: &mut futures_util::stream::SplitSink<WebSocket, WsMessage>,
+    sender: &mut WsSink,
     cancel: &CancellationToken,
-) -> (String, Vec<(String, Value, Value)>, bool, bool) {
+) -> (String, Vec<(String, Value, Value)>, bool, Option<String>) {
     let mut parser = SseParser::new();
     let mut stream = resp.bytes_stream();
     let mut full_text = String::new();
     let mut fcs = Vec::new();
-    let mut malformed = false;
+    let mut malformed: Option<String> = None;
     let mut stream_error = false;
+    // A thinking-only stretch can go a long time without a token — heartbeat
+    // whenever nothing has been sent for HEARTBEAT_INTERVAL.
+    let heartbeat = tokio::time::sleep(HEARTBEAT_INTERVAL);
+    tokio::pin!(heartbeat);
 
     loop {
         tokio::select! {
             _ = cancel.cancelled() => return (full_text, fcs, true, malformed),
+            _ = &mut heartbeat => {
+                let _ = ws_send(sender, &WsServerMessage::Heartbeat).await;
+                heartbeat.as_mut().reset(tokio::time::Instant::now() + HEARTBEAT_INTERVAL);
+            }
             chunk = stream.next() => {
                 match chunk {
                     Some(Ok(b)) => {
-                        for ev in parser.feed(&String::from_utf8_lossy(&b)) {
+                        for ev in parser.feed(&b) {
                             match ev {
                                 SseParsedEvent::TextToken(t) => {
                                     full_text.push_str(&t);
                                     let _ = ws_send(sender, &WsServerMessage::Token { content: t }).await;
+                                    heartbeat.as_mut().reset(tokio::time::Instant::now() + HEARTBEAT_INTERVAL);
+                                }
+                                SseParsedEvent::Thought(t) => {
+                                    let _ = ws_send(sender, &WsServerMessage::Thought { content: t }).await;
+                                    heartbeat.as_mut().reset(tokio::time::Instant::now() + HEARTBEAT_INTERVAL);
                                 }
                                 SseParsedEvent::FunctionCall { name, args, raw_part } => fcs.push((name, args, raw_part)),
-                                SseParsedEvent::MalformedFunctionCall => malformed = true,
+                                SseParsedEvent::MalformedFunctionCall(msg) => {
+                                    malformed = Some(msg.unwrap_or_default())
+                                }
                             }
                         }
                     }
@@ -1464,8 +2242,13 @@ async fn consume_gemini_stream(
                                     full_text.push_str(&t);
                                     let _ = ws_send(sender, &WsServerMessage::Token { content: t }).await;
                                 }
+                                SseParsedEvent::Thought(t) => {
+                                    let _ = ws_send(sender, &WsServerMessage::Thought { content: t }).await;
+                                }
                                 SseParsedEvent::FunctionCall { name, args, raw_part } => fcs.push((name, args, raw_part)),
-                                SseParsedEvent::MalformedFunctionCall => malformed = true,
+                                SseParsedEvent::MalformedFunctionCall(msg) => {
+                                    malformed = Some(msg.unwrap_or_default())
+                                }
                             }
                         }
                         break;
@@ -1477,8 +2260,13 @@ async fn consume_gemini_stream(
                                     full_text.push_str(&t);
                                     let _ = ws_send(sender, &WsServerMessage::Token { content: t }).await;
                                 }
+                                SseParsedEvent::Thought(t) => {
+                                    let _ = ws_send(sender, &WsServerMessage::Thought { content: t }).await;
+                                }
                                 SseParsedEvent::FunctionCall { name, args, raw_part } => fcs.push((name, args, raw_part)),
-                                SseParsedEvent::MalformedFunctionCall => malformed = true,
+                                SseParsedEvent::MalformedFunctionCall(msg) => {
+                                    malformed = Some(msg.unwrap_or_default())
+                                }
                             }
                         }
                         break;
@@ -1490,14 +2278,146 @@ async fn consume_gemini_stream(
     (full_text, fcs, stream_error, malformed)
 }
 
+// ---------------------------------------------------------------------------
+// Tool-call Repair
+// ---------------------------------------------------------------------------
+
+/// Cheap model used to re-emit tool-call args that failed the schema check.
+const TOOL_REPAIR_MODEL: &str = "gemini-2.5-flash";
+/// Upper bound on a single repair call — it must never stall the turn.
+const TOOL_REPAIR_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Find the declared tool a MALFORMED_FUNCTION_CALL `finishMessage` refers to,
+/// e.g. `print(default_api.read_file(path=...))` → `read_file`.
+fn malformed_call_tool_name(tools: &Value, message: &str) -> Option<String> {
+    tools
+        .as_array()?
+        .iter()
+        .filter_map(|t| t.get("function_declarations").and_then(|d| d.as_array()))
+        .flatten()
+        .filter_map(|decl| decl["name"].as_str())
+        .filter_map(|name| {
+            message
+                .match_indices(&format!("{}(", name))
+                .find(|(pos, _)| {
+                    !message[..*pos]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                })
+                .map(|(pos, _)| (pos, name))
+        })
+        .min_by_key(|(pos, _)| *pos)
+        .map(|(_, name)| name.to_string())
+}
+
+/// Rebuild a function call from a MALFORMED_FUNCTION_CALL `finishMessage`.
+/// Returns `(name, args, raw_part)` like `consume_gemini_stream` does.
+async fn recover_malformed_call(
+    state: &AppState,
+    ctx: &ExecuteContext,
+    tools: &Value,
+    message: &str,
+) -> Option<(String, Value, Value)> {
+    let name = malformed_call_tool_name(tools, message)?;
+    let schema = find_tool_schema(tools, &name)?;
+    let args = repair_tool_args(state, ctx, &name, schema, message).await?;
+    tracing::info!("recovered malformed call to '{}'", name);
+
+    let mut raw_part = json!({ "functionCall": { "name": name, "args": args } });
+    if ctx.model.contains("gemini-3") {
+        // Synthesized call has no signature of its own — Gemini 3 rejects
+        // unsigned function calls in history unless told to skip validation.
+        raw_part["thoughtSignature"] = json!("skip_thought_signature_validator");
+    }
+    Some((name, args, raw_part))
+}
+
+/// Ask a Flash model to re-emit valid JSON args for `name`, constrained by
+/// `responseSchema`, and accept them only if they pass `validate_tool_args`.
+async fn repair_tool_args(
+    state: &AppState,
+    ctx: &ExecuteContext,
+    name: &str,
+    schema: &Value,
+    broken: &str,
+) -> Option<Value> {
+    let url = reqwest::Url::parse(
+        &state
+            .providers
+            .gemini_model(TOOL_REPAIR_MODEL, "generateContent"),
+    )
+    .ok()?;
+    let prompt = format!(
+        "A call to the tool `{}` was emitted with invalid arguments:\n\n{}\n\n\
+         Re-emit the arguments the caller intended as a single JSON object matching \
+         the tool's parameter schema. Keep every value the caller provided; do not invent new ones.",
+        name,
+        truncate_for_context_with_limit(broken, 8000)
+    );
+    let body = json!({
+        "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+        "generationConfig": {
+            "temperature": 0.0,
+            "maxOutputTokens": 4096,
+            "responseMimeType": "application/json",
+            "responseSchema": schema,
+            "thinkingConfig": { "thinkingBudget": 0 }
+        }
+    });
+
+    let resp = crate::oauth::apply_google_auth(state.client.post(url), &ctx.api_key, ctx.is_oauth)
+        .json(&body)
+        .timeout(TOOL_REPAIR_TIMEOUT)
+        .send()
+        .await
+        .ok()?;
+    if !resp.status().is_success() {
+        tracing::warn!("tool repair for '{}' failed: HTTP {}", name, resp.status());
+        return None;
+    }
+    let j: Value = resp.json().await.ok()?;
+    let text = j["candidates"][0]["content"]["parts"][0]["text"].as_str()?;
+    let args: Value = serde_json::from_str(text).ok()?;
+
+    match validate_tool_args(schema, &args) {
+        Ok(()) => Some(args),
+        Err(e) => {
+            tracing::warn!("tool repair for '{}' still invalid: {}", name, e);
+            None
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // DB Helpers
 // ---------------------------------------------------------------------------
 
+/// Working directory and model override of the session, empty without one.
+pub(crate) async fn session_wd_and_model(
+    state: &AppState,
+    sid: Option<&Uuid>,
+) -> (String, Option<String>) {
+    let Some(s) = sid else {
+        return (String::new(), None);
+    };
+    sqlx::query_as("SELECT working_directory, model_override FROM gh_sessions WHERE id = $1")
+        .bind(s)
+        .fetch_optional(&state.db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// The session's locked agent, or a keyword classification. The result is
+/// saved as the lock only when `lock` is set, so plan-only previews never
+/// pin an agent to the session.
 async fn resolve_session_agent(
     state: &AppState,
     sid: &Uuid,
     prompt: &str,
+    lock: bool,
 ) -> (String, f64, String) {
     if let Some(aid) =
         sqlx::query_as::<_, (Option<String>,)>("SELECT agent_id FROM gh_sessions WHERE id = $1")
@@ -1514,34 +2434,98 @@ async fn resolve_session_agent(
     }
 
     let agents = state.agents.read().await;
-    let (aid, conf, reas) = crate::classify::classify_prompt(prompt, &agents);
+    let (aid, conf, reas) =
+        crate::classify::classify_prompt(prompt, &agents, &state.classification);
 
-    if let Err(e) = sqlx::query("UPDATE gh_sessions SET agent_id = $1 WHERE id = $2")
-        .bind(&aid)
-        .bind(sid)
-        .execute(&state.db)
-        .await
+    if lock
+        && let Err(e) = sqlx::query("UPDATE gh_sessions SET agent_id = $1 WHERE id = $2")
+            .bind(&aid)
+            .bind(sid)
+            .execute(&state.db)
+            .await
     {
         tracing::error!("Failed to lock session agent: {}", e);
     }
     (aid, conf, reas)
 }
 
-async fn load_session_history(db: &sqlx::PgPool, sid: &Uuid) -> Vec<Value> {
-    // #22 — Reduced from 50 to 20 to save context window budget
-    let mut messages: Vec<Value> = sqlx::query_as::<_, (String, String)>(
-        "SELECT role, content FROM gh_chat_messages WHERE session_id = $1 ORDER BY created_at DESC LIMIT 20"
+/// Max pinned messages from outside the window injected per request.
+const MAX_PINNED_CONTEXT_MESSAGES: i64 = 10;
+/// Total characters of injected out-of-window pinned messages.
+const PINNED_CONTEXT_CHAR_BUDGET: usize = 16_000;
+
+/// Last `window_size` messages of the session (plus pinned ones that scrolled out),
+/// with all but the last `keep_full` truncated.
+async fn load_session_history(
+    db: &sqlx::PgPool,
+    sid: &Uuid,
+    window_size: i64,
+    keep_full: usize,
+) -> Vec<Value> {
+    // #22 — The window is the `history_window` setting, scaled per model tier
+    let window: Vec<(Uuid, String, String, bool)> = sqlx::query_as(
+        "SELECT id, role, content, pinned FROM gh_chat_messages \
+         WHERE session_id = $1 ORDER BY created_at DESC LIMIT $2",
     )
-        .bind(sid).fetch_all(db).await.unwrap_or_else(|e| {
-            tracing::error!("Failed to load session history: {}", e);
-            vec![]
-        }).into_iter().rev()
-        .map(|(r, c)| json!({ "role": if r == "assistant" { "model" } else { "user" }, "parts": [{ "text": c }] }))
-        .collect();
+    .bind(sid)
+    .bind(window_size)
+    .fetch_all(db)
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!("Failed to load session history: {}", e);
+        vec![]
+    });
 
-    // #23 — Compress old messages: truncate everything except the last 6 messages
+    // Pinned messages that scrolled out of the window — most recent first,
+    // capped by count and total size so they can't crowd out the conversation.
+    let window_ids: Vec<Uuid> = window.iter().map(|(id, ..)| *id).collect();
+    let older_pinned: Vec<(String, String)> = sqlx::query_as(
+        "SELECT role, content FROM gh_chat_messages \
+         WHERE session_id = $1 AND pinned AND NOT (id = ANY($2)) \
+         ORDER BY created_at DESC LIMIT $3",
+    )
+    .bind(sid)
+    .bind(&window_ids)
+    .bind(MAX_PINNED_CONTEXT_MESSAGES)
+    .fetch_all(db)
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!("Failed to load pinned messages: {}", e);
+        vec![]
+    });
+    let mut pinned_chars = 0;
+    let older_pinned = older_pinned.into_iter().take_while(|(_, c)| {
+        pinned_chars += c.len();
+        pinned_chars <= PINNED_CONTEXT_CHAR_BUDGET
+    });
+
+    let to_content = |role: &str, text: String| {
+        let role = if role == "assistant" { "model" } else { "user" };
+        json!({ "role": role, "parts": [{ "text": text }] })
+    };
+    let (mut messages, pinned): (Vec<Value>, Vec<bool>) = older_pinned
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .map(|(r, c)| {
+            (
+                to_content(&r, format!("[Pinned earlier message] {}", c)),
+                true,
+            )
+        })
+        .chain(
+            window
+                .into_iter()
+                .rev()
+                .map(|(_, r, c, p)| (to_content(&r, c), p)),
+        )
+        .unzip();
+
+    // #23 — Compress old messages: truncate everything except the last
+    // `keep_full` messages. Pinned messages are kept verbatim.
     for i in 0..messages.len() {
-        if i < messages.len().saturating_sub(6)
+        if i < messages.len().saturating_sub(keep_full)
+            && !pinned[i]
             && let Some(text) = messages[i]
                 .get_mut("parts")
                 .and_then(|p| p.get_mut(0))
@@ -1566,31 +2550,57 @@ async fn load_session_history(db: &sqlx::PgPool, sid: &Uuid) -> Vec<Value> {
 }
 
 async fn store_messages(
-    db: &sqlx::PgPool,
+    state: &AppState,
     sid: Option<Uuid>,
     rid: Uuid,
     prompt: &str,
     result: &str,
     ctx: &ExecuteContext,
 ) {
-    if let Err(e) = sqlx::query("INSERT INTO gh_chat_messages (id, role, content, model, agent, session_id) VALUES ($1, 'user', $2, $3, $4, $5)")
-        .bind(rid).bind(prompt).bind(Some(&ctx.model)).bind(Some(&ctx.agent_id)).bind(sid).execute(db).await
-    {
-        tracing::error!("Failed to store chat message: {}", e);
+    use crate::dead_letter::{PendingMessage, insert_message};
+
+    let now = chrono::Utc::now();
+    let mut messages = vec![PendingMessage {
+        id: rid,
+        role: "user",
+        content: prompt.to_string(),
+        model: Some(ctx.model.clone()),
+        agent: Some(ctx.agent_id.clone()),
+        session_id: sid,
+        created_at: now,
+        attempts: 0,
+    }];
+    if !result.is_empty() {
+        messages.push(PendingMessage {
+            id: Uuid::new_v4(),
+            role: "assistant",
+            content: result.to_string(),
+            model: Some(ctx.model.clone()),
+            agent: Some(ctx.reasoning.clone()),
+            session_id: sid,
+            created_at: now + chrono::Duration::milliseconds(1),
+            attempts: 0,
+        });
     }
-    if !result.is_empty()
-        && let Err(e) = sqlx::query("INSERT INTO gh_chat_messages (id, role, content, model, agent, session_id) VALUES ($1, 'assistant', $2, $3, $4, $5)")
-            .bind(Uuid::new_v4()).bind(result).bind(Some(&ctx.model)).bind(Some(&ctx.reasoning)).bind(sid).execute(db).await
-        {
-            tracing::error!("Failed to store chat message: {}", e);
+
+    for message in messages {
+        if let Err(e) = insert_message(&state.db, &message).await {
+            tracing::error!(
+                "Failed to store {} message for session {:?} — queued for retry: {}",
+                message.role,
+                sid,
+                e
+            );
+            state.dead_letters.push(message);
         }
+    }
 }
 
 // ── Agent Swarm SSE Integration ──────────────────────────────────────
 
 use axum::response::sse::{Event, Sse};
-use std::convert::Infallible;
 use futures_util::stream::Stream;
+use std::convert::Infallible;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AgentMessage {
@@ -1603,7 +2613,7 @@ pub async fn swarm_sse_handler(
     axum::extract::State(state): axum::extract::State<crate::state::AppState>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
     let mut rx = state.swarm_tx.subscribe();
-    
+
     let stream = async_stream::stream! {
         while let Ok(msg) = rx.recv().await {
             let json_str = serde_json::to_string(&msg).unwrap_or_default();
@@ -1617,3 +2627,270 @@ pub async fn swarm_sse_handler(
             .text("keep-alive"),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, signature: Option<&str>) -> (String, Value, Value) {
+        let mut raw = json!({ "functionCall": { "name": name, "args": {} } });
+        if let Some(sig) = signature {
+            raw["thoughtSignature"] = json!(sig);
+        }
+        (name.to_string(), json!({}), raw)
+    }
+
+    #[test]
+    fn truncation_keeps_failure_at_the_end() {
+        let mut log: String = (0..2000)
+            .map(|i| format!("test case_{} ... ok\n", i))
+            .collect();
+        log.push_str("test case_final ... FAILED\nerror: test failed, to rerun pass `--lib`\n");
+
+        let out = truncate_head_tail(&log, 4000, 60);
+        assert!(out.starts_with("test case_0 ... ok"));
+        assert!(out.contains("test case_final ... FAILED"));
+        assert!(out.contains("error: test failed"));
+        assert!(out.contains(" chars omitted ...]"));
+        assert!(out.len() < 4000 + 300);
+
+        let head = out.split("\n\n[... ").next().unwrap();
+        assert_eq!(head.len(), 2400);
+    }
+
+    #[test]
+    fn truncation_respects_ratio_and_char_boundaries() {
+        assert_eq!(truncate_head_tail("short", 100, 60), "short");
+
+        let text = "ż".repeat(100); // 200 bytes
+        let out = truncate_head_tail(&text, 51, 20);
+        let (head, rest) = out.split_once("\n\n[... ").unwrap();
+        assert_eq!(head, "ż".repeat(5));
+        assert!(rest.contains(&format!("]\n\n{}\n\n[Output", "ż".repeat(20))));
+    }
+
+    #[test]
+    fn display_cap_is_independent_of_the_saved_output() {
+        assert_eq!(tool_result_display_md("ok", 1024), tool_result_md("ok"));
+
+        let output: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        let display = tool_result_display_md(&output, 4096);
+        assert!(display.starts_with("```\nline 0\n"));
+        assert!(display.contains("line 4999"));
+        assert!(display.contains(" bytes not shown ...]"));
+        assert!(display.contains(&format!("Showing 4096 of {} bytes", output.len())));
+        assert!(display.len() < 4096 + 400);
+        // The model-facing copy is cut by its own limit, not the display cap
+        assert!(truncate_for_context_with_limit(&output, 25000).len() > display.len());
+        assert!(tool_result_md(&output).contains(&output));
+    }
+
+    #[test]
+    fn mark_cancelled_keeps_partial_text() {
+        assert_eq!(
+            mark_cancelled("Half an answer\n"),
+            "Half an answer\n\n[cancelled]"
+        );
+        assert_eq!(mark_cancelled("  "), "[cancelled]");
+    }
+
+    #[tokio::test]
+    async fn tool_loop_collector_records_tool_calls_and_errors() {
+        let mut collector = ToolLoopCollector::default();
+        for msg in [
+            WsServerMessage::Token {
+                content: "hi".into(),
+            },
+            WsServerMessage::ToolCall {
+                name: "read_file".into(),
+                args: json!({ "path": "a.rs" }),
+                iteration: 1,
+            },
+            WsServerMessage::ToolCall {
+                name: "list_directory".into(),
+                args: json!({}),
+                iteration: 2,
+            },
+            WsServerMessage::Error {
+                message: "AI service error".into(),
+                code: Some("GEMINI_ERROR".into()),
+            },
+        ] {
+            assert!(ws_send(&mut collector, &msg).await);
+        }
+        assert_eq!(collector.tools_used, ["read_file", "list_directory"]);
+        assert_eq!(collector.last_error.as_deref(), Some("AI service error"));
+    }
+
+    #[tokio::test]
+    async fn retries_stop_at_the_configured_maximum() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // Upstream that is always overloaded
+        let hits = Arc::new(AtomicU32::new(0));
+        let counter = hits.clone();
+        let upstream = axum::Router::new().fallback(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { axum::http::StatusCode::SERVICE_UNAVAILABLE }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = reqwest::Url::parse(&format!(
+            "http://{}/v1beta/models/m:streamGenerateContent",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move { axum::serve(listener, upstream).await });
+
+        let policy = RetryPolicy {
+            max_retries: 2,
+            backoff_base: Duration::from_millis(1),
+            jitter_ms: 0,
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let err = gemini_request_with_retry(
+            &reqwest::Client::new(),
+            &url,
+            "key",
+            false,
+            &json!({}),
+            &policy,
+            Some(&tx),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(err.contains("after 3 attempts"), "{}", err);
+        let mut notices = Vec::new();
+        while let Ok(WsServerMessage::Retry {
+            attempt,
+            max,
+            delay_ms,
+        }) = rx.try_recv()
+        {
+            notices.push((attempt, max, delay_ms));
+        }
+        assert_eq!(notices, [(2, 3, 1), (3, 3, 2)]);
+    }
+
+    #[test]
+    fn retry_settings_are_clamped() {
+        let policy = RetryPolicy::from_settings(99, 5, -1);
+        assert_eq!(policy.max_retries, MAX_GEMINI_RETRIES);
+        assert_eq!(
+            policy.backoff_base,
+            Duration::from_millis(MIN_BACKOFF_BASE_MS)
+        );
+        assert_eq!(policy.jitter_ms, 0);
+        assert_eq!(RetryPolicy::from_settings(0, 1000, 500).max_retries, 0);
+
+        let slow = RetryPolicy::from_settings(10, 30_000, 0);
+        assert_eq!(slow.backoff(10), MAX_BACKOFF_DELAY);
+    }
+
+    #[test]
+    fn parse_parts_keeps_thought_signature_in_raw_part() {
+        let chunk = json!({ "candidates": [{ "content": { "parts": [
+            { "text": "Reading" },
+            { "functionCall": { "name": "read_file", "args": { "path": "a.rs" } }, "thoughtSignature": "sig-a" },
+            { "functionCall": { "name": "list_directory", "args": {} } }
+        ] } }] });
+        let events = SseParser::parse_parts(&chunk);
+        assert_eq!(events.len(), 3);
+        match &events[1] {
+            SseParsedEvent::FunctionCall {
+                name,
+                args,
+                raw_part,
+            } => {
+                assert_eq!(name, "read_file");
+                assert_eq!(args["path"], "a.rs");
+                assert_eq!(raw_part["thoughtSignature"], "sig-a");
+            }
+            other => panic!("expected function call, got {:?}", other),
+        }
+        match &events[2] {
+            SseParsedEvent::FunctionCall { raw_part, .. } => {
+                assert!(raw_part.get("thoughtSignature").is_none());
+            }
+            other => panic!("expected function call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn feed_preserves_signature_across_chunk_boundaries() {
+        let data = json!({ "candidates": [{ "content": { "parts": [
+            { "functionCall": { "name": "grep", "args": {} }, "thoughtSignature": "sig-split" }
+        ] } }] })
+        .to_string();
+        let sse = format!("data: {}\n\n", data);
+        let (head, tail) = sse.split_at(sse.len() / 2);
+
+        let mut parser = SseParser::new();
+        assert!(parser.feed(head.as_bytes()).is_empty());
+        let events = parser.feed(tail.as_bytes());
+        assert!(matches!(
+            &events[..],
+            [SseParsedEvent::FunctionCall { raw_part, .. }] if raw_part["thoughtSignature"] == "sig-split"
+        ));
+    }
+
+    #[test]
+    fn parse_parts_separates_thoughts_from_answer_text() {
+        let chunk = json!({ "candidates": [{ "content": { "parts": [
+            { "text": "Considering the options", "thought": true },
+            { "text": "Answer" }
+        ] } }] });
+        let events = SseParser::parse_parts(&chunk);
+        assert!(matches!(
+            &events[..],
+            [SseParsedEvent::Thought(t), SseParsedEvent::TextToken(a)]
+                if t == "Considering the options" && a == "Answer"
+        ));
+        assert!(stream_thinking_config("gemini-3-pro", "none").is_none());
+        assert_eq!(
+            stream_thinking_config("gemini-3-pro", "high").unwrap()["includeThoughts"],
+            true
+        );
+    }
+
+    #[test]
+    fn feed_keeps_multibyte_characters_split_across_chunks() {
+        let data = json!({ "candidates": [{ "content": { "parts": [{ "text": "Zażółć 🚀" }] } }] })
+            .to_string();
+        let sse = format!("data: {}\n\n", data);
+        let bytes = sse.as_bytes();
+        // Split inside the 4-byte rocket emoji
+        let cut = sse.find('🚀').unwrap() + 2;
+
+        let mut parser = SseParser::new();
+        assert!(parser.feed(&bytes[..cut]).is_empty());
+        let events = parser.feed(&bytes[cut..]);
+        assert!(matches!(
+            &events[..],
+            [SseParsedEvent::TextToken(text)] if text == "Zażółć 🚀"
+        ));
+    }
+
+    #[test]
+    fn signatures_are_matched_by_position_not_name() {
+        let fcs = vec![
+            call("read_file", Some("sig-1")),
+            call("read_file", None),
+            call("read_file", Some("sig-3")),
+        ];
+        let signatures = thought_signatures(&fcs);
+        assert_eq!(
+            signature_for_response(&signatures, &fcs, 0, "read_file"),
+            Some(&json!("sig-1"))
+        );
+        assert!(signature_for_response(&signatures, &fcs, 1, "read_file").is_none());
+        assert_eq!(
+            signature_for_response(&signatures, &fcs, 2, "read_file"),
+            Some(&json!("sig-3"))
+        );
+        // A response that doesn't belong to the call at its position gets nothing
+        assert!(signature_for_response(&signatures, &fcs, 0, "grep").is_none());
+        assert!(signature_for_response(&signatures, &fcs, 3, "read_file").is_none());
+    }
+}
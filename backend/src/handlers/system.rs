@@ -7,6 +7,7 @@ use axum::extract::State;
 use axum::response::IntoResponse;
 use serde_json::{Value, json};
 
+use crate::alerts::{AlertPayload, AlertSeverity};
 use crate::models::{
     DetailedHealthResponse, GeminiModelInfo, GeminiModelsResponse, HealthResponse, SystemStats,
 };
@@ -85,9 +86,15 @@ pub async fn health_detailed(State(state): State<AppState>) -> Json<DetailedHeal
     let google = cache.models.get("google").cloned().unwrap_or_default();
     drop(cache);
     let snap = state.system_monitor.read().await;
+    let database = state.db_health.read().await.clone();
+    let status = if database.last_check.is_some() && !database.healthy {
+        "degraded"
+    } else {
+        "ok"
+    };
 
     Json(DetailedHealthResponse {
-        status: "ok".to_string(),
+        status: status.to_string(),
         version: "15.0.0".to_string(),
         app: "GeminiHydra".to_string(),
         uptime_seconds: state.start_time.elapsed().as_secs(),
@@ -95,6 +102,8 @@ pub async fn health_detailed(State(state): State<AppState>) -> Json<DetailedHeal
         memory_usage_mb: snap.memory_used_mb,
         cpu_usage_percent: snap.cpu_usage_percent,
         platform: snap.platform.clone(),
+        database,
+        unsaved_messages: state.dead_letters.len(),
     })
 }
 
@@ -164,8 +173,8 @@ pub async fn gemini_models(State(state): State<AppState>) -> Json<Value> {
     // 1. Fetch Gemini models
     let google_cred = crate::oauth::get_google_credential(&state).await;
     if let Some((key, is_oauth)) = google_cred {
-        let url = "https://generativelanguage.googleapis.com/v1beta/models";
-        if let Ok(parsed) = reqwest::Url::parse(url)
+        let url = state.providers.gemini_models();
+        if let Ok(parsed) = reqwest::Url::parse(&url)
             && let Ok(res) =
                 crate::oauth::apply_google_auth(state.client.get(parsed), &key, is_oauth)
                     .send()
@@ -232,3 +241,183 @@ pub async fn rotate_key(
         "message": format!("API key for '{}' updated successfully", provider),
     })))
 }
+
+// ---------------------------------------------------------------------------
+// Admin — Cache Flush
+// ---------------------------------------------------------------------------
+
+/// In-memory caches `POST /api/admin/cache/clear` can flush. Native tool
+/// definitions are not listed — they are built from code and never go stale.
+pub(crate) const CLEARABLE_CACHES: &[&str] = &[
+    "prompt",
+    "models",
+    "model_health",
+    "settings",
+    "agents",
+    "web",
+];
+
+/// Cache names from `{"caches": [...]}`; `"all"` (alone or in the list) selects
+/// every cache. Returned in `CLEARABLE_CACHES` order, without duplicates.
+pub(crate) fn parse_cache_names(body: &Value) -> Result<Vec<&'static str>, ApiError> {
+    let requested: Vec<&str> = match body.get("caches") {
+        Some(Value::String(s)) => vec![s.as_str()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .ok_or_else(|| ApiError::BadRequest("'caches' must contain strings".into()))
+            })
+            .collect::<Result<_, _>>()?,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "missing 'caches' field — a list of cache names or \"all\"".into(),
+            ));
+        }
+    };
+    if let Some(unknown) = requested
+        .iter()
+        .find(|c| **c != "all" && !CLEARABLE_CACHES.contains(c))
+    {
+        return Err(ApiError::BadRequest(format!(
+            "unknown cache '{}' — expected all or one of: {}",
+            unknown,
+            CLEARABLE_CACHES.join(", ")
+        )));
+    }
+    if requested.is_empty() {
+        return Err(ApiError::BadRequest("'caches' must not be empty".into()));
+    }
+
+    let all = requested.contains(&"all");
+    Ok(CLEARABLE_CACHES
+        .iter()
+        .copied()
+        .filter(|c| all || requested.contains(c))
+        .collect())
+}
+
+/// Flush the requested in-memory caches so stale entries are rebuilt on the
+/// next request, without a restart. Protected — requires auth when AUTH_SECRET is set.
+pub async fn clear_caches(
+    State(state): State<AppState>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, ApiError> {
+    let caches = parse_cache_names(&body)?;
+
+    for cache in &caches {
+        match *cache {
+            "prompt" => state.clear_prompt_cache().await,
+            "models" => *state.model_cache.write().await = crate::model_registry::ModelCache::new(),
+            "model_health" => *state.model_health.lock().await = Default::default(),
+            "settings" => state.invalidate_settings().await,
+            // Reloads the roster from the DB (and drops cached prompts with it)
+            "agents" => state.refresh_agents().await,
+            "web" => state.web_cache.clear(),
+            _ => unreachable!("parse_cache_names only returns CLEARABLE_CACHES"),
+        }
+    }
+
+    tracing::info!("Admin cache flush: {}", caches.join(", "));
+
+    Ok(Json(json!({
+        "ok": true,
+        "cleared": caches,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// Admin — Circuit Breakers
+// ---------------------------------------------------------------------------
+
+/// State, failure count and last trip of the shared Gemini breaker and every
+/// per-model breaker. Protected — requires auth when AUTH_SECRET is set.
+pub async fn circuit_status(State(state): State<AppState>) -> Json<Value> {
+    let mut circuits = Vec::new();
+    for circuit in state.circuits().await {
+        circuits.push(circuit.snapshot().await);
+    }
+    Json(json!({ "circuits": circuits }))
+}
+
+/// Force breakers closed — all of them, or only `{"provider": "..."}` (a name
+/// from `GET /api/admin/circuit`). The body is optional.
+/// Protected — requires auth when AUTH_SECRET is set.
+pub async fn reset_circuits(
+    State(state): State<AppState>,
+    body: Option<Json<Value>>,
+) -> Result<Json<Value>, ApiError> {
+    let provider = match body.as_ref().map(|Json(b)| b.get("provider")) {
+        None | Some(None) | Some(Some(Value::Null)) => None,
+        Some(Some(Value::String(p))) => Some(p.as_str()),
+        Some(Some(_)) => {
+            return Err(ApiError::BadRequest("'provider' must be a string".into()));
+        }
+    };
+
+    let circuits: Vec<_> = state
+        .circuits()
+        .await
+        .into_iter()
+        .filter(|c| provider.is_none_or(|p| c.provider() == p))
+        .collect();
+    if let (Some(p), true) = (provider, circuits.is_empty()) {
+        return Err(ApiError::NotFound(format!("no circuit breaker '{}'", p)));
+    }
+
+    let mut closed = Vec::new();
+    let mut snapshots = Vec::new();
+    for circuit in &circuits {
+        if circuit.reset() {
+            closed.push(circuit.provider().to_string());
+        }
+        snapshots.push(circuit.snapshot().await);
+    }
+
+    tracing::info!(
+        "Admin circuit reset ({}): closed {}",
+        provider.unwrap_or("all"),
+        if closed.is_empty() {
+            "none".to_string()
+        } else {
+            closed.join(", ")
+        }
+    );
+
+    Ok(Json(json!({
+        "ok": true,
+        "closed": closed,
+        "circuits": snapshots,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// Admin — Alert Webhook Test
+// ---------------------------------------------------------------------------
+
+/// Send a test alert to `ALERT_WEBHOOK_URL` (bypasses debounce).
+/// Protected — requires auth when AUTH_SECRET is set.
+pub async fn test_alert(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    if !state.alerts.is_configured() {
+        return Err(ApiError::BadRequest(
+            "ALERT_WEBHOOK_URL is not set — alerting is disabled".into(),
+        ));
+    }
+
+    let payload = AlertPayload::new(
+        "test_alert",
+        AlertSeverity::Info,
+        "Test alert from GeminiHydra — webhook is configured correctly",
+    );
+    let status = state
+        .alerts
+        .send_now(&state.client, &payload)
+        .await
+        .map_err(ApiError::Upstream)?;
+
+    Ok(Json(json!({
+        "ok": true,
+        "webhook_status": status,
+        "payload": payload,
+    })))
+}
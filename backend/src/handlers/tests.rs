@@ -2,7 +2,9 @@
 // handlers/tests.rs — Unit tests for classification, keyword matching, helpers
 // ---------------------------------------------------------------------------
 
-use crate::classify::{classify_agent_score, classify_prompt, keyword_match, strip_diacritics};
+use crate::classify::{
+    ClassificationConfig, classify_agent_score, classify_prompt, keyword_match, strip_diacritics,
+};
 use crate::models::WitcherAgent;
 
 /// Build a minimal set of test agents with keywords matching the DB seed.
@@ -28,6 +30,7 @@ fn test_agents() -> Vec<WitcherAgent> {
             thinking_level: None,
             model_b: None,
             ab_split: None,
+            display_order: 0,
         },
         WitcherAgent {
             id: "triss".to_string(),
@@ -49,6 +52,7 @@ fn test_agents() -> Vec<WitcherAgent> {
             thinking_level: None,
             model_b: None,
             ab_split: None,
+            display_order: 0,
         },
         WitcherAgent {
             id: "dijkstra".to_string(),
@@ -69,6 +73,7 @@ fn test_agents() -> Vec<WitcherAgent> {
             thinking_level: None,
             model_b: None,
             ab_split: None,
+            display_order: 0,
         },
         WitcherAgent {
             id: "eskel".to_string(),
@@ -93,6 +98,7 @@ fn test_agents() -> Vec<WitcherAgent> {
             thinking_level: None,
             model_b: None,
             ab_split: None,
+            display_order: 0,
         },
     ]
 }
@@ -101,7 +107,11 @@ fn test_agents() -> Vec<WitcherAgent> {
 fn test_refactor_routes_to_yennefer() {
     let agents = test_agents();
     // "refactor this code" contains the keyword "refactor" (>= 4 chars → substring match)
-    let (agent, confidence, _) = classify_prompt("refactor this code please", &agents);
+    let (agent, confidence, _) = classify_prompt(
+        "refactor this code please",
+        &agents,
+        &ClassificationConfig::default(),
+    );
     assert_eq!(agent, "yennefer");
     assert!(confidence >= 0.8);
 }
@@ -109,7 +119,11 @@ fn test_refactor_routes_to_yennefer() {
 #[test]
 fn test_sql_routes_to_triss() {
     let agents = test_agents();
-    let (agent, confidence, _) = classify_prompt("query sql database", &agents);
+    let (agent, confidence, _) = classify_prompt(
+        "query sql database",
+        &agents,
+        &ClassificationConfig::default(),
+    );
     assert_eq!(agent, "triss");
     assert!(confidence >= 0.8);
 }
@@ -117,15 +131,22 @@ fn test_sql_routes_to_triss() {
 #[test]
 fn test_unknown_prompt_falls_back_to_eskel() {
     let agents = test_agents();
-    let (agent, _, _) = classify_prompt("what is the meaning of life", &agents);
+    let (agent, _, _) = classify_prompt(
+        "what is the meaning of life",
+        &agents,
+        &ClassificationConfig::default(),
+    );
     assert_eq!(agent, "eskel");
 }
 
 #[test]
 fn test_backend_routes_to_eskel() {
     let agents = test_agents();
-    let (agent, confidence, _) =
-        classify_prompt("add a new api endpoint for user registration", &agents);
+    let (agent, confidence, _) = classify_prompt(
+        "add a new api endpoint for user registration",
+        &agents,
+        &ClassificationConfig::default(),
+    );
     assert_eq!(agent, "eskel");
     assert!(confidence >= 0.7);
 }
@@ -133,7 +154,11 @@ fn test_backend_routes_to_eskel() {
 #[test]
 fn test_classify_agent_score_returns_zero_for_no_match() {
     let agents = test_agents();
-    let score = classify_agent_score("nothing relevant here", &agents[0]);
+    let score = classify_agent_score(
+        "nothing relevant here",
+        &agents[0],
+        &ClassificationConfig::default(),
+    );
     assert_eq!(score, 0.0);
 }
 
@@ -141,10 +166,72 @@ fn test_classify_agent_score_returns_zero_for_no_match() {
 fn test_classify_agent_score_positive_for_match() {
     let agents = test_agents();
     let triss = &agents[1]; // triss has "sql", "database" etc.
-    let score = classify_agent_score("query sql database migration", triss);
+    let score = classify_agent_score(
+        "query sql database migration",
+        triss,
+        &ClassificationConfig::default(),
+    );
     assert!(score > 0.65);
 }
 
+#[test]
+fn test_classify_debug_scores_every_agent() {
+    let agents = test_agents();
+    let debug = super::agents::classify_debug(
+        "query sql database migration",
+        &agents,
+        &ClassificationConfig::default(),
+    );
+    assert_eq!(debug.scores.len(), agents.len());
+    assert_eq!(debug.scores[0].agent, "triss");
+    assert!(
+        debug.scores[0]
+            .matched_keywords
+            .contains(&"sql".to_string())
+    );
+    assert_eq!(debug.agent, "triss");
+    assert!(!debug.gemini_fallback);
+
+    let vague =
+        super::agents::classify_debug("hello there", &agents, &ClassificationConfig::default());
+    assert!(vague.gemini_fallback);
+    assert!(vague.scores.iter().all(|s| s.score == 0.0));
+}
+
+#[test]
+fn test_fallback_threshold_controls_gemini_fallback() {
+    let agents = test_agents();
+    let prompt = "query sql database";
+    let default = ClassificationConfig::default();
+    let (_, confidence, _) = classify_prompt(prompt, &agents, &default);
+    assert!(!default.wants_gemini_fallback(confidence));
+    assert!(!super::agents::classify_debug(prompt, &agents, &default).gemini_fallback);
+
+    let strict = ClassificationConfig {
+        fallback_threshold: 0.99,
+        ..default
+    };
+    assert!(strict.wants_gemini_fallback(confidence));
+    assert!(super::agents::classify_debug(prompt, &agents, &strict).gemini_fallback);
+}
+
+#[test]
+fn test_keyword_weights_come_from_config() {
+    let agents = test_agents();
+    let triss = &agents[1];
+    let default = ClassificationConfig::default();
+    let flat = ClassificationConfig {
+        base_confidence: 0.5,
+        long_keyword_weight: 1.0,
+        medium_keyword_weight: 1.0,
+        ..default
+    };
+    let prompt = "query sql database migration";
+    assert!(
+        classify_agent_score(prompt, triss, &flat) < classify_agent_score(prompt, triss, &default)
+    );
+}
+
 #[test]
 fn test_short_keyword_whole_word() {
     assert!(keyword_match("query sql database", "sql"));
@@ -156,3 +243,150 @@ fn test_strip_diacritics_works() {
     assert_eq!(strip_diacritics("refaktoryzację"), "refaktoryzacje");
     assert_eq!(strip_diacritics("żółw"), "zolw");
 }
+
+#[test]
+fn test_gemini_diagnose_details_reports_safety_block() {
+    let resp = serde_json::json!({
+        "promptFeedback": {
+            "blockReason": "SAFETY",
+            "safetyRatings": [
+                { "category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH" },
+                { "category": "HARM_CATEGORY_HATE_SPEECH", "probability": "NEGLIGIBLE" }
+            ]
+        }
+    });
+    let details = super::gemini_diagnose_details(&resp);
+    assert_eq!(details["blockReason"], "SAFETY");
+    assert!(details["finishReason"].is_null());
+    assert_eq!(details["safetyRatings"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        details["safetyRatings"][0]["category"],
+        "HARM_CATEGORY_HARASSMENT"
+    );
+}
+
+#[test]
+fn test_safety_settings_levels() {
+    assert!(crate::prompt::build_safety_settings("default").is_none());
+    let relaxed = crate::prompt::build_safety_settings("relaxed").unwrap();
+    let relaxed = relaxed.as_array().unwrap();
+    assert_eq!(relaxed.len(), 4);
+    assert!(relaxed.iter().all(|s| s["threshold"] == "BLOCK_ONLY_HIGH"));
+    let strict = crate::prompt::build_safety_settings("strict").unwrap();
+    assert_eq!(strict[0]["threshold"], "BLOCK_LOW_AND_ABOVE");
+}
+
+#[test]
+fn test_rating_histogram_zero_fills_star_levels() {
+    let hist = super::agents::rating_histogram(&[(5, 3), (2, 1)]);
+    let counts: Vec<i64> = hist.iter().map(|b| b.count).collect();
+    assert_eq!(counts, [0, 1, 0, 0, 3]);
+    assert_eq!(hist[4].stars, 5);
+}
+
+#[test]
+fn test_parse_cache_names() {
+    use super::system::{CLEARABLE_CACHES, parse_cache_names};
+    use serde_json::json;
+
+    let picked = parse_cache_names(&json!({ "caches": ["settings", "prompt", "prompt"] })).unwrap();
+    assert_eq!(picked, ["prompt", "settings"]);
+    assert_eq!(
+        parse_cache_names(&json!({ "caches": "all" })).unwrap(),
+        CLEARABLE_CACHES
+    );
+    assert_eq!(
+        parse_cache_names(&json!({ "caches": ["models", "all"] })).unwrap(),
+        CLEARABLE_CACHES
+    );
+    assert!(parse_cache_names(&json!({ "caches": ["tool_defs"] })).is_err());
+    assert!(parse_cache_names(&json!({ "caches": [] })).is_err());
+    assert!(parse_cache_names(&json!({ "caches": [1] })).is_err());
+    assert!(parse_cache_names(&json!({})).is_err());
+}
+
+#[test]
+fn test_agents_etag_changes_with_roster_and_matches_if_none_match() {
+    use super::agents::{agents_etag, etag_matches};
+
+    let body = serde_json::json!({ "agents": test_agents() });
+    let etag = agents_etag(&body);
+    assert!(etag.starts_with("W/\"") && etag.ends_with('"'));
+    assert_eq!(etag, agents_etag(&body));
+
+    let mut reordered = test_agents();
+    reordered.reverse();
+    assert_ne!(
+        etag,
+        agents_etag(&serde_json::json!({ "agents": reordered }))
+    );
+
+    let strong = etag.trim_start_matches("W/");
+    assert!(etag_matches(Some(&etag), &etag));
+    assert!(etag_matches(Some(strong), &etag));
+    assert!(etag_matches(Some(&format!("\"stale\", {}", etag)), &etag));
+    assert!(etag_matches(Some("*"), &etag));
+    assert!(!etag_matches(Some("W/\"stale\""), &etag));
+    assert!(!etag_matches(None, &etag));
+}
+
+// ── classify_with_gemini retry ──────────────────────────────────────────
+
+/// Local Gemini stand-in answering each request with the next status in
+/// `statuses`; successes name `triss`. Returns the endpoints and a hit counter.
+async fn serve_classifier(
+    statuses: Vec<u16>,
+) -> (
+    crate::state::ProviderEndpoints,
+    std::sync::Arc<std::sync::atomic::AtomicUsize>,
+) {
+    use std::sync::atomic::Ordering;
+
+    let hits = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = hits.clone();
+    let upstream = axum::Router::new().fallback(move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        let status = statuses[n.min(statuses.len() - 1)];
+        async move {
+            let body = serde_json::json!({ "candidates": [{ "content": { "parts": [{ "text": "triss" }] } }] });
+            (
+                axum::http::StatusCode::from_u16(status).unwrap(),
+                axum::Json(body),
+            )
+        }
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, upstream).await });
+    let endpoints = crate::state::ProviderEndpoints {
+        gemini: format!("http://{}", addr),
+        ..Default::default()
+    };
+    (endpoints, hits)
+}
+
+#[tokio::test]
+async fn gemini_classification_retries_once_on_transient_errors() {
+    use crate::classify::classify_with_gemini;
+    use std::sync::atomic::Ordering;
+
+    let client = reqwest::Client::new();
+    let agents = test_agents();
+
+    let (endpoints, hits) = serve_classifier(vec![503, 200]).await;
+    let result = classify_with_gemini(&client, &endpoints, "key", false, "sql", &agents).await;
+    assert_eq!(result.map(|r| r.0).as_deref(), Some("triss"));
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // Only one retry: a second 429 gives up
+    let (endpoints, hits) = serve_classifier(vec![429, 429, 200]).await;
+    let result = classify_with_gemini(&client, &endpoints, "key", false, "sql", &agents).await;
+    assert!(result.is_none());
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // Non-transient errors are not retried
+    let (endpoints, hits) = serve_classifier(vec![400, 200]).await;
+    let result = classify_with_gemini(&client, &endpoints, "key", false, "sql", &agents).await;
+    assert!(result.is_none());
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,231 @@
+// GeminiHydra v15 — Idempotency-Key cache for POST /api/execute
+//
+// A client that retries `POST /api/execute` with the same `Idempotency-Key`
+// header gets the first response back instead of a second Gemini run.
+// Keys are scoped per auth identity and peer IP and kept for
+// `IDEMPOTENCY_TTL_SECS` (default 10 min). While the first request is still
+// running, a repeat is answered with 409 rather than started in parallel.
+// Reusing a key with a different request body is answered with 422.
+// Responses that ended in a 5xx are not kept, so the client can retry them
+// for real.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+const DEFAULT_TTL_SECS: u64 = 600;
+/// Keys remembered at most; expired entries are evicted first, then the
+/// oldest finished one. In-flight keys are never evicted, so the cache can
+/// briefly exceed this while that many requests are running.
+const MAX_KEYS: usize = 1_000;
+/// Longest accepted `Idempotency-Key` header value.
+pub const MAX_KEY_LEN: usize = 255;
+
+/// Result of claiming an idempotency key.
+#[derive(Debug, PartialEq)]
+pub enum Claim {
+    /// First use of the key — the caller runs the request and must then call
+    /// `complete` or `release`.
+    New,
+    /// The original request has not finished yet.
+    InFlight,
+    /// The original request finished; replay its response.
+    Done(StatusCode, Value),
+    /// The key was first used with a different request body.
+    Mismatch,
+}
+
+struct Entry {
+    /// `fingerprint` of the request body the key was first used with.
+    fingerprint: String,
+    /// `None` while the original request is still running.
+    response: Option<(StatusCode, Value)>,
+    stored_at: Instant,
+}
+
+/// Hash of a request body, compared when a key is reused.
+pub fn fingerprint(body: &impl Serialize) -> String {
+    let bytes = serde_json::to_vec(body).unwrap_or_default();
+    Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub struct IdempotencyCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl IdempotencyCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// Read `IDEMPOTENCY_TTL_SECS`.
+    pub fn from_env() -> Self {
+        let ttl = std::env::var("IDEMPOTENCY_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|secs: &u64| *secs > 0)
+            .unwrap_or(DEFAULT_TTL_SECS);
+        Self::new(MAX_KEYS, Duration::from_secs(ttl))
+    }
+
+    /// Cache key for `key` sent by `identity` (`None` when auth is disabled)
+    /// from `peer`. `AUTH_SECRET` is shared by every client, so the identity
+    /// alone would make the key space global.
+    pub fn scoped_key(identity: Option<&str>, peer: Option<IpAddr>, key: &str) -> String {
+        let peer = peer.map(|ip| ip.to_string()).unwrap_or_default();
+        format!("{}@{}:{}", identity.unwrap_or("anonymous"), peer, key)
+    }
+
+    /// Look up `key`, reserving it for the caller when it is unknown or expired.
+    /// `fingerprint` identifies the request body (see [`fingerprint`]).
+    pub fn claim(&self, key: &str, fingerprint: &str) -> Claim {
+        let mut entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+        let now = Instant::now();
+        if let Some(entry) = entries.get(key)
+            && (entry.response.is_none() || now.duration_since(entry.stored_at) < self.ttl)
+        {
+            if entry.fingerprint != fingerprint {
+                return Claim::Mismatch;
+            }
+            return match &entry.response {
+                Some((status, body)) => Claim::Done(*status, body.clone()),
+                None => Claim::InFlight,
+            };
+        }
+
+        if entries.len() >= self.capacity {
+            entries
+                .retain(|_, e| e.response.is_none() || now.duration_since(e.stored_at) < self.ttl);
+        }
+        if entries.len() >= self.capacity
+            && let Some(oldest) = entries
+                .iter()
+                .filter(|(_, e)| e.response.is_some())
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone())
+        {
+            entries.remove(&oldest);
+        }
+        entries.insert(
+            key.to_string(),
+            Entry {
+                fingerprint: fingerprint.to_string(),
+                response: None,
+                stored_at: now,
+            },
+        );
+        Claim::New
+    }
+
+    /// Store the response for a claimed key; the TTL restarts from now.
+    pub fn complete(&self, key: &str, fingerprint: &str, status: StatusCode, body: Value) {
+        let mut entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+        entries.insert(
+            key.to_string(),
+            Entry {
+                fingerprint: fingerprint.to_string(),
+                response: Some((status, body)),
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Drop a claimed key so the next request with it runs again.
+    pub fn release(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+        entries.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn repeat_replays_the_stored_response() {
+        let cache = IdempotencyCache::new(10, Duration::from_secs(60));
+        assert_eq!(cache.claim("alice:k1", "f"), Claim::New);
+        assert_eq!(cache.claim("alice:k1", "f"), Claim::InFlight);
+
+        cache.complete("alice:k1", "f", StatusCode::OK, json!({ "result": "hi" }));
+        assert_eq!(
+            cache.claim("alice:k1", "f"),
+            Claim::Done(StatusCode::OK, json!({ "result": "hi" }))
+        );
+        // Same key from another identity is independent.
+        assert_eq!(cache.claim("bob:k1", "f"), Claim::New);
+    }
+
+    #[test]
+    fn reused_key_with_another_body_is_a_mismatch() {
+        let cache = IdempotencyCache::new(10, Duration::from_secs(60));
+        let first = fingerprint(&json!({ "prompt": "a" }));
+        let second = fingerprint(&json!({ "prompt": "b" }));
+        assert_ne!(first, second);
+
+        assert_eq!(cache.claim("k", &first), Claim::New);
+        assert_eq!(cache.claim("k", &second), Claim::Mismatch);
+        cache.complete("k", &first, StatusCode::OK, json!(1));
+        assert_eq!(cache.claim("k", &second), Claim::Mismatch);
+    }
+
+    #[test]
+    fn keys_are_scoped_by_identity_and_peer() {
+        let ip_a: IpAddr = "10.0.0.1".parse().unwrap();
+        let ip_b: IpAddr = "10.0.0.2".parse().unwrap();
+        assert_ne!(
+            IdempotencyCache::scoped_key(Some("bearer:x"), Some(ip_a), "k"),
+            IdempotencyCache::scoped_key(Some("bearer:x"), Some(ip_b), "k")
+        );
+    }
+
+    #[test]
+    fn released_and_expired_keys_run_again() {
+        let cache = IdempotencyCache::new(10, Duration::from_secs(60));
+        assert_eq!(cache.claim("k", "f"), Claim::New);
+        cache.release("k");
+        assert_eq!(cache.claim("k", "f"), Claim::New);
+
+        let expiring = IdempotencyCache::new(10, Duration::ZERO);
+        expiring.complete("k", "f", StatusCode::OK, json!({}));
+        assert_eq!(expiring.claim("k", "f"), Claim::New);
+    }
+
+    #[test]
+    fn full_cache_evicts_the_oldest_key() {
+        let cache = IdempotencyCache::new(2, Duration::from_secs(60));
+        cache.complete("a", "f", StatusCode::OK, json!(1));
+        std::thread::sleep(Duration::from_millis(2));
+        cache.complete("b", "f", StatusCode::OK, json!(2));
+        assert_eq!(cache.claim("c", "f"), Claim::New);
+        assert_eq!(cache.claim("b", "f"), Claim::Done(StatusCode::OK, json!(2)));
+        assert_eq!(cache.claim("a", "f"), Claim::New);
+    }
+
+    #[test]
+    fn eviction_skips_in_flight_keys() {
+        let cache = IdempotencyCache::new(2, Duration::ZERO);
+        assert_eq!(cache.claim("a", "f"), Claim::New);
+        assert_eq!(cache.claim("b", "f"), Claim::New);
+        assert_eq!(cache.claim("c", "f"), Claim::New);
+        // Still running, so neither expired nor evicted.
+        assert_eq!(cache.claim("a", "f"), Claim::InFlight);
+        assert_eq!(cache.claim("b", "f"), Claim::InFlight);
+    }
+}
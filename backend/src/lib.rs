@@ -1,15 +1,18 @@
 #![recursion_limit = "512"]
 
 pub mod a2a;
+pub mod alerts;
 pub mod analysis;
 pub mod audit;
 pub mod auth;
 pub mod browser_proxy;
 pub mod classify;
 pub mod context;
+pub mod dead_letter;
 pub mod error;
 pub mod files;
 pub mod handlers;
+pub mod idempotency;
 pub mod logs;
 pub mod mcp;
 pub mod model_registry;
@@ -20,6 +23,7 @@ pub mod oauth_google;
 pub mod oauth_vercel;
 pub mod ocr;
 pub mod prompt;
+pub mod redact;
 pub mod service_tokens;
 pub mod sessions;
 pub mod state;
@@ -29,11 +33,12 @@ pub mod tools;
 pub mod watchdog;
 
 use axum::Router;
-use axum::extract::State;
+use axum::extract::{DefaultBodyLimit, State};
 use axum::http::HeaderValue;
 use axum::middleware;
 use axum::routing::{delete, get, post};
 use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
+use tower_http::limit::RequestBodyLimitLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
@@ -45,6 +50,7 @@ use state::AppState;
 
 /// Middleware that assigns a UUID correlation ID to every request.
 /// - Adds the ID to the current tracing span for structured logging.
+/// - Scopes it in `error::REQUEST_ID` so `ApiError` bodies carry the same ID.
 /// - Returns it as `X-Request-Id` response header for client-side correlation.
 pub async fn request_id_middleware(
     request: axum::http::Request<axum::body::Body>,
@@ -56,7 +62,9 @@ pub async fn request_id_middleware(
     tracing::Span::current().record("request_id", tracing::field::display(&request_id));
     tracing::debug!(request_id = %request_id, "assigned correlation ID");
 
-    let mut response = next.run(request).await;
+    let mut response = error::REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .await;
 
     // Attach as response header — infallible for valid UUID strings.
     if let Ok(val) = HeaderValue::from_str(&request_id) {
@@ -87,11 +95,19 @@ pub async fn request_id_middleware(
         // Agents
         handlers::list_agents,
         handlers::classify_agent,
+        handlers::classify_agent_debug,
         handlers::create_agent,
         handlers::update_agent,
         handlers::delete_agent,
+        handlers::reorder_agents,
+        handlers::list_prompt_versions,
+        handlers::restore_prompt_version,
+        handlers::agent_ratings,
         // Execute / Chat
         handlers::execute,
+        handlers::execute_ensemble,
+        handlers::list_tools,
+        handlers::tool_execute,
         handlers::gemini_models,
         // Files
         handlers::read_file,
@@ -102,30 +118,53 @@ pub async fn request_id_middleware(
         model_registry::pin_model,
         model_registry::unpin_model,
         model_registry::list_pins,
+        model_registry::models_health,
         // Sessions
         sessions::list_sessions,
         sessions::create_session,
         sessions::get_session,
         sessions::update_session,
         sessions::delete_session,
+        sessions::bulk_delete_sessions,
+        sessions::restore_session,
+        sessions::get_session_stats,
+        sessions::list_session_tags,
+        sessions::add_session_tag,
+        sessions::remove_session_tag,
         sessions::get_session_messages,
         sessions::add_session_message,
+        sessions::pin_session_message,
+        sessions::unpin_session_message,
+        sessions::list_pinned_messages,
+        sessions::clear_session_messages,
         sessions::generate_session_title,
+        sessions::summarize_session,
+        sessions::update_session_model,
+        sessions::reset_session_model,
         // History
         sessions::get_history,
         sessions::search_history,
+        sessions::get_history_stats,
         sessions::add_message,
         sessions::clear_history,
         // Settings
         sessions::get_settings,
         sessions::update_settings,
         sessions::reset_settings,
+        sessions::reset_setting_field,
+        sessions::list_settings_profiles,
+        sessions::create_settings_profile,
+        sessions::apply_settings_profile,
+        sessions::delete_settings_profile,
         // Memory
         sessions::list_memories,
         sessions::add_memory,
         sessions::clear_memories,
         sessions::get_knowledge_graph,
+        sessions::export_knowledge_graph,
         sessions::add_knowledge_node,
+        sessions::delete_knowledge_node,
+        sessions::get_graph_neighbors,
         sessions::add_graph_edge,
         // Prompt history
         sessions::list_prompt_history,
@@ -136,22 +175,42 @@ pub async fn request_id_middleware(
         // Core models
         models::HealthResponse,
         models::DetailedHealthResponse,
+        models::DbHealthStatus,
         models::ProviderInfo,
         models::SystemStats,
         // Agents
         models::WitcherAgent,
+        models::ReorderAgentsRequest,
         models::ClassifyRequest,
+        models::ClassifyDebugResponse,
+        models::AgentClassifyScore,
         models::ClassifyResponse,
+        models::AgentPromptVersion,
+        models::AgentRatingBreakdown,
+        models::RatingBucket,
+        models::RatingTrendPoint,
+        models::LowRatedMessage,
         // Execute
         models::ExecuteRequest,
         models::ExecuteResponse,
+        models::ToolCatalogEntry,
+        models::ToolExecuteRequest,
+        models::ToolExecuteResponse,
+        models::ToolInlineData,
+        models::EnsembleRequest,
+        models::EnsembleAnswer,
+        models::EnsembleResponse,
         models::ExecutePlan,
+        models::ExecutionPreview,
         // Gemini
         models::GeminiModelsResponse,
         models::GeminiModelInfo,
         models::GeminiStreamRequest,
         // Settings
         models::AppSettings,
+        sessions::PartialSettings,
+        sessions::SettingsProfile,
+        sessions::CreateSettingsProfileRequest,
         // Chat
         models::ChatMessage,
         // Files
@@ -163,12 +222,26 @@ pub async fn request_id_middleware(
         // Sessions
         models::Session,
         models::SessionSummary,
+        models::SessionAbstract,
         models::CreateSessionRequest,
         models::UpdateSessionRequest,
+        models::UpdateSessionModelRequest,
+        models::SessionModelResponse,
+        models::BulkDeleteSessionsRequest,
+        models::BulkDeleteSessionsResponse,
+        models::AddSessionTagRequest,
+        models::SessionTagsResponse,
+        models::SessionStats,
+        models::SessionAgentUsage,
+        models::SessionToolUsage,
+        models::HistoryStats,
+        models::DailyMessageCount,
+        models::ModelMessageCount,
         // Model registry
         model_registry::ModelInfo,
         model_registry::ResolvedModels,
         model_registry::PinModelRequest,
+        model_registry::ModelProbe,
         // Prompt history
         models::AddPromptRequest,
         // Browser proxy
@@ -192,6 +265,40 @@ pub async fn request_id_middleware(
 )]
 pub struct ApiDoc;
 
+// ── Request body limits ─────────────────────────────────────────────────────
+
+/// Body cap for every route except OCR (`BODY_LIMIT_MB`).
+const DEFAULT_BODY_LIMIT_MB: usize = 10;
+/// OCR uploads arrive base64-encoded inside JSON, so a 22 MB scan is ~30 MB
+/// on the wire (`OCR_BODY_LIMIT_MB`).
+const DEFAULT_OCR_BODY_LIMIT_MB: usize = 32;
+const MAX_BODY_LIMIT_MB: usize = 256;
+
+/// Body limit in bytes from a megabyte env var (1..=256, else the default).
+fn body_limit_bytes(var: &str, default_mb: usize) -> usize {
+    let mb = std::env::var(var)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|mb| (1..=MAX_BODY_LIMIT_MB).contains(mb))
+        .unwrap_or(default_mb);
+    mb * 1024 * 1024
+}
+
+/// Cap request bodies on every route of `router` at `limit` bytes — raising
+/// axum's 2 MB extractor default to match — and answer over-limit bodies
+/// with a JSON `ApiError` instead of a bare 413.
+pub(crate) fn with_body_limit<S>(router: Router<S>, limit: usize) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(DefaultBodyLimit::max(limit))
+        .layer(RequestBodyLimitLayer::new(limit))
+        .layer(middleware::map_response(move |response| {
+            error::payload_too_large_json(response, limit)
+        }))
+}
+
 /// Build the application router with the given state.
 /// Extracted from `main()` so integration tests can construct the app
 /// without binding to a network port.
@@ -211,6 +318,7 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
         .per_second(6)
         .burst_size(10)
         .use_headers()
+        .key_extractor(auth::IdentityKeyExtractor)
         .finish()
         .expect("rate limiter config: ws");
         
@@ -218,9 +326,23 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
         .per_second(2)
         .burst_size(30)
         .use_headers()
+        .key_extractor(auth::IdentityKeyExtractor)
         .finish()
         .expect("rate limiter config: execute");
-        
+
+    // Outer per-IP buckets run before auth, so rejected tokens still count
+    let rl_ws_peer = GovernorConfigBuilder::default()
+        .per_second(6)
+        .burst_size(10)
+        .finish()
+        .expect("rate limiter config: ws peer");
+
+    let rl_execute_peer = GovernorConfigBuilder::default()
+        .per_second(2)
+        .burst_size(30)
+        .finish()
+        .expect("rate limiter config: execute peer");
+
     let rl_default = GovernorConfigBuilder::default()
         .per_millisecond(100) // Much faster limit
         .burst_size(500)      // Massive burst
@@ -299,7 +421,15 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
     let ws_routes = if rate_limit {
         Router::new()
             .route("/ws/execute", get(handlers::ws_execute))
-            .layer(GovernorLayer::new(rl_ws))
+            // Governor sits inside identify_ws so it can key on the principal
+            .route_layer(GovernorLayer::new(rl_ws).error_handler(error::governor_error_response))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::identify_ws,
+            ))
+            .route_layer(
+                GovernorLayer::new(rl_ws_peer).error_handler(error::governor_error_response),
+            )
     } else {
         Router::new().route("/ws/execute", get(handlers::ws_execute))
     };
@@ -308,15 +438,23 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
     let execute_routes = if rate_limit {
         Router::new()
             .route("/api/execute", post(handlers::execute))
+            .route("/api/execute/ensemble", post(handlers::execute_ensemble))
             .route("/api/v1/swarm/stream", get(handlers::streaming::swarm_sse_handler))
+            // Governor sits inside require_auth so it can key on the principal
+            .route_layer(
+                GovernorLayer::new(rl_execute).error_handler(error::governor_error_response),
+            )
             .route_layer(middleware::from_fn_with_state(
                 state.clone(),
                 auth::require_auth,
             ))
-            .layer(GovernorLayer::new(rl_execute))
+            .route_layer(
+                GovernorLayer::new(rl_execute_peer).error_handler(error::governor_error_response),
+            )
     } else {
         Router::new()
             .route("/api/execute", post(handlers::execute))
+            .route("/api/execute/ensemble", post(handlers::execute_ensemble))
             .route("/api/v1/swarm/stream", get(handlers::streaming::swarm_sse_handler))
             .route_layer(middleware::from_fn_with_state(
                 state.clone(),
@@ -335,20 +473,15 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
             delete(model_registry::unpin_model),
         )
         .route("/api/models/pins", get(model_registry::list_pins))
+        .route("/api/models/health", get(model_registry::models_health))
         // Logs — backend log ring buffer
         .route(
             "/api/logs/backend",
             get(logs::backend_logs).delete(logs::clear_backend_logs),
         )
-        // OCR — text extraction from images and PDFs
-        .route("/api/ocr", post(ocr::ocr))
-        .route("/api/ocr/stream", post(ocr::ocr_stream))
-        .route("/api/ocr/batch/stream", post(ocr::ocr_batch_stream))
-        .route("/api/ocr/history", get(ocr::ocr_history))
-        .route(
-            "/api/ocr/history/{id}",
-            get(ocr::ocr_history_item).delete(ocr::ocr_history_delete),
-        )
+        .route("/api/logs/tool-calls", get(logs::tool_call_logs))
+        .route("/api/logs/usage/export", get(logs::export_usage_csv))
+        .route("/api/logs/leaderboard", get(logs::leaderboard))
         // A2A v0.3 — Agent-to-Agent protocol endpoints
         .route("/a2a/message/send", post(a2a::message_send))
         .route("/a2a/message/stream", post(a2a::message_stream))
@@ -368,6 +501,22 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
             auth::require_auth,
         ));
 
+    // ── OCR — text extraction from images and PDFs (larger body limit) ──
+    let ocr_routes = Router::new()
+        .route("/api/ocr", post(ocr::ocr))
+        .route("/api/ocr/stream", post(ocr::ocr_stream))
+        .route("/api/ocr/batch/stream", post(ocr::ocr_batch_stream))
+        .route("/api/ocr/languages", get(ocr::ocr_languages))
+        .route("/api/ocr/history", get(ocr::ocr_history))
+        .route(
+            "/api/ocr/history/{id}",
+            get(ocr::ocr_history_item).delete(ocr::ocr_history_delete),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
+
     // ── Metrics endpoint (public, no auth) ─────────────────────────
     let metrics = Router::new().route("/api/metrics", get(metrics_handler));
 
@@ -384,6 +533,7 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
         .merge(protected)
         .merge(handlers::agents_router(state.clone()))
         .merge(handlers::files_router(state.clone()))
+        .merge(handlers::tools_router(state.clone()))
         .merge(handlers::system_router(state.clone()))
         .merge(mcp::mcp_router(state.clone()))
         .merge(metrics)
@@ -398,10 +548,20 @@ fn create_router_inner(state: AppState, rate_limit: bool) -> Router {
         // Swagger UI — no auth required
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()));
 
+    // Body limits: OCR is merged after the general limit so only its own applies
+    let combined = with_body_limit(
+        combined,
+        body_limit_bytes("BODY_LIMIT_MB", DEFAULT_BODY_LIMIT_MB),
+    )
+    .merge(with_body_limit(
+        ocr_routes,
+        body_limit_bytes("OCR_BODY_LIMIT_MB", DEFAULT_OCR_BODY_LIMIT_MB),
+    ));
+
     // Apply global rate limit only in production (requires ConnectInfo from TCP listener)
     if rate_limit {
         combined
-            .layer(GovernorLayer::new(rl_default))
+            .layer(GovernorLayer::new(rl_default).error_handler(error::governor_error_response))
             .with_state(state)
     } else {
         combined.with_state(state)
@@ -2,10 +2,16 @@
 // Backend log endpoints for the Logs View.
 
 use axum::Json;
+use axum::body::Body;
 use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
 use serde::Deserialize;
 use serde_json::{Value, json};
 
+use crate::audit::TOOL_CALL_ACTION;
+use crate::error::ApiError;
 use crate::state::AppState;
 
 // ── Query parameters ────────────────────────────────────────────────
@@ -17,6 +23,35 @@ pub struct BackendLogsQuery {
     pub search: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct ToolCallLogsQuery {
+    pub limit: Option<i64>,
+    pub tool: Option<String>,
+    pub agent: Option<String>,
+    pub session: Option<String>,
+    /// Inclusive lower bound (RFC 3339).
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Exclusive upper bound (RFC 3339).
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct UsageExportQuery {
+    /// Inclusive lower bound (RFC 3339).
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Exclusive upper bound (RFC 3339).
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    /// `day`, `week`, `month` or `all` (default).
+    pub window: Option<String>,
+    /// `messages` (default), `tokens` or `ratings`.
+    pub metric: Option<String>,
+    pub limit: Option<usize>,
+}
+
 // ── GET /api/logs/backend ───────────────────────────────────────────
 
 pub async fn backend_logs(
@@ -36,3 +71,370 @@ pub async fn clear_backend_logs(State(state): State<AppState>) -> Json<Value> {
     state.log_buffer.clear();
     Json(json!({ "cleared": true }))
 }
+
+// ── GET /api/logs/tool-calls ────────────────────────────────────────
+
+/// Audited tool calls (see `audit::AUDITED_TOOLS`), newest first.
+pub async fn tool_call_logs(
+    State(state): State<AppState>,
+    Query(q): Query<ToolCallLogsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let limit = q.limit.unwrap_or(200).clamp(1, 500);
+    let rows = sqlx::query_as::<_, (i32, chrono::DateTime<chrono::Utc>, Value)>(
+        "SELECT id, timestamp, details FROM gh_audit_log \
+         WHERE action = $1 \
+           AND ($2::text IS NULL OR details->>'tool' = $2) \
+           AND ($3::text IS NULL OR details->>'agent_id' = $3) \
+           AND ($4::text IS NULL OR details->>'session_id' = $4) \
+           AND ($5::timestamptz IS NULL OR timestamp >= $5) \
+           AND ($6::timestamptz IS NULL OR timestamp < $6) \
+         ORDER BY timestamp DESC LIMIT $7",
+    )
+    .bind(TOOL_CALL_ACTION)
+    .bind(&q.tool)
+    .bind(&q.agent)
+    .bind(&q.session)
+    .bind(q.from)
+    .bind(q.to)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let entries: Vec<Value> = rows
+        .into_iter()
+        .map(|(id, timestamp, details)| {
+            json!({
+                "id": id,
+                "timestamp": timestamp.to_rfc3339(),
+                "tool": details["tool"],
+                "args": details["args"],
+                "agent_id": details["agent_id"],
+                "session_id": details["session_id"],
+                "status": details["status"],
+                "success": details["success"],
+                "duration_ms": details["duration_ms"],
+            })
+        })
+        .collect();
+    let total = entries.len();
+    Ok(Json(json!({ "tool_calls": entries, "total": total })))
+}
+
+// ── GET /api/logs/usage/export ──────────────────────────────────────
+
+const USAGE_CSV_HEADER: &str = "id,created_at,agent_id,model,tier,input_tokens,output_tokens,\
+total_tokens,latency_ms,success\r\n";
+
+type UsageRow = (
+    i32,
+    Option<chrono::DateTime<chrono::Utc>>,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    Option<bool>,
+);
+
+/// `gh_agent_usage` rows as a CSV attachment, oldest first, streamed straight
+/// from the database so large ranges don't build up in memory.
+pub async fn export_usage_csv(
+    State(state): State<AppState>,
+    Query(q): Query<UsageExportQuery>,
+) -> Result<Response, ApiError> {
+    if let (Some(from), Some(to)) = (q.from, q.to)
+        && from >= to
+    {
+        return Err(ApiError::BadRequest("'from' must be before 'to'".into()));
+    }
+
+    let db = state.db.clone();
+    let (from, to) = (q.from, q.to);
+    let rows = async_stream::stream! {
+        yield Ok::<_, std::io::Error>(USAGE_CSV_HEADER.to_string());
+        let mut rows = sqlx::query_as::<_, UsageRow>(
+            "SELECT id, created_at, agent_id, model, tier, input_tokens, output_tokens, \
+             total_tokens, latency_ms, success FROM gh_agent_usage \
+             WHERE ($1::timestamptz IS NULL OR created_at >= $1) \
+               AND ($2::timestamptz IS NULL OR created_at < $2) \
+             ORDER BY created_at ASC, id ASC",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch(&db);
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(row) => yield Ok(usage_csv_line(&row)),
+                Err(e) => {
+                    tracing::error!("usage export: {}", e);
+                    yield Err(std::io::Error::other(e));
+                    break;
+                }
+            }
+        }
+    };
+
+    let filename = match (from, to) {
+        (None, None) => "agent-usage.csv".to_string(),
+        _ => format!(
+            "agent-usage_{}_{}.csv",
+            from.map(|d| d.format("%Y%m%d").to_string())
+                .unwrap_or_else(|| "start".into()),
+            to.map(|d| d.format("%Y%m%d").to_string())
+                .unwrap_or_else(|| "now".into()),
+        ),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        Body::from_stream(rows),
+    )
+        .into_response())
+}
+
+fn usage_csv_line(row: &UsageRow) -> String {
+    let (id, created_at, agent, model, tier, input, output, total, latency, success) = row;
+    let num = |v: &Option<i32>| v.map(|n| n.to_string()).unwrap_or_default();
+    let fields = [
+        id.to_string(),
+        created_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        csv_field(agent.as_deref().unwrap_or("")),
+        csv_field(model),
+        csv_field(tier.as_deref().unwrap_or("")),
+        num(input),
+        num(output),
+        num(total),
+        num(latency),
+        success.map(|b| b.to_string()).unwrap_or_default(),
+    ];
+    format!("{}\r\n", fields.join(","))
+}
+
+/// RFC 4180 quoting, plus a leading `'` on values a spreadsheet would
+/// evaluate as a formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+// ── GET /api/logs/leaderboard ───────────────────────────────────────
+
+/// Lookback in days for a `window` value; `None` means all time.
+fn leaderboard_window_days(window: &str) -> Result<Option<i32>, ApiError> {
+    match window {
+        "day" => Ok(Some(1)),
+        "week" => Ok(Some(7)),
+        "month" => Ok(Some(30)),
+        "all" => Ok(None),
+        other => Err(ApiError::BadRequest(format!(
+            "Invalid window '{}' — expected day, week, month or all",
+            other
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LeaderboardEntry {
+    agent_id: String,
+    messages: i64,
+    tokens: i64,
+    ratings: i64,
+    avg_rating: Option<f64>,
+}
+
+/// Ranks entries by `metric`, falling back to the other metrics and finally
+/// `agent_id` so equal scores come back in the same order on every request.
+fn rank_leaderboard(entries: &mut [LeaderboardEntry], metric: &str) -> Result<(), ApiError> {
+    let avg = |e: &LeaderboardEntry| e.avg_rating.unwrap_or(f64::NEG_INFINITY);
+    match metric {
+        "messages" => entries.sort_by(|a, b| {
+            b.messages
+                .cmp(&a.messages)
+                .then(b.tokens.cmp(&a.tokens))
+                .then(a.agent_id.cmp(&b.agent_id))
+        }),
+        "tokens" => entries.sort_by(|a, b| {
+            b.tokens
+                .cmp(&a.tokens)
+                .then(b.messages.cmp(&a.messages))
+                .then(a.agent_id.cmp(&b.agent_id))
+        }),
+        "ratings" => entries.sort_by(|a, b| {
+            avg(b)
+                .total_cmp(&avg(a))
+                .then(b.ratings.cmp(&a.ratings))
+                .then(a.agent_id.cmp(&b.agent_id))
+        }),
+        other => {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid metric '{}' — expected messages, tokens or ratings",
+                other
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Per-agent message, token and rating totals over a time window.
+pub async fn leaderboard(
+    State(state): State<AppState>,
+    Query(q): Query<LeaderboardQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let window = q.window.as_deref().unwrap_or("all");
+    let metric = q.metric.as_deref().unwrap_or("messages");
+    let days = leaderboard_window_days(window)?;
+    let limit = q.limit.unwrap_or(20).clamp(1, 100);
+
+    let rows = sqlx::query_as::<_, (String, i64, i64, i64, Option<f64>)>(
+        "WITH msgs AS ( \
+             SELECT agent AS agent_id, COUNT(*) AS messages FROM gh_chat_messages \
+             WHERE role = 'assistant' AND agent IS NOT NULL \
+               AND ($1::int IS NULL OR created_at > NOW() - make_interval(days => $1)) \
+             GROUP BY agent), \
+         toks AS ( \
+             SELECT agent_id, SUM(total_tokens)::bigint AS tokens FROM gh_agent_usage \
+             WHERE agent_id IS NOT NULL \
+               AND ($1::int IS NULL OR created_at > NOW() - make_interval(days => $1)) \
+             GROUP BY agent_id), \
+         rates AS ( \
+             SELECT m.agent AS agent_id, COUNT(*) AS ratings, AVG(r.rating)::float8 AS avg_rating \
+             FROM gh_ratings r JOIN gh_chat_messages m ON m.id = r.message_id \
+             WHERE m.agent IS NOT NULL \
+               AND ($1::int IS NULL OR r.created_at > NOW() - make_interval(days => $1)) \
+             GROUP BY m.agent) \
+         SELECT agent_id, COALESCE(msgs.messages, 0), COALESCE(toks.tokens, 0), \
+                COALESCE(rates.ratings, 0), rates.avg_rating \
+         FROM msgs FULL OUTER JOIN toks USING (agent_id) FULL OUTER JOIN rates USING (agent_id)",
+    )
+    .bind(days)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let mut entries: Vec<LeaderboardEntry> = rows
+        .into_iter()
+        .map(
+            |(agent_id, messages, tokens, ratings, avg_rating)| LeaderboardEntry {
+                agent_id,
+                messages,
+                tokens,
+                ratings,
+                avg_rating,
+            },
+        )
+        .collect();
+    rank_leaderboard(&mut entries, metric)?;
+
+    let ranked: Vec<Value> = entries
+        .into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, e)| {
+            json!({
+                "rank": i + 1,
+                "agent_id": e.agent_id,
+                "messages": e.messages,
+                "tokens": e.tokens,
+                "ratings": e.ratings,
+                "avg_rating": e.avg_rating,
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "window": window,
+        "metric": metric,
+        "entries": ranked,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_field_quotes_and_neutralizes_formulas() {
+        assert_eq!(csv_field("gemini-3.1-pro"), "gemini-3.1-pro");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("=SUM(A1)"), "'=SUM(A1)");
+        assert_eq!(csv_field("\t=SUM(A1)"), "'\t=SUM(A1)");
+        assert_eq!(csv_field("\r=SUM(A1)"), "\"'\r=SUM(A1)\"");
+    }
+
+    #[test]
+    fn usage_csv_line_matches_header_columns() {
+        let row: UsageRow = (
+            7,
+            None,
+            Some("geralt".into()),
+            "gemini-3.1-flash".into(),
+            None,
+            Some(10),
+            Some(20),
+            Some(30),
+            Some(400),
+            Some(true),
+        );
+        let line = usage_csv_line(&row);
+        assert_eq!(line, "7,,geralt,gemini-3.1-flash,,10,20,30,400,true\r\n");
+        assert_eq!(line.split(',').count(), USAGE_CSV_HEADER.split(',').count());
+    }
+
+    fn entry(
+        agent_id: &str,
+        messages: i64,
+        tokens: i64,
+        avg_rating: Option<f64>,
+    ) -> LeaderboardEntry {
+        LeaderboardEntry {
+            agent_id: agent_id.into(),
+            messages,
+            tokens,
+            ratings: if avg_rating.is_some() { 1 } else { 0 },
+            avg_rating,
+        }
+    }
+
+    #[test]
+    fn leaderboard_window_maps_to_days() {
+        assert_eq!(leaderboard_window_days("day").unwrap(), Some(1));
+        assert_eq!(leaderboard_window_days("month").unwrap(), Some(30));
+        assert_eq!(leaderboard_window_days("all").unwrap(), None);
+        assert!(leaderboard_window_days("year").is_err());
+    }
+
+    #[test]
+    fn rank_leaderboard_breaks_ties_deterministically() {
+        let mut entries = vec![
+            entry("yennefer", 5, 100, None),
+            entry("dijkstra", 5, 100, Some(4.0)),
+            entry("geralt", 5, 300, Some(2.0)),
+            entry("ciri", 9, 50, Some(4.0)),
+        ];
+        rank_leaderboard(&mut entries, "messages").unwrap();
+        let order: Vec<&str> = entries.iter().map(|e| e.agent_id.as_str()).collect();
+        assert_eq!(order, ["ciri", "geralt", "dijkstra", "yennefer"]);
+
+        rank_leaderboard(&mut entries, "ratings").unwrap();
+        let order: Vec<&str> = entries.iter().map(|e| e.agent_id.as_str()).collect();
+        assert_eq!(order, ["ciri", "dijkstra", "geralt", "yennefer"]);
+
+        assert!(rank_leaderboard(&mut entries, "likes").is_err());
+    }
+}
@@ -2,11 +2,11 @@ use axum::http::{HeaderValue, Method, header};
 use sqlx::postgres::PgPoolOptions;
 use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
-use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
 
 use geminihydra_backend::model_registry;
+use geminihydra_backend::redact::redact_secrets;
 use geminihydra_backend::state::{AppState, LogEntry, LogRingBuffer};
 use geminihydra_backend::watchdog;
 
@@ -90,7 +90,6 @@ async fn build_app(log_buffer: std::sync::Arc<LogRingBuffer>) -> (axum::Router,
     // WS: 10/min, /api/execute: 30/min, other: 120/min
 
     let app = geminihydra_backend::create_router(state.clone())
-        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024))
         .layer(cors)
         .layer(nosniff)
         .layer(frame_deny)
@@ -137,7 +136,7 @@ impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for LogBufferLayer {
             timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
             level: meta.level().to_string(),
             target: meta.target().to_string(),
-            message: visitor.0,
+            message: redact_secrets(&visitor.0).into_owned(),
         });
     }
 }
@@ -274,6 +273,9 @@ async fn main() -> anyhow::Result<()> {
     // ── Spawn background watchdog ──
     let _watchdog = watchdog::spawn(state.clone());
 
+    // ── Spawn retry loop for chat messages that failed to persist ──
+    let _dead_letters = geminihydra_backend::dead_letter::spawn(state.clone());
+
     // ── Spawn MCP client startup (connect to enabled MCP servers) ──
     let mcp_state = state.clone();
     tokio::spawn(async move {
@@ -295,9 +297,23 @@ async fn main() -> anyhow::Result<()> {
         listener,
         app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
     )
-    .with_graceful_shutdown(shutdown_signal())
+    .with_graceful_shutdown(shutdown_signal(state.shutdown.clone()))
     .await?;
 
+    // Upgraded WebSockets outlive the server loop — give their executions a
+    // moment to store partial answers.
+    state.executions.close();
+    if tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, state.executions.wait())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            "Shutdown: {} execution(s) still running after {:?}, exiting anyway",
+            state.executions.len(),
+            SHUTDOWN_DRAIN_TIMEOUT
+        );
+    }
+
     Ok(())
 }
 
@@ -332,7 +348,12 @@ fn print_banner(port: u16) {
     println!();
 }
 
-async fn shutdown_signal() {
+/// How long shutdown waits for in-flight executions to wind down.
+const SHUTDOWN_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Resolves on Ctrl+C / SIGTERM and cancels `shutdown`, stopping every
+/// running execution.
+async fn shutdown_signal(shutdown: tokio_util::sync::CancellationToken) {
     let ctrl_c = tokio::signal::ctrl_c();
     #[cfg(unix)]
     {
@@ -348,4 +369,5 @@ async fn shutdown_signal() {
         ctrl_c.await.ok();
     }
     tracing::info!("Shutdown signal received, starting graceful shutdown");
+    shutdown.cancel();
 }
@@ -50,7 +50,7 @@ enum McpTransport {
         auth_token: Option<String>,
     },
     Stdio {
-        _child: Box<tokio::sync::Mutex<tokio::process::Child>>,
+        child: Box<tokio::sync::Mutex<tokio::process::Child>>,
         stdin: tokio::sync::Mutex<tokio::process::ChildStdin>,
         stdout: tokio::sync::Mutex<tokio::io::BufReader<tokio::process::ChildStdout>>,
     },
@@ -61,6 +61,7 @@ enum McpTransport {
 /// An active connection to an MCP server.
 #[derive(Debug)]
 struct McpConnection {
+    server_id: String,
     server_name: String,
     transport: McpTransport,
     timeout: Duration,
@@ -319,6 +320,7 @@ impl McpClientManager {
 
         // Store connection
         let conn = Arc::new(McpConnection {
+            server_id: cfg.id.clone(),
             server_name: cfg.name.clone(),
             transport,
             timeout,
@@ -333,8 +335,8 @@ impl McpClientManager {
     pub async fn disconnect_server(&self, server_id: &str) {
         if let Some(conn) = self.connections.write().await.remove(server_id) {
             tracing::info!("MCP: disconnected server '{}'", conn.server_name);
-            // For stdio, the child process is dropped when all Arc references are gone.
-            // tokio::process::Child::drop kills the child.
+            // For stdio, the child process is dropped when all Arc references are gone
+            // and killed via `kill_on_drop`.
         }
     }
 
@@ -369,22 +371,94 @@ impl McpClientManager {
         None
     }
 
+    /// Call a tool by server (ID or case-insensitive name) and its original,
+    /// unprefixed tool name.
+    pub async fn call_server_tool(
+        &self,
+        server: &str,
+        tool: &str,
+        arguments: &Value,
+    ) -> Result<String, String> {
+        let prefixed_name = {
+            let lock = self.connections.read().await;
+            let (_, conn) = lock
+                .iter()
+                .find(|(id, c)| id.as_str() == server || c.server_name.eq_ignore_ascii_case(server))
+                .ok_or_else(|| format!("MCP server '{}' is not connected", server))?;
+            conn.tools
+                .iter()
+                .find(|t| t.name == tool)
+                .map(|t| t.prefixed_name.clone())
+                .ok_or_else(|| format!("MCP server '{}' has no tool '{}'", server, tool))?
+        };
+        self.call_tool(&prefixed_name, arguments).await
+    }
+
+    // ── Stdio process supervision ───────────────────────────────────────
+
+    /// Re-spawn a stdio server whose process died, using its stored config.
+    /// Replaces the connection in place; the old child is killed on drop.
+    async fn restart_stdio_server(&self, server_id: &str) -> Result<Arc<McpConnection>, String> {
+        let cfg = config::get_mcp_server(&self.db, server_id)
+            .await
+            .map_err(|e| format!("Failed to load MCP server config: {e}"))?
+            .ok_or_else(|| format!("MCP server '{}' no longer exists", server_id))?;
+
+        tracing::warn!("MCP: stdio server '{}' exited — restarting", cfg.name);
+        self.connect_server(&cfg).await?;
+
+        self.connections
+            .read()
+            .await
+            .get(server_id)
+            .cloned()
+            .ok_or_else(|| format!("MCP server '{}' did not reconnect", cfg.name))
+    }
+
     // ── Call tool ───────────────────────────────────────────────────────
 
     /// Call a tool on a connected MCP server by its prefixed name.
     /// Enforces a timeout of max(TOOL_CALL_TIMEOUT, server.timeout).
+    ///
+    /// Stdio servers whose process has exited (or whose pipes broke mid-call)
+    /// are restarted once and the call is retried.
     pub async fn call_tool(
         &self,
         prefixed_name: &str,
         arguments: &Value,
     ) -> Result<String, String> {
-        let (conn, original_name) = self.resolve_tool(prefixed_name).await.ok_or_else(|| {
-            format!(
-                "MCP tool '{}' not found in any connected server",
-                prefixed_name
-            )
-        })?;
+        let (mut conn, original_name) =
+            self.resolve_tool(prefixed_name).await.ok_or_else(|| {
+                format!(
+                    "MCP tool '{}' not found in any connected server",
+                    prefixed_name
+                )
+            })?;
 
+        if conn.stdio_exited().await {
+            conn = self.restart_stdio_server(&conn.server_id).await?;
+        }
+
+        match self
+            .call_tool_on(&conn, prefixed_name, &original_name, arguments)
+            .await
+        {
+            Err(e) if is_stdio_pipe_error(&e) && conn.stdio_exited().await => {
+                let conn = self.restart_stdio_server(&conn.server_id).await?;
+                self.call_tool_on(&conn, prefixed_name, &original_name, arguments)
+                    .await
+            }
+            other => other,
+        }
+    }
+
+    async fn call_tool_on(
+        &self,
+        conn: &McpConnection,
+        prefixed_name: &str,
+        original_name: &str,
+        arguments: &Value,
+    ) -> Result<String, String> {
         let call_timeout = conn.timeout.max(TOOL_CALL_TIMEOUT);
 
         tokio::time::timeout(call_timeout, async {
@@ -406,7 +480,7 @@ impl McpClientManager {
                     extract_tool_result(&response)
                 }
                 McpTransport::Stdio { stdin, stdout, .. } => {
-                    self.stdio_call_tool(stdin, stdout, &original_name, arguments, conn.timeout)
+                    self.stdio_call_tool(stdin, stdout, original_name, arguments, conn.timeout)
                         .await
                 }
             }
@@ -543,12 +617,25 @@ impl McpClientManager {
             .envs(env_vars)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped());
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
 
         let mut child = cmd
             .spawn()
             .map_err(|e| format!("Failed to spawn MCP stdio server '{}': {}", command, e))?;
 
+        // Drain stderr so a chatty server can't block on a full pipe.
+        if let Some(stderr) = child.stderr.take() {
+            let name = command.to_string();
+            tokio::spawn(async move {
+                use tokio::io::AsyncBufReadExt;
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!("MCP stdio [{}] stderr: {}", name, line);
+                }
+            });
+        }
+
         let stdin = child
             .stdin
             .take()
@@ -648,7 +735,7 @@ impl McpClientManager {
         let tools = parse_tools_list(&tools_result);
 
         let transport = McpTransport::Stdio {
-            _child: Box::new(tokio::sync::Mutex::new(child)),
+            child: Box::new(tokio::sync::Mutex::new(child)),
             stdin: stdin_mutex,
             stdout: stdout_mutex,
         };
@@ -775,6 +862,16 @@ impl McpClientManager {
     }
 }
 
+impl McpConnection {
+    /// True if this is a stdio connection whose child process has exited.
+    async fn stdio_exited(&self) -> bool {
+        match &self.transport {
+            McpTransport::Stdio { child, .. } => !matches!(child.lock().await.try_wait(), Ok(None)),
+            McpTransport::Http { .. } => false,
+        }
+    }
+}
+
 // ── Raw tool (before prefixing) ──────────────────────────────────────────────
 
 struct RawMcpTool {
@@ -785,6 +882,14 @@ struct RawMcpTool {
 
 // ── Helpers ─────────────────────────────────────────────────────────────────
 
+/// Errors from `stdio_request` that indicate the child's pipes are gone.
+fn is_stdio_pipe_error(err: &str) -> bool {
+    err.starts_with("Failed to write to MCP stdio stdin")
+        || err.starts_with("Failed to flush MCP stdio stdin")
+        || err.starts_with("MCP stdio: EOF")
+        || err.starts_with("MCP stdio read error")
+}
+
 /// Parse tools/list result into raw tool descriptors.
 fn parse_tools_list(result: &Value) -> Vec<RawMcpTool> {
     let tools_array = result
@@ -959,4 +1064,18 @@ mod tests {
             Err("Something failed".to_string())
         );
     }
+
+    #[test]
+    fn test_is_stdio_pipe_error() {
+        assert!(is_stdio_pipe_error("MCP stdio: EOF while reading response"));
+        assert!(is_stdio_pipe_error(
+            "Failed to write to MCP stdio stdin: Broken pipe (os error 32)"
+        ));
+        assert!(!is_stdio_pipe_error(
+            "MCP JSON-RPC error: {\"code\":-32601}"
+        ));
+        assert!(!is_stdio_pipe_error(
+            "MCP stdio: timeout waiting for response to 'tools/call'"
+        ));
+    }
 }
@@ -9,7 +9,9 @@
 //!
 //! **Config** (`config`): CRUD for `gh_mcp_servers` + `gh_mcp_discovered_tools`.
 //!
-//! Protocol: JSON-RPC 2.0 over HTTP (lightweight, no stdio transport needed).
+//! Protocol: JSON-RPC 2.0. The client speaks it over HTTP or over a spawned
+//! child's stdin/stdout (`transport = 'stdio'` in `gh_mcp_servers`); stdio
+//! servers that exit are restarted on the next tool call. The server is HTTP only.
 //! Spec: <https://spec.modelcontextprotocol.io/2024-11-05/>
 
 pub mod client;
@@ -163,7 +163,15 @@ async fn handle_tools_call(state: &AppState, request: &Value, id: &Value) -> Val
         .unwrap_or_default();
 
     // Execute native tool
-    match tools::execute_tool(tool_name, &arguments, state, &wd).await {
+    match tools::execute_tool(
+        tool_name,
+        &arguments,
+        state,
+        &wd,
+        &tools::ToolCaller::default(),
+    )
+    .await
+    {
         Ok(output) => {
             let mut content = vec![json!({ "type": "text", "text": output.text })];
 
@@ -267,7 +275,7 @@ async fn handle_resources_read(state: &AppState, request: &Value, id: &Value) ->
 
         "geminihydra://sessions" => {
             let rows = sqlx::query_as::<_, crate::models::SessionSummaryRow>(
-                "SELECT id, title, created_at, message_count FROM gh_sessions ORDER BY created_at DESC LIMIT 50",
+                "SELECT id, title, created_at, message_count FROM gh_sessions WHERE archived_at IS NULL ORDER BY created_at DESC LIMIT 50",
             )
             .fetch_all(&state.db)
             .await
@@ -407,14 +415,26 @@ fn build_mcp_tool_list() -> Vec<Value> {
                 "required": ["path", "pattern"]
             }),
         ),
+        mcp_tool(
+            "file_stat",
+            "Get file metadata without reading it: exists, is_dir, size_bytes, modified, line_count_estimate.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File or directory path" }
+                },
+                "required": ["path"]
+            }),
+        ),
         mcp_tool(
             "find_file",
-            "Find files by glob pattern. Returns matching file paths with sizes.",
+            "Find files by glob pattern, optionally only those whose content matches 'contains'. Returns matching file paths with sizes.",
             json!({
                 "type": "object",
                 "properties": {
                     "path": { "type": "string", "description": "Root directory to search" },
-                    "pattern": { "type": "string", "description": "Glob pattern like '*.tsx' or 'auth*'" }
+                    "pattern": { "type": "string", "description": "Glob pattern like '*.tsx' or 'auth*'" },
+                    "contains": { "type": "string", "description": "Optional text or regex the file content must match" }
                 },
                 "required": ["path", "pattern"]
             }),
@@ -453,6 +473,30 @@ fn build_mcp_tool_list() -> Vec<Value> {
                 "required": ["path", "old_text", "new_text"]
             }),
         ),
+        mcp_tool(
+            "copy_file",
+            "Copy a file, creating destination parent directories. Refuses to overwrite unless overwrite=true.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "src": { "type": "string", "description": "Absolute path of the file to copy" },
+                    "dst": { "type": "string", "description": "Absolute destination file path" },
+                    "overwrite": { "type": "boolean", "description": "Replace an existing destination (default false)" }
+                },
+                "required": ["src", "dst"]
+            }),
+        ),
+        mcp_tool(
+            "create_directory",
+            "Create a directory and any missing parents. Succeeds if it already exists.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Absolute path of the directory" }
+                },
+                "required": ["path"]
+            }),
+        ),
         mcp_tool(
             "diff_files",
             "Compare two files and show line-by-line differences in unified diff format.",
@@ -460,7 +504,10 @@ fn build_mcp_tool_list() -> Vec<Value> {
                 "type": "object",
                 "properties": {
                     "path_a": { "type": "string", "description": "First file path" },
-                    "path_b": { "type": "string", "description": "Second file path" }
+                    "path_b": { "type": "string", "description": "Second file path" },
+                    "max_lines": { "type": "integer", "description": "Max diff lines (default 200, max 2000)" },
+                    "context_lines": { "type": "integer", "description": "Context lines around changes in hunks_only mode (default 3, max 100)" },
+                    "hunks_only": { "type": "boolean", "description": "Only output changed hunks, omitting unchanged regions (default false)" }
                 },
                 "required": ["path_a", "path_b"]
             }),
@@ -477,7 +524,8 @@ fn build_mcp_tool_list() -> Vec<Value> {
                     "include_images": { "type": "boolean", "description": "Include image alt text (default: false)" },
                     "output_format": { "type": "string", "description": "'text' or 'json' (default: 'text')" },
                     "max_text_length": { "type": "integer", "description": "Max text chars, 0=unlimited (default: 0)" },
-                    "headers": { "type": "object", "description": "Custom HTTP headers" }
+                    "headers": { "type": "object", "description": "Custom HTTP headers" },
+                    "no_cache": { "type": "boolean", "description": "Bypass the page cache (default: false)" }
                 },
                 "required": ["url"]
             }),
@@ -502,7 +550,8 @@ fn build_mcp_tool_list() -> Vec<Value> {
                     "output_format": { "type": "string", "description": "'text' or 'json' (default: 'text')" },
                     "max_text_length": { "type": "integer", "description": "Max text per page (default: 2000)" },
                     "include_metadata": { "type": "boolean", "description": "Include metadata per page (default: false)" },
-                    "headers": { "type": "object", "description": "Custom HTTP headers" }
+                    "headers": { "type": "object", "description": "Custom HTTP headers" },
+                    "resume_token": { "type": "string", "description": "Continue an incomplete crawl (token from previous output)" }
                 },
                 "required": ["url"]
             }),
@@ -514,7 +563,8 @@ fn build_mcp_tool_list() -> Vec<Value> {
                 "type": "object",
                 "properties": {
                     "path": { "type": "string", "description": "Absolute path to the PDF file" },
-                    "page_range": { "type": "string", "description": "Page range, e.g. '1-5' or '3' (optional, reads all if omitted)" }
+                    "page_range": { "type": "string", "description": "Page range, e.g. '1-5' or '3' (optional, reads all if omitted)" },
+                    "mode": { "type": "string", "enum": ["text", "outline"], "description": "'outline' lists each page's first line and character count instead of the full text (default 'text')" }
                 },
                 "required": ["path"]
             }),
@@ -525,11 +575,11 @@ fn build_mcp_tool_list() -> Vec<Value> {
             json!({
                 "type": "object",
                 "properties": {
-                    "path": { "type": "string", "description": "Absolute path to the image file (PNG, JPEG, WebP, GIF)" },
+                    "path": { "type": "string", "description": "Absolute path to the image file (PNG, JPEG, WebP, GIF, HEIC/HEIF, BMP, TIFF)" },
+                    "paths": { "type": "array", "items": { "type": "string" }, "description": "Up to 8 image paths analyzed together in one request (instead of path)" },
                     "prompt": { "type": "string", "description": "Custom analysis prompt (optional)" },
                     "extract_text": { "type": "boolean", "description": "Focus on text extraction (OCR mode)" }
-                },
-                "required": ["path"]
+                }
             }),
         ),
         mcp_tool(
@@ -539,7 +589,8 @@ fn build_mcp_tool_list() -> Vec<Value> {
                 "type": "object",
                 "properties": {
                     "path": { "type": "string", "description": "Absolute path to the image or PDF file" },
-                    "prompt": { "type": "string", "description": "Additional OCR instructions (optional)" }
+                    "prompt": { "type": "string", "description": "Additional OCR instructions (optional)" },
+                    "language": { "type": "string", "description": "Document language hint, e.g. 'pl' (optional, default auto-detect)" }
                 },
                 "required": ["path"]
             }),
@@ -558,7 +609,7 @@ fn build_mcp_tool_list() -> Vec<Value> {
         ),
         mcp_tool(
             "list_mcp_tools",
-            "List all available MCP tools from connected external servers. Returns tool names, descriptions, and which server provides each tool.",
+            "List all discovered MCP tools from external servers, grouped by server with connection status and short descriptions.",
             json!({
                 "type": "object",
                 "properties": {},
@@ -567,14 +618,15 @@ fn build_mcp_tool_list() -> Vec<Value> {
         ),
         mcp_tool(
             "execute_mcp_tool",
-            "Execute a specific MCP tool by its prefixed name (mcp_servername_toolname) with custom arguments. Use list_mcp_tools first to discover available tools.",
+            "Execute a tool on a connected external MCP server. Use list_mcp_tools first to discover servers and tool names.",
             json!({
                 "type": "object",
                 "properties": {
-                    "tool_name": { "type": "string", "description": "Prefixed MCP tool name (e.g. mcp_brave_web_search)" },
+                    "server": { "type": "string", "description": "MCP server name (as shown by list_mcp_tools) or ID" },
+                    "tool": { "type": "string", "description": "Tool name on that server (unprefixed)" },
                     "arguments": { "type": "object", "description": "Tool arguments as a JSON object" }
                 },
-                "required": ["tool_name"]
+                "required": ["server", "tool"]
             }),
         ),
         // Git tools
@@ -601,16 +653,34 @@ fn build_mcp_tool_list() -> Vec<Value> {
         ),
         mcp_tool(
             "git_diff",
-            "Show changes (diff) in a git repository.",
+            "Show changes (diff) in a git repository, with a --stat summary header.",
             json!({
                 "type": "object",
                 "properties": {
                     "repo_path": { "type": "string", "description": "Absolute path to the git repository" },
-                    "target": { "type": "string", "description": "'staged', '--stat', or a commit/branch ref" }
+                    "mode": { "type": "string", "enum": ["unstaged", "staged", "range"], "description": "What to diff (default: unstaged)" },
+                    "from": { "type": "string", "description": "Start ref for mode='range'" },
+                    "to": { "type": "string", "description": "End ref for mode='range' (default: HEAD)" },
+                    "path": { "type": "string", "description": "Limit the diff to one file" },
+                    "max_lines": { "type": "integer", "description": "Maximum patch lines (default: 200, max: 2000)" },
+                    "target": { "type": "string", "description": "Deprecated: 'staged', '--stat', or a commit/branch ref" }
                 },
                 "required": ["repo_path"]
             }),
         ),
+        mcp_tool(
+            "git_show",
+            "Show a commit's metadata and diff.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "repo_path": { "type": "string", "description": "Absolute path to the git repository" },
+                    "ref": { "type": "string", "description": "Commit hash, branch, tag or relative ref" },
+                    "path": { "type": "string", "description": "Limit the diff to one file" }
+                },
+                "required": ["repo_path", "ref"]
+            }),
+        ),
         mcp_tool(
             "git_branch",
             "List, create, or switch git branches.",
@@ -625,17 +695,41 @@ fn build_mcp_tool_list() -> Vec<Value> {
         ),
         mcp_tool(
             "git_commit",
-            "Stage files and create a git commit (no push).",
+            "Stage files and create a git commit (no push). Returns the commit hash and short stat.",
             json!({
                 "type": "object",
                 "properties": {
                     "repo_path": { "type": "string", "description": "Absolute path to the git repository" },
                     "message": { "type": "string", "description": "Commit message" },
-                    "files": { "type": "string", "description": "'all' or comma-separated paths" }
+                    "files": { "type": "string", "description": "'all' or comma-separated paths" },
+                    "dry_run": { "type": "boolean", "description": "Preview the commit without staging or committing" }
                 },
                 "required": ["repo_path", "message"]
             }),
         ),
+        mcp_tool(
+            "git_stash",
+            "Stash uncommitted changes to tracked files.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "repo_path": { "type": "string", "description": "Absolute path to the git repository" },
+                    "message": { "type": "string", "description": "Stash description" }
+                },
+                "required": ["repo_path"]
+            }),
+        ),
+        mcp_tool(
+            "git_stash_pop",
+            "Re-apply and drop the most recent stash.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "repo_path": { "type": "string", "description": "Absolute path to the git repository" }
+                },
+                "required": ["repo_path"]
+            }),
+        ),
         // GitHub tools
         mcp_tool(
             "github_list_repos",
@@ -11,17 +11,23 @@ use axum::Json;
 use axum::extract::{Path, State};
 use axum::http::header;
 use axum::response::IntoResponse;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
 use utoipa::ToSchema;
 
-use crate::state::AppState;
+use crate::redact::redact_secrets;
+use crate::state::{AppState, ProviderEndpoints};
 
 // --- Jaskier Shared Core Types ---
 
 // ── Cache TTL ────────────────────────────────────────────────────────────────
 
 const CACHE_TTL: Duration = Duration::from_secs(3600); // 1 hour
+const HEALTH_TTL: Duration = Duration::from_secs(60);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const PROBE_CONCURRENCY: usize = 4;
 
 // ── Model info ───────────────────────────────────────────────────────────────
 
@@ -56,6 +62,27 @@ pub struct PinModelRequest {
 pub struct ModelCache {
     pub models: HashMap<String, Vec<ModelInfo>>,
     pub fetched_at: Option<Instant>,
+    /// Wall-clock time of the last refresh attempt that produced a model list.
+    pub last_refreshed: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether the last refresh reused the cached Google list (304 / unchanged body).
+    pub from_cache: bool,
+    /// Validator of the cached Google list, sent back on the next refresh.
+    pub google_validator: Option<ListValidator>,
+}
+
+/// Identifies a fetched model list so an unchanged one can be skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListValidator {
+    /// Upstream `ETag` header, sent as `If-None-Match`.
+    pub etag: Option<String>,
+    /// SHA-256 of the response body — used when the API sends no ETag.
+    pub content_hash: String,
+}
+
+/// Result of a conditional model-list fetch.
+enum FetchOutcome {
+    Fresh(Vec<ModelInfo>, ListValidator),
+    NotModified,
 }
 
 impl Default for ModelCache {
@@ -69,6 +96,9 @@ impl ModelCache {
         Self {
             models: HashMap::new(),
             fetched_at: None,
+            last_refreshed: None,
+            from_cache: false,
+            google_validator: None,
         }
     }
 
@@ -80,29 +110,142 @@ impl ModelCache {
     }
 }
 
+// ── Model health probes ──────────────────────────────────────────────────────
+
+/// Liveness of a single model, from a 1-token `generateContent` ping.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ModelProbe {
+    pub id: String,
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Last probe results — shared so concurrent callers reuse one sweep.
+#[derive(Default)]
+pub struct ModelHealthCache {
+    pub probes: Vec<ModelProbe>,
+    pub checked_at: Option<Instant>,
+}
+
+impl ModelHealthCache {
+    pub fn is_stale(&self) -> bool {
+        self.checked_at.is_none_or(|t| t.elapsed() > HEALTH_TTL)
+    }
+}
+
+async fn probe_google_model(
+    client: &reqwest::Client,
+    endpoints: &ProviderEndpoints,
+    model_id: &str,
+    api_key: &str,
+    is_oauth: bool,
+) -> ModelProbe {
+    let started = Instant::now();
+    let url = endpoints.gemini_model(model_id, "generateContent");
+    let body = json!({
+        "contents": [{ "role": "user", "parts": [{ "text": "ping" }] }],
+        "generationConfig": { "maxOutputTokens": 1 }
+    });
+
+    let result = match reqwest::Url::parse(&url) {
+        Ok(parsed_url) => {
+            crate::oauth::apply_google_auth(client.post(parsed_url), api_key, is_oauth)
+                .json(&body)
+                .timeout(PROBE_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| {
+                    if e.is_timeout() {
+                        format!("timed out after {}s", PROBE_TIMEOUT.as_secs())
+                    } else {
+                        format!("request failed: {}", redact_secrets(&e.to_string()))
+                    }
+                })
+        }
+        Err(e) => Err(format!("Invalid URL: {}", e)),
+    };
+
+    let error = match result {
+        Ok(resp) if resp.status().is_success() => None,
+        Ok(resp) => {
+            let status = resp.status();
+            let body: Value = resp.json().await.unwrap_or_default();
+            let message: String = body["error"]["message"]
+                .as_str()
+                .unwrap_or("")
+                .chars()
+                .take(200)
+                .collect();
+            Some(if message.is_empty() {
+                format!("HTTP {}", status.as_u16())
+            } else {
+                format!("HTTP {}: {}", status.as_u16(), message)
+            })
+        }
+        Err(e) => Some(e),
+    };
+
+    ModelProbe {
+        id: model_id.to_string(),
+        ok: error.is_none(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
 // ── Fetch models from providers ──────────────────────────────────────────────
 
+/// Fetch the Google model list. With a `validator` from the previous fetch the
+/// request is conditional (`If-None-Match`), and a 304 — or a body identical to
+/// the cached one — yields `NotModified` without re-parsing.
 async fn fetch_google_models(
     client: &reqwest::Client,
+    endpoints: &ProviderEndpoints,
     api_key: &str,
     is_oauth: bool,
-) -> Result<Vec<ModelInfo>, String> {
-    let url = "https://generativelanguage.googleapis.com/v1beta/models";
+    validator: Option<&ListValidator>,
+) -> Result<FetchOutcome, String> {
+    let url = endpoints.gemini_models();
 
-    let parsed_url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let parsed_url = reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
 
-    let resp = crate::oauth::apply_google_auth(client.get(parsed_url), api_key, is_oauth)
-        .send()
-        .await
-        .map_err(|e| format!("Google models request failed: {:?}", e))?;
+    let mut request = crate::oauth::apply_google_auth(client.get(parsed_url), api_key, is_oauth);
+    if let Some(etag) = validator.and_then(|v| v.etag.as_deref()) {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    let resp = request.send().await.map_err(|e| {
+        format!(
+            "Google models request failed: {}",
+            redact_secrets(&format!("{:?}", e))
+        )
+    })?;
 
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED && validator.is_some() {
+        return Ok(FetchOutcome::NotModified);
+    }
     if !resp.status().is_success() {
         return Err(format!("Google models API returned {}", resp.status()));
     }
 
-    let body: Value = resp
-        .json()
+    let etag = resp
+        .headers()
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let bytes = resp
+        .bytes()
         .await
+        .map_err(|e| format!("Failed to read Google models: {}", e))?;
+    let content_hash: String = Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if validator.is_some_and(|v| v.content_hash == content_hash) {
+        return Ok(FetchOutcome::NotModified);
+    }
+
+    let body: Value = serde_json::from_slice(&bytes)
         .map_err(|e| format!("Failed to parse Google models: {}", e))?;
 
     let models_arr = body["models"].as_array().cloned().unwrap_or_default();
@@ -153,20 +296,29 @@ async fn fetch_google_models(
         }
     }
 
-    Ok(models)
+    Ok(FetchOutcome::Fresh(
+        models,
+        ListValidator { etag, content_hash },
+    ))
 }
 
 async fn fetch_anthropic_models(
     client: &reqwest::Client,
+    endpoints: &ProviderEndpoints,
     api_key: &str,
 ) -> Result<Vec<ModelInfo>, String> {
     let resp = client
-        .get("https://api.anthropic.com/v1/models")
+        .get(endpoints.anthropic_models())
         .header("x-api-key", api_key)
         .header("anthropic-version", "2023-06-01")
         .send()
         .await
-        .map_err(|e| format!("Anthropic models request failed: {}", e))?;
+        .map_err(|e| {
+            format!(
+                "Anthropic models request failed: {}",
+                redact_secrets(&e.to_string())
+            )
+        })?;
 
     if !resp.status().is_success() {
         return Err(format!("Anthropic models API returned {}", resp.status()));
@@ -213,6 +365,16 @@ pub async fn refresh_cache(state: &AppState) -> (HashMap<String, Vec<ModelInfo>>
 
     // Prepare credentials for both providers before parallel fetch
     let google_cred = crate::oauth::get_google_credential(state).await;
+    // Conditional fetch is only safe while the cached Google list is still present
+    let (google_validator, cached_google) = {
+        let cache = state.model_cache.read().await;
+        match cache.models.get("google") {
+            Some(models) => (cache.google_validator.clone(), models.clone()),
+            None => (None, Vec::new()),
+        }
+    };
+    let mut google_from_cache = false;
+    let mut new_google_validator = None;
     let anthropic_key = {
         let rt = state.runtime.read().await;
         rt.api_keys.get("anthropic").cloned()
@@ -223,7 +385,14 @@ pub async fn refresh_cache(state: &AppState) -> (HashMap<String, Vec<ModelInfo>>
         async {
             if let Some((cred, is_oauth)) = google_cred {
                 Some((
-                    fetch_google_models(&state.client, &cred, is_oauth).await,
+                    fetch_google_models(
+                        &state.client,
+                        &state.providers,
+                        &cred,
+                        is_oauth,
+                        google_validator.as_ref(),
+                    )
+                    .await,
                     is_oauth,
                 ))
             } else {
@@ -232,7 +401,7 @@ pub async fn refresh_cache(state: &AppState) -> (HashMap<String, Vec<ModelInfo>>
         },
         async {
             if let Some(ref key) = anthropic_key {
-                Some(fetch_anthropic_models(&state.client, key).await)
+                Some(fetch_anthropic_models(&state.client, &state.providers, key).await)
             } else {
                 None
             }
@@ -242,9 +411,19 @@ pub async fn refresh_cache(state: &AppState) -> (HashMap<String, Vec<ModelInfo>>
     // Process Google result — preserve OAuth → API key fallback logic
     if let Some((result, is_oauth)) = google_result {
         match result {
-            Ok(models) => {
+            Ok(FetchOutcome::Fresh(models, validator)) => {
                 tracing::info!("model_registry: fetched {} Google models", models.len());
                 all_models.insert("google".to_string(), models);
+                new_google_validator = Some(validator);
+            }
+            Ok(FetchOutcome::NotModified) => {
+                tracing::info!(
+                    "model_registry: Google model list unchanged — reusing {} cached models",
+                    cached_google.len()
+                );
+                all_models.insert("google".to_string(), cached_google.clone());
+                new_google_validator = google_validator.clone();
+                google_from_cache = true;
             }
             Err(e) => {
                 tracing::warn!("model_registry: Google fetch failed: {}", e);
@@ -255,15 +434,27 @@ pub async fn refresh_cache(state: &AppState) -> (HashMap<String, Vec<ModelInfo>>
                     if let Some((fallback_cred, fallback_is_oauth)) =
                         crate::oauth::get_google_api_key_credential(state).await
                     {
-                        match fetch_google_models(&state.client, &fallback_cred, fallback_is_oauth)
-                            .await
+                        match fetch_google_models(
+                            &state.client,
+                            &state.providers,
+                            &fallback_cred,
+                            fallback_is_oauth,
+                            google_validator.as_ref(),
+                        )
+                        .await
                         {
-                            Ok(models) => {
+                            Ok(FetchOutcome::Fresh(models, validator)) => {
                                 tracing::info!(
                                     "model_registry: fallback OK — fetched {} Google models via API key",
                                     models.len()
                                 );
                                 all_models.insert("google".to_string(), models);
+                                new_google_validator = Some(validator);
+                            }
+                            Ok(FetchOutcome::NotModified) => {
+                                all_models.insert("google".to_string(), cached_google.clone());
+                                new_google_validator = google_validator.clone();
+                                google_from_cache = true;
                             }
                             Err(e2) => {
                                 tracing::warn!(
@@ -301,6 +492,9 @@ pub async fn refresh_cache(state: &AppState) -> (HashMap<String, Vec<ModelInfo>>
     let mut cache = state.model_cache.write().await;
     cache.models = all_models.clone();
     cache.fetched_at = Some(Instant::now());
+    cache.last_refreshed = Some(chrono::Utc::now());
+    cache.from_cache = google_from_cache;
+    cache.google_validator = new_google_validator;
 
     (all_models, errors)
 }
@@ -549,6 +743,7 @@ pub async fn startup_sync(state: &AppState) {
         .execute(&state.db)
         .await;
 
+        state.invalidate_settings().await;
         match res {
             Ok(_) => tracing::info!("model_registry: default_model updated to {}", best.id),
             Err(e) => tracing::warn!("model_registry: failed to update default_model: {}", e),
@@ -629,9 +824,15 @@ pub async fn refresh_models(State(state): State<AppState>) -> Json<Value> {
     let pins = get_pins_map(&state).await;
 
     let total: usize = models.values().map(|v| v.len()).sum();
+    let (last_refreshed, from_cache) = {
+        let cache = state.model_cache.read().await;
+        (cache.last_refreshed, cache.from_cache)
+    };
 
     let mut resp = json!({
         "refreshed": true,
+        "from_cache": from_cache,
+        "last_refreshed": last_refreshed.map(|t| t.to_rfc3339()),
         "total_models": total,
         "pins": pins,
         "selected": {
@@ -717,6 +918,66 @@ pub async fn unpin_model(
     }
 }
 
+/// GET /api/models/health — Ping every cached Google model (results cached for 60s)
+#[utoipa::path(get, path = "/api/models/health", tag = "models",
+    responses(
+        (status = 200, description = "Per-model liveness from a 1-token generateContent ping", body = Value),
+        (status = 503, description = "No Google credential configured")
+    )
+)]
+pub async fn models_health(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, crate::error::ApiError> {
+    // Held across the sweep so concurrent callers wait for it instead of re-probing
+    let mut health = state.model_health.lock().await;
+
+    let cached = !health.is_stale();
+    if !cached {
+        let (cred, is_oauth) = crate::oauth::get_google_credential(&state)
+            .await
+            .ok_or_else(|| {
+                crate::error::ApiError::Unavailable("No Google credential configured".into())
+            })?;
+        let ids: Vec<String> = {
+            let cache = state.model_cache.read().await;
+            cache
+                .models
+                .get("google")
+                .map(|models| models.iter().map(|m| m.id.clone()).collect())
+                .unwrap_or_default()
+        };
+
+        health.probes = futures_util::stream::iter(ids)
+            .map(|id| {
+                let client = &state.client;
+                let endpoints = &state.providers;
+                let cred = &cred;
+                async move { probe_google_model(client, endpoints, &id, cred, is_oauth).await }
+            })
+            .buffered(PROBE_CONCURRENCY)
+            .collect()
+            .await;
+        health.checked_at = Some(Instant::now());
+
+        let failing = health.probes.iter().filter(|p| !p.ok).count();
+        if failing > 0 {
+            tracing::warn!(
+                "model_registry: {}/{} Google models failed the health probe",
+                failing,
+                health.probes.len()
+            );
+        }
+    }
+
+    Ok(Json(json!({
+        "cached": cached,
+        "checked_seconds_ago": health.checked_at.map(|t| t.elapsed().as_secs()),
+        "healthy": health.probes.iter().filter(|p| p.ok).count(),
+        "total": health.probes.len(),
+        "models": health.probes,
+    })))
+}
+
 /// GET /api/models/pins — List all active pins
 #[utoipa::path(get, path = "/api/models/pins", tag = "models",
     responses((status = 200, description = "All active model pins", body = Value))
@@ -898,6 +1159,77 @@ mod tests {
         assert!(cache.models.is_empty());
     }
 
+    // ── conditional Google fetch ─────────────────────────────────────────
+
+    /// Local stand-in for the Google models endpoint; answers 304 when
+    /// `If-None-Match` matches `etag`.
+    async fn serve_google_models(etag: Option<&'static str>) -> ProviderEndpoints {
+        let upstream = axum::Router::new().route(
+            "/v1beta/models",
+            axum::routing::get(move |headers: axum::http::HeaderMap| async move {
+                let body = json!({ "models": [{
+                    "name": "models/gemini-2.5-flash",
+                    "supportedGenerationMethods": ["generateContent"]
+                }] })
+                .to_string();
+                let mut response = body.into_response();
+                if let Some(etag) = etag {
+                    if headers
+                        .get(header::IF_NONE_MATCH)
+                        .is_some_and(|v| v == etag)
+                    {
+                        return axum::http::StatusCode::NOT_MODIFIED.into_response();
+                    }
+                    response
+                        .headers_mut()
+                        .insert(header::ETAG, header::HeaderValue::from_static(etag));
+                }
+                response
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, upstream).await });
+        ProviderEndpoints {
+            gemini: format!("http://{}", addr),
+            ..ProviderEndpoints::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn google_fetch_skips_unchanged_lists() {
+        let client = reqwest::Client::new();
+        for etag in [Some("\"v1\""), None] {
+            let endpoints = serve_google_models(etag).await;
+            let validator = match fetch_google_models(&client, &endpoints, "key", false, None)
+                .await
+                .unwrap()
+            {
+                FetchOutcome::Fresh(models, validator) => {
+                    assert_eq!(models[0].id, "gemini-2.5-flash");
+                    validator
+                }
+                FetchOutcome::NotModified => panic!("first fetch must be fresh"),
+            };
+            assert_eq!(validator.etag.as_deref(), etag);
+
+            // 304 with an ETag, identical body hash without one
+            let again = fetch_google_models(&client, &endpoints, "key", false, Some(&validator))
+                .await
+                .unwrap();
+            assert!(matches!(again, FetchOutcome::NotModified));
+
+            let stale = ListValidator {
+                etag: None,
+                content_hash: "other".into(),
+            };
+            let changed = fetch_google_models(&client, &endpoints, "key", false, Some(&stale))
+                .await
+                .unwrap();
+            assert!(matches!(changed, FetchOutcome::Fresh(..)));
+        }
+    }
+
     // ── customtools model ───────────────────────────────────────────────
 
     #[test]
@@ -938,4 +1270,18 @@ mod tests {
         );
         assert_eq!(fallback.unwrap().id, "gemini-3.1-pro-preview");
     }
+
+    // ── ModelHealthCache ────────────────────────────────────────────────
+
+    #[test]
+    fn model_health_cache_expires_after_ttl() {
+        let mut cache = ModelHealthCache::default();
+        assert!(cache.is_stale());
+
+        cache.checked_at = Some(Instant::now());
+        assert!(!cache.is_stale());
+
+        cache.checked_at = Instant::now().checked_sub(HEALTH_TTL + Duration::from_secs(1));
+        assert!(cache.is_stale());
+    }
 }
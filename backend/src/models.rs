@@ -5,7 +5,7 @@ use utoipa::ToSchema;
 // DB row types
 // ---------------------------------------------------------------------------
 
-#[derive(sqlx::FromRow)]
+#[derive(Clone, sqlx::FromRow)]
 pub struct SettingsRow {
     pub temperature: f64,
     pub max_tokens: i32,
@@ -30,6 +30,51 @@ pub struct SettingsRow {
     /// Force all agents to use this model (NULL = auto-select per agent)
     #[sqlx(default)]
     pub force_model: Option<String>,
+    /// Stop the tool-call loop on the first TOOL_ERROR result
+    #[sqlx(default)]
+    pub stop_on_tool_error: bool,
+    /// Executables `execute_command` may run (empty = denylist-only mode)
+    #[sqlx(default)]
+    pub command_allowlist: Vec<String>,
+    /// Extra case-insensitive substrings `execute_command` refuses, on top of the built-ins
+    #[sqlx(default)]
+    pub extra_blocked_patterns: Vec<String>,
+    /// Models tried in order when the primary model keeps failing
+    #[sqlx(default)]
+    pub fallback_models: Vec<String>,
+    /// How many levels deep `call_agent` delegation may nest
+    #[sqlx(default)]
+    pub max_agent_call_depth: i32,
+    /// Session messages replayed per request, before per-tier scaling
+    #[sqlx(default)]
+    pub history_window: i32,
+    /// Most recent history messages kept untruncated
+    #[sqlx(default)]
+    pub history_truncate_keep: i32,
+    /// Gemini safetySettings preset: 'default', 'relaxed', 'strict'
+    #[sqlx(default)]
+    pub safety_level: String,
+    /// Recent average rating below which the quality alert is injected
+    #[sqlx(default)]
+    pub rating_alert_threshold: f64,
+    /// Recent ratings required before the quality alert can fire
+    #[sqlx(default)]
+    pub rating_alert_min_count: i32,
+    /// Byte cap on content written by write_file / edit_file
+    #[sqlx(default)]
+    pub max_write_bytes: i32,
+    /// Byte cap on tool output streamed into the chat
+    #[sqlx(default)]
+    pub max_tool_display_bytes: i32,
+    /// Retries of a transient Gemini error on the streaming path
+    #[sqlx(default)]
+    pub gemini_max_retries: i32,
+    /// First retry delay in ms (doubles per attempt)
+    #[sqlx(default)]
+    pub gemini_backoff_base_ms: i32,
+    /// Random jitter added to each retry delay, in ms
+    #[sqlx(default)]
+    pub gemini_backoff_jitter_ms: i32,
 }
 
 #[derive(sqlx::FromRow)]
@@ -40,6 +85,8 @@ pub struct ChatMessageRow {
     pub model: Option<String>,
     pub agent: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    #[sqlx(default)]
+    pub pinned: bool,
 }
 
 #[derive(sqlx::FromRow)]
@@ -65,6 +112,13 @@ pub struct KnowledgeEdgeRow {
     pub label: String,
 }
 
+#[derive(sqlx::FromRow)]
+pub struct SettingsProfileRow {
+    pub name: String,
+    pub settings: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 // ---------------------------------------------------------------------------
 // Witcher Agents
 // ---------------------------------------------------------------------------
@@ -101,6 +155,58 @@ pub struct WitcherAgent {
     #[serde(default)]
     #[sqlx(default)]
     pub ab_split: Option<f64>,
+    /// Position in the roster (ascending), set by `POST /api/agents/reorder`
+    #[serde(default)]
+    #[sqlx(default)]
+    pub display_order: i32,
+}
+
+/// Previous `system_prompt` of an agent, recorded before each change.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct AgentPromptVersion {
+    pub version: i32,
+    pub system_prompt: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Number of ratings given at one star level.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct RatingBucket {
+    pub stars: i32,
+    pub count: i64,
+}
+
+/// Ratings received during one calendar week (Monday start).
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct RatingTrendPoint {
+    pub week_start: chrono::DateTime<chrono::Utc>,
+    pub count: i64,
+    pub average: f64,
+}
+
+/// A rated-2-or-below response, with the start of the message for context.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct LowRatedMessage {
+    pub message_id: String,
+    pub session_id: Option<String>,
+    pub rating: i32,
+    pub feedback: Option<String>,
+    pub content_preview: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Rating distribution of an agent, as returned by `GET /api/agents/{id}/ratings`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AgentRatingBreakdown {
+    pub agent_id: String,
+    pub total: i64,
+    pub average: Option<f64>,
+    /// One bucket per star level, 1 through 5
+    pub histogram: Vec<RatingBucket>,
+    /// Weekly buckets over the last 12 weeks, oldest first
+    pub weekly: Vec<RatingTrendPoint>,
+    /// Most recent low ratings, newest first
+    pub recent_low: Vec<LowRatedMessage>,
 }
 
 // ---------------------------------------------------------------------------
@@ -135,6 +241,23 @@ pub struct DetailedHealthResponse {
     pub memory_usage_mb: f64,
     pub cpu_usage_percent: f32,
     pub platform: String,
+    /// Result of the watchdog's last DB pool check
+    pub database: DbHealthStatus,
+    /// Chat messages waiting to be written after a failed insert
+    pub unsaved_messages: usize,
+}
+
+/// DB pool health as last seen by the watchdog (`SELECT 1` with a short timeout).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct DbHealthStatus {
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    /// RFC 3339 timestamp of the last check (None until the first one runs)
+    pub last_check: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub pool_size: u32,
+    pub pool_idle: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -144,9 +267,27 @@ pub struct DetailedHealthResponse {
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ExecuteRequest {
     pub prompt: String,
+    /// `auto`, an agent id/name, or `plan-only` to return an
+    /// `ExecutionPreview` without calling Gemini
     pub mode: String,
     #[serde(default)]
     pub model: Option<String>,
+    /// Run the full tool loop (as over WebSocket) and return once it finishes
+    #[serde(default)]
+    pub enable_tools: bool,
+    /// Session to run in: its working directory and model override apply,
+    /// and tool-enabled runs see its history
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Temperature for this request only (clamped to 0.0-2.0)
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    /// topP for this request only (clamped to 0.0-1.0)
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    /// Thinking level for this request only: none, minimal, low, medium, high
+    #[serde(default)]
+    pub thinking_level: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -156,6 +297,23 @@ pub struct ExecutePlan {
     pub estimated_time: Option<String>,
 }
 
+/// Result of `mode: "plan-only"` — what an execute request would do, without
+/// calling Gemini.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExecutionPreview {
+    pub plan: ExecutePlan,
+    pub confidence: f64,
+    pub reasoning: String,
+    /// Model the request would be sent to
+    pub model: String,
+    pub thinking_level: String,
+    pub max_tokens: i32,
+    pub files_loaded: Vec<String>,
+    pub system_prompt_chars: usize,
+    /// Final user prompt, including loaded file contents
+    pub prompt_chars: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ExecuteResponse {
     pub id: String,
@@ -165,6 +323,91 @@ pub struct ExecuteResponse {
     pub mode: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub files_loaded: Vec<String>,
+    /// Tools called, in order, when `enable_tools` was set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools_used: Vec<String>,
+}
+
+/// One native tool in the `GET /api/tools` catalog.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ToolCatalogEntry {
+    pub name: String,
+    /// Registry category (`filesystem`, `git`, ...); `client` for tools the
+    /// chat client answers, like `ask_user`
+    pub category: String,
+    pub description: String,
+    /// JSON schema of the arguments, as declared to Gemini
+    pub parameters: serde_json::Value,
+    /// Whether `POST /api/tools/execute` can run it
+    pub executable: bool,
+}
+
+/// Body of `POST /api/tools/execute`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ToolExecuteRequest {
+    /// Native tool name, as listed by `tools::list_available_tools`
+    pub name: String,
+    /// Tool arguments, as the model would send them
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+/// Binary attachment returned by a tool (e.g. an analyzed image).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ToolInlineData {
+    pub mime_type: String,
+    /// Base64-encoded bytes
+    pub data: String,
+}
+
+/// Result of `POST /api/tools/execute`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ToolExecuteResponse {
+    pub name: String,
+    pub success: bool,
+    /// Tool output text (empty when the tool returned an error)
+    pub text: String,
+    /// Error message when the tool failed before producing output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<ToolInlineData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EnsembleRequest {
+    pub prompt: String,
+    /// Agent IDs (or names) that answer the prompt in parallel — 2 to 6.
+    pub agents: Vec<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Add a final judge call that synthesizes a consensus answer.
+    #[serde(default)]
+    pub judge: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EnsembleAnswer {
+    pub agent: String,
+    pub model: String,
+    /// Answer text, or the error message when `success` is false.
+    pub content: String,
+    pub success: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EnsembleResponse {
+    pub id: String,
+    pub answers: Vec<EnsembleAnswer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consensus: Option<String>,
+    /// The shared deadline expired before every branch (or the judge) finished.
+    pub timed_out: bool,
+    pub duration_ms: u64,
 }
 
 // ---------------------------------------------------------------------------
@@ -221,6 +464,43 @@ pub struct AppSettings {
     pub working_directory: String,
     /// Force all agents to use this model (None = auto-select per agent)
     pub force_model: Option<String>,
+    /// Stop the tool-call loop on the first TOOL_ERROR result
+    pub stop_on_tool_error: bool,
+    /// Executables `execute_command` may run (empty = denylist-only mode)
+    pub command_allowlist: Vec<String>,
+    /// Extra case-insensitive substrings `execute_command` refuses, on top of the built-ins
+    pub extra_blocked_patterns: Vec<String>,
+    /// Models tried in order when the primary model keeps failing
+    /// (empty = the flash tier model)
+    pub fallback_models: Vec<String>,
+    /// How many levels deep `call_agent` delegation may nest (1-6)
+    pub max_agent_call_depth: i32,
+    /// Session messages replayed per request for a mid-tier model (2-100);
+    /// halved for flash models, doubled for pro models
+    pub history_window: i32,
+    /// Most recent history messages kept untruncated (0-50)
+    pub history_truncate_keep: i32,
+    /// Gemini safetySettings preset: 'default' (Gemini's thresholds),
+    /// 'relaxed' (block only high), 'strict' (block low and above)
+    pub safety_level: String,
+    /// #50 — Quality alert fires when the average of the agent's last 20
+    /// ratings is below this (1.0-5.0)
+    pub rating_alert_threshold: f64,
+    /// #50 — Recent ratings required before the quality alert can fire (1-20)
+    pub rating_alert_min_count: i32,
+    /// Byte cap on content written by the write_file / edit_file tools
+    /// (1 KB - 50 MB)
+    pub max_write_bytes: i32,
+    /// Byte cap on each tool result streamed into the chat; the saved message
+    /// keeps the full output (1 KB - 1 MB)
+    pub max_tool_display_bytes: i32,
+    /// Retries of a transient Gemini error (429, 503, timeout) before the
+    /// request fails over (0-10)
+    pub gemini_max_retries: i32,
+    /// First retry delay in ms, doubling per attempt (100-30000)
+    pub gemini_backoff_base_ms: i32,
+    /// Random jitter added to each retry delay, in ms (0-10000)
+    pub gemini_backoff_jitter_ms: i32,
 }
 
 impl Default for AppSettings {
@@ -239,6 +519,22 @@ impl Default for AppSettings {
             thinking_level: "medium".into(),
             working_directory: String::new(),
             force_model: None,
+            stop_on_tool_error: false,
+            command_allowlist: Vec::new(),
+            extra_blocked_patterns: Vec::new(),
+            fallback_models: Vec::new(),
+            max_agent_call_depth: 3,
+            history_window: 20,
+            history_truncate_keep: 6,
+            safety_level: "default".into(),
+            rating_alert_threshold: 3.0,
+            rating_alert_min_count: 5,
+            max_write_bytes: crate::files::DEFAULT_MAX_WRITE_BYTES as i32,
+            max_tool_display_bytes: crate::handlers::streaming::DEFAULT_TOOL_DISPLAY_BYTES as i32,
+            gemini_max_retries: crate::handlers::streaming::GEMINI_MAX_RETRIES as i32,
+            gemini_backoff_base_ms: crate::handlers::streaming::GEMINI_BACKOFF_BASE.as_millis()
+                as i32,
+            gemini_backoff_jitter_ms: crate::handlers::streaming::GEMINI_BACKOFF_JITTER_MS as i32,
         }
     }
 }
@@ -269,6 +565,9 @@ pub struct ChatMessage {
     pub timestamp: String,
     #[serde(default)]
     pub agent: Option<String>,
+    /// User-marked critical context, always replayed to the model.
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -287,6 +586,9 @@ pub struct FileReadResponse {
     pub size_bytes: u64,
     pub truncated: bool,
     pub extension: String,
+    /// Source encoding when the file was not UTF-8 (content is always UTF-8).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -316,6 +618,14 @@ pub struct FileEntryResponse {
 // Classify
 // ---------------------------------------------------------------------------
 
+/// Body of `POST /api/agents/reorder`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReorderAgentsRequest {
+    /// Agent ids in the new order. Agents not listed keep their relative
+    /// order after the listed ones.
+    pub ids: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ClassifyRequest {
     pub prompt: String,
@@ -328,6 +638,29 @@ pub struct ClassifyResponse {
     pub reasoning: String,
 }
 
+/// Keyword score of one agent in a classification preview.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AgentClassifyScore {
+    pub agent: String,
+    /// Keyword confidence (0 when nothing matched)
+    pub score: f64,
+    pub matched_keywords: Vec<String>,
+}
+
+/// Result of `POST /api/agents/classify/debug`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClassifyDebugResponse {
+    /// Keyword winner, as `POST /api/agents/classify` would return it
+    pub agent: String,
+    pub confidence: f64,
+    pub reasoning: String,
+    /// Whether execution would ask Gemini Flash to re-classify
+    pub gemini_fallback: bool,
+    pub gemini_fallback_threshold: f64,
+    /// Every agent, highest score first
+    pub scores: Vec<AgentClassifyScore>,
+}
+
 // ---------------------------------------------------------------------------
 // Ratings
 // ---------------------------------------------------------------------------
@@ -370,6 +703,10 @@ pub struct SessionRow {
     pub updated_at: chrono::DateTime<chrono::Utc>,
     #[sqlx(default)]
     pub working_directory: String,
+    #[sqlx(default)]
+    pub model_override: Option<String>,
+    #[sqlx(default)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -382,6 +719,10 @@ pub struct SessionSummaryRow {
     pub working_directory: String,
     #[sqlx(default)]
     pub agent_id: Option<String>,
+    #[sqlx(default)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[sqlx(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -392,6 +733,12 @@ pub struct Session {
     pub messages: Vec<ChatMessage>,
     #[serde(default)]
     pub working_directory: String,
+    /// Model pinned for this session only (overrides global pins).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_override: Option<String>,
+    /// Set when the session was soft-deleted (RFC 3339).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -404,6 +751,24 @@ pub struct SessionSummary {
     pub working_directory: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_id: Option<String>,
+    /// Set when the session was soft-deleted (RFC 3339).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Response of `POST /api/sessions/{id}/summarize`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionAbstract {
+    pub session_id: String,
+    /// One-paragraph abstract of what the session accomplished
+    pub summary: String,
+    /// Messages the summary was generated from
+    pub message_count: i32,
+    pub model: String,
+    /// RFC 3339
+    pub generated_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -419,6 +784,115 @@ pub struct UpdateSessionRequest {
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateWorkingDirectoryRequest {
     pub working_directory: String,
+    /// Save a path that does not exist yet (it must still not be a file)
+    #[serde(default)]
+    pub allow_nonexistent: bool,
+}
+
+/// `PATCH /api/sessions/{id}/model` — `null` or empty clears the pin.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSessionModelRequest {
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionModelResponse {
+    pub session_id: String,
+    pub model_override: Option<String>,
+    /// Model a request without an explicit `model` would start from.
+    pub effective_model: String,
+    /// `force_model` (global setting), `session` (this pin) or `default`
+    /// (global default — agent overrides and auto-tier routing still apply).
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AddSessionTagRequest {
+    pub tag: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionTagsResponse {
+    pub session_id: String,
+    /// Sorted alphabetically.
+    pub tags: Vec<String>,
+}
+
+/// `POST /api/sessions/bulk-delete` — at least one filter is required;
+/// when both are set a session must match both.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkDeleteSessionsRequest {
+    #[serde(default)]
+    pub ids: Vec<String>,
+    /// Delete sessions not updated in the last N days (N >= 1).
+    #[serde(default)]
+    pub older_than_days: Option<i32>,
+}
+
+/// `GET /api/sessions/{id}/stats` — per-session activity summary.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionStats {
+    pub session_id: String,
+    pub message_count: i64,
+    pub user_messages: i64,
+    pub assistant_messages: i64,
+    /// RFC 3339; `None` for a session without messages.
+    pub first_message_at: Option<String>,
+    pub last_message_at: Option<String>,
+    /// Agents the session's messages are attributed to, most active first.
+    pub agents: Vec<SessionAgentUsage>,
+    /// Most used tools, most frequent first (at most 10).
+    pub top_tools: Vec<SessionToolUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionAgentUsage {
+    pub agent: String,
+    pub messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionToolUsage {
+    pub tool: String,
+    pub calls: i64,
+}
+
+/// `GET /api/history/stats` — activity across all sessions.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistoryStats {
+    /// Length of the window the breakdowns below cover, in days.
+    pub days: i32,
+    /// All stored messages, regardless of the window.
+    pub total_messages: i64,
+    pub window_messages: i64,
+    /// One entry per UTC day of the window, oldest first, zero-filled.
+    pub daily: Vec<DailyMessageCount>,
+    /// Messages routed to each agent, most active first.
+    pub agents: Vec<SessionAgentUsage>,
+    /// Assistant replies per model, most used first.
+    pub models: Vec<ModelMessageCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DailyMessageCount {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModelMessageCount {
+    pub model: String,
+    pub messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkDeleteSessionsResponse {
+    pub deleted: u64,
+    pub messages_deleted: u64,
+    /// More sessions matched than one batch allows — call again to continue.
+    pub has_more: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -451,6 +925,18 @@ pub enum WsClientMessage {
         model: Option<String>,
         #[serde(default)]
         session_id: Option<String>,
+        /// Per-request override of the `stop_on_tool_error` setting
+        #[serde(default)]
+        stop_on_tool_error: Option<bool>,
+        /// Per-request override of the temperature (clamped to 0.0-2.0)
+        #[serde(default)]
+        temperature: Option<f64>,
+        /// Per-request override of topP (clamped to 0.0-1.0)
+        #[serde(default)]
+        top_p: Option<f64>,
+        /// Per-request override of the thinking level
+        #[serde(default)]
+        thinking_level: Option<String>,
     },
     /// Orchestrated multi-agent execution via ADK sidecar.
     Orchestrate {
@@ -462,6 +948,15 @@ pub enum WsClientMessage {
         #[serde(default)]
         session_id: Option<String>,
     },
+    /// Same prompt answered by several agents concurrently, optionally judged.
+    Ensemble {
+        prompt: String,
+        agents: Vec<String>,
+        #[serde(default)]
+        model: Option<String>,
+        #[serde(default)]
+        judge: bool,
+    },
     Cancel,
     Ping,
     /// Response from the user to an `ask_user` tool call.
@@ -476,6 +971,8 @@ pub enum WsClientMessage {
 pub enum WsServerMessage {
     Start {
         id: String,
+        /// Correlation ID of the WebSocket connection, as recorded on its log lines
+        request_id: String,
         agent: String,
         model: String,
         files_loaded: Vec<String>,
@@ -483,15 +980,26 @@ pub enum WsServerMessage {
     Token {
         content: String,
     },
+    /// Model reasoning (thinking summary), streamed apart from the answer.
+    Thought {
+        content: String,
+    },
     Plan {
         agent: String,
         confidence: f64,
         steps: Vec<String>,
         reasoning: String,
     },
+    /// Reply to a `plan-only` execute — sent instead of any tokens.
+    PlanPreview(ExecutionPreview),
     Complete {
         duration_ms: u64,
     },
+    /// Sent instead of `Complete` when the client cancelled (or the server is
+    /// shutting down) — the partial answer was stored with a `[cancelled]` note.
+    Cancelled {
+        duration_ms: u64,
+    },
     ToolCall {
         name: String,
         args: serde_json::Value,
@@ -512,6 +1020,29 @@ pub enum WsServerMessage {
         number: u32,
         max: u32,
     },
+    /// The primary model failed after all retries; the answer that follows
+    /// comes from fallback model `to`.
+    ModelFallback {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// A transient Gemini error (429, 503, timeout) — attempt `attempt` of
+    /// `max` follows after `delay_ms`.
+    Retry {
+        attempt: u32,
+        max: u32,
+        delay_ms: u64,
+    },
+    /// Oldest turns were dropped (or tool results shortened) so the next
+    /// Gemini request fits the model's context budget.
+    ContextTrimmed {
+        dropped_messages: u32,
+        shrunk_results: u32,
+        bytes_before: u64,
+        bytes_after: u64,
+        budget_bytes: u64,
+    },
     AgentSuggestion {
         agent: String,
         confidence: f64,
@@ -335,6 +335,7 @@ pub async fn google_redirect(
     let resp = match token_resp {
         Ok(r) => r,
         Err(e) => {
+            let e = crate::redact::redact_secrets(&e.to_string()).into_owned();
             tracing::error!("Google token exchange request failed: {}", e);
             return Html(format!(
                 r#"<!DOCTYPE html><html><head><title>Auth Error</title></head>
@@ -434,7 +435,7 @@ pub async fn save_api_key(
     // Validate by listing models
     let resp = state
         .client
-        .get("https://generativelanguage.googleapis.com/v1beta/models")
+        .get(state.providers.gemini_models())
         .header("x-goog-api-key", key)
         .timeout(std::time::Duration::from_secs(15))
         .send()
@@ -442,14 +443,23 @@ pub async fn save_api_key(
         .map_err(|e| {
             (
                 StatusCode::BAD_GATEWAY,
-                Json(json!({ "error": format!("Validation request failed: {}", e) })),
+                Json(json!({
+                    "error": format!(
+                        "Validation request failed: {}",
+                        crate::redact::redact_secrets(&e.to_string())
+                    )
+                })),
             )
         })?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let err = resp.text().await.unwrap_or_default();
-        tracing::warn!("API key validation failed: {} — {}", status, err);
+        tracing::warn!(
+            "API key validation failed: {} — {}",
+            status,
+            crate::redact::redact_secrets(&err)
+        );
         return Err((
             StatusCode::BAD_REQUEST,
             Json(json!({ "error": "Invalid API key", "valid": false })),
@@ -5,12 +5,13 @@
 //   POST /api/ocr              — synchronous OCR (single image or PDF)
 //   POST /api/ocr/stream       — SSE streaming OCR with progress events
 //   POST /api/ocr/batch/stream — SSE batch OCR (multiple files)
+//   GET  /api/ocr/languages    — supported document language hints
 //   GET  /api/ocr/history      — paginated OCR history
 //   GET  /api/ocr/history/{id} — single history entry (full text)
 //   DELETE /api/ocr/history/{id} — delete history entry
 
 use std::convert::Infallible;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
 
 use axum::Json;
@@ -24,7 +25,9 @@ use serde_json::{Value, json};
 use tokio::sync::{Semaphore, mpsc};
 use tokio_stream::wrappers::ReceiverStream;
 
+use crate::handlers::streaming::{GEMINI_MAX_RETRIES, gemini_backoff};
 use crate::oauth;
+use crate::redact::redact_secrets;
 use crate::state::AppState;
 
 // ── Constants ────────────────────────────────────────────────────────────────
@@ -82,15 +85,43 @@ Return ONLY a valid JSON object with these fields (omit fields not found):\n\
 \n\
 Return ONLY the JSON, no explanation or markdown fences.";
 
-const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+const PAGE_HEADINGS_PROMPT: &str = "\
+For each listed page of this PDF, give its title, first heading, or first line of text.\n\
+Answer with exactly one line per page in the form `Page N: <heading>` and nothing else.\n\
+Pages: ";
+
 const OCR_MODEL: &str = "gemini-3.1-flash-preview";
 const MAX_INPUT_SIZE: usize = 30_000_000; // ~22 MB decoded
 const MAX_BATCH_ITEMS: usize = 10;
+/// Files OCR'd at the same time by `ocr_batch_stream` (`OCR_BATCH_CONCURRENCY`).
+const DEFAULT_BATCH_CONCURRENCY: usize = 3;
 
 // Table name for OCR history (compile-time, matching oauth.rs concat! pattern)
 // Note: concat!() uses inline string literals, but this constant documents the table name.
 #[allow(dead_code)]
 const OCR_HISTORY_TABLE: &str = "gh_ocr_history";
+/// Free-form language hints longer than this are cut before reaching the prompt.
+const MAX_LANGUAGE_HINT_CHARS: usize = 40;
+
+/// Document language hints advertised by `GET /api/ocr/languages` (code, name).
+/// Any other value is still passed to the model as given.
+pub const OCR_LANGUAGES: &[(&str, &str)] = &[
+    ("pl", "Polish"),
+    ("en", "English"),
+    ("de", "German"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("cs", "Czech"),
+    ("sk", "Slovak"),
+    ("uk", "Ukrainian"),
+    ("ru", "Russian"),
+    ("ja", "Japanese"),
+    ("zh", "Chinese"),
+    ("ko", "Korean"),
+];
 
 // ── Request / Response models ────────────────────────────────────────────────
 
@@ -104,7 +135,8 @@ pub struct OcrRequest {
     pub prompt: Option<String>,
     /// For PDFs: optional page range (informational).
     pub page_range: Option<String>,
-    /// Language hint (e.g. "pl", "en", "de") — improves accuracy for diacritics and domain terms.
+    /// Language hint (e.g. "pl", "en", "de", see `/api/ocr/languages`) — improves
+    /// accuracy for diacritics and domain terms. Unset or "auto" = auto-detect.
     pub language: Option<String>,
     /// OCR preset: "invoice", "document", "handwriting", "table", "receipt".
     pub preset: Option<String>,
@@ -146,6 +178,8 @@ pub struct OcrPage {
 pub struct OcrBatchRequest {
     pub items: Vec<OcrBatchItem>,
     pub prompt: Option<String>,
+    /// Default language hint for items that don't set their own.
+    pub language: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -162,6 +196,8 @@ pub struct OcrBatchItem {
 #[derive(Debug, Clone, Serialize)]
 pub struct OcrBatchItemResult {
     pub filename: Option<String>,
+    /// "ok" or "error" — a failed file is reported here, never aborts the batch.
+    pub status: &'static str,
     pub response: Option<OcrResponse>,
     pub error: Option<String>,
 }
@@ -236,16 +272,45 @@ fn detect_preset(filename: Option<&str>, _mime_type: &str) -> Option<&'static st
     None
 }
 
+/// Concurrent Gemini Vision calls per batch — `OCR_BATCH_CONCURRENCY` env,
+/// clamped to 1..=`MAX_BATCH_ITEMS`.
+fn batch_concurrency() -> usize {
+    static CONCURRENCY: OnceLock<usize> = OnceLock::new();
+    *CONCURRENCY.get_or_init(|| {
+        std::env::var("OCR_BATCH_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_BATCH_CONCURRENCY)
+            .clamp(1, MAX_BATCH_ITEMS)
+    })
+}
+
 // ── Prompt builder ──────────────────────────────────────────────────────────
 
+/// Turn a language hint into the name used in the prompt: known codes and names
+/// map to the English name, "auto" or blank means auto-detect (`None`).
+fn resolve_ocr_language(hint: Option<&str>) -> Option<String> {
+    let hint = hint.map(str::trim).filter(|h| !h.is_empty())?;
+    if hint.eq_ignore_ascii_case("auto") {
+        return None;
+    }
+    let known = OCR_LANGUAGES
+        .iter()
+        .find(|(code, name)| hint.eq_ignore_ascii_case(code) || hint.eq_ignore_ascii_case(name));
+    match known {
+        Some((_, name)) => Some(name.to_string()),
+        None => Some(hint.chars().take(MAX_LANGUAGE_HINT_CHARS).collect()),
+    }
+}
+
 /// Build the effective OCR prompt from base prompt + optional language/preset.
 fn build_ocr_prompt(base: &str, language: Option<&str>, preset: Option<&str>) -> String {
     let mut prompt = base.to_string();
 
-    if let Some(lang) = language {
+    if let Some(lang) = resolve_ocr_language(language) {
         prompt.push_str(&format!(
-            "\n\nThe document is in {lang}. Pay special attention to language-specific \
-             characters, diacritics, and terminology."
+            "\n\nExtract the text; the document is in {lang}. Pay special attention to \
+             language-specific characters, diacritics, and terminology."
         ));
     }
 
@@ -287,6 +352,17 @@ fn build_ocr_prompt(base: &str, language: Option<&str>, preset: Option<&str>) ->
     prompt
 }
 
+// ── Language hints ───────────────────────────────────────────────────────────
+
+/// GET /api/ocr/languages — language hints accepted by the `language` field.
+pub async fn ocr_languages() -> Json<Value> {
+    let languages: Vec<Value> = OCR_LANGUAGES
+        .iter()
+        .map(|(code, name)| json!({ "code": code, "name": name }))
+        .collect();
+    Json(json!({ "default": "auto", "languages": languages }))
+}
+
 // ── Synchronous OCR endpoint ─────────────────────────────────────────────────
 
 pub async fn ocr(
@@ -548,17 +624,19 @@ pub async fn ocr_batch_stream(
     tokio::spawn(async move {
         let started = Instant::now();
 
-        // Spawn all OCR tasks in parallel with concurrency cap
-        let semaphore = Arc::new(Semaphore::new(3));
+        // Spawn all OCR tasks in parallel with concurrency cap. Each task reports
+        // its own start/done/error events, so progress streams in completion order.
+        let semaphore = Arc::new(Semaphore::new(batch_concurrency()));
         let mut handles = Vec::with_capacity(body.items.len());
 
         for (idx, item) in body.items.iter().enumerate() {
             let state = state.clone();
+            let tx = tx.clone();
             let data_base64 = item.data_base64.clone();
             let mime_type = item.mime_type.clone();
             let filename = item.filename.clone();
             let preset = item.preset.clone();
-            let language = item.language.clone();
+            let language = item.language.clone().or_else(|| body.language.clone());
             let extract_structured = item.extract_structured;
             let output_format = item.output_format.clone();
             let batch_prompt = body.prompt.clone();
@@ -566,6 +644,13 @@ pub async fn ocr_batch_stream(
 
             handles.push(tokio::spawn(async move {
                 let _permit = sem.acquire().await.expect("semaphore closed unexpectedly");
+                let file_started = Instant::now();
+
+                let start_event = Event::default().event("batch_file_start").data(
+                    json!({"file_index": idx, "filename": &filename, "files_total": files_total})
+                        .to_string(),
+                );
+                let _ = tx.send(Ok(start_event)).await;
 
                 let detected = detect_preset(filename.as_deref(), &mime_type);
                 let effective_preset = preset.as_deref().or(detected);
@@ -583,7 +668,7 @@ pub async fn ocr_batch_stream(
                     ocr_with_gemini(&state, &data_base64, &mime_type, &effective_prompt).await;
 
                 // Post-process OCR result
-                match ocr_result {
+                let result = match ocr_result {
                     Ok((text, confidence)) => {
                         let pages = if format == "html" {
                             split_html_into_pages(&text)
@@ -604,7 +689,7 @@ pub async fn ocr_batch_stream(
                             text,
                             pages,
                             total_pages,
-                            processing_time_ms: 0, // will be set when sending
+                            processing_time_ms: file_started.elapsed().as_millis() as u64,
                             provider: "gemini".to_string(),
                             output_format: format.to_string(),
                             confidence,
@@ -632,66 +717,57 @@ pub async fn ocr_batch_stream(
                             }
                         });
 
-                        (idx, filename, Ok(response))
+                        OcrBatchItemResult {
+                            filename,
+                            status: "ok",
+                            response: Some(response),
+                            error: None,
+                        }
                     }
                     Err(e) => {
                         tracing::error!("Batch OCR file {idx} failed: {e}");
-                        (idx, filename, Err(e))
+                        OcrBatchItemResult {
+                            filename,
+                            status: "error",
+                            response: None,
+                            error: Some(e),
+                        }
                     }
-                }
+                };
+
+                let event = if result.response.is_some() {
+                    Event::default().event("batch_file_done").data(
+                        serde_json::to_string(
+                            &json!({"file_index": idx, "status": "ok", "result": &result}),
+                        )
+                        .unwrap_or_default(),
+                    )
+                } else {
+                    Event::default().event("batch_file_error").data(
+                        json!({
+                            "file_index": idx,
+                            "status": "error",
+                            "filename": &result.filename,
+                            "error": &result.error,
+                        })
+                        .to_string(),
+                    )
+                };
+                let _ = tx.send(Ok(event)).await;
+                result
             }));
         }
 
-        // Await all handles in order and send SSE events sequentially
-        let mut results: Vec<OcrBatchItemResult> = Vec::new();
-
+        // A failed file never aborts the batch — collect every outcome in input order
+        let mut results: Vec<OcrBatchItemResult> = Vec::with_capacity(handles.len());
         for handle in handles {
             match handle.await {
-                Ok((idx, filename, ocr_result)) => {
-                    // batch/file_start
-                    let start_event = Event::default()
-                        .event("batch_file_start")
-                        .data(json!({"file_index": idx, "filename": &filename, "files_total": files_total}).to_string());
-                    let _ = tx.send(Ok(start_event)).await;
-
-                    match ocr_result {
-                        Ok(mut response) => {
-                            // Set actual elapsed time
-                            response.processing_time_ms = started.elapsed().as_millis() as u64;
-
-                            let result = OcrBatchItemResult {
-                                filename: filename.clone(),
-                                response: Some(response),
-                                error: None,
-                            };
-                            let done_event = Event::default().event("batch_file_done").data(
-                                serde_json::to_string(
-                                    &json!({"file_index": idx, "result": &result}),
-                                )
-                                .unwrap_or_default(),
-                            );
-                            let _ = tx.send(Ok(done_event)).await;
-                            results.push(result);
-                        }
-                        Err(e) => {
-                            let result = OcrBatchItemResult {
-                                filename: filename.clone(),
-                                response: None,
-                                error: Some(e.clone()),
-                            };
-                            let err_event = Event::default().event("batch_file_error").data(
-                                json!({"file_index": idx, "filename": &filename, "error": e})
-                                    .to_string(),
-                            );
-                            let _ = tx.send(Ok(err_event)).await;
-                            results.push(result);
-                        }
-                    }
-                }
+                Ok(result) => results.push(result),
                 Err(e) => {
                     tracing::error!("Batch OCR task panicked: {e}");
                     results.push(OcrBatchItemResult {
                         filename: None,
+                        status: "error",
                         response: None,
                         error: Some(format!("Task panicked: {e}")),
                     });
@@ -867,7 +943,7 @@ async fn ocr_with_gemini(
         .await
         .ok_or_else(|| "No Google API credential configured".to_string())?;
 
-    let url = format!("{GEMINI_API_BASE}/{OCR_MODEL}:generateContent");
+    let url = state.providers.gemini_model(OCR_MODEL, "generateContent");
 
     let request_body = json!({
         "contents": [{
@@ -889,14 +965,33 @@ async fn ocr_with_gemini(
         }
     });
 
-    let builder = state.client.post(&url).json(&request_body);
-    let builder = oauth::apply_google_auth(builder, &credential, is_oauth);
-
-    let response = builder
-        .timeout(std::time::Duration::from_secs(120))
-        .send()
-        .await
-        .map_err(|e| format!("Gemini API request failed: {e}"))?;
+    // Rate limits (429) and overload (503) back off like `gemini_request_with_retry`
+    let mut attempt = 0;
+    let response = loop {
+        let builder = state.client.post(&url).json(&request_body);
+        let builder = oauth::apply_google_auth(builder, &credential, is_oauth);
+        let response = builder
+            .timeout(std::time::Duration::from_secs(120))
+            .send()
+            .await
+            .map_err(|e| {
+                format!(
+                    "Gemini API request failed: {}",
+                    redact_secrets(&e.to_string())
+                )
+            })?;
+
+        let status = response.status().as_u16();
+        if !matches!(status, 429 | 503) || attempt >= GEMINI_MAX_RETRIES {
+            break response;
+        }
+        attempt += 1;
+        let delay = gemini_backoff(attempt);
+        tracing::warn!(
+            "ocr: Gemini returned HTTP {status}, retry {attempt}/{GEMINI_MAX_RETRIES} in {delay:?}"
+        );
+        tokio::time::sleep(delay).await;
+    };
 
     let status = response.status();
     let body: Value = response
@@ -942,7 +1037,7 @@ async fn extract_structured_data(state: &AppState, ocr_text: &str) -> Result<Val
         .await
         .ok_or_else(|| "No Google API credential configured".to_string())?;
 
-    let url = format!("{GEMINI_API_BASE}/{OCR_MODEL}:generateContent");
+    let url = state.providers.gemini_model(OCR_MODEL, "generateContent");
 
     let prompt = format!("{STRUCTURED_EXTRACTION_PROMPT}\n\nOCR TEXT:\n{ocr_text}");
 
@@ -959,10 +1054,12 @@ async fn extract_structured_data(state: &AppState, ocr_text: &str) -> Result<Val
     let builder = state.client.post(&url).json(&request_body);
     let builder = oauth::apply_google_auth(builder, &credential, is_oauth);
 
-    let response = builder
-        .send()
-        .await
-        .map_err(|e| format!("Structured extraction request failed: {e}"))?;
+    let response = builder.send().await.map_err(|e| {
+        format!(
+            "Structured extraction request failed: {}",
+            redact_secrets(&e.to_string())
+        )
+    })?;
 
     let status = response.status();
     let body: Value = response
@@ -1037,26 +1134,64 @@ pub async fn ocr_pdf_text(
     state: &AppState,
     data_b64: &str,
     _page_range: Option<&str>,
+    language: Option<&str>,
 ) -> Result<String, String> {
-    ocr_with_gemini(state, data_b64, "application/pdf", OCR_PROMPT)
+    let prompt = build_ocr_prompt(OCR_PROMPT, language, None);
+    ocr_with_gemini(state, data_b64, "application/pdf", &prompt)
         .await
         .map(|(text, _)| text)
 }
 
+/// Ask Gemini Vision for a one-line heading of each listed PDF page (1-indexed).
+/// Used by `read_pdf` outline mode for pages with no embedded text.
+pub async fn ocr_pdf_page_headings(
+    state: &AppState,
+    data_b64: &str,
+    pages: &[usize],
+) -> Result<Vec<(usize, String)>, String> {
+    let page_list = pages
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let prompt = format!("{PAGE_HEADINGS_PROMPT}{page_list}");
+    ocr_with_gemini(state, data_b64, "application/pdf", &prompt)
+        .await
+        .map(|(text, _)| parse_page_headings(&text))
+}
+
 /// OCR a single image via Gemini Vision API. Used by `analyze_image` tool
 /// when `extract_text` parameter is true.
 pub async fn ocr_image_text(
     state: &AppState,
     data_b64: &str,
     mime_type: &str,
+    language: Option<&str>,
 ) -> Result<String, String> {
-    ocr_with_gemini(state, data_b64, mime_type, OCR_PROMPT)
+    let prompt = build_ocr_prompt(OCR_PROMPT, language, None);
+    ocr_with_gemini(state, data_b64, mime_type, &prompt)
         .await
         .map(|(text, _)| text)
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
+/// Parse `Page N: heading` lines from the page-headings prompt.
+fn parse_page_headings(text: &str) -> Vec<(usize, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let rest = line.trim().trim_start_matches(['-', '*', ' ']);
+            let rest = rest.strip_prefix("Page ")?;
+            let (num, heading) = rest.split_once(':')?;
+            let heading = heading.trim();
+            if heading.is_empty() {
+                return None;
+            }
+            Some((num.trim().parse().ok()?, heading.to_string()))
+        })
+        .collect()
+}
+
 /// Split HTML OCR output into pages by `<hr data-page="N">` markers.
 fn split_html_into_pages(html: &str) -> Vec<OcrPage> {
     let re = regex::Regex::new(r#"<hr\s+data-page="(\d+)"\s*/?>"#)
@@ -1163,3 +1298,44 @@ async fn send_progress(
         tracing::warn!("OCR SSE {status}: client disconnected");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_hints_resolve_to_names_or_auto_detect() {
+        assert_eq!(resolve_ocr_language(Some("PL")).as_deref(), Some("Polish"));
+        assert_eq!(
+            resolve_ocr_language(Some(" german ")).as_deref(),
+            Some("German")
+        );
+        assert_eq!(
+            resolve_ocr_language(Some("Kashubian")).as_deref(),
+            Some("Kashubian")
+        );
+        assert_eq!(resolve_ocr_language(Some("auto")), None);
+        assert_eq!(resolve_ocr_language(Some("  ")), None);
+        assert_eq!(resolve_ocr_language(None), None);
+    }
+
+    #[test]
+    fn page_headings_parse_numbered_lines_only() {
+        let parsed = parse_page_headings(
+            "Page 2: Invoice 17/2026\n- Page 5:  Terms \nnoise\nPage x: bad\nPage 6:",
+        );
+        assert_eq!(
+            parsed,
+            vec![(2, "Invoice 17/2026".to_string()), (5, "Terms".to_string())]
+        );
+    }
+
+    #[test]
+    fn prompt_mentions_language_only_when_set() {
+        let prompt = build_ocr_prompt(OCR_PROMPT, Some("pl"), None);
+        assert!(prompt.ends_with(
+            "the document is in Polish. Pay special attention to language-specific characters, diacritics, and terminology."
+        ));
+        assert_eq!(build_ocr_prompt(OCR_PROMPT, None, None), OCR_PROMPT);
+    }
+}
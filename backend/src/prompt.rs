@@ -181,7 +181,7 @@ Operate using a **Research -> Strategy -> Execution** lifecycle.
             "\n\n## Working Directory\n\
              **Current working directory**: `{wd}`\n\
              - All relative file paths in tool calls resolve against this directory.\n\
-             - For `list_directory`, `read_file`, `search_files`, `find_file`, `get_code_structure`, `read_file_section`, `diff_files`: you can use relative paths (e.g., `src/main.rs` instead of `{wd}\\src\\main.rs`).\n\
+             - For `list_directory`, `read_file`, `search_files`, `find_file`, `file_stat`, `get_code_structure`, `read_file_section`, `diff_files`: you can use relative paths (e.g., `src/main.rs` instead of `{wd}\\src\\main.rs`).\n\
              - For `execute_command`: if no `working_directory` parameter is set, it defaults to `{wd}`.\n\
              - Absolute paths still work as before.",
             wd = working_directory
@@ -203,6 +203,41 @@ Operate using a **Research -> Strategy -> Execution** lifecycle.
 // Gemini 3 Thinking Config Helper
 // ---------------------------------------------------------------------------
 
+/// Harm categories covered by the `safety_level` setting.
+const SAFETY_CATEGORIES: [&str; 4] = [
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// Build the `safetySettings` array for a `safety_level` setting.
+/// - "default": omitted — Gemini's own per-model thresholds apply
+/// - "relaxed": `BLOCK_ONLY_HIGH` for harassment, hate speech, sexually explicit
+///   and dangerous content
+/// - "strict": `BLOCK_LOW_AND_ABOVE` for the same four categories
+pub fn build_safety_settings(safety_level: &str) -> Option<Value> {
+    let threshold = match safety_level {
+        "relaxed" => "BLOCK_ONLY_HIGH",
+        "strict" => "BLOCK_LOW_AND_ABOVE",
+        _ => return None,
+    };
+    Some(Value::Array(
+        SAFETY_CATEGORIES
+            .iter()
+            .map(|c| json!({ "category": c, "threshold": threshold }))
+            .collect(),
+    ))
+}
+
+/// Add `safetySettings` to a generateContent request body (a top-level field,
+/// next to `generationConfig`) unless `safety_level` is "default".
+pub fn apply_safety_settings(body: &mut Value, safety_level: &str) {
+    if let Some(settings) = build_safety_settings(safety_level) {
+        body["safetySettings"] = settings;
+    }
+}
+
 /// Build the thinkingConfig JSON for Gemini generationConfig.
 /// - Gemini 3+ models: use `thinkingLevel` (string enum: minimal/low/medium/high)
 /// - Gemini 2.5 models: use `thinkingBudget` (integer) mapped from thinking_level
@@ -6,13 +6,20 @@ use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use serde_json::{Value, json};
 
+use crate::error::ApiError;
 use crate::models::{
-    CreateSessionRequest, RatingRequest, RatingResponse, Session, SessionRow, SessionSummary,
-    SessionSummaryRow, UnlockAgentResponse, UpdateSessionRequest, UpdateWorkingDirectoryRequest,
+    BulkDeleteSessionsRequest, BulkDeleteSessionsResponse, CreateSessionRequest, RatingRequest,
+    RatingResponse, Session, SessionAbstract, SessionModelResponse, SessionRow, SessionSummary,
+    SessionSummaryRow, UnlockAgentResponse, UpdateSessionModelRequest, UpdateSessionRequest,
+    UpdateWorkingDirectoryRequest,
 };
 use crate::state::AppState;
 
-use super::{MAX_TITLE_LENGTH, PaginationParams};
+use super::{DeleteSessionParams, MAX_TITLE_LENGTH, PaginationParams};
+
+const MAX_MODEL_ID_LENGTH: usize = 128;
+/// Max sessions removed by one `POST /api/sessions/bulk-delete` call.
+const MAX_BULK_DELETE_SESSIONS: i64 = 500;
 
 // ============================================================================
 // Session CRUD handlers
@@ -20,11 +27,14 @@ use super::{MAX_TITLE_LENGTH, PaginationParams};
 
 /// GET /api/sessions?limit=100&offset=0
 /// Also supports cursor-based pagination: ?after=<session_id>&limit=20
+/// Archived sessions are hidden unless `?include_archived=true`.
 #[utoipa::path(get, path = "/api/sessions", tag = "sessions",
     params(
         ("limit" = Option<i64>, Query, description = "Max sessions to return (default 100, max 500)"),
         ("offset" = Option<i64>, Query, description = "Number of sessions to skip (default 0)"),
         ("after" = Option<String>, Query, description = "Cursor: return sessions after this session ID (by updated_at)"),
+        ("include_archived" = Option<bool>, Query, description = "Also return archived (soft-deleted) sessions"),
+        ("tag" = Option<String>, Query, description = "Only return sessions with this tag"),
     ),
     responses((status = 200, description = "List of session summaries", body = Vec<SessionSummary>))
 )]
@@ -33,21 +43,32 @@ pub async fn list_sessions(
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<Value>, StatusCode> {
     let limit = params.limit.unwrap_or(100).clamp(1, 500);
+    let tag = params
+        .tag
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
 
     // Cursor-based pagination: when `after` is provided, use it instead of offset
     if let Some(ref after_id) = params.after {
         let cursor_id = uuid::Uuid::parse_str(after_id).map_err(|_| StatusCode::BAD_REQUEST)?;
 
         let rows = sqlx::query_as::<_, SessionSummaryRow>(
-            "SELECT s.id, s.title, s.created_at, s.working_directory, s.agent_id, \
-             (SELECT COUNT(*) FROM gh_chat_messages WHERE session_id = s.id) as message_count \
+            "SELECT s.id, s.title, s.created_at, s.working_directory, s.agent_id, s.archived_at, \
+             (SELECT COUNT(*) FROM gh_chat_messages WHERE session_id = s.id) as message_count, \
+             ARRAY(SELECT tag FROM gh_session_tags WHERE session_id = s.id ORDER BY tag) as tags \
              FROM gh_sessions s \
              WHERE s.updated_at < (SELECT updated_at FROM gh_sessions WHERE id = $1) \
+             AND ($3 OR s.archived_at IS NULL) \
+             AND ($4::text IS NULL OR EXISTS \
+                 (SELECT 1 FROM gh_session_tags t WHERE t.session_id = s.id AND t.tag = $4)) \
              ORDER BY s.updated_at DESC \
              LIMIT $2",
         )
         .bind(cursor_id)
         .bind(limit)
+        .bind(params.include_archived)
+        .bind(tag)
         .fetch_all(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -61,6 +82,8 @@ pub async fn list_sessions(
                 message_count: r.message_count as usize,
                 working_directory: r.working_directory.clone(),
                 agent_id: r.agent_id.clone(),
+                archived_at: r.archived_at.map(|t| t.to_rfc3339()),
+                tags: r.tags.clone(),
             })
             .collect();
 
@@ -81,13 +104,19 @@ pub async fn list_sessions(
     let offset = params.offset.unwrap_or(0).max(0);
 
     let rows = sqlx::query_as::<_, SessionSummaryRow>(
-        "SELECT s.id, s.title, s.created_at, s.working_directory, s.agent_id, \
-         (SELECT COUNT(*) FROM gh_chat_messages WHERE session_id = s.id) as message_count \
-         FROM gh_sessions s ORDER BY s.updated_at DESC \
+        "SELECT s.id, s.title, s.created_at, s.working_directory, s.agent_id, s.archived_at, \
+         (SELECT COUNT(*) FROM gh_chat_messages WHERE session_id = s.id) as message_count, \
+         ARRAY(SELECT tag FROM gh_session_tags WHERE session_id = s.id ORDER BY tag) as tags \
+         FROM gh_sessions s WHERE ($3 OR s.archived_at IS NULL) \
+         AND ($4::text IS NULL OR EXISTS \
+             (SELECT 1 FROM gh_session_tags t WHERE t.session_id = s.id AND t.tag = $4)) \
+         ORDER BY s.updated_at DESC \
          LIMIT $1 OFFSET $2",
     )
     .bind(limit)
     .bind(offset)
+    .bind(params.include_archived)
+    .bind(tag)
     .fetch_all(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -101,6 +130,8 @@ pub async fn list_sessions(
             message_count: r.message_count as usize,
             working_directory: r.working_directory,
             agent_id: r.agent_id,
+            archived_at: r.archived_at.map(|t| t.to_rfc3339()),
+            tags: r.tags,
         })
         .collect();
 
@@ -129,7 +160,7 @@ pub async fn create_session(
 
     let row = sqlx::query_as::<_, SessionRow>(
         "INSERT INTO gh_sessions (title) VALUES ($1) \
-         RETURNING id, title, created_at, updated_at, working_directory",
+         RETURNING id, title, created_at, updated_at, working_directory, model_override",
     )
     .bind(&req.title)
     .fetch_one(&state.db)
@@ -142,6 +173,8 @@ pub async fn create_session(
         created_at: row.created_at.to_rfc3339(),
         messages: Vec::new(),
         working_directory: row.working_directory,
+        model_override: row.model_override,
+        archived_at: None,
     };
 
     Ok((
@@ -172,7 +205,8 @@ pub async fn get_session(
     let msg_offset = params.offset.unwrap_or(0).max(0);
 
     let session_row = sqlx::query_as::<_, SessionRow>(
-        "SELECT id, title, created_at, updated_at, working_directory FROM gh_sessions WHERE id = $1",
+        "SELECT id, title, created_at, updated_at, working_directory, model_override, archived_at \
+         FROM gh_sessions WHERE id = $1",
     )
     .bind(session_id)
     .fetch_optional(&state.db)
@@ -191,7 +225,7 @@ pub async fn get_session(
     // Fetch the most recent N messages (subquery DESC, then re-sort ASC)
     let message_rows = sqlx::query_as::<_, crate::models::ChatMessageRow>(
         "SELECT * FROM (\
-            SELECT id, role, content, model, agent, created_at \
+            SELECT id, role, content, model, agent, created_at, pinned \
             FROM gh_chat_messages WHERE session_id = $1 \
             ORDER BY created_at DESC LIMIT $2 OFFSET $3\
         ) sub ORDER BY created_at ASC",
@@ -214,6 +248,8 @@ pub async fn get_session(
         created_at: session_row.created_at.to_rfc3339(),
         messages,
         working_directory: session_row.working_directory,
+        model_override: session_row.model_override,
+        archived_at: session_row.archived_at.map(|t| t.to_rfc3339()),
     };
 
     let mut result =
@@ -255,7 +291,7 @@ pub async fn update_session(
 
     let row = sqlx::query_as::<_, SessionRow>(
         "UPDATE gh_sessions SET title = $1, updated_at = NOW() WHERE id = $2 \
-         RETURNING id, title, created_at, updated_at, working_directory",
+         RETURNING id, title, created_at, updated_at, working_directory, archived_at",
     )
     .bind(&req.title)
     .bind(session_id)
@@ -264,6 +300,13 @@ pub async fn update_session(
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .ok_or(StatusCode::NOT_FOUND)?;
 
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT tag FROM gh_session_tags WHERE session_id = $1 ORDER BY tag")
+            .bind(session_id)
+            .fetch_all(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     let summary = SessionSummary {
         id: row.id.to_string(),
         title: row.title,
@@ -271,6 +314,8 @@ pub async fn update_session(
         message_count: 0,
         working_directory: row.working_directory,
         agent_id: None,
+        archived_at: row.archived_at.map(|t| t.to_rfc3339()),
+        tags,
     };
 
     Ok(Json(
@@ -279,10 +324,17 @@ pub async fn update_session(
 }
 
 /// DELETE /api/sessions/:id
+///
+/// Archives the session (soft delete) — it disappears from the session list but
+/// can be brought back with `POST /api/sessions/:id/restore`. `?hard=true`
+/// permanently removes the session and its messages.
 #[utoipa::path(delete, path = "/api/sessions/{id}", tag = "sessions",
-    params(("id" = String, Path, description = "Session UUID")),
+    params(
+        ("id" = String, Path, description = "Session UUID"),
+        ("hard" = Option<bool>, Query, description = "Permanently delete instead of archiving"),
+    ),
     responses(
-        (status = 200, description = "Session deleted", body = Value),
+        (status = 200, description = "Session archived (or already archived) or deleted", body = Value),
         (status = 404, description = "Session not found")
     )
 )]
@@ -290,9 +342,43 @@ pub async fn delete_session(
     State(state): State<AppState>,
     axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
     Path(id): Path<String>,
+    Query(params): Query<DeleteSessionParams>,
 ) -> Result<Json<Value>, StatusCode> {
     let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
+    if !params.hard {
+        let result = sqlx::query(
+            "UPDATE gh_sessions SET archived_at = NOW() WHERE id = $1 AND archived_at IS NULL",
+        )
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if result.rows_affected() > 0 {
+            crate::audit::log_audit(
+                &state.db,
+                "archive_session",
+                serde_json::json!({ "session_id": id }),
+                Some(&addr.ip().to_string()),
+            )
+            .await;
+        } else {
+            // Idempotent for an already archived session, but not a missing one
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM gh_sessions WHERE id = $1)")
+                    .bind(session_id)
+                    .fetch_one(&state.db)
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            if !exists {
+                return Err(StatusCode::NOT_FOUND);
+            }
+        }
+
+        return Ok(Json(json!({ "status": "archived", "id": id })));
+    }
+
     let result = sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
         .bind(session_id)
         .execute(&state.db)
@@ -313,6 +399,144 @@ pub async fn delete_session(
     Ok(Json(json!({ "status": "deleted", "id": id })))
 }
 
+/// POST /api/sessions/:id/restore — un-archive a soft-deleted session
+#[utoipa::path(post, path = "/api/sessions/{id}/restore", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "Session restored", body = Value),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn restore_session(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let result = sqlx::query("UPDATE gh_sessions SET archived_at = NULL WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    crate::audit::log_audit(
+        &state.db,
+        "restore_session",
+        serde_json::json!({ "session_id": id }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
+
+    Ok(Json(json!({ "status": "restored", "id": id })))
+}
+
+/// POST /api/sessions/bulk-delete
+///
+/// Delete sessions by ID list and/or age in one transaction. At most
+/// `MAX_BULK_DELETE_SESSIONS` (oldest first) go per call; `has_more` reports the rest.
+#[utoipa::path(post, path = "/api/sessions/bulk-delete", tag = "sessions",
+    request_body = BulkDeleteSessionsRequest,
+    responses(
+        (status = 200, description = "Sessions deleted", body = BulkDeleteSessionsResponse),
+        (status = 400, description = "No filter, invalid ID, or too many IDs")
+    )
+)]
+pub async fn bulk_delete_sessions(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    Json(req): Json<BulkDeleteSessionsRequest>,
+) -> Result<Json<BulkDeleteSessionsResponse>, ApiError> {
+    if req.ids.is_empty() && req.older_than_days.is_none() {
+        return Err(ApiError::BadRequest(
+            "Provide 'ids' and/or 'older_than_days' — refusing to delete every session".into(),
+        ));
+    }
+    if req.ids.len() as i64 > MAX_BULK_DELETE_SESSIONS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {} session IDs per request (got {})",
+            MAX_BULK_DELETE_SESSIONS,
+            req.ids.len()
+        )));
+    }
+    if req.older_than_days.is_some_and(|days| days < 1) {
+        return Err(ApiError::BadRequest(
+            "'older_than_days' must be at least 1".into(),
+        ));
+    }
+    let ids = if req.ids.is_empty() {
+        None
+    } else {
+        let parsed = req
+            .ids
+            .iter()
+            .map(|id| {
+                id.parse::<uuid::Uuid>()
+                    .map_err(|_| ApiError::BadRequest(format!("Invalid session ID '{}'", id)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Some(parsed)
+    };
+
+    let db_err = |e: sqlx::Error| ApiError::Internal(format!("bulk delete failed: {}", e));
+    let mut tx = state.db.begin().await.map_err(db_err)?;
+
+    // One extra row tells us whether anything is left after this batch.
+    let mut matched: Vec<uuid::Uuid> = sqlx::query_scalar(
+        "SELECT id FROM gh_sessions \
+         WHERE ($1::uuid[] IS NULL OR id = ANY($1)) \
+           AND ($2::int IS NULL OR updated_at < NOW() - make_interval(days => $2)) \
+         ORDER BY updated_at ASC LIMIT $3 FOR UPDATE",
+    )
+    .bind(&ids)
+    .bind(req.older_than_days)
+    .bind(MAX_BULK_DELETE_SESSIONS + 1)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    let has_more = matched.len() as i64 > MAX_BULK_DELETE_SESSIONS;
+    matched.truncate(MAX_BULK_DELETE_SESSIONS as usize);
+
+    let messages_deleted = sqlx::query("DELETE FROM gh_chat_messages WHERE session_id = ANY($1)")
+        .bind(&matched)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+    let deleted = sqlx::query("DELETE FROM gh_sessions WHERE id = ANY($1)")
+        .bind(&matched)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+    tx.commit().await.map_err(db_err)?;
+
+    if deleted > 0 {
+        crate::audit::log_audit(
+            &state.db,
+            "bulk_delete_sessions",
+            json!({
+                "session_ids": matched.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+                "older_than_days": req.older_than_days,
+                "requested_ids": req.ids.len(),
+                "deleted": deleted,
+                "messages_deleted": messages_deleted,
+            }),
+            Some(&addr.ip().to_string()),
+        )
+        .await;
+    }
+
+    Ok(Json(BulkDeleteSessionsResponse {
+        deleted,
+        messages_deleted,
+        has_more,
+    }))
+}
+
 /// PATCH /api/sessions/:id/working-directory
 ///
 /// Update the per-session working directory. Empty string = inherit from global settings.
@@ -321,7 +545,7 @@ pub async fn delete_session(
     request_body = UpdateWorkingDirectoryRequest,
     responses(
         (status = 200, description = "Working directory updated", body = Value),
-        (status = 400, description = "Invalid path or session ID"),
+        (status = 400, description = "Path is a file, does not exist (without allow_nonexistent), or invalid session ID"),
         (status = 404, description = "Session not found")
     )
 )]
@@ -329,13 +553,12 @@ pub async fn update_session_working_directory(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(req): Json<UpdateWorkingDirectoryRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+) -> Result<Json<Value>, ApiError> {
+    let session_id: uuid::Uuid = id
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid session ID '{}'", id)))?;
     let wd = req.working_directory.trim().to_string();
-
-    if !wd.is_empty() && !std::path::Path::new(&wd).is_dir() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    super::check_working_directory(&wd, req.allow_nonexistent).await?;
 
     let result = sqlx::query(
         "UPDATE gh_sessions SET working_directory = $1, updated_at = NOW() WHERE id = $2",
@@ -344,81 +567,143 @@ pub async fn update_session_working_directory(
     .bind(session_id)
     .execute(&state.db)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
 
     if result.rows_affected() == 0 {
-        return Err(StatusCode::NOT_FOUND);
+        return Err(ApiError::NotFound("Session not found".into()));
     }
 
     Ok(Json(json!({ "working_directory": wd })))
 }
 
-// ============================================================================
-// AI title generation — Jaskier Shared Pattern
-// ============================================================================
-
-/// POST /api/sessions/:id/generate-title
+/// PATCH /api/sessions/:id/model
 ///
-/// Reads the first user message from the session and asks Gemini Flash
-/// to produce a concise 3-7 word title. Updates the DB and returns the title.
-#[utoipa::path(post, path = "/api/sessions/{id}/generate-title", tag = "sessions",
+/// Pin a model for every request in this session. It outranks the global
+/// default, per-agent overrides and auto-tier routing, but not an explicit
+/// per-request `model` or the global `force_model`. `null`/empty clears the pin.
+#[utoipa::path(patch, path = "/api/sessions/{id}/model", tag = "sessions",
     params(("id" = String, Path, description = "Session UUID")),
+    request_body = UpdateSessionModelRequest,
     responses(
-        (status = 200, description = "AI-generated title", body = Value),
-        (status = 404, description = "Session not found or no user messages"),
-        (status = 503, description = "No API key configured")
+        (status = 200, description = "Session model updated", body = SessionModelResponse),
+        (status = 400, description = "Invalid model or session ID"),
+        (status = 404, description = "Session not found")
     )
 )]
-pub async fn generate_session_title(
+pub async fn update_session_model(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<Value>, StatusCode> {
+    Json(req): Json<UpdateSessionModelRequest>,
+) -> Result<Json<SessionModelResponse>, StatusCode> {
     let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let model = req
+        .model
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+
+    if let Some(ref m) = model {
+        if m.len() > MAX_MODEL_ID_LENGTH
+            || !m
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '/'))
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        // Reject unknown IDs once the registry has a model list to check against
+        let cache = state.model_cache.read().await;
+        let known = cache.models.values().flatten().any(|info| &info.id == m);
+        if !cache.models.is_empty() && !known {
+            tracing::warn!("update_session_model: unknown model '{}'", m);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
 
-    // Fetch first user message
-    let first_msg = sqlx::query_scalar::<_, String>(
-        "SELECT content FROM gh_chat_messages \
-         WHERE session_id = $1 AND role = 'user' \
-         ORDER BY created_at ASC LIMIT 1",
+    set_session_model(&state, session_id, model).await.map(Json)
+}
+
+/// DELETE /api/sessions/:id/model
+///
+/// Clear the session's model pin so it follows the global settings again.
+#[utoipa::path(delete, path = "/api/sessions/{id}/model", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "Session model pin cleared", body = SessionModelResponse),
+        (status = 404, description = "Session not found")
     )
-    .bind(session_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::NOT_FOUND)?;
+)]
+pub async fn reset_session_model(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SessionModelResponse>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    set_session_model(&state, session_id, None).await.map(Json)
+}
+
+async fn set_session_model(
+    state: &AppState,
+    session_id: uuid::Uuid,
+    model: Option<String>,
+) -> Result<SessionModelResponse, StatusCode> {
+    let result =
+        sqlx::query("UPDATE gh_sessions SET model_override = $1, updated_at = NOW() WHERE id = $2")
+            .bind(&model)
+            .bind(session_id)
+            .execute(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let (effective_model, source) =
+        crate::context::resolve_session_model(state, model.as_deref()).await;
+    Ok(SessionModelResponse {
+        session_id: session_id.to_string(),
+        model_override: model,
+        effective_model,
+        source: source.to_string(),
+    })
+}
+
+// ============================================================================
+// AI title generation — Jaskier Shared Pattern
+// ============================================================================
+
+const FLASH_MODEL: &str = "gemini-2.0-flash";
+
+/// Longest prefix of `s` that fits in `max_bytes` without splitting a char.
+fn truncate_at_char_boundary(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let end = s
+        .char_indices()
+        .take_while(|(i, _)| *i < max_bytes)
+        .last()
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(max_bytes.min(s.len()));
+    &s[..end]
+}
 
+/// One-shot Gemini Flash call shared by title and summary generation.
+/// Returns the trimmed response text; `caller` prefixes log lines.
+async fn flash_generate(
+    state: &AppState,
+    prompt: &str,
+    max_output_tokens: u32,
+    caller: &str,
+) -> Result<String, StatusCode> {
     // Get Google credential (API key or OAuth token)
-    let (api_key, is_oauth) = match crate::oauth::get_google_credential(&state).await {
+    let (api_key, is_oauth) = match crate::oauth::get_google_credential(state).await {
         Some(cred) => cred,
         None => {
-            tracing::warn!("generate_session_title: no Google credential");
+            tracing::warn!("{}: no Google credential", caller);
             return Err(StatusCode::SERVICE_UNAVAILABLE);
         }
     };
 
-    // Truncate message to ~500 chars for the prompt (safe UTF-8 boundary)
-    let snippet: &str = if first_msg.len() > 500 {
-        let end = first_msg
-            .char_indices()
-            .take_while(|(i, _)| *i < 500)
-            .last()
-            .map(|(i, c)| i + c.len_utf8())
-            .unwrap_or(500.min(first_msg.len()));
-        &first_msg[..end]
-    } else {
-        &first_msg
-    };
-    let prompt = format!(
-        "Generate a concise 3-7 word title for a chat that starts with this message. \
-         Return ONLY the title text, no quotes, no explanation.\n\nMessage: {}",
-        snippet
-    );
-
-    let model = "gemini-2.0-flash";
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
-        model
-    );
+    let url = state.providers.gemini_model(FLASH_MODEL, "generateContent");
     let parsed_url = match reqwest::Url::parse(&url) {
         Ok(u) if u.scheme() == "https" => u,
         _ => return Err(StatusCode::INTERNAL_SERVER_ERROR),
@@ -426,7 +711,7 @@ pub async fn generate_session_title(
 
     let body = json!({
         "contents": [{ "parts": [{ "text": prompt }] }],
-        "generationConfig": { "temperature": 1.0, "maxOutputTokens": 256 }
+        "generationConfig": { "temperature": 1.0, "maxOutputTokens": max_output_tokens }
     });
 
     let res = crate::oauth::apply_google_auth(state.client.post(parsed_url), &api_key, is_oauth)
@@ -435,17 +720,21 @@ pub async fn generate_session_title(
         .send()
         .await
         .map_err(|e| {
-            tracing::error!("generate_session_title: API call failed: {}", e);
+            tracing::error!(
+                "{}: API call failed: {}",
+                caller,
+                crate::redact::redact_secrets(&e.to_string())
+            );
             StatusCode::BAD_GATEWAY
         })?;
 
     if !res.status().is_success() {
-        tracing::error!("generate_session_title: API returned {}", res.status());
+        tracing::error!("{}: API returned {}", caller, res.status());
         return Err(StatusCode::BAD_GATEWAY);
     }
 
     let json_resp: Value = res.json().await.map_err(|_| StatusCode::BAD_GATEWAY)?;
-    let raw_title = json_resp
+    let text = json_resp
         .get("candidates")
         .and_then(|c| c.get(0))
         .and_then(|c0| c0.get("content"))
@@ -453,16 +742,57 @@ pub async fn generate_session_title(
         .and_then(|p| p.get(0))
         .and_then(|p0| p0.get("text"))
         .and_then(|t| t.as_str())
-        .unwrap_or("");
-    let raw_title = raw_title.trim().trim_matches('"').trim();
+        .unwrap_or("")
+        .trim();
 
-    if raw_title.is_empty() {
+    if text.is_empty() {
         tracing::warn!(
-            "generate_session_title: Gemini response missing text — {}",
+            "{}: Gemini response missing text — {}",
+            caller,
             crate::handlers::gemini_diagnose(&json_resp)
         );
         return Err(StatusCode::BAD_GATEWAY);
     }
+    Ok(text.to_string())
+}
+
+/// POST /api/sessions/:id/generate-title
+///
+/// Reads the first user message from the session and asks Gemini Flash
+/// to produce a concise 3-7 word title. Updates the DB and returns the title.
+#[utoipa::path(post, path = "/api/sessions/{id}/generate-title", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "AI-generated title", body = Value),
+        (status = 404, description = "Session not found or no user messages"),
+        (status = 503, description = "No API key configured")
+    )
+)]
+pub async fn generate_session_title(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Fetch first user message
+    let first_msg = sqlx::query_scalar::<_, String>(
+        "SELECT content FROM gh_chat_messages \
+         WHERE session_id = $1 AND role = 'user' \
+         ORDER BY created_at ASC LIMIT 1",
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let prompt = format!(
+        "Generate a concise 3-7 word title for a chat that starts with this message. \
+         Return ONLY the title text, no quotes, no explanation.\n\nMessage: {}",
+        truncate_at_char_boundary(&first_msg, 500)
+    );
+    let raw_title = flash_generate(&state, &prompt, 256, "generate_session_title").await?;
+    let raw_title = raw_title.trim().trim_matches('"').trim();
 
     // Sanitize: cap at MAX_TITLE_LENGTH
     let title: String = raw_title.chars().take(MAX_TITLE_LENGTH).collect();
@@ -483,6 +813,102 @@ pub async fn generate_session_title(
     Ok(Json(json!({ "title": title })))
 }
 
+/// Most recent messages fed to the summarizer.
+const SUMMARY_MAX_MESSAGES: i64 = 40;
+/// Per-message cap (bytes) in the summarizer transcript.
+const SUMMARY_MESSAGE_CHARS: usize = 600;
+/// Stored summaries are capped at this many characters.
+const MAX_SUMMARY_LENGTH: usize = 1200;
+
+/// Plain-text transcript of `(role, content)` pairs for the summary prompt.
+fn summary_transcript(messages: &[(String, String)]) -> String {
+    messages
+        .iter()
+        .map(|(role, content)| {
+            format!(
+                "{}: {}",
+                role,
+                truncate_at_char_boundary(content.trim(), SUMMARY_MESSAGE_CHARS)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// POST /api/sessions/:id/summarize
+///
+/// Asks Gemini Flash for a one-paragraph abstract of the session's most
+/// recent messages, stores it in `gh_session_summaries` and returns it.
+#[utoipa::path(post, path = "/api/sessions/{id}/summarize", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "AI-generated session summary", body = SessionAbstract),
+        (status = 404, description = "Session not found or has no messages"),
+        (status = 503, description = "No API key configured")
+    )
+)]
+pub async fn summarize_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SessionAbstract>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut messages = sqlx::query_as::<_, (String, String)>(
+        "SELECT role, content FROM gh_chat_messages \
+         WHERE session_id = $1 ORDER BY created_at DESC LIMIT $2",
+    )
+    .bind(session_id)
+    .bind(SUMMARY_MAX_MESSAGES)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if messages.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    messages.reverse();
+
+    let prompt = format!(
+        "Summarize what this chat session accomplished in one short paragraph \
+         (2-4 sentences). Mention the main task and its outcome. \
+         Return ONLY the summary text, no heading.\n\nConversation:\n{}",
+        summary_transcript(&messages)
+    );
+    let raw_summary = flash_generate(&state, &prompt, 512, "summarize_session").await?;
+    let summary: String = raw_summary.chars().take(MAX_SUMMARY_LENGTH).collect();
+    let message_count = messages.len() as i32;
+
+    let generated_at: chrono::DateTime<chrono::Utc> = sqlx::query_scalar(
+        "INSERT INTO gh_session_summaries (session_id, summary, message_count, model) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (session_id) DO UPDATE SET summary = EXCLUDED.summary, \
+         message_count = EXCLUDED.message_count, model = EXCLUDED.model, generated_at = NOW() \
+         RETURNING generated_at",
+    )
+    .bind(session_id)
+    .bind(&summary)
+    .bind(message_count)
+    .bind(FLASH_MODEL)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("summarize_session: failed to store summary: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tracing::info!(
+        "summarize_session: session {} ({} messages)",
+        session_id,
+        message_count
+    );
+    Ok(Json(SessionAbstract {
+        session_id: session_id.to_string(),
+        summary,
+        message_count,
+        model: FLASH_MODEL.to_string(),
+        generated_at: generated_at.to_rfc3339(),
+    }))
+}
+
 // ============================================================================
 // Agent unlock & message rating
 // ============================================================================
@@ -1,14 +1,20 @@
 //! Memory and knowledge-graph handlers.
 
+use std::collections::HashSet;
+
 use axum::Json;
-use axum::extract::{Query, State};
-use axum::http::StatusCode;
+use axum::extract::{Path, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
 use serde_json::{Value, json};
 
 use crate::models::{KnowledgeEdgeRow, KnowledgeNodeRow, MemoryRow};
 use crate::state::AppState;
 
-use super::{AddMemoryRequest, ClearMemoryParams, KnowledgeEdge, KnowledgeNode, MemoryQueryParams};
+use super::{
+    AddMemoryRequest, ClearMemoryParams, GraphExportFormat, GraphExportParams,
+    GraphNeighborsParams, KnowledgeEdge, KnowledgeNode, MAX_GRAPH_DEPTH, MemoryQueryParams,
+};
 
 // ============================================================================
 // Memory handlers
@@ -141,6 +147,152 @@ pub async fn get_knowledge_graph(State(state): State<AppState>) -> Result<Json<V
     })))
 }
 
+/// GET /api/memory/graph/export?format=graphml|dot
+///
+/// Serializes the whole knowledge graph for external tools (Gephi, Graphviz).
+#[utoipa::path(get, path = "/api/memory/graph/export", tag = "memory",
+    params(("format" = Option<String>, Query, description = "`graphml` (default) or `dot`")),
+    responses(
+        (status = 200, description = "GraphML (application/xml) or DOT (text/vnd.graphviz)", body = String),
+        (status = 400, description = "Unsupported format")
+    )
+)]
+pub async fn export_knowledge_graph(
+    State(state): State<AppState>,
+    Query(params): Query<GraphExportParams>,
+) -> Result<Response, StatusCode> {
+    let node_rows = sqlx::query_as::<_, KnowledgeNodeRow>(
+        "SELECT id, node_type, label FROM gh_knowledge_nodes ORDER BY id",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let edge_rows = sqlx::query_as::<_, KnowledgeEdgeRow>(
+        "SELECT source, target, label FROM gh_knowledge_edges ORDER BY source, target, label",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let nodes: Vec<KnowledgeNode> = node_rows.into_iter().map(super::row_to_node).collect();
+    let edges: Vec<KnowledgeEdge> = edge_rows.into_iter().map(super::row_to_edge).collect();
+
+    let (body, content_type, filename) = match params.format {
+        GraphExportFormat::Graphml => (
+            render_graphml(&nodes, &edges),
+            "application/xml; charset=utf-8",
+            "knowledge-graph.graphml",
+        ),
+        GraphExportFormat::Dot => (
+            render_dot(&nodes, &edges),
+            "text/vnd.graphviz; charset=utf-8",
+            "knowledge-graph.dot",
+        ),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Escape text for XML content and attribute values. Characters that are not
+/// allowed anywhere in XML 1.0 (most C0 controls) are dropped.
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape text for use inside a double-quoted DOT ID.
+fn escape_dot(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub(crate) fn render_graphml(nodes: &[KnowledgeNode], edges: &[KnowledgeEdge]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+         <key id=\"node_type\" for=\"node\" attr.name=\"node_type\" attr.type=\"string\"/>\n  \
+         <key id=\"label\" for=\"all\" attr.name=\"label\" attr.type=\"string\"/>\n  \
+         <graph id=\"knowledge\" edgedefault=\"directed\">\n",
+    );
+    for n in nodes {
+        out.push_str(&format!(
+            "    <node id=\"{}\">\n      \
+             <data key=\"node_type\">{}</data>\n      \
+             <data key=\"label\">{}</data>\n    \
+             </node>\n",
+            escape_xml(&n.id),
+            escape_xml(&n.node_type),
+            escape_xml(&n.label),
+        ));
+    }
+    for e in edges {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">\n      \
+             <data key=\"label\">{}</data>\n    \
+             </edge>\n",
+            escape_xml(&e.source),
+            escape_xml(&e.target),
+            escape_xml(&e.label),
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub(crate) fn render_dot(nodes: &[KnowledgeNode], edges: &[KnowledgeEdge]) -> String {
+    let mut out = String::from("digraph knowledge {\n");
+    for n in nodes {
+        out.push_str(&format!(
+            "  \"{}\" [label=\"{}\", node_type=\"{}\"];\n",
+            escape_dot(&n.id),
+            escape_dot(&n.label),
+            escape_dot(&n.node_type),
+        ));
+    }
+    for e in edges {
+        out.push_str(&format!(
+            "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+            escape_dot(&e.source),
+            escape_dot(&e.target),
+            escape_dot(&e.label),
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
 /// POST /api/memory/graph/nodes
 #[utoipa::path(post, path = "/api/memory/graph/nodes", tag = "memory",
     responses((status = 200, description = "Knowledge node added/updated", body = Value))
@@ -184,3 +336,131 @@ pub async fn add_graph_edge(
 
     Ok(Json(json!(edge)))
 }
+
+/// DELETE /api/memory/graph/nodes/:id — remove a node and all incident edges
+#[utoipa::path(delete, path = "/api/memory/graph/nodes/{id}", tag = "memory",
+    params(("id" = String, Path, description = "Knowledge node ID")),
+    responses(
+        (status = 200, description = "Knowledge node deleted", body = Value),
+        (status = 404, description = "Node not found")
+    )
+)]
+pub async fn delete_knowledge_node(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let edges = sqlx::query("DELETE FROM gh_knowledge_edges WHERE source = $1 OR target = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let node = sqlx::query("DELETE FROM gh_knowledge_nodes WHERE id = $1")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if node.rows_affected() == 0 {
+        // Dropping the transaction rolls back the edge delete.
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(json!({
+        "deleted": true,
+        "id": id,
+        "edges_removed": edges.rows_affected(),
+    })))
+}
+
+/// GET /api/memory/graph/neighbors/:node_id?depth=1
+///
+/// Breadth-first traversal from `node_id`, following edges in either
+/// direction. Returns every node reachable within `depth` hops (max 3)
+/// together with the edges between them.
+#[utoipa::path(get, path = "/api/memory/graph/neighbors/{node_id}", tag = "memory",
+    params(
+        ("node_id" = String, Path, description = "Start node ID"),
+        ("depth" = Option<u32>, Query, description = "Hops to traverse (default 1, max 3)"),
+    ),
+    responses(
+        (status = 200, description = "Reachable subgraph", body = Value),
+        (status = 404, description = "Node not found")
+    )
+)]
+pub async fn get_graph_neighbors(
+    State(state): State<AppState>,
+    Path(node_id): Path<String>,
+    Query(params): Query<GraphNeighborsParams>,
+) -> Result<Json<Value>, StatusCode> {
+    let depth = params.depth.unwrap_or(1).clamp(1, MAX_GRAPH_DEPTH);
+
+    sqlx::query("SELECT 1 FROM gh_knowledge_nodes WHERE id = $1")
+        .bind(&node_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut visited: HashSet<String> = HashSet::from([node_id.clone()]);
+    let mut frontier: Vec<String> = vec![node_id.clone()];
+
+    for _ in 0..depth {
+        if frontier.is_empty() {
+            break;
+        }
+        let rows = sqlx::query_as::<_, KnowledgeEdgeRow>(
+            "SELECT source, target, label FROM gh_knowledge_edges \
+             WHERE source = ANY($1) OR target = ANY($1)",
+        )
+        .bind(&frontier)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        frontier = rows
+            .into_iter()
+            .flat_map(|e| [e.source, e.target])
+            .filter(|id| visited.insert(id.clone()))
+            .collect();
+    }
+
+    let ids: Vec<String> = visited.into_iter().collect();
+
+    let node_rows = sqlx::query_as::<_, KnowledgeNodeRow>(
+        "SELECT id, node_type, label FROM gh_knowledge_nodes WHERE id = ANY($1)",
+    )
+    .bind(&ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let edge_rows = sqlx::query_as::<_, KnowledgeEdgeRow>(
+        "SELECT source, target, label FROM gh_knowledge_edges \
+         WHERE source = ANY($1) AND target = ANY($1)",
+    )
+    .bind(&ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let nodes: Vec<KnowledgeNode> = node_rows.into_iter().map(super::row_to_node).collect();
+    let edges: Vec<KnowledgeEdge> = edge_rows.into_iter().map(super::row_to_edge).collect();
+
+    Ok(Json(json!({
+        "root": node_id,
+        "depth": depth,
+        "nodes": nodes,
+        "edges": edges,
+    })))
+}
@@ -142,15 +142,20 @@ pub async fn clear_history(State(state): State<AppState>) -> Result<Json<Value>,
 /// GET /api/sessions/:id/messages?limit=50&offset=0
 ///
 /// Paginated message history for a session. Returns messages in chronological
-/// order with total count for client-side pagination controls.
+/// order (or newest-first with `order=desc`) with total count for client-side
+/// pagination controls. Passing `after=<message id>` switches to cursor mode,
+/// which adds `has_more` and `next_cursor` to the response.
 #[utoipa::path(get, path = "/api/sessions/{id}/messages", tag = "sessions",
     params(
         ("id" = String, Path, description = "Session UUID"),
         ("limit" = Option<i64>, Query, description = "Max messages to return (default 50, max 500)"),
-        ("offset" = Option<i64>, Query, description = "Number of messages to skip (default 0)"),
+        ("offset" = Option<i64>, Query, description = "Number of messages to skip (default 0, ignored with `after`)"),
+        ("after" = Option<String>, Query, description = "Cursor: return messages after this message ID in the requested order"),
+        ("order" = Option<String>, Query, description = "`asc` (oldest first, default) or `desc` (newest first)"),
     ),
     responses(
         (status = 200, description = "Paginated messages", body = Value),
+        (status = 400, description = "Invalid session ID, cursor or order"),
         (status = 404, description = "Session not found")
     )
 )]
@@ -162,6 +167,12 @@ pub async fn get_session_messages(
     let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
     let limit = params.limit.unwrap_or(50).clamp(1, 500);
     let offset = params.offset.unwrap_or(0).max(0);
+    let descending = params.descending().ok_or(StatusCode::BAD_REQUEST)?;
+    let (direction, cursor_cmp) = if descending {
+        ("DESC", "<")
+    } else {
+        ("ASC", ">")
+    };
 
     // Verify session exists
     sqlx::query("SELECT 1 FROM gh_sessions WHERE id = $1")
@@ -179,12 +190,61 @@ pub async fn get_session_messages(
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Fetch paginated messages in chronological order
-    let rows = sqlx::query_as::<_, ChatMessageRow>(
-        "SELECT id, role, content, model, agent, created_at \
+    // Cursor-based pagination: (created_at, id) keyset after the cursor message
+    if let Some(ref after_id) = params.after {
+        let cursor_id = uuid::Uuid::parse_str(after_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+        let cursor_at: chrono::DateTime<chrono::Utc> = sqlx::query_scalar(
+            "SELECT created_at FROM gh_chat_messages WHERE id = $1 AND session_id = $2",
+        )
+        .bind(cursor_id)
+        .bind(session_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+        // Fetch one extra row to know whether another page exists
+        let mut rows = sqlx::query_as::<_, ChatMessageRow>(&format!(
+            "SELECT id, role, content, model, agent, created_at, pinned \
+             FROM gh_chat_messages WHERE session_id = $1 \
+             AND (created_at, id) {cursor_cmp} ($2, $3) \
+             ORDER BY created_at {direction}, id {direction} LIMIT $4"
+        ))
+        .bind(session_id)
+        .bind(cursor_at)
+        .bind(cursor_id)
+        .bind(limit + 1)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        let messages: Vec<ChatMessage> = rows.into_iter().map(super::row_to_message).collect();
+        let next_cursor = if has_more {
+            messages.last().map(|m| m.id.clone())
+        } else {
+            None
+        };
+
+        return Ok(Json(json!({
+            "session_id": id,
+            "messages": messages,
+            "total": total,
+            "returned": messages.len(),
+            "limit": limit,
+            "order": direction.to_ascii_lowercase(),
+            "has_more": has_more,
+            "next_cursor": next_cursor,
+        })));
+    }
+
+    // Offset-based pagination (backwards compatible)
+    let rows = sqlx::query_as::<_, ChatMessageRow>(&format!(
+        "SELECT id, role, content, model, agent, created_at, pinned \
          FROM gh_chat_messages WHERE session_id = $1 \
-         ORDER BY created_at ASC LIMIT $2 OFFSET $3",
-    )
+         ORDER BY created_at {direction}, id {direction} LIMIT $2 OFFSET $3"
+    ))
     .bind(session_id)
     .bind(limit)
     .bind(offset)
@@ -194,6 +254,12 @@ pub async fn get_session_messages(
 
     let messages: Vec<ChatMessage> = rows.into_iter().map(super::row_to_message).collect();
     let returned = messages.len();
+    let has_more = offset + (returned as i64) < total;
+    let next_cursor = if has_more {
+        messages.last().map(|m| m.id.clone())
+    } else {
+        None
+    };
 
     Ok(Json(json!({
         "session_id": id,
@@ -202,6 +268,9 @@ pub async fn get_session_messages(
         "returned": returned,
         "limit": limit,
         "offset": offset,
+        "order": direction.to_ascii_lowercase(),
+        "has_more": has_more,
+        "next_cursor": next_cursor,
     })))
 }
 
@@ -262,3 +331,167 @@ pub async fn add_session_message(
     let msg = super::row_to_message(row);
     Ok((StatusCode::CREATED, Json(json!(msg))))
 }
+
+/// POST /api/sessions/:id/messages/:message_id/pin
+///
+/// Pin a message so it is always included in the model context, even after it
+/// scrolls out of the recent-history window.
+#[utoipa::path(post, path = "/api/sessions/{id}/messages/{message_id}/pin", tag = "sessions",
+    params(
+        ("id" = String, Path, description = "Session UUID"),
+        ("message_id" = String, Path, description = "Message UUID"),
+    ),
+    responses(
+        (status = 200, description = "Message pinned", body = ChatMessage),
+        (status = 404, description = "Message not found in session")
+    )
+)]
+pub async fn pin_session_message(
+    State(state): State<AppState>,
+    Path((id, message_id)): Path<(String, String)>,
+) -> Result<Json<ChatMessage>, StatusCode> {
+    set_message_pinned(&state, &id, &message_id, true).await
+}
+
+/// DELETE /api/sessions/:id/messages/:message_id/pin
+#[utoipa::path(delete, path = "/api/sessions/{id}/messages/{message_id}/pin", tag = "sessions",
+    params(
+        ("id" = String, Path, description = "Session UUID"),
+        ("message_id" = String, Path, description = "Message UUID"),
+    ),
+    responses(
+        (status = 200, description = "Message unpinned", body = ChatMessage),
+        (status = 404, description = "Message not found in session")
+    )
+)]
+pub async fn unpin_session_message(
+    State(state): State<AppState>,
+    Path((id, message_id)): Path<(String, String)>,
+) -> Result<Json<ChatMessage>, StatusCode> {
+    set_message_pinned(&state, &id, &message_id, false).await
+}
+
+async fn set_message_pinned(
+    state: &AppState,
+    id: &str,
+    message_id: &str,
+    pinned: bool,
+) -> Result<Json<ChatMessage>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let message_id: uuid::Uuid = message_id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let row = sqlx::query_as::<_, ChatMessageRow>(
+        "UPDATE gh_chat_messages SET pinned = $1 WHERE id = $2 AND session_id = $3 \
+         RETURNING id, role, content, model, agent, created_at, pinned",
+    )
+    .bind(pinned)
+    .bind(message_id)
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(super::row_to_message(row)))
+}
+
+/// GET /api/sessions/:id/pinned
+///
+/// All pinned messages of a session in chronological order.
+#[utoipa::path(get, path = "/api/sessions/{id}/pinned", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "Pinned messages", body = Value),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn list_pinned_messages(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Verify session exists
+    sqlx::query("SELECT 1 FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let rows = sqlx::query_as::<_, ChatMessageRow>(
+        "SELECT id, role, content, model, agent, created_at, pinned \
+         FROM gh_chat_messages WHERE session_id = $1 AND pinned \
+         ORDER BY created_at ASC",
+    )
+    .bind(session_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let messages: Vec<ChatMessage> = rows.into_iter().map(super::row_to_message).collect();
+    let count = messages.len();
+
+    Ok(Json(json!({
+        "session_id": id,
+        "messages": messages,
+        "count": count,
+    })))
+}
+
+/// POST /api/sessions/:id/clear-messages
+///
+/// Empty a session's conversation — its messages and generated summary — while
+/// keeping the session row with its title, agent lock, model pin, tags and
+/// working directory.
+#[utoipa::path(post, path = "/api/sessions/{id}/clear-messages", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "Messages removed (`messages_removed` count)", body = Value),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn clear_session_messages(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let db_err = |_: sqlx::Error| StatusCode::INTERNAL_SERVER_ERROR;
+
+    let mut tx = state.db.begin().await.map_err(db_err)?;
+    let touched = sqlx::query("UPDATE gh_sessions SET updated_at = NOW() WHERE id = $1")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+    if touched == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let removed = sqlx::query("DELETE FROM gh_chat_messages WHERE session_id = $1")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+    sqlx::query("DELETE FROM gh_session_summaries WHERE session_id = $1")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+    tx.commit().await.map_err(db_err)?;
+
+    crate::audit::log_audit(
+        &state.db,
+        "clear_session_messages",
+        json!({ "session_id": id, "messages_removed": removed }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
+
+    Ok(Json(json!({
+        "session_id": id,
+        "messages_removed": removed,
+    })))
+}
@@ -8,14 +8,17 @@ mod history;
 mod memory;
 mod messages;
 mod settings;
+mod stats;
+mod tags;
 
 use axum::Router;
-use axum::routing::{get, patch, post};
+use axum::routing::{delete, get, patch, post};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::models::{
-    ChatMessage, ChatMessageRow, KnowledgeEdgeRow, KnowledgeNodeRow, MemoryRow, SettingsRow,
+    ChatMessage, ChatMessageRow, KnowledgeEdgeRow, KnowledgeNodeRow, MemoryRow, SettingsProfileRow,
+    SettingsRow,
 };
 use crate::state::AppState;
 
@@ -26,10 +29,14 @@ pub use history::*;
 pub use memory::*;
 pub use messages::*;
 pub use settings::*;
+pub use stats::*;
+pub use tags::*;
 
 // ── Input length limits — Jaskier Shared Pattern ────────────────────────────
 pub(crate) const MAX_TITLE_LENGTH: usize = 200;
+pub(crate) const MAX_TAG_LENGTH: usize = MAX_TITLE_LENGTH;
 pub(crate) const MAX_MESSAGE_LENGTH: usize = 50_000; // 50KB
+pub(crate) const MAX_PROFILE_NAME_LENGTH: usize = 64;
 
 // ============================================================================
 // Response models
@@ -75,6 +82,13 @@ pub struct AddMessageRequest {
     pub agent: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct HistoryStatsParams {
+    /// Window in days (default 30, clamped to 1-365).
+    #[serde(default)]
+    pub days: Option<i32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct HistoryParams {
     /// Max messages to return (default 50, max 500).
@@ -96,10 +110,39 @@ pub struct PaginationParams {
     /// Number of items to skip (offset-based pagination).
     #[serde(default)]
     pub offset: Option<i64>,
-    /// Cursor-based pagination: return sessions created before this session ID.
-    /// When provided, `offset` is ignored.
+    /// Cursor-based pagination: return items after this ID (sessions: updated
+    /// before it; messages: next in `order`). When provided, `offset` is ignored.
     #[serde(default)]
     pub after: Option<String>,
+    /// `asc` (default) or `desc` — message order for `GET /api/sessions/{id}/messages`.
+    #[serde(default)]
+    pub order: Option<String>,
+    /// Include soft-deleted (archived) sessions in `GET /api/sessions`.
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Only return sessions carrying this tag (`GET /api/sessions`).
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+impl PaginationParams {
+    /// `Some(true)` for `order=desc`, `Some(false)` for `asc`/unset, `None` if invalid.
+    pub fn descending(&self) -> Option<bool> {
+        match self.order.as_deref().map(str::trim) {
+            None | Some("") => Some(false),
+            Some(o) if o.eq_ignore_ascii_case("asc") => Some(false),
+            Some(o) if o.eq_ignore_ascii_case("desc") => Some(true),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Query parameters for `DELETE /api/sessions/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct DeleteSessionParams {
+    /// Permanently remove the session and its messages instead of archiving it.
+    #[serde(default)]
+    pub hard: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -116,8 +159,33 @@ pub struct ClearMemoryParams {
     pub agent: Option<String>,
 }
 
+/// Maximum traversal depth for knowledge-graph neighbor queries.
+pub(crate) const MAX_GRAPH_DEPTH: u32 = 3;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphExportFormat {
+    #[default]
+    Graphml,
+    Dot,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphExportParams {
+    /// `graphml` (default) or `dot`.
+    #[serde(default)]
+    pub format: GraphExportFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphNeighborsParams {
+    /// Number of hops to follow from the start node (default 1, max 3).
+    #[serde(default)]
+    pub depth: Option<u32>,
+}
+
 /// Partial settings for PATCH merge.
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PartialSettings {
     #[serde(default)]
     pub temperature: Option<f64>,
@@ -151,6 +219,70 @@ pub struct PartialSettings {
     /// Force all agents to use this model (empty string = clear, model ID = force)
     #[serde(default)]
     pub force_model: Option<String>,
+    /// Stop the tool-call loop on the first TOOL_ERROR result
+    #[serde(default)]
+    pub stop_on_tool_error: Option<bool>,
+    /// Executables `execute_command` may run (empty list = denylist-only mode)
+    #[serde(default)]
+    pub command_allowlist: Option<Vec<String>>,
+    /// Extra case-insensitive substrings `execute_command` refuses (site-specific bans)
+    #[serde(default)]
+    pub extra_blocked_patterns: Option<Vec<String>>,
+    /// Models tried in order when the primary model keeps failing
+    #[serde(default)]
+    pub fallback_models: Option<Vec<String>>,
+    /// How many levels deep `call_agent` delegation may nest (clamped to 1-6)
+    #[serde(default)]
+    pub max_agent_call_depth: Option<i32>,
+    /// Session messages replayed per request before per-tier scaling (clamped to 2-100)
+    #[serde(default)]
+    pub history_window: Option<i32>,
+    /// Most recent history messages kept untruncated (clamped to 0-50)
+    #[serde(default)]
+    pub history_truncate_keep: Option<i32>,
+    /// Gemini safetySettings preset: 'default', 'relaxed', 'strict'
+    #[serde(default)]
+    pub safety_level: Option<String>,
+    /// Quality alert threshold on the recent average rating (clamped to 1.0-5.0)
+    #[serde(default)]
+    pub rating_alert_threshold: Option<f64>,
+    /// Recent ratings required before the quality alert fires (clamped to 1-20)
+    #[serde(default)]
+    pub rating_alert_min_count: Option<i32>,
+    /// Byte cap for write_file / edit_file (clamped to 1 KB - 50 MB)
+    #[serde(default)]
+    pub max_write_bytes: Option<i32>,
+    /// Byte cap for streamed tool output (clamped to 1 KB - 1 MB)
+    #[serde(default)]
+    pub max_tool_display_bytes: Option<i32>,
+    /// Retries of transient Gemini errors (clamped to 0-10)
+    #[serde(default)]
+    pub gemini_max_retries: Option<i32>,
+    /// First retry delay in ms (clamped to 100-30000)
+    #[serde(default)]
+    pub gemini_backoff_base_ms: Option<i32>,
+    /// Retry delay jitter in ms (clamped to 0-10000)
+    #[serde(default)]
+    pub gemini_backoff_jitter_ms: Option<i32>,
+    /// Accept a `working_directory` that does not exist yet. Not a setting —
+    /// never stored in profiles.
+    #[serde(default, skip_serializing)]
+    pub allow_nonexistent: bool,
+}
+
+/// Named settings preset — only the fields it sets are stored and applied.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SettingsProfile {
+    pub name: String,
+    #[schema(value_type = Object)]
+    pub settings: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSettingsProfileRequest {
+    pub name: String,
+    pub settings: PartialSettings,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -172,6 +304,7 @@ pub(crate) fn row_to_message(row: ChatMessageRow) -> ChatMessage {
         model: row.model,
         timestamp: row.created_at.to_rfc3339(),
         agent: row.agent,
+        pinned: row.pinned,
     }
 }
 
@@ -202,9 +335,111 @@ pub(crate) fn row_to_settings(row: SettingsRow) -> crate::models::AppSettings {
         },
         working_directory: row.working_directory,
         force_model: row.force_model,
+        stop_on_tool_error: row.stop_on_tool_error,
+        command_allowlist: row.command_allowlist,
+        extra_blocked_patterns: row.extra_blocked_patterns,
+        fallback_models: row.fallback_models,
+        max_agent_call_depth: if row.max_agent_call_depth == 0 {
+            3
+        } else {
+            row.max_agent_call_depth
+        },
+        history_window: if row.history_window == 0 {
+            20
+        } else {
+            row.history_window
+        },
+        history_truncate_keep: row.history_truncate_keep,
+        safety_level: if row.safety_level.is_empty() {
+            "default".to_string()
+        } else {
+            row.safety_level
+        },
+        rating_alert_threshold: if row.rating_alert_threshold == 0.0 {
+            3.0
+        } else {
+            row.rating_alert_threshold
+        },
+        rating_alert_min_count: if row.rating_alert_min_count == 0 {
+            5
+        } else {
+            row.rating_alert_min_count
+        },
+        max_write_bytes: if row.max_write_bytes == 0 {
+            crate::files::DEFAULT_MAX_WRITE_BYTES as i32
+        } else {
+            row.max_write_bytes
+        },
+        max_tool_display_bytes: if row.max_tool_display_bytes == 0 {
+            crate::handlers::streaming::DEFAULT_TOOL_DISPLAY_BYTES as i32
+        } else {
+            row.max_tool_display_bytes
+        },
+        gemini_max_retries: row.gemini_max_retries,
+        gemini_backoff_base_ms: if row.gemini_backoff_base_ms == 0 {
+            crate::handlers::streaming::GEMINI_BACKOFF_BASE.as_millis() as i32
+        } else {
+            row.gemini_backoff_base_ms
+        },
+        gemini_backoff_jitter_ms: row.gemini_backoff_jitter_ms,
+    }
+}
+
+pub(crate) fn row_to_profile(row: SettingsProfileRow) -> SettingsProfile {
+    SettingsProfile {
+        name: row.name,
+        settings: row.settings,
+        created_at: row.created_at.to_rfc3339(),
+    }
+}
+
+/// Profile names are used in URLs — keep them short and slug-like.
+pub(crate) fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Reject a non-empty `path` that is a file, or that does not exist unless
+/// `allow_nonexistent` is set. Empty means "no working directory".
+pub(crate) async fn check_working_directory(
+    path: &str,
+    allow_nonexistent: bool,
+) -> Result<(), crate::error::ApiError> {
+    if path.is_empty() {
+        return Ok(());
+    }
+    match tokio::fs::metadata(path).await {
+        Ok(meta) if meta.is_dir() => Ok(()),
+        Ok(_) => Err(crate::error::ApiError::BadRequest(format!(
+            "working_directory '{}' is not a directory",
+            path
+        ))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && allow_nonexistent => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(crate::error::ApiError::BadRequest(format!(
+                "working_directory '{}' does not exist (set allow_nonexistent to save it anyway)",
+                path
+            )))
+        }
+        Err(e) => Err(crate::error::ApiError::BadRequest(format!(
+            "working_directory '{}' is not accessible: {}",
+            path, e
+        ))),
     }
 }
 
+/// JSON stored for a profile: only the fields that are set.
+pub(crate) fn profile_settings_json(settings: &PartialSettings) -> serde_json::Value {
+    let mut value = serde_json::to_value(settings).unwrap_or_default();
+    if let Some(obj) = value.as_object_mut() {
+        obj.retain(|_, v| !v.is_null());
+    }
+    value
+}
+
 pub(crate) fn row_to_memory(row: MemoryRow) -> MemoryEntry {
     MemoryEntry {
         id: row.id.to_string(),
@@ -242,17 +477,41 @@ pub fn session_routes() -> Router<AppState> {
             get(get_history).post(add_message).delete(clear_history),
         )
         .route("/api/history/search", get(search_history))
+        .route("/api/history/stats", get(get_history_stats))
         .route("/api/settings", get(get_settings).patch(update_settings))
         .route("/api/settings/reset", post(reset_settings))
+        .route("/api/settings/{field}", delete(reset_setting_field))
+        .route(
+            "/api/settings/profiles",
+            get(list_settings_profiles).post(create_settings_profile),
+        )
+        .route(
+            "/api/settings/profiles/{name}",
+            delete(delete_settings_profile),
+        )
+        .route(
+            "/api/settings/profiles/{name}/apply",
+            post(apply_settings_profile),
+        )
         .route(
             "/api/memory/memories",
             get(list_memories).post(add_memory).delete(clear_memories),
         )
         .route("/api/memory/graph", get(get_knowledge_graph))
         .route("/api/memory/graph/nodes", post(add_knowledge_node))
+        .route(
+            "/api/memory/graph/nodes/{id}",
+            delete(delete_knowledge_node),
+        )
+        .route(
+            "/api/memory/graph/neighbors/{node_id}",
+            get(get_graph_neighbors),
+        )
         .route("/api/memory/graph/edges", post(add_graph_edge))
+        .route("/api/memory/graph/export", get(export_knowledge_graph))
         // Session CRUD
         .route("/api/sessions", get(list_sessions).post(create_session))
+        .route("/api/sessions/bulk-delete", post(bulk_delete_sessions))
         .route(
             "/api/sessions/{id}",
             get(get_session)
@@ -263,15 +522,36 @@ pub fn session_routes() -> Router<AppState> {
             "/api/sessions/{id}/messages",
             get(get_session_messages).post(add_session_message),
         )
+        .route(
+            "/api/sessions/{id}/messages/{message_id}/pin",
+            post(pin_session_message).delete(unpin_session_message),
+        )
+        .route("/api/sessions/{id}/pinned", get(list_pinned_messages))
+        .route(
+            "/api/sessions/{id}/clear-messages",
+            post(clear_session_messages),
+        )
         .route(
             "/api/sessions/{id}/generate-title",
             post(generate_session_title),
         )
+        .route("/api/sessions/{id}/summarize", post(summarize_session))
+        .route("/api/sessions/{id}/restore", post(restore_session))
+        .route("/api/sessions/{id}/stats", get(get_session_stats))
+        .route(
+            "/api/sessions/{id}/tags",
+            get(list_session_tags).post(add_session_tag),
+        )
+        .route("/api/sessions/{id}/tags/{tag}", delete(remove_session_tag))
         .route("/api/sessions/{id}/unlock", post(unlock_session_agent))
         .route(
             "/api/sessions/{id}/working-directory",
             patch(update_session_working_directory),
         )
+        .route(
+            "/api/sessions/{id}/model",
+            patch(update_session_model).delete(reset_session_model),
+        )
         .route("/api/ratings", post(rate_message))
         // Prompt history
         .route(
@@ -294,6 +574,19 @@ mod tests {
     };
     use chrono::Utc;
 
+    // ── PaginationParams::descending ────────────────────────────────────
+
+    #[test]
+    fn pagination_order_parses_asc_desc() {
+        let params = |order: Option<&str>| -> PaginationParams {
+            serde_json::from_value(serde_json::json!({ "order": order })).unwrap()
+        };
+        assert_eq!(params(None).descending(), Some(false));
+        assert_eq!(params(Some("asc")).descending(), Some(false));
+        assert_eq!(params(Some("DESC")).descending(), Some(true));
+        assert_eq!(params(Some("newest")).descending(), None);
+    }
+
     // ── row_to_message ──────────────────────────────────────────────────
 
     #[test]
@@ -306,6 +599,7 @@ mod tests {
             model: Some("gemini-pro".to_string()),
             agent: Some("Geralt".to_string()),
             created_at: now,
+            pinned: true,
         };
         let msg = row_to_message(row);
         assert_eq!(msg.id, uuid::Uuid::nil().to_string());
@@ -314,6 +608,7 @@ mod tests {
         assert_eq!(msg.model, Some("gemini-pro".to_string()));
         assert_eq!(msg.agent, Some("Geralt".to_string()));
         assert_eq!(msg.timestamp, now.to_rfc3339());
+        assert!(msg.pinned);
     }
 
     #[test]
@@ -325,6 +620,7 @@ mod tests {
             model: None,
             agent: None,
             created_at: Utc::now(),
+            pinned: false,
         };
         let msg = row_to_message(row);
         assert!(msg.model.is_none());
@@ -349,6 +645,21 @@ mod tests {
             thinking_level: "high".to_string(),
             working_directory: "C:\\Users\\test".to_string(),
             force_model: None,
+            stop_on_tool_error: true,
+            command_allowlist: vec!["cargo".to_string()],
+            extra_blocked_patterns: vec!["terraform destroy".to_string()],
+            fallback_models: vec!["gemini-2.5-flash".to_string()],
+            max_agent_call_depth: 5,
+            history_window: 40,
+            history_truncate_keep: 8,
+            safety_level: "relaxed".to_string(),
+            rating_alert_threshold: 2.5,
+            rating_alert_min_count: 10,
+            max_write_bytes: 2048,
+            max_tool_display_bytes: 4096,
+            gemini_max_retries: 5,
+            gemini_backoff_base_ms: 250,
+            gemini_backoff_jitter_ms: 0,
         };
         let settings = row_to_settings(row);
         assert!((settings.temperature - 0.7).abs() < f64::EPSILON);
@@ -362,6 +673,21 @@ mod tests {
         assert_eq!(settings.response_style, "detailed");
         assert_eq!(settings.max_iterations, 15);
         assert_eq!(settings.thinking_level, "high");
+        assert!(settings.stop_on_tool_error);
+        assert_eq!(settings.command_allowlist, vec!["cargo"]);
+        assert_eq!(settings.extra_blocked_patterns, vec!["terraform destroy"]);
+        assert_eq!(settings.fallback_models, vec!["gemini-2.5-flash"]);
+        assert_eq!(settings.max_agent_call_depth, 5);
+        assert_eq!(settings.history_window, 40);
+        assert_eq!(settings.history_truncate_keep, 8);
+        assert_eq!(settings.safety_level, "relaxed");
+        assert!((settings.rating_alert_threshold - 2.5).abs() < f64::EPSILON);
+        assert_eq!(settings.rating_alert_min_count, 10);
+        assert_eq!(settings.max_write_bytes, 2048);
+        assert_eq!(settings.max_tool_display_bytes, 4096);
+        assert_eq!(settings.gemini_max_retries, 5);
+        assert_eq!(settings.gemini_backoff_base_ms, 250);
+        assert_eq!(settings.gemini_backoff_jitter_ms, 0);
     }
 
     // ── row_to_memory ───────────────────────────────────────────────────
@@ -461,6 +787,125 @@ mod tests {
         assert_eq!(patch.response_style, Some("concise".to_string()));
     }
 
+    #[test]
+    fn profile_settings_json_keeps_only_set_fields() {
+        let json = r#"{"default_model":"gemini-3.1-flash-preview","thinking_level":"minimal"}"#;
+        let patch: PartialSettings = serde_json::from_str(json).unwrap();
+        let stored = profile_settings_json(&patch);
+        assert_eq!(
+            stored,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+
+        let back: PartialSettings = serde_json::from_value(stored).unwrap();
+        assert_eq!(back.thinking_level.as_deref(), Some("minimal"));
+        assert!(back.temperature.is_none());
+    }
+
+    #[test]
+    fn settings_patch_clamps_numeric_fields() {
+        let json = r#"{"temperature":9.0,"top_p":-0.5,"max_iterations":500,"max_agent_call_depth":10,
+            "history_window":1000,"history_truncate_keep":-3,"rating_alert_threshold":0.5,
+            "rating_alert_min_count":99,"max_write_bytes":10,"max_tool_display_bytes":99999999,
+            "gemini_max_retries":50,"gemini_backoff_base_ms":1,"gemini_backoff_jitter_ms":-5}"#;
+        let mut patch: PartialSettings = serde_json::from_str(json).unwrap();
+        validate_settings_patch(&mut patch).unwrap();
+        assert_eq!(patch.temperature, Some(2.0));
+        assert_eq!(patch.top_p, Some(0.0));
+        assert_eq!(patch.max_iterations, Some(50));
+        assert_eq!(patch.max_agent_call_depth, Some(6));
+        assert_eq!(patch.history_window, Some(100));
+        assert_eq!(patch.history_truncate_keep, Some(0));
+        assert_eq!(patch.rating_alert_threshold, Some(1.0));
+        assert_eq!(patch.rating_alert_min_count, Some(20));
+        assert_eq!(patch.max_write_bytes, Some(1024));
+        assert_eq!(patch.max_tool_display_bytes, Some(1024 * 1024));
+        assert_eq!(patch.gemini_max_retries, Some(10));
+        assert_eq!(patch.gemini_backoff_base_ms, Some(100));
+        assert_eq!(patch.gemini_backoff_jitter_ms, Some(0));
+    }
+
+    #[tokio::test]
+    async fn working_directory_check_requires_an_existing_directory() {
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        assert!(check_working_directory("", false).await.is_ok());
+        assert!(check_working_directory(dir, false).await.is_ok());
+
+        let missing = format!("{}/gh-missing-{}", dir, uuid::Uuid::new_v4());
+        let err = check_working_directory(&missing, false).await.unwrap_err();
+        assert!(matches!(err, crate::error::ApiError::BadRequest(ref m) if m.contains(&missing)));
+        assert!(check_working_directory(&missing, true).await.is_ok());
+
+        let file = format!("{}/gh-file-{}", dir, uuid::Uuid::new_v4());
+        std::fs::write(&file, "x").unwrap();
+        let result = check_working_directory(&file, true).await;
+        std::fs::remove_file(&file).ok();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn default_field_patch_sets_only_that_field() {
+        let defaults = crate::models::AppSettings::default();
+        let patch = default_field_patch("working_directory", &defaults).unwrap();
+        assert_eq!(patch.working_directory.as_deref(), Some(""));
+        assert!(patch.temperature.is_none() && patch.language.is_none());
+
+        let patch = default_field_patch("history_window", &defaults).unwrap();
+        assert_eq!(patch.history_window, Some(20));
+        // Clearing force_model needs "" — null would mean "keep" in a PATCH.
+        let patch = default_field_patch("force_model", &defaults).unwrap();
+        assert_eq!(patch.force_model.as_deref(), Some(""));
+
+        assert!(default_field_patch("no_such_field", &defaults).is_none());
+    }
+
+    #[test]
+    fn settings_patch_rejects_unknown_enum_values() {
+        for json in [
+            r#"{"thinking_level":"turbo"}"#,
+            r#"{"response_style":"verbose"}"#,
+            r#"{"language":"xx"}"#,
+            r#"{"safety_level":"off"}"#,
+        ] {
+            let mut patch: PartialSettings = serde_json::from_str(json).unwrap();
+            let err = validate_settings_patch(&mut patch).unwrap_err();
+            assert!(
+                matches!(err, crate::error::ApiError::BadRequest(ref m) if m.contains("allowed"))
+            );
+        }
+    }
+
+    #[test]
+    fn settings_patch_normalizes_fallback_models() {
+        let json = r#"{"fallback_models":[" gemini-2.5-flash ","","gemini-2.5-flash","models/gemini-2.0-flash"]}"#;
+        let mut patch: PartialSettings = serde_json::from_str(json).unwrap();
+        validate_settings_patch(&mut patch).unwrap();
+        assert_eq!(
+            patch.fallback_models.unwrap(),
+            vec!["gemini-2.5-flash", "models/gemini-2.0-flash"]
+        );
+
+        let mut patch: PartialSettings =
+            serde_json::from_str(r#"{"fallback_models":["gemini flash"]}"#).unwrap();
+        assert!(validate_settings_patch(&mut patch).is_err());
+        let mut patch: PartialSettings =
+            serde_json::from_str(r#"{"fallback_models":["a","b","c","d","e","f"]}"#).unwrap();
+        assert!(validate_settings_patch(&mut patch).is_err());
+    }
+
+    #[test]
+    fn profile_name_validation() {
+        assert!(is_valid_profile_name("flash-fast"));
+        assert!(is_valid_profile_name("pro_deep2"));
+        assert!(!is_valid_profile_name(""));
+        assert!(!is_valid_profile_name("with space"));
+        assert!(!is_valid_profile_name("../etc"));
+        assert!(!is_valid_profile_name(
+            &"a".repeat(MAX_PROFILE_NAME_LENGTH + 1)
+        ));
+    }
+
     #[test]
     fn knowledge_node_roundtrip() {
         let node = KnowledgeNode {
@@ -509,6 +954,77 @@ mod tests {
         let params: PaginationParams = serde_json::from_str(json).unwrap();
         assert!(params.limit.is_none());
         assert!(params.offset.is_none());
+        assert!(!params.include_archived);
+        assert!(params.tag.is_none());
+    }
+
+    #[test]
+    fn session_tags_are_trimmed_and_length_limited() {
+        assert_eq!(normalize_tag("  work/client-a ").unwrap(), "work/client-a");
+        assert!(normalize_tag("   ").is_err());
+        assert!(normalize_tag(&"ż".repeat(MAX_TAG_LENGTH)).is_ok());
+        assert!(normalize_tag(&"x".repeat(MAX_TAG_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn graph_export_format_parsing() {
+        let params: GraphExportParams = serde_json::from_str("{}").unwrap();
+        assert_eq!(params.format, GraphExportFormat::Graphml);
+        let params: GraphExportParams = serde_json::from_str(r#"{"format":"dot"}"#).unwrap();
+        assert_eq!(params.format, GraphExportFormat::Dot);
+        assert!(serde_json::from_str::<GraphExportParams>(r#"{"format":"svg"}"#).is_err());
+    }
+
+    fn export_fixture() -> (Vec<KnowledgeNode>, Vec<KnowledgeEdge>) {
+        let nodes = vec![
+            KnowledgeNode {
+                id: "abc".to_string(),
+                node_type: "entity".to_string(),
+                label: "Test <special> & chars".to_string(),
+            },
+            KnowledgeNode {
+                id: "say \"hi\"".to_string(),
+                node_type: "concept".to_string(),
+                label: "back\\slash\nnewline".to_string(),
+            },
+        ];
+        let edges = vec![KnowledgeEdge {
+            source: "abc".to_string(),
+            target: "say \"hi\"".to_string(),
+            label: "edge with unicode: \u{1F5E1}".to_string(),
+        }];
+        (nodes, edges)
+    }
+
+    #[test]
+    fn graphml_export_escapes_labels() {
+        let (nodes, edges) = export_fixture();
+        let xml = memory::render_graphml(&nodes, &edges);
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("Test &lt;special&gt; &amp; chars"));
+        assert!(xml.contains(r#"<node id="say &quot;hi&quot;">"#));
+        assert!(xml.contains("edge with unicode: \u{1F5E1}"));
+        assert!(!xml.contains("<special>"));
+        assert!(xml.trim_end().ends_with("</graphml>"));
+    }
+
+    #[test]
+    fn dot_export_escapes_labels() {
+        let (nodes, edges) = export_fixture();
+        let dot = memory::render_dot(&nodes, &edges);
+        assert!(dot.starts_with("digraph knowledge {"));
+        assert!(dot.contains(r#"label="back\\slash\nnewline""#));
+        assert!(dot.contains(r#""abc" -> "say \"hi\"""#));
+        assert!(dot.contains("Test <special> & chars"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn graph_neighbors_params_depth_optional() {
+        let params: GraphNeighborsParams = serde_json::from_str("{}").unwrap();
+        assert!(params.depth.is_none());
+        let params: GraphNeighborsParams = serde_json::from_str(r#"{"depth":2}"#).unwrap();
+        assert_eq!(params.depth, Some(2));
     }
 
     #[test]
@@ -1,13 +1,177 @@
-//! Settings endpoints: get, update (partial PATCH), and reset to defaults.
+//! Settings endpoints: get, update (partial PATCH), reset to defaults, and
+//! named settings profiles (presets applied onto the active settings row).
 
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 
-use crate::models::{AppSettings, SettingsRow};
+use crate::error::ApiError;
+use crate::models::{AppSettings, SettingsProfileRow, SettingsRow};
 use crate::state::AppState;
 
-use super::PartialSettings;
+use super::{CreateSettingsProfileRequest, PartialSettings, SettingsProfile};
+
+const RESPONSE_STYLES: [&str; 4] = ["concise", "balanced", "detailed", "technical"];
+pub(crate) const THINKING_LEVELS: [&str; 5] = ["none", "minimal", "low", "medium", "high"];
+const SAFETY_LEVELS: [&str; 3] = ["default", "relaxed", "strict"];
+const LANGUAGES: [&str; 2] = ["en", "pl"];
+const MAX_LIST_ENTRIES: usize = 100;
+/// Each fallback costs a full retry cycle — keep the chain short.
+const MAX_FALLBACK_MODELS: usize = 5;
+
+/// Validate a settings patch in place: numeric fields are clamped to their
+/// supported ranges, oversized strings and unknown enum values are rejected.
+pub(crate) fn validate_settings_patch(patch: &mut PartialSettings) -> Result<(), ApiError> {
+    // Limit string field sizes to prevent uncontrolled memory allocation
+    if patch
+        .welcome_message
+        .as_ref()
+        .is_some_and(|s| s.len() > 10_000)
+    {
+        return Err(ApiError::BadRequest(
+            "welcome_message exceeds 10000 characters".into(),
+        ));
+    }
+    if patch.default_model.as_ref().is_some_and(|s| s.len() > 200) {
+        return Err(ApiError::BadRequest(
+            "default_model exceeds 200 characters".into(),
+        ));
+    }
+
+    check_enum(
+        "response_style",
+        patch.response_style.as_deref(),
+        &RESPONSE_STYLES,
+    )?;
+    check_enum(
+        "thinking_level",
+        patch.thinking_level.as_deref(),
+        &THINKING_LEVELS,
+    )?;
+    check_enum("language", patch.language.as_deref(), &LANGUAGES)?;
+    check_enum(
+        "safety_level",
+        patch.safety_level.as_deref(),
+        &SAFETY_LEVELS,
+    )?;
+
+    for (field, value) in [
+        ("temperature", patch.temperature),
+        ("top_p", patch.top_p),
+        ("rating_alert_threshold", patch.rating_alert_threshold),
+    ] {
+        if value.is_some_and(|v| !v.is_finite()) {
+            return Err(ApiError::BadRequest(format!(
+                "{} must be a finite number",
+                field
+            )));
+        }
+    }
+    patch.temperature = patch.temperature.map(|v| v.clamp(0.0, 2.0));
+    patch.top_p = patch.top_p.map(|v| v.clamp(0.0, 1.0));
+    patch.max_iterations = patch.max_iterations.map(|v| v.clamp(1, 50));
+    patch.max_agent_call_depth = patch
+        .max_agent_call_depth
+        .map(|v| v.clamp(1, crate::a2a::MAX_AGENT_CALL_DEPTH_CAP as i32));
+    patch.history_window = patch.history_window.map(|v| v.clamp(2, 100));
+    patch.history_truncate_keep = patch.history_truncate_keep.map(|v| v.clamp(0, 50));
+    patch.rating_alert_threshold = patch.rating_alert_threshold.map(|v| v.clamp(1.0, 5.0));
+    patch.rating_alert_min_count = patch
+        .rating_alert_min_count
+        .map(|v| v.clamp(1, crate::context::RATING_ALERT_WINDOW as i32));
+    patch.max_write_bytes = patch
+        .max_write_bytes
+        .map(|v| v.clamp(1024, crate::files::MAX_WRITE_BYTES_CAP as i32));
+    patch.max_tool_display_bytes = patch.max_tool_display_bytes.map(|v| {
+        v.clamp(
+            1024,
+            crate::handlers::streaming::MAX_TOOL_DISPLAY_BYTES_CAP as i32,
+        )
+    });
+    patch.gemini_max_retries = patch
+        .gemini_max_retries
+        .map(|v| v.clamp(0, crate::handlers::streaming::MAX_GEMINI_RETRIES as i32));
+    patch.gemini_backoff_base_ms = patch.gemini_backoff_base_ms.map(|v| {
+        v.clamp(
+            crate::handlers::streaming::MIN_BACKOFF_BASE_MS as i32,
+            crate::handlers::streaming::MAX_BACKOFF_BASE_MS as i32,
+        )
+    });
+    patch.gemini_backoff_jitter_ms = patch
+        .gemini_backoff_jitter_ms
+        .map(|v| v.clamp(0, crate::handlers::streaming::MAX_BACKOFF_JITTER_MS as i32));
+
+    if let Some(list) = patch.command_allowlist.as_mut() {
+        normalize_list("command_allowlist", list)?;
+        if let Some(bad) = list
+            .iter()
+            .find(|e| e.len() > 64 || e.contains(char::is_whitespace))
+        {
+            return Err(ApiError::BadRequest(format!(
+                "command_allowlist entry '{}' must be a single executable name",
+                bad
+            )));
+        }
+    }
+    if let Some(list) = patch.extra_blocked_patterns.as_mut() {
+        normalize_list("extra_blocked_patterns", list)?;
+        if let Some(bad) = list.iter().find(|e| e.len() > 200) {
+            return Err(ApiError::BadRequest(format!(
+                "extra_blocked_patterns entry '{}...' exceeds 200 characters",
+                bad.chars().take(40).collect::<String>()
+            )));
+        }
+    }
+    if let Some(list) = patch.fallback_models.as_mut() {
+        normalize_list("fallback_models", list)?;
+        if list.len() > MAX_FALLBACK_MODELS {
+            return Err(ApiError::BadRequest(format!(
+                "fallback_models exceeds {} entries",
+                MAX_FALLBACK_MODELS
+            )));
+        }
+        if let Some(bad) = list.iter().find(|m| !is_valid_model_id(m)) {
+            return Err(ApiError::BadRequest(format!(
+                "fallback_models entry '{}' is not a valid model ID",
+                bad
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Trim entries, drop empty ones and duplicates, and cap the list length.
+fn normalize_list(field: &str, list: &mut Vec<String>) -> Result<(), ApiError> {
+    let mut seen = std::collections::HashSet::new();
+    list.iter_mut().for_each(|e| *e = e.trim().to_string());
+    list.retain(|e| !e.is_empty() && seen.insert(e.clone()));
+    if list.len() > MAX_LIST_ENTRIES {
+        return Err(ApiError::BadRequest(format!(
+            "{} exceeds {} entries",
+            field, MAX_LIST_ENTRIES
+        )));
+    }
+    Ok(())
+}
+
+fn is_valid_model_id(model: &str) -> bool {
+    model.len() <= 128
+        && model
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '/'))
+}
+
+fn check_enum(field: &str, value: Option<&str>, allowed: &[&str]) -> Result<(), ApiError> {
+    match value {
+        Some(v) if !allowed.contains(&v) => Err(ApiError::BadRequest(format!(
+            "invalid {} '{}' (allowed: {})",
+            field,
+            v,
+            allowed.join(", ")
+        ))),
+        _ => Ok(()),
+    }
+}
 
 // ============================================================================
 // Settings handlers
@@ -20,7 +184,11 @@ use super::PartialSettings;
 pub async fn get_settings(State(state): State<AppState>) -> Result<Json<AppSettings>, StatusCode> {
     let row = sqlx::query_as::<_, SettingsRow>(
         "SELECT temperature, max_tokens, default_model, language, theme, welcome_message, \
-         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model \
+         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model, \
+         stop_on_tool_error, command_allowlist, extra_blocked_patterns, fallback_models, max_agent_call_depth, \
+         history_window, history_truncate_keep, safety_level, rating_alert_threshold, rating_alert_min_count, \
+         max_write_bytes, max_tool_display_bytes, gemini_max_retries, gemini_backoff_base_ms, \
+         gemini_backoff_jitter_ms \
          FROM gh_settings WHERE id = 1",
     )
     .fetch_one(&state.db)
@@ -30,40 +198,67 @@ pub async fn get_settings(State(state): State<AppState>) -> Result<Json<AppSetti
     Ok(Json(super::row_to_settings(row)))
 }
 
-/// PATCH /api/settings — partial update (read-modify-write)
+/// PATCH /api/settings — partial update (read-modify-write).
+/// Returns the effective settings after clamping.
 #[utoipa::path(patch, path = "/api/settings", tag = "settings",
-    responses((status = 200, description = "Updated settings", body = AppSettings))
+    responses(
+        (status = 200, description = "Updated settings", body = AppSettings),
+        (status = 400, description = "Invalid settings value")
+    )
 )]
 pub async fn update_settings(
     State(state): State<AppState>,
     axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
-    Json(patch): Json<PartialSettings>,
-) -> Result<Json<AppSettings>, StatusCode> {
-    // Limit string field sizes to prevent uncontrolled memory allocation
-    if patch
-        .welcome_message
-        .as_ref()
-        .is_some_and(|s| s.len() > 10_000)
-        || patch.default_model.as_ref().is_some_and(|s| s.len() > 200)
-        || patch.response_style.as_ref().is_some_and(|s| {
-            !["concise", "balanced", "detailed", "technical"].contains(&s.as_str())
-        })
-        || patch
-            .thinking_level
-            .as_ref()
-            .is_some_and(|s| !["none", "minimal", "low", "medium", "high"].contains(&s.as_str()))
-    {
-        return Err(StatusCode::PAYLOAD_TOO_LARGE);
-    }
+    Json(mut patch): Json<PartialSettings>,
+) -> Result<Json<AppSettings>, ApiError> {
+    validate_settings_patch(&mut patch)?;
+
+    let row = merge_settings(&state, patch).await?;
+
+    crate::audit::log_audit(
+        &state.db,
+        "update_settings",
+        serde_json::json!({
+            "temperature": row.temperature,
+            "max_tokens": row.max_tokens,
+            "default_model": row.default_model,
+            "language": row.language,
+            "theme": row.theme,
+            "top_p": row.top_p,
+            "response_style": row.response_style,
+            "max_iterations": row.max_iterations,
+            "thinking_level": row.thinking_level,
+            "working_directory": row.working_directory,
+            "safety_level": row.safety_level,
+            "rating_alert_threshold": row.rating_alert_threshold,
+            "rating_alert_min_count": row.rating_alert_min_count,
+            "max_write_bytes": row.max_write_bytes,
+            "max_tool_display_bytes": row.max_tool_display_bytes,
+            "gemini_max_retries": row.gemini_max_retries,
+            "gemini_backoff_base_ms": row.gemini_backoff_base_ms,
+            "gemini_backoff_jitter_ms": row.gemini_backoff_jitter_ms,
+        }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
 
+    Ok(Json(super::row_to_settings(row)))
+}
+
+/// Read-modify-write `patch` onto the active settings row (id=1).
+async fn merge_settings(state: &AppState, patch: PartialSettings) -> Result<SettingsRow, ApiError> {
     let current = sqlx::query_as::<_, SettingsRow>(
         "SELECT temperature, max_tokens, default_model, language, theme, welcome_message, \
-         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model \
+         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model, \
+         stop_on_tool_error, command_allowlist, extra_blocked_patterns, fallback_models, max_agent_call_depth, \
+         history_window, history_truncate_keep, safety_level, rating_alert_threshold, rating_alert_min_count, \
+         max_write_bytes, max_tool_display_bytes, gemini_max_retries, gemini_backoff_base_ms, \
+         gemini_backoff_jitter_ms \
          FROM gh_settings WHERE id = 1",
     )
     .fetch_one(&state.db)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
 
     let temperature = patch.temperature.unwrap_or(current.temperature);
     let max_tokens = patch
@@ -81,6 +276,9 @@ pub async fn update_settings(
     let response_style = patch.response_style.unwrap_or(current.response_style);
     let max_iterations = patch.max_iterations.unwrap_or(current.max_iterations);
     let thinking_level = patch.thinking_level.unwrap_or(current.thinking_level);
+    if let Some(wd) = patch.working_directory.as_deref() {
+        super::check_working_directory(wd, patch.allow_nonexistent).await?;
+    }
     let working_directory = patch.working_directory.unwrap_or(current.working_directory);
     // Empty string = clear force_model (set to NULL); absent = keep current; model ID = force
     let force_model = match patch.force_model {
@@ -88,19 +286,59 @@ pub async fn update_settings(
         Some(ref s) if s.is_empty() => None,
         Some(s) => Some(s),
     };
-
-    // Validate working_directory if non-empty
-    if !working_directory.is_empty() && !std::path::Path::new(&working_directory).is_dir() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    let stop_on_tool_error = patch
+        .stop_on_tool_error
+        .unwrap_or(current.stop_on_tool_error);
+    let command_allowlist = patch.command_allowlist.unwrap_or(current.command_allowlist);
+    let extra_blocked_patterns = patch
+        .extra_blocked_patterns
+        .unwrap_or(current.extra_blocked_patterns);
+    let fallback_models = patch.fallback_models.unwrap_or(current.fallback_models);
+    let max_agent_call_depth = patch
+        .max_agent_call_depth
+        .unwrap_or(current.max_agent_call_depth);
+    let history_window = patch.history_window.unwrap_or(current.history_window);
+    let history_truncate_keep = patch
+        .history_truncate_keep
+        .unwrap_or(current.history_truncate_keep);
+    let safety_level = patch.safety_level.unwrap_or(current.safety_level);
+    let rating_alert_threshold = patch
+        .rating_alert_threshold
+        .unwrap_or(current.rating_alert_threshold);
+    let rating_alert_min_count = patch
+        .rating_alert_min_count
+        .unwrap_or(current.rating_alert_min_count);
+    let max_write_bytes = patch.max_write_bytes.unwrap_or(current.max_write_bytes);
+    let max_tool_display_bytes = patch
+        .max_tool_display_bytes
+        .unwrap_or(current.max_tool_display_bytes);
+    let gemini_max_retries = patch
+        .gemini_max_retries
+        .unwrap_or(current.gemini_max_retries);
+    let gemini_backoff_base_ms = patch
+        .gemini_backoff_base_ms
+        .unwrap_or(current.gemini_backoff_base_ms);
+    let gemini_backoff_jitter_ms = patch
+        .gemini_backoff_jitter_ms
+        .unwrap_or(current.gemini_backoff_jitter_ms);
 
     let row = sqlx::query_as::<_, SettingsRow>(
         "UPDATE gh_settings SET temperature=$1, max_tokens=$2, default_model=$3, \
          language=$4, theme=$5, welcome_message=$6, use_docker_sandbox=$7, \
          top_p=$8, response_style=$9, max_iterations=$10, thinking_level=$11, \
-         working_directory=$12, force_model=$13, updated_at=NOW() WHERE id=1 \
+         working_directory=$12, force_model=$13, stop_on_tool_error=$14, \
+         command_allowlist=$15, extra_blocked_patterns=$16, fallback_models=$17, \
+         max_agent_call_depth=$18, history_window=$19, history_truncate_keep=$20, \
+         safety_level=$21, rating_alert_threshold=$22, rating_alert_min_count=$23, \
+         max_write_bytes=$24, max_tool_display_bytes=$25, \
+         gemini_max_retries=$26, gemini_backoff_base_ms=$27, gemini_backoff_jitter_ms=$28, \
+         updated_at=NOW() WHERE id=1 \
          RETURNING temperature, max_tokens, default_model, language, theme, welcome_message, \
-         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model",
+         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model, \
+         stop_on_tool_error, command_allowlist, extra_blocked_patterns, fallback_models, max_agent_call_depth, \
+         history_window, history_truncate_keep, safety_level, rating_alert_threshold, rating_alert_min_count, \
+         max_write_bytes, max_tool_display_bytes, gemini_max_retries, gemini_backoff_base_ms, \
+         gemini_backoff_jitter_ms",
     )
     .bind(temperature)
     .bind(max_tokens)
@@ -115,30 +353,29 @@ pub async fn update_settings(
     .bind(&thinking_level)
     .bind(&working_directory)
     .bind(force_model.as_deref())
+    .bind(stop_on_tool_error)
+    .bind(&command_allowlist)
+    .bind(&extra_blocked_patterns)
+    .bind(&fallback_models)
+    .bind(max_agent_call_depth)
+    .bind(history_window)
+    .bind(history_truncate_keep)
+    .bind(&safety_level)
+    .bind(rating_alert_threshold)
+    .bind(rating_alert_min_count)
+    .bind(max_write_bytes)
+    .bind(max_tool_display_bytes)
+    .bind(gemini_max_retries)
+    .bind(gemini_backoff_base_ms)
+    .bind(gemini_backoff_jitter_ms)
     .fetch_one(&state.db)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    crate::audit::log_audit(
-        &state.db,
-        "update_settings",
-        serde_json::json!({
-            "temperature": temperature,
-            "max_tokens": max_tokens,
-            "default_model": default_model,
-            "language": language,
-            "theme": theme,
-            "top_p": top_p,
-            "response_style": response_style,
-            "max_iterations": max_iterations,
-            "thinking_level": thinking_level,
-            "working_directory": working_directory,
-        }),
-        Some(&addr.ip().to_string()),
-    )
-    .await;
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
 
-    Ok(Json(super::row_to_settings(row)))
+    // Language / model / working directory feed the cached system prompts
+    state.invalidate_settings().await;
+    state.clear_prompt_cache().await;
+    Ok(row)
 }
 
 /// POST /api/settings/reset — restore defaults (picks best model from cache)
@@ -155,14 +392,190 @@ pub async fn reset_settings(
          default_model=$1, language='en', theme='dark', \
          welcome_message='', use_docker_sandbox=FALSE, \
          top_p=0.95, response_style='balanced', max_iterations=20, \
-         thinking_level='medium', working_directory='', force_model=NULL, updated_at=NOW() WHERE id=1 \
+         thinking_level='medium', working_directory='', force_model=NULL, \
+         stop_on_tool_error=FALSE, command_allowlist='{}', \
+         extra_blocked_patterns='{}', fallback_models='{}', max_agent_call_depth=3, \
+         history_window=20, history_truncate_keep=6, safety_level='default', \
+         rating_alert_threshold=3.0, rating_alert_min_count=5, max_write_bytes=5242880, \
+         max_tool_display_bytes=16384, gemini_max_retries=3, gemini_backoff_base_ms=1000, \
+         gemini_backoff_jitter_ms=500, \
+         updated_at=NOW() WHERE id=1 \
          RETURNING temperature, max_tokens, default_model, language, theme, welcome_message, \
-         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model",
+         use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model, \
+         stop_on_tool_error, command_allowlist, extra_blocked_patterns, fallback_models, max_agent_call_depth, \
+         history_window, history_truncate_keep, safety_level, rating_alert_threshold, rating_alert_min_count, \
+         max_write_bytes, max_tool_display_bytes, gemini_max_retries, gemini_backoff_base_ms, \
+         gemini_backoff_jitter_ms",
     )
     .bind(&best_model)
     .fetch_one(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    state.invalidate_settings().await;
+    state.clear_prompt_cache().await;
+    Ok(Json(super::row_to_settings(row)))
+}
+
+/// DELETE /api/settings/{field} — reset one setting to its default
+#[utoipa::path(delete, path = "/api/settings/{field}", tag = "settings",
+    params(("field" = String, Path, description = "Settings field name, e.g. working_directory")),
+    responses(
+        (status = 200, description = "Settings with the field reset", body = AppSettings),
+        (status = 400, description = "Unknown settings field")
+    )
+)]
+pub async fn reset_setting_field(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    Path(field): Path<String>,
+) -> Result<Json<AppSettings>, ApiError> {
+    let defaults = AppSettings {
+        default_model: crate::model_registry::get_model_id(&state, "chat").await,
+        ..AppSettings::default()
+    };
+    let patch = default_field_patch(&field, &defaults)
+        .ok_or_else(|| ApiError::BadRequest(format!("Unknown settings field '{}'", field)))?;
+
+    let row = merge_settings(&state, patch).await?;
+
+    crate::audit::log_audit(
+        &state.db,
+        "reset_setting",
+        serde_json::json!({ "field": field }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
+
+    Ok(Json(super::row_to_settings(row)))
+}
+
+/// A patch that sets only `field` to its value in `defaults`, or `None` when
+/// `field` is not a settings field.
+pub(crate) fn default_field_patch(field: &str, defaults: &AppSettings) -> Option<PartialSettings> {
+    let mut value = serde_json::to_value(defaults).ok()?.get(field)?.clone();
+    // A PATCH treats null as "keep"; force_model is cleared with "".
+    if value.is_null() {
+        value = serde_json::Value::String(String::new());
+    }
+    serde_json::from_value(serde_json::json!({ field: value })).ok()
+}
+
+// ============================================================================
+// Settings profiles
+// ============================================================================
+
+/// GET /api/settings/profiles
+#[utoipa::path(get, path = "/api/settings/profiles", tag = "settings",
+    responses((status = 200, description = "Saved settings profiles", body = Vec<SettingsProfile>))
+)]
+pub async fn list_settings_profiles(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SettingsProfile>>, StatusCode> {
+    let rows = sqlx::query_as::<_, SettingsProfileRow>(
+        "SELECT name, settings, created_at FROM gh_settings_profiles ORDER BY name",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(rows.into_iter().map(super::row_to_profile).collect()))
+}
+
+/// POST /api/settings/profiles — save a named preset
+#[utoipa::path(post, path = "/api/settings/profiles", tag = "settings",
+    request_body = CreateSettingsProfileRequest,
+    responses(
+        (status = 201, description = "Profile created", body = SettingsProfile),
+        (status = 400, description = "Invalid name or settings values"),
+        (status = 409, description = "Profile name already exists")
+    )
+)]
+pub async fn create_settings_profile(
+    State(state): State<AppState>,
+    Json(mut req): Json<CreateSettingsProfileRequest>,
+) -> Result<(StatusCode, Json<SettingsProfile>), StatusCode> {
+    if !super::is_valid_profile_name(&req.name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    validate_settings_patch(&mut req.settings).map_err(|e| e.status_code())?;
+
+    let row = sqlx::query_as::<_, SettingsProfileRow>(
+        "INSERT INTO gh_settings_profiles (name, settings) VALUES ($1, $2) \
+         ON CONFLICT (name) DO NOTHING \
+         RETURNING name, settings, created_at",
+    )
+    .bind(&req.name)
+    .bind(super::profile_settings_json(&req.settings))
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::CONFLICT)?;
+
+    Ok((StatusCode::CREATED, Json(super::row_to_profile(row))))
+}
+
+/// POST /api/settings/profiles/{name}/apply — copy a profile into the active settings
+#[utoipa::path(post, path = "/api/settings/profiles/{name}/apply", tag = "settings",
+    params(("name" = String, Path, description = "Profile name")),
+    responses(
+        (status = 200, description = "Settings after applying the profile", body = AppSettings),
+        (status = 404, description = "Profile not found")
+    )
+)]
+pub async fn apply_settings_profile(
+    State(state): State<AppState>,
+    axum::extract::ConnectInfo(addr): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    Path(name): Path<String>,
+) -> Result<Json<AppSettings>, StatusCode> {
+    let profile = sqlx::query_as::<_, SettingsProfileRow>(
+        "SELECT name, settings, created_at FROM gh_settings_profiles WHERE name = $1",
+    )
+    .bind(&name)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let patch: PartialSettings = serde_json::from_value(profile.settings.clone()).map_err(|e| {
+        tracing::error!("settings profile '{}' is corrupt: {}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let row = merge_settings(&state, patch)
+        .await
+        .map_err(|e| e.status_code())?;
+
+    crate::audit::log_audit(
+        &state.db,
+        "apply_settings_profile",
+        serde_json::json!({ "profile": name, "settings": profile.settings }),
+        Some(&addr.ip().to_string()),
+    )
+    .await;
+
     Ok(Json(super::row_to_settings(row)))
 }
+
+/// DELETE /api/settings/profiles/{name}
+#[utoipa::path(delete, path = "/api/settings/profiles/{name}", tag = "settings",
+    params(("name" = String, Path, description = "Profile name")),
+    responses(
+        (status = 204, description = "Profile deleted"),
+        (status = 404, description = "Profile not found")
+    )
+)]
+pub async fn delete_settings_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query("DELETE FROM gh_settings_profiles WHERE name = $1")
+        .bind(&name)
+        .execute(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
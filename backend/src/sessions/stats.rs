@@ -0,0 +1,221 @@
+//! Per-session analytics: message counts, date range, agents involved and the
+//! most used tools, aggregated from `gh_chat_messages` — plus the same kind of
+//! summary across all sessions for dashboards (`GET /api/history/stats`).
+//!
+//! Tool usage is counted from the `**🔧 Tool:** `name`` headers the streaming
+//! loop writes into assistant messages — the tool-call audit log only covers
+//! mutating tools, so it would miss reads and searches.
+
+use std::collections::HashMap;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use crate::error::ApiError;
+use crate::models::{
+    DailyMessageCount, HistoryStats, ModelMessageCount, SessionAgentUsage, SessionStats,
+    SessionToolUsage,
+};
+use crate::state::AppState;
+
+use super::HistoryStatsParams;
+
+/// Tools listed in `top_tools`.
+const TOP_TOOLS_LIMIT: i64 = 10;
+/// Postgres regex matching the tool header in a stored assistant message.
+const TOOL_HEADER_PATTERN: &str = r"\*\*🔧 Tool:\*\* `([^`]+)`";
+/// `?days=` window of `GET /api/history/stats`.
+const DEFAULT_HISTORY_STATS_DAYS: i32 = 30;
+const MAX_HISTORY_STATS_DAYS: i32 = 365;
+
+/// GET /api/sessions/:id/stats
+#[utoipa::path(get, path = "/api/sessions/{id}/stats", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "Session activity summary", body = SessionStats),
+        (status = 400, description = "Invalid session ID"),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn get_session_stats(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SessionStats>, ApiError> {
+    let session_id: uuid::Uuid = id
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid session ID '{}'", id)))?;
+    sqlx::query("SELECT 1 FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ApiError::NotFound("Session not found".into()))?;
+
+    let (message_count, user_messages, assistant_messages, first, last): (
+        i64,
+        i64,
+        i64,
+        Option<DateTime<Utc>>,
+        Option<DateTime<Utc>>,
+    ) = sqlx::query_as(
+        "SELECT COUNT(*), \
+                COUNT(*) FILTER (WHERE role = 'user'), \
+                COUNT(*) FILTER (WHERE role = 'assistant'), \
+                MIN(created_at), MAX(created_at) \
+         FROM gh_chat_messages WHERE session_id = $1",
+    )
+    .bind(session_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    // The `agent` column also carries free-form routing notes on some rows,
+    // so only count values that name a known agent.
+    let agent_ids: Vec<String> = state
+        .agents
+        .read()
+        .await
+        .iter()
+        .map(|a| a.id.clone())
+        .collect();
+    let agents: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT agent, COUNT(*) FROM gh_chat_messages \
+         WHERE session_id = $1 AND agent = ANY($2) \
+         GROUP BY agent ORDER BY COUNT(*) DESC, agent",
+    )
+    .bind(session_id)
+    .bind(&agent_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let tools: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT m[1], COUNT(*) \
+         FROM gh_chat_messages, regexp_matches(content, $2, 'g') AS m \
+         WHERE session_id = $1 AND role = 'assistant' \
+         GROUP BY m[1] ORDER BY COUNT(*) DESC, m[1] LIMIT $3",
+    )
+    .bind(session_id)
+    .bind(TOOL_HEADER_PATTERN)
+    .bind(TOP_TOOLS_LIMIT)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(SessionStats {
+        session_id: session_id.to_string(),
+        message_count,
+        user_messages,
+        assistant_messages,
+        first_message_at: first.map(|t| t.to_rfc3339()),
+        last_message_at: last.map(|t| t.to_rfc3339()),
+        agents: agents
+            .into_iter()
+            .map(|(agent, messages)| SessionAgentUsage { agent, messages })
+            .collect(),
+        top_tools: tools
+            .into_iter()
+            .map(|(tool, calls)| SessionToolUsage { tool, calls })
+            .collect(),
+    }))
+}
+
+/// GET /api/history/stats?days=30
+#[utoipa::path(get, path = "/api/history/stats", tag = "history",
+    params(("days" = Option<i32>, Query, description = "Window in days (default 30, max 365)")),
+    responses((status = 200, description = "Message activity across all sessions", body = HistoryStats))
+)]
+pub async fn get_history_stats(
+    State(state): State<AppState>,
+    Query(params): Query<HistoryStatsParams>,
+) -> Result<Json<HistoryStats>, ApiError> {
+    let days = params
+        .days
+        .unwrap_or(DEFAULT_HISTORY_STATS_DAYS)
+        .clamp(1, MAX_HISTORY_STATS_DAYS);
+    // Whole UTC days, today included
+    let first_day = Utc::now().date_naive() - chrono::Days::new(days as u64 - 1);
+    let since = first_day.and_time(NaiveTime::MIN).and_utc();
+
+    let total_messages: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM gh_chat_messages")
+        .fetch_one(&state.db)
+        .await
+        .map_err(db_error)?;
+
+    // Every query below is a range scan on idx_gh_chat_created
+    let per_day: Vec<(NaiveDate, i64)> = sqlx::query_as(
+        "SELECT (created_at AT TIME ZONE 'UTC')::date AS day, COUNT(*) \
+         FROM gh_chat_messages WHERE created_at >= $1 GROUP BY day",
+    )
+    .bind(since)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    // Same rule as the per-session stats: only values naming a known agent
+    let agent_ids: Vec<String> = state
+        .agents
+        .read()
+        .await
+        .iter()
+        .map(|a| a.id.clone())
+        .collect();
+    let agents: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT agent, COUNT(*) FROM gh_chat_messages \
+         WHERE created_at >= $1 AND agent = ANY($2) \
+         GROUP BY agent ORDER BY COUNT(*) DESC, agent",
+    )
+    .bind(since)
+    .bind(&agent_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let models: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT model, COUNT(*) FROM gh_chat_messages \
+         WHERE created_at >= $1 AND role = 'assistant' AND model <> '' \
+         GROUP BY model ORDER BY COUNT(*) DESC, model",
+    )
+    .bind(since)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let daily = daily_counts(first_day, days, &per_day);
+    Ok(Json(HistoryStats {
+        days,
+        total_messages,
+        window_messages: daily.iter().map(|d| d.messages).sum(),
+        daily,
+        agents: agents
+            .into_iter()
+            .map(|(agent, messages)| SessionAgentUsage { agent, messages })
+            .collect(),
+        models: models
+            .into_iter()
+            .map(|(model, messages)| ModelMessageCount { model, messages })
+            .collect(),
+    }))
+}
+
+/// `days` consecutive entries from `first_day`, zero where nothing was counted.
+fn daily_counts(
+    first_day: NaiveDate,
+    days: i32,
+    counts: &[(NaiveDate, i64)],
+) -> Vec<DailyMessageCount> {
+    let counts: HashMap<NaiveDate, i64> = counts.iter().copied().collect();
+    first_day
+        .iter_days()
+        .take(days.max(0) as usize)
+        .map(|day| DailyMessageCount {
+            date: day.format("%Y-%m-%d").to_string(),
+            messages: counts.get(&day).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    ApiError::Internal(format!("stats query failed: {}", e))
+}
@@ -0,0 +1,160 @@
+//! Session tag handlers: add, remove and list free-form tags used to
+//! organize sessions (`GET /api/sessions?tag=` filters by them).
+
+use axum::Json;
+use axum::extract::{Path, State};
+
+use crate::error::ApiError;
+use crate::models::{AddSessionTagRequest, SessionTagsResponse};
+use crate::state::AppState;
+
+use super::MAX_TAG_LENGTH;
+
+/// Max distinct tags on one session.
+const MAX_TAGS_PER_SESSION: i64 = 20;
+
+/// Trim a tag and check it is non-empty and within `MAX_TAG_LENGTH`.
+pub(crate) fn normalize_tag(tag: &str) -> Result<String, ApiError> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(ApiError::BadRequest("Tag cannot be empty".into()));
+    }
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "Tag exceeds {} characters",
+            MAX_TAG_LENGTH
+        )));
+    }
+    Ok(tag.to_string())
+}
+
+/// GET /api/sessions/:id/tags
+#[utoipa::path(get, path = "/api/sessions/{id}/tags", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses(
+        (status = 200, description = "Session tags", body = SessionTagsResponse),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn list_session_tags(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SessionTagsResponse>, ApiError> {
+    let session_id = parse_session_id(&id)?;
+    ensure_session_exists(&state, session_id).await?;
+    tags_response(&state, session_id).await
+}
+
+/// POST /api/sessions/:id/tags — adding a tag the session already has is a no-op
+#[utoipa::path(post, path = "/api/sessions/{id}/tags", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    request_body = AddSessionTagRequest,
+    responses(
+        (status = 200, description = "Updated session tags", body = SessionTagsResponse),
+        (status = 400, description = "Empty or too long tag, or tag limit reached"),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn add_session_tag(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<AddSessionTagRequest>,
+) -> Result<Json<SessionTagsResponse>, ApiError> {
+    let session_id = parse_session_id(&id)?;
+    let tag = normalize_tag(&req.tag)?;
+
+    // Locking the session row serializes concurrent adds, so the count
+    // check below cannot let a session past the limit.
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    sqlx::query("SELECT 1 FROM gh_sessions WHERE id = $1 FOR UPDATE")
+        .bind(session_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ApiError::NotFound("Session not found".into()))?;
+    let inserted = sqlx::query(
+        "INSERT INTO gh_session_tags (session_id, tag) \
+         SELECT $1, $2 WHERE (SELECT COUNT(*) FROM gh_session_tags WHERE session_id = $1) < $3 \
+         ON CONFLICT (session_id, tag) DO NOTHING",
+    )
+    .bind(session_id)
+    .bind(&tag)
+    .bind(MAX_TAGS_PER_SESSION)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .rows_affected();
+    tx.commit().await.map_err(db_error)?;
+
+    let response = tags_response(&state, session_id).await?;
+    if inserted == 0 && !response.tags.contains(&tag) {
+        return Err(ApiError::BadRequest(format!(
+            "A session can have at most {} tags",
+            MAX_TAGS_PER_SESSION
+        )));
+    }
+    Ok(response)
+}
+
+/// DELETE /api/sessions/:id/tags/:tag — idempotent
+#[utoipa::path(delete, path = "/api/sessions/{id}/tags/{tag}", tag = "sessions",
+    params(
+        ("id" = String, Path, description = "Session UUID"),
+        ("tag" = String, Path, description = "Tag to remove (URL-encoded)"),
+    ),
+    responses(
+        (status = 200, description = "Updated session tags", body = SessionTagsResponse),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn remove_session_tag(
+    State(state): State<AppState>,
+    Path((id, tag)): Path<(String, String)>,
+) -> Result<Json<SessionTagsResponse>, ApiError> {
+    let session_id = parse_session_id(&id)?;
+    ensure_session_exists(&state, session_id).await?;
+
+    sqlx::query("DELETE FROM gh_session_tags WHERE session_id = $1 AND tag = $2")
+        .bind(session_id)
+        .bind(tag.trim())
+        .execute(&state.db)
+        .await
+        .map_err(db_error)?;
+
+    tags_response(&state, session_id).await
+}
+
+fn parse_session_id(id: &str) -> Result<uuid::Uuid, ApiError> {
+    id.parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid session ID '{}'", id)))
+}
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    ApiError::Internal(format!("session tags query failed: {}", e))
+}
+
+async fn ensure_session_exists(state: &AppState, session_id: uuid::Uuid) -> Result<(), ApiError> {
+    sqlx::query("SELECT 1 FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ApiError::NotFound("Session not found".into()))?;
+    Ok(())
+}
+
+async fn tags_response(
+    state: &AppState,
+    session_id: uuid::Uuid,
+) -> Result<Json<SessionTagsResponse>, ApiError> {
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT tag FROM gh_session_tags WHERE session_id = $1 ORDER BY tag")
+            .bind(session_id)
+            .fetch_all(&state.db)
+            .await
+            .map_err(db_error)?;
+    Ok(Json(SessionTagsResponse {
+        session_id: session_id.to_string(),
+        tags,
+    }))
+}
@@ -11,10 +11,11 @@ use sqlx::PgPool;
 use tokio::sync::broadcast;
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
 use crate::mcp::client::McpClientManager;
-use crate::model_registry::ModelCache;
-use crate::models::WitcherAgent;
+use crate::model_registry::{ModelCache, ModelHealthCache};
+use crate::models::{SettingsRow, WitcherAgent};
 
 // ── Log Ring Buffer — Jaskier Shared Pattern ────────────────────────────────
 /// In-memory ring buffer for backend log entries (last N events).
@@ -116,16 +117,33 @@ pub struct CircuitBreaker {
     /// Instant when the circuit was last tripped (OPEN). Protected by RwLock
     /// because `Instant` is not atomic but writes are rare (only on state change).
     last_failure_time: RwLock<Option<Instant>>,
+    /// Times the circuit has tripped since startup.
+    trips: AtomicU32,
     /// Human-readable label for log messages.
     provider: String,
 }
 
+/// Point-in-time view of a breaker, reported by `GET /api/admin/circuit`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CircuitSnapshot {
+    pub provider: String,
+    /// "closed", "open" or "half_open"
+    pub state: &'static str,
+    pub consecutive_failures: u32,
+    pub failure_threshold: u32,
+    pub trips: u32,
+    pub last_tripped_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Seconds until an OPEN circuit lets a probe request through
+    pub retry_in_secs: Option<u64>,
+}
+
 impl CircuitBreaker {
     pub fn new(provider: &str) -> Self {
         Self {
             state: AtomicU32::new(STATE_CLOSED),
             consecutive_failures: AtomicU32::new(0),
             last_failure_time: RwLock::new(None),
+            trips: AtomicU32::new(0),
             provider: provider.to_string(),
         }
     }
@@ -193,6 +211,7 @@ impl CircuitBreaker {
             let prev = self.state.swap(STATE_OPEN, Ordering::Release);
             *self.last_failure_time.write().await = Some(Instant::now());
             if prev != STATE_OPEN {
+                self.trips.fetch_add(1, Ordering::AcqRel);
                 tracing::warn!(
                     "circuit_breaker[{}]: TRIPPED after {} consecutive failures — \
                      failing fast for {}s",
@@ -203,6 +222,101 @@ impl CircuitBreaker {
             }
         }
     }
+
+    /// Closed and never failed — indistinguishable from a fresh breaker.
+    fn is_pristine(&self) -> bool {
+        self.state.load(Ordering::Acquire) == STATE_CLOSED
+            && self.consecutive_failures.load(Ordering::Acquire) == 0
+            && self.trips.load(Ordering::Acquire) == 0
+    }
+
+    /// Whether the circuit is currently tripped (OPEN).
+    pub fn is_open(&self) -> bool {
+        self.state.load(Ordering::Acquire) == STATE_OPEN
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Force the circuit CLOSED — for operators who fixed the cause (e.g.
+    /// rotated a key) and don't want to wait out the recovery window.
+    /// Returns whether it was OPEN or HALF_OPEN.
+    pub fn reset(&self) -> bool {
+        let prev = self.state.swap(STATE_CLOSED, Ordering::Release);
+        self.consecutive_failures.store(0, Ordering::Release);
+        if prev != STATE_CLOSED {
+            tracing::info!(
+                "circuit_breaker[{}]: {} -> CLOSED (manual reset)",
+                self.provider,
+                if prev == STATE_OPEN {
+                    "OPEN"
+                } else {
+                    "HALF_OPEN"
+                }
+            );
+        }
+        prev != STATE_CLOSED
+    }
+
+    pub async fn snapshot(&self) -> CircuitSnapshot {
+        let state = self.state.load(Ordering::Acquire);
+        let last_failure = *self.last_failure_time.read().await;
+        CircuitSnapshot {
+            provider: self.provider.clone(),
+            state: state_name(state),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Acquire),
+            failure_threshold: FAILURE_THRESHOLD,
+            trips: self.trips.load(Ordering::Acquire),
+            last_tripped_at: last_failure.and_then(|t| {
+                chrono::Duration::from_std(t.elapsed())
+                    .ok()
+                    .map(|ago| chrono::Utc::now() - ago)
+            }),
+            retry_in_secs: (state == STATE_OPEN).then(|| {
+                last_failure
+                    .map(|t| RECOVERY_TIMEOUT_SECS.saturating_sub(t.elapsed().as_secs()))
+                    .unwrap_or(RECOVERY_TIMEOUT_SECS)
+            }),
+        }
+    }
+}
+
+/// Cap on per-model breakers, since model names come from clients.
+const MAX_MODEL_CIRCUITS: usize = 64;
+
+/// Breaker for `model` from `circuits`, inserted if missing. When the map is
+/// full, pristine breakers nobody holds are evicted first; if none can go,
+/// the caller gets an untracked breaker so the map stays bounded.
+fn model_circuit_entry(
+    circuits: &mut HashMap<String, Arc<CircuitBreaker>>,
+    model: &str,
+) -> Arc<CircuitBreaker> {
+    if let Some(circuit) = circuits.get(model) {
+        return circuit.clone();
+    }
+    let circuit = Arc::new(CircuitBreaker::new(&format!("gemini:{}", model)));
+    if circuits.len() >= MAX_MODEL_CIRCUITS {
+        circuits.retain(|_, c| Arc::strong_count(c) > 1 || !c.is_pristine());
+        if circuits.len() >= MAX_MODEL_CIRCUITS {
+            tracing::warn!(
+                "model circuits full ({}), not tracking '{}'",
+                MAX_MODEL_CIRCUITS,
+                model
+            );
+            return circuit;
+        }
+    }
+    circuits.insert(model.to_string(), circuit.clone());
+    circuit
+}
+
+fn state_name(state: u32) -> &'static str {
+    match state {
+        STATE_CLOSED => "closed",
+        STATE_OPEN => "open",
+        _ => "half_open",
+    }
 }
 
 // ── Shared: SystemSnapshot ───────────────────────────────────────────────────
@@ -238,7 +352,87 @@ pub struct OAuthPkceState {
     pub state: String,
 }
 
+// ── Provider endpoints ──────────────────────────────────────────────────────
+pub const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+pub const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+
+/// Provider API bases, read once at startup. `GEMINI_BASE_URL` /
+/// `ANTHROPIC_BASE_URL` route traffic through a proxy or gateway (Vertex
+/// proxy, LiteLLM); a path prefix is kept, a trailing `/` is dropped.
+#[derive(Debug, Clone)]
+pub struct ProviderEndpoints {
+    pub gemini: String,
+    pub anthropic: String,
+}
+
+impl Default for ProviderEndpoints {
+    fn default() -> Self {
+        Self {
+            gemini: DEFAULT_GEMINI_BASE_URL.to_string(),
+            anthropic: DEFAULT_ANTHROPIC_BASE_URL.to_string(),
+        }
+    }
+}
+
+impl ProviderEndpoints {
+    pub fn from_env() -> Self {
+        Self {
+            gemini: base_url_from_env("GEMINI_BASE_URL", DEFAULT_GEMINI_BASE_URL),
+            anthropic: base_url_from_env("ANTHROPIC_BASE_URL", DEFAULT_ANTHROPIC_BASE_URL),
+        }
+    }
+
+    /// `{gemini}/v1beta/models`
+    pub fn gemini_models(&self) -> String {
+        format!("{}/v1beta/models", self.gemini)
+    }
+
+    /// `{gemini}/v1beta/models/{model}:{method}`, e.g. `generateContent`.
+    pub fn gemini_model(&self, model: &str, method: &str) -> String {
+        format!("{}/v1beta/models/{}:{}", self.gemini, model, method)
+    }
+
+    /// `{anthropic}/v1/models`
+    pub fn anthropic_models(&self) -> String {
+        format!("{}/v1/models", self.anthropic)
+    }
+}
+
+/// Validate a base URL override: HTTPS only (credentials travel in headers),
+/// no query or fragment.
+pub(crate) fn parse_base_url(raw: &str) -> Result<String, String> {
+    let url = reqwest::Url::parse(raw.trim()).map_err(|e| format!("invalid URL: {}", e))?;
+    if url.scheme() != "https" {
+        return Err("API credentials require HTTPS".into());
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err("query strings and fragments are not allowed".into());
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+fn base_url_from_env(var: &str, default: &str) -> String {
+    let Some(raw) = std::env::var(var).ok().filter(|s| !s.trim().is_empty()) else {
+        return default.to_string();
+    };
+    match parse_base_url(&raw) {
+        Ok(url) => {
+            tracing::info!("{} override: {}", var, url);
+            url
+        }
+        Err(e) => {
+            // The raw value may embed credentials, so only the error is logged
+            tracing::error!("Ignoring {}: {}; using {}", var, e, default);
+            default.to_string()
+        }
+    }
+}
+
 // ── Shared: AppState (project-specific fields vary) ─────────────────────────
+/// How long `AppState::settings` trusts its cached row, so edits made directly
+/// in the database (not through the API) still propagate.
+const SETTINGS_CACHE_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(30);
+
 /// Central application state. Clone-friendly — PgPool and Arc are both Clone.
 #[derive(Clone)]
 pub struct AppState {
@@ -246,6 +440,8 @@ pub struct AppState {
     pub agents: Arc<RwLock<Vec<WitcherAgent>>>,
     pub runtime: Arc<RwLock<RuntimeState>>,
     pub model_cache: Arc<RwLock<ModelCache>>,
+    /// Last `/api/models/health` probe sweep (60s TTL).
+    pub model_health: Arc<tokio::sync::Mutex<ModelHealthCache>>,
     pub start_time: Instant,
     pub client: Client,
     pub oauth_pkce: Arc<RwLock<Option<OAuthPkceState>>>,
@@ -257,9 +453,14 @@ pub struct AppState {
     pub auth_secret: Option<String>,
     /// Circuit breaker for the Gemini API provider.
     pub gemini_circuit: Arc<CircuitBreaker>,
+    /// Per-model circuit breakers consulted when walking the fallback chain.
+    pub model_circuits: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
     /// Cached system prompts keyed by "agent_id:language:model".
     /// Cleared on agent refresh for byte-identical Gemini API requests.
     pub prompt_cache: Arc<RwLock<HashMap<String, String>>>,
+    /// Settings row (id=1) as last read, with the time it was read. Cleared on
+    /// settings writes; re-read after `SETTINGS_CACHE_MAX_AGE` regardless.
+    pub settings_cache: Arc<RwLock<Option<(Instant, SettingsRow)>>>,
     /// A2A — cancellation tokens for running tasks (task_id → token).
     pub a2a_cancel_tokens: Arc<RwLock<HashMap<String, CancellationToken>>>,
     /// `false` when OAuth token was rejected by Gemini API (401/403).
@@ -279,6 +480,8 @@ pub struct AppState {
     pub github_oauth_state: Arc<RwLock<Option<String>>>,
     /// Vercel OAuth state (CSRF protection).
     pub vercel_oauth_state: Arc<RwLock<Option<String>>>,
+    /// Gemini / Anthropic API bases (`GEMINI_BASE_URL`, `ANTHROPIC_BASE_URL`).
+    pub providers: ProviderEndpoints,
     /// Optional Jaskier Knowledge API URL (e.g. http://jaskier-knowledge.internal:8083).
     pub knowledge_api_url: Option<String>,
     /// Optional auth secret for the Knowledge API.
@@ -289,6 +492,23 @@ pub struct AppState {
     pub browser_proxy_status: Arc<RwLock<crate::browser_proxy::BrowserProxyStatus>>,
     /// Ring buffer of proxy health status change events (last 50).
     pub browser_proxy_history: Arc<crate::browser_proxy::ProxyHealthHistory>,
+    /// Last DB pool health check, updated by the watchdog every 15s.
+    pub db_health: Arc<RwLock<crate::models::DbHealthStatus>>,
+    /// Short-lived LRU of `fetch_webpage` results (WEB_CACHE_MAX_ENTRIES / WEB_CACHE_TTL_SECS).
+    pub web_cache: Arc<crate::tools::web_scraping::WebPageCache>,
+    /// Operator webhook alerts raised by the watchdog (ALERT_WEBHOOK_URL).
+    pub alerts: Arc<crate::alerts::AlertDispatcher>,
+    /// Chat messages `store_messages` could not write, retried in the background.
+    pub dead_letters: Arc<crate::dead_letter::DeadLetterQueue>,
+    /// Replayable `POST /api/execute` responses by `Idempotency-Key` (IDEMPOTENCY_TTL_SECS).
+    pub idempotency: Arc<crate::idempotency::IdempotencyCache>,
+    /// Keyword classification tuning from `gh_classification_config`, read at startup.
+    pub classification: crate::classify::ClassificationConfig,
+    /// Fired on shutdown; every execution's cancel token is a child of it.
+    pub shutdown: CancellationToken,
+    /// Running WebSocket executions, awaited on shutdown so partial answers
+    /// are stored before the process exits.
+    pub executions: TaskTracker,
 }
 
 // ── Shared: readiness helpers ───────────────────────────────────────────────
@@ -301,6 +521,12 @@ impl AppState {
         self.ready.store(true, Ordering::Relaxed);
         tracing::info!("Backend marked as READY");
     }
+
+    /// Divert traffic away (readiness probe returns 503) until `mark_ready`.
+    pub fn mark_not_ready(&self) {
+        self.ready.store(false, Ordering::Relaxed);
+        tracing::warn!("Backend marked as NOT READY");
+    }
 }
 
 impl AppState {
@@ -332,9 +558,11 @@ impl AppState {
             },
             // Load agents from DB
             async {
-                sqlx::query_as::<_, WitcherAgent>("SELECT * FROM gh_agents ORDER BY created_at ASC")
-                    .fetch_all(&db)
-                    .await
+                sqlx::query_as::<_, WitcherAgent>(
+                    "SELECT * FROM gh_agents ORDER BY display_order ASC, created_at ASC",
+                )
+                .fetch_all(&db)
+                .await
             },
             // Build HTTP client
             async {
@@ -401,12 +629,14 @@ impl AppState {
         );
 
         let mcp_client = Arc::new(McpClientManager::new(db.clone(), client.clone()));
+        let classification = crate::classify::ClassificationConfig::load(&db).await;
 
         Self {
             db,
             agents: Arc::new(RwLock::new(agents_vec)),
             runtime: Arc::new(RwLock::new(RuntimeState { api_keys })),
             model_cache: Arc::new(RwLock::new(ModelCache::new())),
+            model_health: Arc::new(tokio::sync::Mutex::new(ModelHealthCache::default())),
             start_time: Instant::now(),
             client,
             oauth_pkce: Arc::new(RwLock::new(None)),
@@ -414,7 +644,9 @@ impl AppState {
             ready: Arc::new(AtomicBool::new(false)),
             auth_secret,
             gemini_circuit: Arc::new(CircuitBreaker::new("gemini")),
+            model_circuits: Arc::new(RwLock::new(HashMap::new())),
             prompt_cache: Arc::new(RwLock::new(HashMap::new())),
+            settings_cache: Arc::new(RwLock::new(None)),
             a2a_cancel_tokens: Arc::new(RwLock::new(HashMap::new())),
             oauth_gemini_valid: Arc::new(AtomicBool::new(true)),
             log_buffer,
@@ -423,27 +655,193 @@ impl AppState {
             google_oauth_pkce: Arc::new(RwLock::new(None)),
             github_oauth_state: Arc::new(RwLock::new(None)),
             vercel_oauth_state: Arc::new(RwLock::new(None)),
+            providers: ProviderEndpoints::from_env(),
             knowledge_api_url,
             knowledge_auth_secret,
             swarm_tx: tokio::sync::broadcast::channel(100).0,
             browser_proxy_status: Arc::new(RwLock::new(crate::browser_proxy::BrowserProxyStatus::default())),
             browser_proxy_history: Arc::new(crate::browser_proxy::ProxyHealthHistory::new(50)),
+            db_health: Arc::new(RwLock::new(crate::models::DbHealthStatus::default())),
+            web_cache: Arc::new(crate::tools::web_scraping::WebPageCache::from_env()),
+            alerts: Arc::new(crate::alerts::AlertDispatcher::from_env()),
+            dead_letters: Arc::new(crate::dead_letter::DeadLetterQueue::default()),
+            idempotency: Arc::new(crate::idempotency::IdempotencyCache::from_env()),
+            classification,
+            shutdown: CancellationToken::new(),
+            executions: TaskTracker::new(),
+        }
+    }
+
+    /// Circuit breaker for a single Gemini model, created on first use
+    /// (at most `MAX_MODEL_CIRCUITS` are kept).
+    pub async fn model_circuit(&self, model: &str) -> Arc<CircuitBreaker> {
+        if let Some(circuit) = self.model_circuits.read().await.get(model) {
+            return circuit.clone();
         }
+        model_circuit_entry(&mut *self.model_circuits.write().await, model)
+    }
+
+    /// The shared Gemini breaker followed by the per-model ones, by model name.
+    pub async fn circuits(&self) -> Vec<Arc<CircuitBreaker>> {
+        let models = self.model_circuits.read().await;
+        let mut names: Vec<&String> = models.keys().collect();
+        names.sort();
+        std::iter::once(self.gemini_circuit.clone())
+            .chain(names.into_iter().map(|name| models[name].clone()))
+            .collect()
     }
 
     /// Refresh agents cache from DB
     pub async fn refresh_agents(&self) {
-        if let Ok(new_list) =
-            sqlx::query_as::<_, WitcherAgent>("SELECT * FROM gh_agents ORDER BY created_at ASC")
-                .fetch_all(&self.db)
-                .await
+        if let Ok(new_list) = sqlx::query_as::<_, WitcherAgent>(
+            "SELECT * FROM gh_agents ORDER BY display_order ASC, created_at ASC",
+        )
+        .fetch_all(&self.db)
+        .await
         {
             let mut lock = self.agents.write().await;
             *lock = new_list;
         }
         // Invalidate system prompt cache — agent roster changed
+        self.clear_prompt_cache().await;
+    }
+
+    /// Active settings row, served from memory for up to `SETTINGS_CACHE_MAX_AGE`
+    /// so the execute hot path doesn't query `gh_settings` on every request.
+    pub async fn settings(&self) -> Result<SettingsRow, sqlx::Error> {
+        if let Some((read_at, row)) = self.settings_cache.read().await.as_ref()
+            && read_at.elapsed() < SETTINGS_CACHE_MAX_AGE
+        {
+            return Ok(row.clone());
+        }
+        let row = sqlx::query_as::<_, SettingsRow>(
+            "SELECT temperature, max_tokens, default_model, language, theme, welcome_message, \
+             use_docker_sandbox, top_p, response_style, max_iterations, thinking_level, working_directory, force_model, \
+             stop_on_tool_error, command_allowlist, extra_blocked_patterns, fallback_models, max_agent_call_depth, \
+             history_window, history_truncate_keep, safety_level, rating_alert_threshold, rating_alert_min_count, \
+             max_write_bytes, max_tool_display_bytes, gemini_max_retries, gemini_backoff_base_ms, \
+             gemini_backoff_jitter_ms \
+             FROM gh_settings WHERE id = 1",
+        )
+        .fetch_one(&self.db)
+        .await?;
+        *self.settings_cache.write().await = Some((Instant::now(), row.clone()));
+        Ok(row)
+    }
+
+    /// Forget the cached settings row (after any write to `gh_settings`).
+    pub async fn invalidate_settings(&self) {
+        *self.settings_cache.write().await = None;
+    }
+
+    /// Drop every cached system prompt (agent roster or settings changed).
+    pub async fn clear_prompt_cache(&self) {
         // Use std::mem::take to release the write lock before dropping the old data
         let old_cache = std::mem::take(&mut *self.prompt_cache.write().await);
         drop(old_cache);
     }
+
+    /// Drop cached system prompts built for `agent_id` (keys are `agent_id:language:model:wd`).
+    pub async fn invalidate_agent_prompts(&self, agent_id: &str) {
+        let prefix = format!("{}:", agent_id);
+        self.prompt_cache
+            .write()
+            .await
+            .retain(|key, _| !key.starts_with(&prefix));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_override_must_be_https_and_is_normalised() {
+        assert_eq!(
+            parse_base_url(" https://gw.example.com/gemini/ ").unwrap(),
+            "https://gw.example.com/gemini"
+        );
+        assert_eq!(
+            parse_base_url("https://litellm.internal:4000").unwrap(),
+            "https://litellm.internal:4000"
+        );
+        assert!(parse_base_url("http://gw.example.com").is_err());
+        assert!(parse_base_url("https://gw.example.com/?key=x").is_err());
+        assert!(parse_base_url("not a url").is_err());
+    }
+
+    #[test]
+    fn endpoint_urls_join_onto_base() {
+        let endpoints = ProviderEndpoints {
+            gemini: "https://gw.example.com/gemini".into(),
+            ..ProviderEndpoints::default()
+        };
+        assert_eq!(
+            endpoints.gemini_model("gemini-2.5-flash", "generateContent"),
+            "https://gw.example.com/gemini/v1beta/models/gemini-2.5-flash:generateContent"
+        );
+        assert_eq!(
+            endpoints.gemini_models(),
+            "https://gw.example.com/gemini/v1beta/models"
+        );
+        assert_eq!(
+            endpoints.anthropic_models(),
+            "https://api.anthropic.com/v1/models"
+        );
+    }
+
+    #[tokio::test]
+    async fn circuit_snapshot_tracks_trips_and_manual_reset() {
+        let circuit = CircuitBreaker::new("gemini:test");
+        let snap = circuit.snapshot().await;
+        assert_eq!(snap.state, "closed");
+        assert!(snap.last_tripped_at.is_none());
+        assert!(!circuit.reset());
+
+        for _ in 0..FAILURE_THRESHOLD {
+            circuit.record_failure().await;
+        }
+        let snap = circuit.snapshot().await;
+        assert_eq!(snap.state, "open");
+        assert_eq!(snap.consecutive_failures, FAILURE_THRESHOLD);
+        assert_eq!(snap.trips, 1);
+        assert!(snap.last_tripped_at.is_some());
+        assert!(snap.retry_in_secs.unwrap() > 0);
+        assert!(circuit.check().await.is_err());
+
+        assert!(circuit.reset());
+        let snap = circuit.snapshot().await;
+        assert_eq!(snap.state, "closed");
+        assert_eq!(snap.consecutive_failures, 0);
+        assert_eq!(snap.trips, 1);
+        assert!(snap.last_tripped_at.is_some());
+        assert!(snap.retry_in_secs.is_none());
+        assert!(circuit.check().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn model_circuits_are_capped_and_keep_tripped_breakers() {
+        let mut circuits = HashMap::new();
+        let tripped = model_circuit_entry(&mut circuits, "tripped");
+        for _ in 0..FAILURE_THRESHOLD {
+            tripped.record_failure().await;
+        }
+        drop(tripped);
+        for i in 0..MAX_MODEL_CIRCUITS * 3 {
+            model_circuit_entry(&mut circuits, &format!("junk-{}", i));
+        }
+        assert!(circuits.len() <= MAX_MODEL_CIRCUITS);
+        assert!(circuits["tripped"].is_open());
+        assert!(model_circuit_entry(&mut circuits, "tripped").is_open());
+
+        // Breakers still in use are not evicted either; new ones go untracked
+        let held: Vec<_> = (0..MAX_MODEL_CIRCUITS)
+            .map(|i| model_circuit_entry(&mut circuits, &format!("held-{}", i)))
+            .collect();
+        let extra = model_circuit_entry(&mut circuits, "extra");
+        assert_eq!(circuits.len(), MAX_MODEL_CIRCUITS);
+        assert!(!circuits.contains_key("extra"));
+        assert_eq!(extra.provider(), "gemini:extra");
+        drop(held);
+    }
 }
@@ -4,15 +4,72 @@
 
 use serde_json::{Value, json};
 
+use crate::files::DEFAULT_MAX_WRITE_BYTES;
+use crate::models::ToolCatalogEntry;
+
+/// Tools whose descriptions state the `max_write_bytes` cap.
+const WRITE_LIMITED_TOOLS: &[&str] = &["write_file", "edit_file"];
+
+/// Declared tools the chat client answers itself (the WS loop pauses for the
+/// user) rather than `tools::execute_tool`.
+const CLIENT_SIDE_TOOLS: &[&str] = &["ask_user"];
+
 /// Tool definitions are static and never change — compute once via AppState OnceLock.
 /// Byte-identical tools JSON across all requests enables Gemini implicit caching.
 pub fn build_tools(state: &crate::state::AppState) -> Value {
-    state.tool_defs_cache.get_or_init(|| json!([{
+    state
+        .tool_defs_cache
+        .get_or_init(native_tool_declarations)
+        .clone()
+}
+
+/// Native tool catalog for `GET /api/tools`: every declaration sent to Gemini,
+/// with its category from `tools::list_available_tools`.
+pub fn tool_catalog(state: &crate::state::AppState) -> Vec<ToolCatalogEntry> {
+    catalog_from(&build_tools(state))
+}
+
+fn catalog_from(tools: &Value) -> Vec<ToolCatalogEntry> {
+    let categories = crate::tools::list_available_tools();
+    declared_functions(tools)
+        .map(|decl| {
+            let name = decl["name"].as_str().unwrap_or_default().to_string();
+            let category = categories
+                .iter()
+                .find(|t| t.name == name)
+                .map(|t| t.category);
+            let fallback = if CLIENT_SIDE_TOOLS.contains(&name.as_str()) {
+                "client"
+            } else {
+                "uncategorized"
+            };
+            ToolCatalogEntry {
+                executable: category.is_some(),
+                category: category.unwrap_or(fallback).to_string(),
+                description: decl["description"].as_str().unwrap_or_default().to_string(),
+                parameters: decl["parameters"].clone(),
+                name,
+            }
+        })
+        .collect()
+}
+
+fn declared_functions(tools: &Value) -> impl Iterator<Item = &Value> {
+    tools
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.get("function_declarations").and_then(|d| d.as_array()))
+        .flatten()
+}
+
+fn native_tool_declarations() -> Value {
+    json!([{
         "function_declarations": [
             {
                 "name": "list_directory",
-                "description": "List files and subdirectories in a local directory with sizes and line counts. ALWAYS use this to explore project structure — never use execute_command with dir/ls.",
-                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the local directory" }, "show_hidden": { "type": "boolean", "description": "Include hidden files (dotfiles)" } }, "required": ["path"] }
+                "description": "List files and subdirectories in a local directory with sizes and line counts. Gitignored entries are hidden inside a git repository unless show_ignored is set. ALWAYS use this to explore project structure — never use execute_command with dir/ls.",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the local directory" }, "show_hidden": { "type": "boolean", "description": "Include hidden files (dotfiles)" }, "respect_gitignore": { "type": "boolean", "description": "Skip paths ignored by .gitignore (default true; only applies inside a git repository)" }, "show_ignored": { "type": "boolean", "description": "Include gitignored paths anyway (default false)" } }, "required": ["path"] }
             },
             {
                 "name": "read_file",
@@ -26,27 +83,32 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
             },
             {
                 "name": "search_files",
-                "description": "Search for text or regex patterns across all files in a directory (recursive). Returns matching lines with file paths and line numbers. Supports pagination and multiline regex. ALWAYS use this to search for code patterns — never use execute_command with grep/Select-String/findstr.",
-                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Directory to search in (absolute path)" }, "pattern": { "type": "string", "description": "Text or regex pattern to search for (case-insensitive)" }, "file_extensions": { "type": "string", "description": "Comma-separated extensions to filter, e.g. 'ts,tsx,rs'. Default: all text files" }, "offset": { "type": "integer", "description": "Number of matches to skip (default 0, for pagination)" }, "limit": { "type": "integer", "description": "Max matches to return (default 80)" }, "multiline": { "type": "boolean", "description": "If true, pattern matches across line boundaries with ±2 lines context (default false)" } }, "required": ["path", "pattern"] }
+                "description": "Search for text or regex patterns across all files in a directory (recursive). Returns matching lines with file paths and line numbers. Supports pagination, multiline regex and include/exclude globs. By default skips hidden directories, build/dependency folders (target, node_modules, .git, dist, ...) and gitignored paths — pass exclude_globs to override the folder list, show_ignored to include gitignored paths. ALWAYS use this to search for code patterns — never use execute_command with grep/Select-String/findstr.",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Directory to search in (absolute path)" }, "pattern": { "type": "string", "description": "Text or regex pattern to search for (case-insensitive)" }, "file_extensions": { "type": "string", "description": "Comma-separated extensions to filter, e.g. 'ts,tsx,rs'. Default: all text files" }, "offset": { "type": "integer", "description": "Number of matches to skip (default 0, for pagination)" }, "limit": { "type": "integer", "description": "Max matches to return (default 80). A page is also capped at 64 KB of output — omitted matches are reported" }, "multiline": { "type": "boolean", "description": "If true, pattern matches across line boundaries with ±2 lines context (default false)" }, "context_before": { "type": "integer", "description": "Lines of context to show before each match, like grep -B (default 0, max 5). Overlapping context of nearby matches is merged" }, "context_after": { "type": "integer", "description": "Lines of context to show after each match, like grep -A (default 0, max 5)" }, "include_globs": { "type": "array", "items": { "type": "string" }, "description": "Only search files whose relative path or name matches one of these globs, e.g. ['src/**', '*.rs']" }, "exclude_globs": { "type": "array", "items": { "type": "string" }, "description": "Skip files/directories whose relative path or name matches one of these globs. Replaces the default exclusions (target, node_modules, .git, dist, hidden dirs) — include them again if still wanted" }, "respect_gitignore": { "type": "boolean", "description": "Skip paths ignored by .gitignore (default true; only applies inside a git repository)" }, "show_ignored": { "type": "boolean", "description": "Include gitignored paths anyway (default false)" } }, "required": ["path", "pattern"] }
             },
             {
                 "name": "find_file",
-                "description": "Find files by name pattern (glob). Returns matching file paths with sizes. Use when you don't know exact file location.",
-                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Root directory to search in (absolute path)" }, "pattern": { "type": "string", "description": "Glob pattern like '*.tsx' or 'auth*'" } }, "required": ["path", "pattern"] }
+                "description": "Find files by name pattern (glob). Returns matching file paths with sizes. Use when you don't know exact file location. Set 'contains' to keep only files whose content matches it — returns path + first matching line (\"files named X that contain Y\").",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Root directory to search in (absolute path)" }, "pattern": { "type": "string", "description": "Glob pattern like '*.tsx' or 'auth*'" }, "contains": { "type": "string", "description": "Optional text or regex (case-insensitive) the file content must match. Only text files are scanned, up to 500 per call." }, "respect_gitignore": { "type": "boolean", "description": "Skip paths ignored by .gitignore (default true; only applies inside a git repository)" }, "show_ignored": { "type": "boolean", "description": "Include gitignored paths anyway (default false)" } }, "required": ["path", "pattern"] }
+            },
+            {
+                "name": "file_stat",
+                "description": "Get metadata for a file or directory without reading it: exists, is_dir, size_bytes, modified (RFC 3339) and line_count_estimate. Use before read_file to skip huge/generated files, or after write_file to confirm the write landed.",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the file or directory" } }, "required": ["path"] }
             },
             {
                 "name": "get_code_structure",
-                "description": "Analyze code structure (functions, classes, structs, traits) via AST without reading full file content. Returns symbol names, types, and line numbers. Supports Rust, TypeScript, JavaScript, Python, Go.",
+                "description": "Analyze code structure (functions, classes, structs, traits) via AST without reading full file content. Returns symbol names, types, and line numbers. Supports Rust, TypeScript, JavaScript, Python, Go, Java, C#, and C/C++ headers and sources. For other languages use read_file instead.",
                 "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the source file to analyze" } }, "required": ["path"] }
             },
             {
                 "name": "write_file",
-                "description": "Write or create a file on the local filesystem. Use for creating NEW files or complete rewrites.",
+                "description": format!("Write or create a file on the local filesystem. Use for creating NEW files or complete rewrites. {}", write_limit_note(DEFAULT_MAX_WRITE_BYTES)),
                 "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path for the file to write" }, "content": { "type": "string", "description": "Full file content to write" } }, "required": ["path", "content"] }
             },
             {
                 "name": "edit_file",
-                "description": "Edit an existing file by replacing a specific text section. SAFER than write_file — only changes the targeted section. CRITICAL: old_text must be COPIED VERBATIM from read_file output — every character, space, tab, and newline must match EXACTLY. Even one different space or missing newline causes failure. Use read_file_section first to get the exact text, then copy it character-for-character into old_text.",
+                "description": format!("Edit an existing file by replacing a specific text section. SAFER than write_file — only changes the targeted section. CRITICAL: old_text must be COPIED VERBATIM from read_file output — every character, space, tab, and newline must match EXACTLY. Even one different space or missing newline causes failure. Use read_file_section first to get the exact text, then copy it character-for-character into old_text. {}", write_limit_note(DEFAULT_MAX_WRITE_BYTES)),
                 "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the file to edit" }, "old_text": { "type": "string", "description": "Text to find and replace — must be COPIED VERBATIM from the file (exact whitespace, exact newlines). Keep it short (3-10 lines) to minimize mismatch risk. Must appear exactly once in the file." }, "new_text": { "type": "string", "description": "Replacement text — same indentation style as the original" } }, "required": ["path", "old_text", "new_text"] }
             },
             {
@@ -54,29 +116,39 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
                 "description": "Delete a file or empty directory from the local filesystem. IMPORTANT for Rust refactoring: when you create `foo/mod.rs`, you MUST immediately delete `foo.rs` — having both causes fatal E0761 compile error.",
                 "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the file or empty directory to delete" } }, "required": ["path"] }
             },
+            {
+                "name": "copy_file",
+                "description": "Copy a file to a new location. Missing parent directories of the destination are created. Fails if the destination exists unless overwrite=true. Use instead of execute_command with cp/copy.",
+                "parameters": { "type": "object", "properties": { "src": { "type": "string", "description": "Absolute path of the file to copy" }, "dst": { "type": "string", "description": "Absolute destination file path (not a directory)" }, "overwrite": { "type": "boolean", "description": "Replace an existing destination file (default: false)" } }, "required": ["src", "dst"] }
+            },
+            {
+                "name": "create_directory",
+                "description": "Create a directory, including any missing parents. Succeeds if the directory already exists; fails if a file occupies the path. Use instead of execute_command with mkdir.",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path of the directory to create" } }, "required": ["path"] }
+            },
             {
                 "name": "diff_files",
-                "description": "Compare two files and show line-by-line differences in unified diff format. Max 200 diff lines output.",
-                "parameters": { "type": "object", "properties": { "path_a": { "type": "string", "description": "Absolute path to the first file" }, "path_b": { "type": "string", "description": "Absolute path to the second file" } }, "required": ["path_a", "path_b"] }
+                "description": "Compare two files and show line-by-line differences in unified diff format. Output is capped at max_lines (default 200); when truncated, a summary reports how many changed hunks were omitted. Use hunks_only for large files to skip unchanged regions.",
+                "parameters": { "type": "object", "properties": { "path_a": { "type": "string", "description": "Absolute path to the first file" }, "path_b": { "type": "string", "description": "Absolute path to the second file" }, "max_lines": { "type": "integer", "description": "Max diff lines to output (default 200, max 2000)" }, "context_lines": { "type": "integer", "description": "Unchanged lines shown around each change in hunks_only mode (default 3, max 100)" }, "hunks_only": { "type": "boolean", "description": "If true, output only @@ hunks of changed lines with context_lines of context, omitting unchanged regions entirely (default false: full file listing)" } }, "required": ["path_a", "path_b"] }
             },
             {
                 "name": "read_pdf",
-                "description": "Extract text from a PDF file. Uses pdf-extract for embedded text; falls back to Gemini Vision OCR for scanned/image-based PDFs. Supports page range filtering.",
-                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the PDF file" }, "page_range": { "type": "string", "description": "Optional page range like '1-5' or '3' (1-indexed)" } }, "required": ["path"] }
+                "description": "Extract text from a PDF file. Uses pdf-extract for embedded text; falls back to Gemini Vision OCR for scanned/image-based PDFs. Supports page range filtering. For long documents call mode='outline' first to get each page's first line and character count, then read only the relevant page_range.",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the PDF file" }, "page_range": { "type": "string", "description": "Optional page range like '1-5' or '3' (1-indexed)" }, "mode": { "type": "string", "enum": ["text", "outline"], "description": "'text' (default) returns the extracted text; 'outline' returns a per-page index of headings and character counts" } }, "required": ["path"] }
             },
             {
                 "name": "analyze_image",
-                "description": "Analyze an image file using Gemini Vision API. Describes contents, text, objects, colors, and notable features. Set extract_text=true to perform OCR (extract text from the image). Supports PNG, JPEG, WebP, GIF (max 10 MB).",
-                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the image file" }, "prompt": { "type": "string", "description": "Optional custom analysis prompt" }, "extract_text": { "type": "boolean", "description": "When true, extract text (OCR) from the image instead of describing it" } }, "required": ["path"] }
+                "description": "Analyze an image file using Gemini Vision API. Describes contents, text, objects, colors, and notable features. Set extract_text=true to perform OCR (extract text from the image). Pass `paths` instead of `path` to analyze or compare up to 8 images in one call (20 MB total). Supports PNG, JPEG, WebP, GIF, HEIC/HEIF; BMP and TIFF are converted to PNG first (max 10 MB each). AVIF must be converted to PNG or JPEG beforehand.",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the image file" }, "paths": { "type": "array", "items": { "type": "string" }, "description": "Absolute paths of up to 8 images analyzed together with one prompt (use instead of path)" }, "prompt": { "type": "string", "description": "Optional custom analysis prompt" }, "extract_text": { "type": "boolean", "description": "When true, extract text (OCR) from the image instead of describing it" } } }
             },
             {
                 "name": "ocr_document",
-                "description": "Extract text from an image or PDF using Gemini Vision OCR. Returns text with preserved formatting: tables as markdown (| pipes + --- separators), headers, lists, paragraphs. Ideal for invoices, reports, forms, tables, receipts, scanned documents. The extracted text can be copied with rich formatting (pastes as real tables in Word/Excel). Supports PNG, JPEG, WebP, GIF, PDF (max 22 MB).",
-                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the image or PDF file" }, "prompt": { "type": "string", "description": "Optional custom OCR prompt (default extracts all text preserving tables and formatting)" } }, "required": ["path"] }
+                "description": "Extract text from an image or PDF using Gemini Vision OCR. Returns text with preserved formatting: tables as markdown (| pipes + --- separators), headers, lists, paragraphs. Ideal for invoices, reports, forms, tables, receipts, scanned documents. The extracted text can be copied with rich formatting (pastes as real tables in Word/Excel). Supports PNG, JPEG, WebP, GIF, HEIC/HEIF, BMP, TIFF, PDF (max 22 MB).",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string", "description": "Absolute path to the image or PDF file" }, "prompt": { "type": "string", "description": "Optional custom OCR prompt (default extracts all text preserving tables and formatting)" }, "language": { "type": "string", "description": "Optional document language hint, e.g. 'pl', 'en', 'de' (default: auto-detect)" } }, "required": ["path"] }
             },
             {
                 "name": "fetch_webpage",
-                "description": "Fetch a web page with SSRF protection, extract readable text (HTML tables→markdown, code→fenced blocks, inline links preserved), metadata (OpenGraph, JSON-LD, language), and categorized links (internal/external/resource). Supports retry with backoff, content deduplication, custom headers, and JSON output format.",
+                "description": "Fetch a web page with SSRF protection, extract readable text (HTML tables→markdown, code→fenced blocks, inline links preserved), metadata (OpenGraph, JSON-LD, language), and categorized links (internal/external/resource). Supports retry with backoff, content deduplication, custom headers, and JSON output format. Repeat fetches of the same URL within a few minutes are served from cache (marked cache_hit).",
                 "parameters": { "type": "object", "properties": {
                     "url": { "type": "string", "description": "Full URL to fetch (http/https). Private IPs and localhost are blocked." },
                     "extract_links": { "type": "boolean", "description": "Extract and categorize all links as internal/external/resource (default: true)" },
@@ -84,12 +156,13 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
                     "include_images": { "type": "boolean", "description": "Include image alt text as ![alt](src) in output (default: false)" },
                     "output_format": { "type": "string", "description": "Output format: 'text' (markdown) or 'json' (structured). Default: 'text'" },
                     "max_text_length": { "type": "integer", "description": "Max characters of page text to return. 0 = unlimited (default: 0)" },
-                    "headers": { "type": "object", "description": "Custom HTTP headers as key-value pairs" }
+                    "headers": { "type": "object", "description": "Custom HTTP headers as key-value pairs" },
+                    "no_cache": { "type": "boolean", "description": "Bypass the page cache and always fetch fresh content (default: false)" }
                 }, "required": ["url"] }
             },
             {
                 "name": "crawl_website",
-                "description": "Crawl a website with robots.txt compliance, optional sitemap seeding, concurrent requests, SSRF protection, and content deduplication. Extracts text from each page (tables→markdown, code→fenced) and builds categorized link index. Supports path prefix filtering, exclude patterns, and configurable rate limiting.",
+                "description": "Crawl a website with robots.txt compliance, optional sitemap seeding, concurrent requests, SSRF protection, and content deduplication. Extracts text from each page (tables→markdown, code→fenced) and builds categorized link index. Supports path prefix filtering, exclude patterns, and configurable rate limiting. When the page or time limit cuts a crawl short, the output includes a resume_token — pass it back (with the same url) to continue where it stopped.",
                 "parameters": { "type": "object", "properties": {
                     "url": { "type": "string", "description": "Starting URL to crawl (http/https)" },
                     "max_depth": { "type": "integer", "description": "Max link depth (default: 1, max: 5)" },
@@ -105,7 +178,8 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
                     "output_format": { "type": "string", "description": "Output format: 'text' or 'json' (default: 'text')" },
                     "max_text_length": { "type": "integer", "description": "Max text chars per page excerpt (default: 2000)" },
                     "include_metadata": { "type": "boolean", "description": "Include OpenGraph/JSON-LD metadata per page (default: false)" },
-                    "headers": { "type": "object", "description": "Custom HTTP headers as key-value pairs" }
+                    "headers": { "type": "object", "description": "Custom HTTP headers as key-value pairs" },
+                    "resume_token": { "type": "string", "description": "Token from a previous incomplete crawl of the same url — continues its queue without re-fetching visited pages" }
                 }, "required": ["url"] }
             },
             {
@@ -120,8 +194,13 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
             },
             {
                 "name": "git_diff",
-                "description": "Show changes (diff) in a git repository. Use target='staged' for staged changes, or a commit/branch reference.",
-                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "target": { "type": "string", "description": "What to diff: 'staged', '--stat', or a commit/branch reference (default: working tree --stat)" } }, "required": ["repo_path"] }
+                "description": "Show changes (diff) in a git repository: a --stat summary header followed by the patch. mode='unstaged' (default) diffs the working tree, mode='staged' shows what the next commit would contain, mode='range' compares two refs (from..to). Use path to scope the diff to one file.",
+                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "mode": { "type": "string", "enum": ["unstaged", "staged", "range"], "description": "What to diff (default: unstaged)" }, "from": { "type": "string", "description": "Start ref for mode='range' (commit, branch or tag)" }, "to": { "type": "string", "description": "End ref for mode='range' (default: HEAD)" }, "path": { "type": "string", "description": "Limit the diff to this file or directory (relative to repo_path)" }, "max_lines": { "type": "integer", "description": "Maximum patch lines to return (default: 200, max: 2000)" }, "target": { "type": "string", "description": "Deprecated: 'staged', '--stat', or a ref to diff the working tree against. Ignored when mode is set." } }, "required": ["repo_path"] }
+            },
+            {
+                "name": "git_show",
+                "description": "Inspect a single commit (git show): author, date, message, --stat summary and patch. Use path to limit the patch to one file. Useful for tracking down what a suspect commit changed.",
+                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "ref": { "type": "string", "description": "Commit to show: hash, branch, tag or relative ref such as 'HEAD~2'" }, "path": { "type": "string", "description": "Limit the patch to this file or directory (relative to repo_path)" } }, "required": ["repo_path", "ref"] }
             },
             {
                 "name": "git_branch",
@@ -130,8 +209,18 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
             },
             {
                 "name": "git_commit",
-                "description": "Stage files and create a git commit. Does NOT push — only local commit. Use files='all' to stage everything, or comma-separated file list.",
-                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "message": { "type": "string", "description": "Commit message" }, "files": { "type": "string", "description": "Files to stage: 'all' or comma-separated paths (e.g. 'src/main.rs,Cargo.toml'). If omitted, commits already-staged files." } }, "required": ["repo_path", "message"] }
+                "description": "Stage files and create a git commit. Does NOT push — only local commit. Use files='all' to stage everything, or comma-separated file list. Returns the commit hash and a short stat and refuses to create an empty commit. Use dry_run=true to preview the commit without staging or committing anything.",
+                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "message": { "type": "string", "description": "Commit message" }, "files": { "type": "string", "description": "Files to stage: 'all' or comma-separated paths (e.g. 'src/main.rs,Cargo.toml'). If omitted, commits already-staged files." }, "dry_run": { "type": "boolean", "description": "Preview the would-be commit (staged stat + files that would be staged) without committing (default: false)" } }, "required": ["repo_path", "message"] }
+            },
+            {
+                "name": "git_stash",
+                "description": "Stash uncommitted changes to tracked files (git stash push) so risky edits can be undone. Returns the stash ref and the list of stashed files. Untracked files are left in place.",
+                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "message": { "type": "string", "description": "Stash description (default: 'GeminiHydra agent stash')" } }, "required": ["repo_path"] }
+            },
+            {
+                "name": "git_stash_pop",
+                "description": "Re-apply a stash (the most recent by default) and remove it from the stash list (git stash pop). On conflict the stash is kept.",
+                "parameters": { "type": "object", "properties": { "repo_path": { "type": "string", "description": "Absolute path to the git repository" }, "stash": { "type": "string", "description": "Stash to pop, as stash@{N} (default: stash@{0})" } }, "required": ["repo_path"] }
             },
             {
                 "name": "github_list_repos",
@@ -218,6 +307,11 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
                 "description": "Execute a shell command on the local Windows machine. ONLY use for build/test/git/npm/cargo CLI operations. NEVER use for file reading (use read_file), directory listing (use list_directory), or text search (use search_files). ALWAYS set working_directory when running project commands (cargo, npm, git).",
                 "parameters": { "type": "object", "properties": { "command": { "type": "string", "description": "Shell command to execute (Windows cmd.exe). Do NOT include 'cd' — use working_directory instead." }, "working_directory": { "type": "string", "description": "Absolute path to set as the working directory before executing the command. REQUIRED for cargo/npm/git commands. Example: C:\\Users\\BIURODOM\\Desktop\\GeminiHydra-v15\\backend" } }, "required": ["command"] }
             },
+            {
+                "name": "run_tests",
+                "description": "Run a project's test suite and get a compact structured summary: { passed, failed, failures: [{ name, message }] }. PREFER this over execute_command for running tests — it saves tokens by parsing the runner output for you.",
+                "parameters": { "type": "object", "properties": { "framework": { "type": "string", "enum": ["cargo", "jest", "pytest"], "description": "Test runner to use" }, "working_directory": { "type": "string", "description": "Absolute path of the project root to run the tests in" }, "verbose": { "type": "boolean", "description": "Also include the raw runner output (truncated at 50KB). Default false" } }, "required": ["framework", "working_directory"] }
+            },
             {
                 "name": "ask_user",
                 "description": "Ask the user a question to gather preferences, clarify requirements, or make decisions. Use this ONLY if a wrong decision would cause significant re-work, the request is fundamentally ambiguous, or the user explicitly asks you to confirm. Execution will pause until the user responds.",
@@ -225,23 +319,27 @@ pub fn build_tools(state: &crate::state::AppState) -> Value {
             },
             {
                 "name": "list_mcp_tools",
-                "description": "List all available MCP tools from connected external servers. Returns tool names, descriptions, and which server provides each tool.",
+                "description": "List all discovered MCP tools from external servers, grouped by server with connection status and short descriptions.",
                 "parameters": { "type": "object", "properties": {}, "required": [] }
             },
             {
                 "name": "execute_mcp_tool",
-                "description": "Execute a specific MCP tool by its prefixed name (mcp_servername_toolname) with custom arguments. Use list_mcp_tools first to discover available tools.",
-                "parameters": { "type": "object", "properties": { "tool_name": { "type": "string", "description": "Prefixed MCP tool name (e.g. mcp_brave_web_search)" }, "arguments": { "type": "object", "description": "Tool arguments as a JSON object" } }, "required": ["tool_name"] }
+                "description": "Execute a tool on a connected external MCP server. Use list_mcp_tools first to discover servers and tool names.",
+                "parameters": { "type": "object", "properties": { "server": { "type": "string", "description": "MCP server name (as shown by list_mcp_tools) or ID" }, "tool": { "type": "string", "description": "Tool name on that server (unprefixed)" }, "arguments": { "type": "object", "description": "Tool arguments as a JSON object" } }, "required": ["server", "tool"] }
             }
         ]
-    }])).clone()
+    }])
 }
 
 /// Build tools including dynamically discovered MCP tools.
 /// Native tools are cached (OnceLock), MCP tools merged at request time.
 /// MCP tools are placed FIRST — they are preferred over native equivalents.
 pub async fn build_tools_with_mcp(state: &crate::state::AppState) -> serde_json::Value {
-    let native = build_tools(state);
+    let mut native = build_tools(state);
+    let max_write_bytes = crate::tools::max_write_bytes(state).await;
+    if max_write_bytes != DEFAULT_MAX_WRITE_BYTES {
+        apply_write_limit(&mut native, max_write_bytes);
+    }
     let mcp_decls = state.mcp_client.build_gemini_tool_declarations().await;
 
     if mcp_decls.is_empty() {
@@ -261,3 +359,204 @@ pub async fn build_tools_with_mcp(state: &crate::state::AppState) -> serde_json:
     }
     result
 }
+
+/// Sentence appended to the write-tool descriptions so the model knows the cap.
+fn write_limit_note(max_bytes: usize) -> String {
+    const MB: usize = 1024 * 1024;
+    let limit = if max_bytes.is_multiple_of(MB) {
+        format!("{} MB", max_bytes / MB)
+    } else {
+        format!("{} KB", max_bytes / 1024)
+    };
+    format!(
+        "The resulting file may be at most {} (max_write_bytes setting); larger content is rejected.",
+        limit
+    )
+}
+
+/// Restate a non-default write cap in the cached declarations. Only applied
+/// when the setting differs, so the default tools JSON stays byte-identical.
+fn apply_write_limit(tools: &mut Value, max_bytes: usize) {
+    let default_note = write_limit_note(DEFAULT_MAX_WRITE_BYTES);
+    let note = write_limit_note(max_bytes);
+    for decl in tools
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| {
+            t.get_mut("function_declarations")
+                .and_then(|d| d.as_array_mut())
+        })
+        .flatten()
+        .filter(|decl| WRITE_LIMITED_TOOLS.contains(&decl["name"].as_str().unwrap_or_default()))
+    {
+        if let Some(desc) = decl["description"].as_str() {
+            decl["description"] = Value::String(desc.replace(&default_note, &note));
+        }
+    }
+}
+
+/// Find the `parameters` schema of a declared function by name.
+pub fn find_tool_schema<'a>(tools: &'a Value, name: &str) -> Option<&'a Value> {
+    tools
+        .as_array()?
+        .iter()
+        .filter_map(|t| t.get("function_declarations").and_then(|d| d.as_array()))
+        .flatten()
+        .find(|decl| decl["name"].as_str() == Some(name))
+        .and_then(|decl| decl.get("parameters"))
+}
+
+/// Check function-call args against a declared parameter schema (the OpenAPI
+/// subset Gemini uses: type, properties, required, items, enum).
+pub fn validate_tool_args(schema: &Value, args: &Value) -> Result<(), String> {
+    // Gemini omits `args` entirely for calls without parameters.
+    let empty = json!({});
+    let args = if args.is_null() { &empty } else { args };
+    validate_value(schema, args, "args")
+}
+
+fn validate_value(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let ty = schema["type"]
+        .as_str()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let type_ok = match ty.as_str() {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        // Gemini may serialize integers as `3.0`
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        _ => true,
+    };
+    if !type_ok {
+        return Err(format!("{}: expected {}, got {}", path, ty, value));
+    }
+
+    if let Some(allowed) = schema["enum"].as_array()
+        && !allowed.contains(value)
+    {
+        return Err(format!("{}: {} is not one of {:?}", path, value, allowed));
+    }
+
+    if let Some(obj) = value.as_object() {
+        for key in schema["required"].as_array().into_iter().flatten() {
+            let key = key.as_str().unwrap_or_default();
+            if obj.get(key).is_none_or(Value::is_null) {
+                return Err(format!("{}: missing required '{}'", path, key));
+            }
+        }
+        if let Some(props) = schema["properties"].as_object() {
+            for (key, v) in obj {
+                if let Some(prop) = props.get(key) {
+                    validate_value(prop, v, &format!("{}.{}", path, key))?;
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for (i, v) in arr.iter().enumerate() {
+            validate_value(items, v, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "start_line": { "type": "integer" },
+                "mode": { "type": "string", "enum": ["a", "b"] },
+                "globs": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["path", "start_line"]
+        })
+    }
+
+    #[test]
+    fn valid_args_pass() {
+        let args = json!({ "path": "/x", "start_line": 3, "mode": "a", "globs": ["*.rs"] });
+        assert!(validate_tool_args(&schema(), &args).is_ok());
+    }
+
+    #[test]
+    fn invalid_args_are_reported() {
+        let s = schema();
+        assert!(validate_tool_args(&s, &json!({ "path": "/x" })).is_err());
+        assert!(validate_tool_args(&s, &json!({ "path": "/x", "start_line": "3" })).is_err());
+        assert!(
+            validate_tool_args(&s, &json!({ "path": "/x", "start_line": 1, "mode": "c" })).is_err()
+        );
+        assert!(
+            validate_tool_args(&s, &json!({ "path": "/x", "start_line": 1, "globs": [1] }))
+                .is_err()
+        );
+        assert!(validate_tool_args(&s, &json!("path=/x")).is_err());
+    }
+
+    #[test]
+    fn missing_args_match_schema_without_required() {
+        let s = json!({ "type": "object", "properties": {} });
+        assert!(validate_tool_args(&s, &Value::Null).is_ok());
+    }
+
+    #[test]
+    fn write_limit_is_stated_in_write_tool_descriptions() {
+        let mut tools = native_tool_declarations();
+        let description = |tools: &Value, name: &str| {
+            declared_functions(tools)
+                .find(|d| d["name"] == name)
+                .and_then(|d| d["description"].as_str())
+                .unwrap()
+                .to_string()
+        };
+        for name in WRITE_LIMITED_TOOLS {
+            assert!(description(&tools, name).contains("at most 5 MB"));
+        }
+
+        apply_write_limit(&mut tools, 512 * 1024);
+        for name in WRITE_LIMITED_TOOLS {
+            let desc = description(&tools, name);
+            assert!(desc.contains("at most 512 KB"), "{}", desc);
+            assert!(!desc.contains("5 MB"));
+        }
+    }
+
+    #[test]
+    fn catalog_matches_tool_registry() {
+        let catalog = catalog_from(&native_tool_declarations());
+        for tool in crate::tools::list_available_tools() {
+            assert!(
+                catalog.iter().any(|e| e.name == tool.name && e.executable),
+                "{} is registered but not declared",
+                tool.name
+            );
+        }
+        for entry in &catalog {
+            assert!(
+                entry.executable || CLIENT_SIDE_TOOLS.contains(&entry.name.as_str()),
+                "{} is declared but has no executor",
+                entry.name
+            );
+            assert!(!entry.description.is_empty());
+            assert_eq!(entry.parameters["type"], "object");
+        }
+    }
+
+    #[test]
+    fn finds_declared_schema() {
+        let tools =
+            json!([{ "function_declarations": [{ "name": "read_file", "parameters": schema() }] }]);
+        assert_eq!(find_tool_schema(&tools, "read_file"), Some(&schema()));
+        assert!(find_tool_schema(&tools, "nope").is_none());
+    }
+}
@@ -0,0 +1,180 @@
+// tools/command_policy.rs
+//! Settings-driven command policy for `execute_command`.
+//!
+//! When `command_allowlist` is non-empty the tool runs in allowlist mode:
+//! every command in a `;` / `&&` / `||` / `|` chain must start with one of the
+//! listed executables. Command and process substitution can't be checked that
+//! way, so they are refused outright. The static denylist (plus `extra_blocked_patterns`
+//! from settings) still applies afterwards.
+
+/// Check `command` against a non-empty allowlist of executable names.
+/// Returns the human-readable rejection reason on failure.
+pub(crate) fn check_allowlist(command: &str, allowlist: &[String]) -> Result<(), String> {
+    if ["`", "$(", "<(", ">("].iter().any(|p| command.contains(p)) {
+        return Err("command or process substitution is not allowed in allowlist mode".into());
+    }
+
+    for segment in command_segments(command) {
+        let Some(executable) = segment_executable(segment) else {
+            continue;
+        };
+        if !allowlist.iter().any(|a| executable_matches(executable, a)) {
+            return Err(format!(
+                "'{}' is not in the command allowlist (allowed: {})",
+                executable,
+                allowlist.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Why the command policy refused a command.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Rejection {
+    /// Allowlist mode and the reason the command does not fit the allowlist.
+    NotAllowed(String),
+    /// Denylisted pattern found in the command.
+    Blocked(String),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::NotAllowed(reason) => write!(f, "Command rejected: {}", reason),
+            Rejection::Blocked(pattern) => {
+                write!(f, "Blocked dangerous command pattern: {}", pattern)
+            }
+        }
+    }
+}
+
+/// Full policy check: allowlist mode (non-empty `allowlist`) first, then the
+/// built-in and settings-provided denylists.
+pub(crate) fn check_command(
+    command: &str,
+    allowlist: &[String],
+    builtin: &[&str],
+    extra: &[String],
+) -> Result<(), Rejection> {
+    if !allowlist.is_empty() {
+        check_allowlist(command, allowlist).map_err(Rejection::NotAllowed)?;
+    }
+    match find_blocked_pattern(command, builtin, extra) {
+        Some(pattern) => Err(Rejection::Blocked(pattern.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// First denylisted pattern contained in `command` (case-insensitive substring
+/// match), checking the built-in list before the settings-provided one.
+pub(crate) fn find_blocked_pattern<'a>(
+    command: &str,
+    builtin: &[&'a str],
+    extra: &'a [String],
+) -> Option<&'a str> {
+    let lower = command.to_lowercase();
+    builtin
+        .iter()
+        .copied()
+        .chain(extra.iter().map(String::as_str))
+        .find(|pattern| !pattern.is_empty() && lower.contains(&pattern.to_lowercase()))
+}
+
+/// Split a shell command on control operators (`;`, `|`, `&`, newline).
+/// `&` inside redirections (`2>&1`, `&>file`) is not a separator.
+fn command_segments(command: &str) -> Vec<&str> {
+    let bytes = command.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let is_separator = match b {
+            b';' | b'|' | b'\n' => true,
+            b'&' => {
+                let redirect_before = i > 0 && matches!(bytes[i - 1], b'>' | b'<');
+                let redirect_after = bytes.get(i + 1) == Some(&b'>');
+                !redirect_before && !redirect_after
+            }
+            _ => false,
+        };
+        if is_separator {
+            segments.push(&command[start..i]);
+            start = i + 1;
+        }
+    }
+    segments.push(&command[start..]);
+    segments
+}
+
+/// First word of a segment, skipping leading `VAR=value` assignments and
+/// surrounding quotes. `None` for empty segments (e.g. the gap in `a && b`).
+fn segment_executable(segment: &str) -> Option<&str> {
+    segment
+        .split_whitespace()
+        .find(|token| !is_env_assignment(token))
+        .map(|token| token.trim_matches(|c| c == '"' || c == '\''))
+}
+
+fn is_env_assignment(token: &str) -> bool {
+    token.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Exact, case-insensitive match; `git.exe` also matches an allowed `git`.
+fn executable_matches(executable: &str, allowed: &str) -> bool {
+    let executable = executable.to_lowercase();
+    let allowed = allowed.to_lowercase();
+    executable == allowed || executable.strip_suffix(".exe") == Some(allowed.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allow(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn allowlist_checks_every_command_in_a_chain() {
+        let list = allow(&["cargo", "git", "ls"]);
+        assert!(check_allowlist("cargo test 2>&1", &list).is_ok());
+        assert!(check_allowlist("git status && ls -la | git hash-object --stdin", &list).is_ok());
+        assert!(check_allowlist("RUST_LOG=debug cargo run", &list).is_ok());
+        assert!(check_allowlist("Git.exe log", &list).is_ok());
+
+        let err = check_allowlist("ls; rm -rf build", &list).unwrap_err();
+        assert!(err.contains("'rm'"));
+        assert!(check_allowlist("cargo build || curl evil.sh", &list).is_err());
+        assert!(check_allowlist("/tmp/git status", &list).is_err());
+    }
+
+    #[test]
+    fn blocked_patterns_include_settings_extras() {
+        let extra = allow(&["Terraform Destroy", "kubectl delete"]);
+        assert_eq!(
+            find_blocked_pattern("sudo ls", &["sudo "], &extra),
+            Some("sudo ")
+        );
+        assert_eq!(
+            find_blocked_pattern("TERRAFORM destroy -auto-approve", &["sudo "], &extra),
+            Some("Terraform Destroy")
+        );
+        assert_eq!(
+            find_blocked_pattern("kubectl get pods", &["sudo "], &extra),
+            None
+        );
+    }
+
+    #[test]
+    fn allowlist_rejects_command_substitution() {
+        let list = allow(&["echo", "ls"]);
+        assert!(check_allowlist("echo $(rm -rf ~)", &list).is_err());
+        assert!(check_allowlist("echo `id`", &list).is_err());
+        assert!(check_allowlist("ls <(rm -rf ~/x)", &list).is_err());
+        assert!(check_allowlist("echo hi >(rm -rf ~/x)", &list).is_err());
+    }
+}
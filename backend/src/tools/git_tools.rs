@@ -13,8 +13,20 @@ const GIT_TIMEOUT: Duration = Duration::from_secs(15);
 /// Maximum output length.
 const MAX_OUTPUT_CHARS: usize = 6000;
 
-/// Run a git command in the given repo path, return stdout.
+/// Run a git command in the given repo path, return stdout truncated to
+/// `MAX_OUTPUT_CHARS`.
 async fn run_git(repo_path: &str, args: &[&str]) -> Result<String, String> {
+    let stdout = run_git_raw(repo_path, args).await?;
+    if stdout.len() > MAX_OUTPUT_CHARS {
+        let truncated: String = stdout.chars().take(MAX_OUTPUT_CHARS - 40).collect();
+        Ok(format!("{}\n\n[... truncated ...]", truncated))
+    } else {
+        Ok(stdout)
+    }
+}
+
+/// Run a git command in the given repo path, return the full stdout.
+async fn run_git_raw(repo_path: &str, args: &[&str]) -> Result<String, String> {
     let repo = Path::new(repo_path);
 
     // Validate repo exists
@@ -39,13 +51,7 @@ async fn run_git(repo_path: &str, args: &[&str]) -> Result<String, String> {
     .map_err(|_| "Git command timed out (15s)".to_string())??;
 
     if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        if stdout.len() > MAX_OUTPUT_CHARS {
-            let truncated: String = stdout.chars().take(MAX_OUTPUT_CHARS - 40).collect();
-            Ok(format!("{}\n\n[... truncated ...]", truncated))
-        } else {
-            Ok(stdout)
-        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         Err(format!("git error: {}", stderr))
@@ -84,19 +90,136 @@ pub async fn tool_git_log(repo_path: &str, count: Option<u32>) -> Result<String,
     ))
 }
 
-/// Show changes (diff).
-pub async fn tool_git_diff(repo_path: &str, target: Option<&str>) -> Result<String, String> {
-    let args: Vec<&str> = match target {
-        Some("staged") | Some("--staged") => vec!["diff", "--staged", "--stat"],
-        Some("--stat") => vec!["diff", "--stat"],
-        Some(t) => vec!["diff", t],
-        None => vec!["diff", "--stat"],
+/// What `git_diff` compares.
+#[derive(Debug, PartialEq)]
+pub enum DiffMode<'a> {
+    /// Working tree vs index (`git diff`).
+    Unstaged,
+    /// Index vs HEAD (`git diff --cached`).
+    Staged,
+    /// Between two refs (`git diff from..to`); `to` defaults to HEAD.
+    Range { from: &'a str, to: Option<&'a str> },
+    /// Legacy `target` ref: working tree vs that ref (`git diff <ref>`).
+    Ref(&'a str),
+}
+
+impl<'a> DiffMode<'a> {
+    /// Resolve the `mode`/`from`/`to` arguments, falling back to the legacy
+    /// `target` argument when `mode` is absent.
+    pub fn from_args(
+        mode: Option<&'a str>,
+        target: Option<&'a str>,
+        from: Option<&'a str>,
+        to: Option<&'a str>,
+    ) -> Result<Self, String> {
+        let parsed = match (mode, target) {
+            (Some("unstaged"), _) | (None, None) | (None, Some("--stat")) => Self::Unstaged,
+            (Some("staged"), _) | (None, Some("staged")) | (None, Some("--staged")) => Self::Staged,
+            (Some("range"), _) => Self::Range {
+                from: from.ok_or("mode 'range' requires 'from'")?,
+                to,
+            },
+            (Some(m), _) => {
+                return Err(format!(
+                    "Invalid mode '{}'. Use 'unstaged', 'staged' or 'range'",
+                    m
+                ));
+            }
+            (None, Some(t)) => Self::Ref(t),
+        };
+        match &parsed {
+            Self::Range { from, to } => {
+                check_ref(from)?;
+                if let Some(to) = to {
+                    check_ref(to)?;
+                }
+            }
+            Self::Ref(r) => check_ref(r)?,
+            _ => {}
+        }
+        Ok(parsed)
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Self::Unstaged => "unstaged".to_string(),
+            Self::Staged => "staged".to_string(),
+            Self::Range { from, to } => format!("{}..{}", from, to.unwrap_or("HEAD")),
+            Self::Ref(r) => format!("working tree vs {}", r),
+        }
+    }
+
+    /// `git diff` arguments selecting what to compare.
+    fn args(&self) -> Vec<String> {
+        match self {
+            Self::Unstaged => vec![],
+            Self::Staged => vec!["--cached".to_string()],
+            Self::Range { from, to } => vec![format!("{}..{}", from, to.unwrap_or("HEAD"))],
+            Self::Ref(r) => vec![r.to_string()],
+        }
+    }
+}
+
+/// Accept only ref-like names (`main`, `v1.2`, `HEAD~2`, `abc123^`,
+/// `stash@{0}`); refuse anything git would parse as an option or as a
+/// range of its own.
+fn check_ref(r: &str) -> Result<(), String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "._/-~^@{}".contains(c);
+    if r.is_empty() || r.starts_with('-') || r.contains("..") || !r.chars().all(allowed) {
+        return Err(format!("Invalid git ref: '{}'", r));
+    }
+    Ok(())
+}
+
+/// Show changes (diff): a `--stat` summary header followed by the patch,
+/// capped at `max_lines` lines and optionally scoped to a single `path`.
+pub async fn tool_git_diff(
+    repo_path: &str,
+    mode: &DiffMode<'_>,
+    path: Option<&str>,
+    max_lines: usize,
+) -> Result<String, String> {
+    let mut args: Vec<String> = vec!["diff".to_string()];
+    args.extend(mode.args());
+    let pathspec: Vec<String> = path
+        .map(|p| vec!["--".to_string(), p.to_string()])
+        .unwrap_or_default();
+
+    let mut stat_args = args.clone();
+    stat_args.push("--stat".to_string());
+    stat_args.extend(pathspec.iter().cloned());
+    let stat_refs: Vec<&str> = stat_args.iter().map(String::as_str).collect();
+    let stat = run_git(repo_path, &stat_refs).await?;
+
+    let label = match path {
+        Some(p) => format!("{}, {}", mode.label(), p),
+        None => mode.label(),
     };
-    let diff = run_git(repo_path, &args).await?;
-    let label = target.unwrap_or("working tree");
+    if stat.trim().is_empty() {
+        return Ok(format!(
+            "### Git Diff ({}): {}\n\nNo changes.",
+            label, repo_path
+        ));
+    }
+
+    args.extend(pathspec);
+    let patch_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    let patch = run_git_raw(repo_path, &patch_refs).await?;
+    let total = patch.lines().count();
+    let mut body: String = patch.lines().take(max_lines).collect::<Vec<_>>().join("\n");
+    if total > max_lines {
+        body.push_str(&format!(
+            "\n\n[... {} more lines truncated (max_lines={}) ...]",
+            total - max_lines,
+            max_lines
+        ));
+    }
     Ok(format!(
-        "### Git Diff ({}): {}\n\n{}",
-        label, repo_path, diff
+        "### Git Diff ({}): {}\n\n{}\n{}",
+        label,
+        repo_path,
+        stat.trim_end(),
+        body
     ))
 }
 
@@ -145,39 +268,377 @@ pub async fn tool_git_branch(repo_path: &str, action: Option<&str>) -> Result<St
     }
 }
 
+/// Returned when a commit would be empty.
+const NOTHING_STAGED: &str = "Nothing staged for commit. Use 'files' parameter to stage files (e.g., 'all' or 'file1.rs,file2.rs')";
+
+/// `git add` arguments for the `files` parameter (`None` = use the index as is).
+fn add_args(files: Option<&str>) -> Option<Vec<&str>> {
+    match files {
+        Some("all") | Some(".") => Some(vec!["add", "-A"]),
+        Some(file_list) => {
+            let mut args = vec!["add", "--"];
+            args.extend(
+                file_list
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty()),
+            );
+            (args.len() > 2).then_some(args)
+        }
+        None => None,
+    }
+}
+
 /// Stage files and commit changes. NO PUSH — too dangerous for agent tools.
+/// With `dry_run` nothing is staged or committed; the would-be commit is
+/// described instead.
 pub async fn tool_git_commit(
     repo_path: &str,
     message: &str,
     files: Option<&str>,
+    dry_run: bool,
 ) -> Result<String, String> {
     if message.is_empty() {
         return Err("Commit message cannot be empty".into());
     }
+    let add = add_args(files);
 
-    // Stage files
-    match files {
-        Some("all") | Some(".") => {
-            run_git(repo_path, &["add", "-A"]).await?;
-        }
-        Some(file_list) => {
-            for file in file_list.split(',') {
-                let file = file.trim();
-                if !file.is_empty() {
-                    run_git(repo_path, &["add", file]).await?;
-                }
+    if dry_run {
+        let would_stage = match &add {
+            Some(args) => {
+                let mut args = args.clone();
+                args.insert(1, "--dry-run");
+                run_git(repo_path, &args).await?
             }
+            None => String::new(),
+        };
+        let staged = run_git(repo_path, &["diff", "--cached", "--stat"]).await?;
+        if would_stage.trim().is_empty() && staged.trim().is_empty() {
+            return Err(NOTHING_STAGED.into());
         }
-        None => {
-            // Check if anything is staged
-            let staged = run_git(repo_path, &["diff", "--staged", "--name-only"]).await?;
+        return Ok(format!(
+            "### Git Commit (dry run)\n\n**Message**: {}\n\n### Already staged:\n{}\n\n### Would stage:\n{}\n\nNothing was staged or committed.",
+            message,
             if staged.trim().is_empty() {
-                return Err("Nothing staged for commit. Use 'files' parameter to stage files (e.g., 'all' or 'file1.rs,file2.rs')".into());
-            }
+                "(none)"
+            } else {
+                staged.trim_end()
+            },
+            if would_stage.trim().is_empty() {
+                "(none)"
+            } else {
+                would_stage.trim_end()
+            },
+        ));
+    }
+
+    if let Some(args) = &add {
+        run_git(repo_path, args).await?;
+    }
+    let staged = run_git(repo_path, &["diff", "--cached", "--name-only"]).await?;
+    if staged.trim().is_empty() {
+        return Err(NOTHING_STAGED.into());
+    }
+
+    run_git(repo_path, &["commit", "-m", message]).await?;
+    let hash = run_git(repo_path, &["rev-parse", "--short", "HEAD"]).await?;
+    let stat = run_git(repo_path, &["show", "--shortstat", "--format=", "HEAD"]).await?;
+    Ok(format!(
+        "### Git Commit\n\n**Commit**: {}\n**Message**: {}\n**Changes**: {}",
+        hash.trim(),
+        message,
+        stat.trim()
+    ))
+}
+
+/// Default stash message when the agent doesn't provide one.
+const DEFAULT_STASH_MESSAGE: &str = "GeminiHydra agent stash";
+
+/// Stash tracked working-tree changes so risky edits can be rolled back.
+pub async fn tool_git_stash(repo_path: &str, message: Option<&str>) -> Result<String, String> {
+    let status = run_git(
+        repo_path,
+        &["status", "--porcelain", "--untracked-files=no"],
+    )
+    .await?;
+    if status.trim().is_empty() {
+        return Err("No local changes to stash".into());
+    }
+
+    let message = message
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .unwrap_or(DEFAULT_STASH_MESSAGE);
+    run_git(repo_path, &["stash", "push", "-m", message]).await?;
+
+    let hash = run_git(repo_path, &["rev-parse", "--short", "stash@{0}"]).await?;
+    let files = run_git(repo_path, &["stash", "show", "--name-status", "stash@{0}"]).await?;
+    Ok(format!(
+        "### Git Stash\n\n**Ref**: stash@{{0}} ({})\n**Message**: {}\n\n### Stashed files:\n{}",
+        hash.trim(),
+        message,
+        files
+    ))
+}
+
+/// Only `stash@{N}` is accepted where a stash is named.
+fn check_stash_ref(r: &str) -> Result<(), String> {
+    match r.strip_prefix("stash@{").and_then(|n| n.strip_suffix('}')) {
+        Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => Ok(()),
+        _ => Err(format!("Invalid stash ref: '{}' (expected stash@{{N}})", r)),
+    }
+}
+
+/// Re-apply a stash (the most recent by default) and drop it (kept on conflict).
+pub async fn tool_git_stash_pop(repo_path: &str, stash: Option<&str>) -> Result<String, String> {
+    let stash = stash
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .unwrap_or("stash@{0}");
+    check_stash_ref(stash)?;
+    let list = run_git(repo_path, &["stash", "list"]).await?;
+    if list.trim().is_empty() {
+        return Err("No stash entries to pop".into());
+    }
+    let prefix = format!("{}:", stash);
+    let entry = list
+        .lines()
+        .find(|l| l.starts_with(&prefix))
+        .ok_or_else(|| format!("No stash entry {}", stash))?;
+
+    let files = run_git(repo_path, &["stash", "show", "--name-status", stash]).await?;
+    run_git(repo_path, &["stash", "pop", stash]).await?;
+    let status = run_git(repo_path, &["status", "--short"]).await?;
+    Ok(format!(
+        "### Git Stash Pop\n\n**Restored**: {}\n\n### Restored files:\n{}\n### Working tree:\n{}",
+        entry.trim(),
+        files,
+        status
+    ))
+}
+
+/// Show a commit's metadata, stat and patch, optionally scoped to one path.
+pub async fn tool_git_show(
+    repo_path: &str,
+    rev: &str,
+    path: Option<&str>,
+) -> Result<String, String> {
+    check_ref(rev)?;
+    let mut args = vec!["show", "--format=fuller", "--stat", "--patch", rev];
+    if let Some(p) = path {
+        args.extend(["--", p]);
+    }
+    let shown = run_git(repo_path, &args).await?;
+    let label = match path {
+        Some(p) => format!("{}, {}", rev, p),
+        None => rev.to_string(),
+    };
+    Ok(format!(
+        "### Git Show ({}): {}\n\n{}",
+        label, repo_path, shown
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_args_maps_files_parameter() {
+        assert_eq!(add_args(Some("all")), Some(vec!["add", "-A"]));
+        assert_eq!(
+            add_args(Some("a.rs, b.rs,")),
+            Some(vec!["add", "--", "a.rs", "b.rs"])
+        );
+        assert_eq!(add_args(Some(" , ")), None);
+        assert_eq!(add_args(None), None);
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_commit_and_empty_commits_are_refused() {
+        let dir = std::env::temp_dir().join(format!("gh-git-commit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = dir.to_str().unwrap();
+        if run_git_init(repo).await.is_err() {
+            return; // git not available
         }
+        std::fs::write(dir.join("a.txt"), "hello\n").unwrap();
+
+        let preview = tool_git_commit(repo, "add a", Some("all"), true)
+            .await
+            .unwrap();
+        assert!(preview.contains("a.txt"));
+        let staged = run_git(repo, &["diff", "--cached", "--name-only"])
+            .await
+            .unwrap();
+        assert!(staged.trim().is_empty());
+
+        let committed = tool_git_commit(repo, "add a", Some("all"), false)
+            .await
+            .unwrap();
+        assert!(committed.contains("1 file changed"));
+
+        let err = tool_git_commit(repo, "again", None, false)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("Nothing staged"));
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
-    // Commit
-    let result = run_git(repo_path, &["commit", "-m", message]).await?;
-    Ok(format!("### Git Commit\n\n{}", result))
+    #[test]
+    fn diff_mode_parses_args_and_rejects_option_like_refs() {
+        assert_eq!(
+            DiffMode::from_args(None, None, None, None),
+            Ok(DiffMode::Unstaged)
+        );
+        assert_eq!(
+            DiffMode::from_args(None, Some("staged"), None, None),
+            Ok(DiffMode::Staged)
+        );
+        assert_eq!(
+            DiffMode::from_args(Some("range"), Some("staged"), Some("main"), None),
+            Ok(DiffMode::Range {
+                from: "main",
+                to: None
+            })
+        );
+        assert!(DiffMode::from_args(Some("range"), None, None, None).is_err());
+        assert!(DiffMode::from_args(Some("range"), None, Some("--output=x"), None).is_err());
+        assert!(DiffMode::from_args(Some("range"), None, Some("a"), Some("b c")).is_err());
+        assert!(DiffMode::from_args(Some("bogus"), None, None, None).is_err());
+    }
+
+    #[test]
+    fn check_ref_accepts_ref_syntax_only() {
+        for ok in [
+            "main",
+            "v1.2.0",
+            "HEAD~2",
+            "abc123^",
+            "origin/feat-x",
+            "stash@{0}",
+        ] {
+            assert!(check_ref(ok).is_ok(), "{}", ok);
+        }
+        for bad in ["", "-p", "--output=x", "a..b", "HEAD;rm", "$(id)", "a b"] {
+            assert!(check_ref(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[tokio::test]
+    async fn stash_push_list_and_pop() {
+        let dir = std::env::temp_dir().join(format!("gh-git-stash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = dir.to_str().unwrap();
+        if run_git_init(repo).await.is_err() {
+            return; // git not available
+        }
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        tool_git_commit(repo, "init", Some("all"), false)
+            .await
+            .unwrap();
+        let err = tool_git_stash(repo, None).await.unwrap_err();
+        assert_eq!(err, "No local changes to stash");
+
+        std::fs::write(dir.join("a.txt"), "one\nfirst\n").unwrap();
+        let pushed = tool_git_stash(repo, Some("first")).await.unwrap();
+        assert!(pushed.contains("**Ref**: stash@{0}"));
+        assert!(pushed.contains("M\ta.txt"));
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "one\n");
+        std::fs::write(dir.join("a.txt"), "one\nsecond\n").unwrap();
+        tool_git_stash(repo, None).await.unwrap();
+
+        let list = run_git(repo, &["stash", "list"]).await.unwrap();
+        assert_eq!(list.lines().count(), 2);
+        assert!(
+            list.lines()
+                .next()
+                .unwrap()
+                .ends_with(DEFAULT_STASH_MESSAGE)
+        );
+        assert!(list.lines().nth(1).unwrap().ends_with(": first"));
+
+        for bad in ["main", "stash@{x}", "stash@{}", "--index", "stash@{0};id"] {
+            let err = tool_git_stash_pop(repo, Some(bad)).await.unwrap_err();
+            assert!(err.starts_with("Invalid stash ref"), "{}", err);
+        }
+        let err = tool_git_stash_pop(repo, Some("stash@{5}"))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "No stash entry stash@{5}");
+
+        let popped = tool_git_stash_pop(repo, Some("stash@{1}")).await.unwrap();
+        assert!(popped.contains(": first"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.txt")).unwrap(),
+            "one\nfirst\n"
+        );
+        let list = run_git(repo, &["stash", "list"]).await.unwrap();
+        assert_eq!(list.lines().count(), 1);
+        assert!(list.contains(DEFAULT_STASH_MESSAGE));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn diff_modes_staged_unstaged_range_and_path() {
+        let dir = std::env::temp_dir().join(format!("gh-git-diff-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = dir.to_str().unwrap();
+        if run_git_init(repo).await.is_err() {
+            return; // git not available
+        }
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "one\n").unwrap();
+        tool_git_commit(repo, "init", Some("all"), false)
+            .await
+            .unwrap();
+
+        std::fs::write(dir.join("a.txt"), "one\nstaged\n").unwrap();
+        run_git(repo, &["add", "a.txt"]).await.unwrap();
+        std::fs::write(dir.join("b.txt"), "one\nunstaged\n").unwrap();
+
+        let staged = tool_git_diff(repo, &DiffMode::Staged, None, 200)
+            .await
+            .unwrap();
+        assert!(staged.contains("1 file changed"));
+        assert!(staged.contains("+staged") && !staged.contains("+unstaged"));
+
+        let unstaged = tool_git_diff(repo, &DiffMode::Unstaged, None, 200)
+            .await
+            .unwrap();
+        assert!(unstaged.contains("+unstaged") && !unstaged.contains("+staged"));
+
+        let scoped = tool_git_diff(repo, &DiffMode::Unstaged, Some("a.txt"), 200)
+            .await
+            .unwrap();
+        assert!(scoped.ends_with("No changes."));
+
+        tool_git_commit(repo, "second", None, false).await.unwrap();
+        let range = DiffMode::Range {
+            from: "HEAD~1",
+            to: Some("HEAD"),
+        };
+        let diff = tool_git_diff(repo, &range, None, 3).await.unwrap();
+        assert!(diff.contains("a.txt | 1 +"));
+        assert!(diff.contains("more lines truncated (max_lines=3)"));
+
+        let shown = tool_git_show(repo, "HEAD", Some("a.txt")).await.unwrap();
+        assert!(shown.contains("second") && shown.contains("+staged"));
+        assert!(!shown.contains("b.txt"));
+        assert!(tool_git_show(repo, "--output=/tmp/x", None).await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    async fn run_git_init(repo: &str) -> Result<(), String> {
+        Command::new("git")
+            .args(["init", "-q", repo])
+            .output()
+            .await
+            .map_err(|e| e.to_string())?;
+        run_git(repo, &["config", "user.email", "test@example.com"]).await?;
+        run_git(repo, &["config", "user.name", "Test"]).await?;
+        Ok(())
+    }
 }
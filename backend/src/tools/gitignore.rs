@@ -0,0 +1,168 @@
+// backend/src/tools/gitignore.rs
+//! `.gitignore` filtering for the directory-walking tools (`list_directory`,
+//! `find_file`, `search_files`).
+//!
+//! Active only inside a git repository. The repo's `.git/info/exclude` and
+//! every `.gitignore` from the repo root down to the walk root are loaded up
+//! front; `.gitignore` files below the walk root are added as the walk
+//! reaches them.
+
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub struct GitignoreFilter {
+    /// Shallowest first, so a nested `.gitignore` always follows its parents.
+    matchers: Vec<Gitignore>,
+}
+
+impl GitignoreFilter {
+    /// Whether the tool's `respect_gitignore` (default true) and
+    /// `show_ignored` (default false) arguments ask for filtering.
+    pub fn enabled(args: &Value) -> bool {
+        args["respect_gitignore"].as_bool().unwrap_or(true)
+            && !args["show_ignored"].as_bool().unwrap_or(false)
+    }
+
+    /// Filter for a walk rooted at `root`; `None` outside a git repo.
+    pub fn for_root(root: &Path) -> Option<Self> {
+        let root = absolute(root);
+        let repo = root.ancestors().find(|d| d.join(".git").exists())?;
+
+        let mut filter = Self {
+            matchers: Vec::new(),
+        };
+        filter.add_file(repo, &repo.join(".git").join("info").join("exclude"));
+        let mut dirs: Vec<&Path> = root
+            .ancestors()
+            .take_while(|d| d.starts_with(repo))
+            .collect();
+        dirs.reverse();
+        for dir in dirs {
+            filter.enter_dir(dir);
+        }
+        Some(filter)
+    }
+
+    /// Load `dir/.gitignore`, if any. Call once per directory the walk
+    /// descends into (the walk root is already loaded by `for_root`).
+    pub fn enter_dir(&mut self, dir: &Path) {
+        let dir = absolute(dir);
+        self.add_file(&dir, &dir.join(".gitignore"));
+    }
+
+    fn add_file(&mut self, base: &Path, file: &Path) {
+        if !file.is_file() {
+            return;
+        }
+        let mut builder = GitignoreBuilder::new(base);
+        if let Some(e) = builder.add(file) {
+            tracing::debug!("Skipping unreadable ignore file {}: {}", file.display(), e);
+            return;
+        }
+        match builder.build() {
+            Ok(gi) if !gi.is_empty() => self.matchers.push(gi),
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Invalid ignore file {}: {}", file.display(), e),
+        }
+    }
+
+    /// Whether `path` is ignored. The deepest `.gitignore` with an opinion
+    /// wins, so a nested `!pattern` can re-include what a parent ignores.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = absolute(path);
+        for gi in self.matchers.iter().rev() {
+            if !path.starts_with(gi.path()) {
+                continue;
+            }
+            match gi.matched(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gh-gitignore-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn repo(name: &str) -> PathBuf {
+        let root = scratch(name);
+        let root = root.as_path();
+        std::fs::create_dir_all(root.join(".git/info")).unwrap();
+        std::fs::write(root.join(".git/info/exclude"), "local.txt\n").unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::create_dir_all(root.join("sub/target")).unwrap();
+        std::fs::write(root.join("sub/.gitignore"), "!keep.log\ngenerated.rs\n").unwrap();
+        root.to_path_buf()
+    }
+
+    #[test]
+    fn outside_a_repo_there_is_no_filter() {
+        assert!(GitignoreFilter::for_root(&scratch("norepo")).is_none());
+    }
+
+    #[test]
+    fn root_gitignore_and_info_exclude_apply() {
+        let root = &repo("rules");
+        let filter = GitignoreFilter::for_root(root).unwrap();
+        assert!(filter.is_ignored(&root.join("target"), true));
+        assert!(filter.is_ignored(&root.join("debug.log"), false));
+        assert!(filter.is_ignored(&root.join("local.txt"), false));
+        assert!(!filter.is_ignored(&root.join("main.rs"), false));
+        // `target/` only matches directories
+        assert!(!filter.is_ignored(&root.join("target"), false));
+    }
+
+    #[test]
+    fn nested_gitignore_loaded_on_enter_and_can_whitelist() {
+        let root = &repo("nested");
+        let sub = root.join("sub");
+        let mut filter = GitignoreFilter::for_root(root).unwrap();
+        assert!(!filter.is_ignored(&sub.join("generated.rs"), false));
+
+        filter.enter_dir(&sub);
+        assert!(filter.is_ignored(&sub.join("generated.rs"), false));
+        assert!(filter.is_ignored(&sub.join("target"), true));
+        assert!(filter.is_ignored(&sub.join("other.log"), false));
+        assert!(!filter.is_ignored(&sub.join("keep.log"), false));
+        // Rules of `sub/.gitignore` do not leak to siblings
+        assert!(!filter.is_ignored(&root.join("generated.rs"), false));
+    }
+
+    #[test]
+    fn walk_root_below_repo_root_inherits_parent_rules() {
+        let sub = repo("below").join("sub");
+        let filter = GitignoreFilter::for_root(&sub).unwrap();
+        assert!(filter.is_ignored(&sub.join("generated.rs"), false));
+        assert!(filter.is_ignored(&sub.join("x.log"), false));
+        assert!(!filter.is_ignored(&sub.join("keep.log"), false));
+    }
+
+    #[test]
+    fn args_disable_or_override() {
+        assert!(GitignoreFilter::enabled(&serde_json::json!({})));
+        assert!(!GitignoreFilter::enabled(
+            &serde_json::json!({ "respect_gitignore": false })
+        ));
+        assert!(!GitignoreFilter::enabled(
+            &serde_json::json!({ "show_ignored": true })
+        ));
+    }
+}
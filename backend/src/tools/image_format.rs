@@ -0,0 +1,149 @@
+//! Image normalization before a file is sent to Gemini Vision.
+//!
+//! PNG, JPEG, WebP, GIF, HEIC and HEIF are sent as-is — Gemini accepts them
+//! directly. BMP and TIFF are decoded with the `image` crate and re-encoded
+//! as PNG at their original dimensions. Decoding is refused above
+//! `MAX_DECODED_PIXELS` so a huge scan can't exhaust memory.
+//!
+//! AVIF is not accepted: decoding it needs the crate's `avif-native` feature,
+//! which links the system dav1d library, and this build doesn't enable it.
+
+use std::io::Cursor;
+
+use image::{ImageFormat, ImageReader};
+
+/// Extensions Gemini Vision accepts as-is, with their MIME types.
+const PASSTHROUGH: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+    ("heic", "image/heic"),
+    ("heif", "image/heif"),
+];
+
+/// Extensions transcoded to PNG before sending.
+const TRANSCODED: &[(&str, ImageFormat)] = &[
+    ("bmp", ImageFormat::Bmp),
+    ("tif", ImageFormat::Tiff),
+    ("tiff", ImageFormat::Tiff),
+];
+
+/// Largest image (width × height) decoded for transcoding — 40 MP, about
+/// 160 MB as RGBA.
+const MAX_DECODED_PIXELS: u64 = 40_000_000;
+
+/// Image bytes ready for an `inlineData` part.
+pub struct SendableImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    /// `Some("BMP→PNG, 1920×1080")` when the file was transcoded.
+    pub conversion: Option<String>,
+}
+
+/// Whether `ext` (lowercase, no dot) is an image extension these tools accept.
+pub fn is_supported(ext: &str) -> bool {
+    PASSTHROUGH.iter().any(|(e, _)| *e == ext) || TRANSCODED.iter().any(|(e, _)| *e == ext)
+}
+
+/// Every accepted image extension, for error messages and tool descriptions.
+pub fn supported_extensions() -> Vec<&'static str> {
+    PASSTHROUGH
+        .iter()
+        .map(|(e, _)| *e)
+        .chain(TRANSCODED.iter().map(|(e, _)| *e))
+        .collect()
+}
+
+/// Turn the raw bytes of a file with extension `ext` into something Gemini
+/// accepts. CPU-bound for transcoded formats — call from `spawn_blocking`.
+pub fn normalize(ext: &str, bytes: Vec<u8>) -> Result<SendableImage, String> {
+    if let Some((_, mime_type)) = PASSTHROUGH.iter().find(|(e, _)| *e == ext) {
+        return Ok(SendableImage {
+            bytes,
+            mime_type,
+            conversion: None,
+        });
+    }
+    let (_, format) = TRANSCODED
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .ok_or_else(|| format!("Not a supported image format: .{}", ext))?;
+    let label = ext.to_uppercase();
+
+    let (width, height) = ImageReader::with_format(Cursor::new(&bytes), *format)
+        .into_dimensions()
+        .map_err(|e| decode_error(&label, e))?;
+    check_pixel_count(width, height)?;
+
+    let decoded = ImageReader::with_format(Cursor::new(&bytes), *format)
+        .decode()
+        .map_err(|e| decode_error(&label, e))?;
+    let mut png = Vec::new();
+    decoded
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Cannot re-encode {} image as PNG: {}", label, e))?;
+
+    Ok(SendableImage {
+        bytes: png,
+        mime_type: "image/png",
+        conversion: Some(format!(
+            "{}→PNG, {}×{}",
+            label,
+            decoded.width(),
+            decoded.height()
+        )),
+    })
+}
+
+fn check_pixel_count(width: u32, height: u32) -> Result<(), String> {
+    let pixels = width as u64 * height as u64;
+    if pixels > MAX_DECODED_PIXELS {
+        return Err(format!(
+            "Image too large to convert: {}×{} ({} MP, max {} MP)",
+            width,
+            height,
+            pixels / 1_000_000,
+            MAX_DECODED_PIXELS / 1_000_000
+        ));
+    }
+    Ok(())
+}
+
+fn decode_error(label: &str, e: image::ImageError) -> String {
+    format!(
+        "Cannot decode {} image ({}). Convert it to PNG or JPEG first.",
+        label, e
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gemini_native_formats_pass_through_untouched() {
+        let bytes = vec![1, 2, 3];
+        let heic = normalize("heic", bytes.clone()).unwrap();
+        assert_eq!(heic.bytes, bytes);
+        assert_eq!(heic.mime_type, "image/heic");
+        assert!(heic.conversion.is_none());
+        assert_eq!(normalize("jpg", bytes).unwrap().mime_type, "image/jpeg");
+    }
+
+    #[test]
+    fn unknown_and_undecodable_files_are_rejected() {
+        assert!(!is_supported("svg"));
+        assert!(!is_supported("avif"));
+        assert!(normalize("svg", vec![]).is_err());
+        let err = normalize("bmp", b"not a bitmap".to_vec()).err().unwrap();
+        assert!(err.contains("Cannot decode BMP"), "{}", err);
+    }
+
+    #[test]
+    fn pixel_cap_rejects_huge_images() {
+        assert!(check_pixel_count(6000, 4000).is_ok());
+        assert!(check_pixel_count(20_000, 20_000).is_err());
+    }
+}
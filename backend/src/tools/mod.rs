@@ -3,29 +3,38 @@
 //!
 //! Provides local tools that Gemini agents can invoke:
 //! - `execute_command` — run shell commands with timeout + safety filters
+//! - `run_tests` — run cargo/jest/pytest and return a structured pass/fail summary
 //! - `read_file` — read file contents (reuses files::read_file_for_context)
 //! - `read_file_section` — read specific line range from a file (1-indexed)
 //! - `write_file` — create/overwrite files with size + path restrictions
 //! - `edit_file` — targeted text replacement in existing files (safer than write_file)
-//! - `list_directory` — list directory contents with line counts
+//! - `copy_file` — copy a file, creating destination parents (no overwrite by default)
+//! - `create_directory` — create a directory and its parents (idempotent)
+//! - `list_directory` — list directory contents with line counts (gitignore-aware)
 //! - `search_files` — search for text/regex patterns across files (pagination + multiline)
 //! - `get_code_structure` — analyze code AST without full read
-//! - `find_file` — find files by glob pattern (recursive)
+//! - `find_file` — find files by glob pattern (recursive), optionally filtered by content
+//! - `file_stat` — existence, type, size, mtime and line estimate without reading
 //! - `diff_files` — line-by-line diff between two files
 //! - `read_pdf` — extract text from PDF with OCR fallback via Gemini Vision
 //! - `analyze_image` — Gemini Vision API image analysis + OCR text extraction
 //! - `fetch_webpage` — fetch and extract content from a web page
 //! - `crawl_website` — multi-page crawl with robots.txt compliance
 
+pub mod command_policy;
 pub mod fly_tools;
 pub mod git_tools;
 pub mod github_tools;
+pub mod gitignore;
+pub mod image_format;
+pub mod test_runner;
 pub mod vercel_tools;
 pub mod web_scraping;
 pub mod zip_tools;
 
 use crate::state::AppState;
 use base64::Engine;
+use gitignore::GitignoreFilter;
 use regex::Regex;
 use serde_json::{Value, json};
 use std::time::Duration;
@@ -48,6 +57,13 @@ pub struct ToolOutput {
     pub text: String,
     /// Optional binary data (e.g., image) with MIME type for Gemini multimodal function responses
     pub inline_data: Option<InlineData>,
+    /// False when the tool itself failed. A command that ran and exited
+    /// non-zero is still a success — see `exit_code`.
+    pub success: bool,
+    /// Process exit code for `execute_command` / `run_tests`.
+    pub exit_code: Option<i32>,
+    /// Wall-clock execution time, filled in by `execute_tool`.
+    pub duration_ms: u64,
 }
 
 /// Binary data attachment for multimodal function responses.
@@ -63,8 +79,34 @@ impl ToolOutput {
         Self {
             text: s,
             inline_data: None,
+            success: true,
+            exit_code: None,
+            duration_ms: 0,
         }
     }
+
+    /// Failed tool call. The text keeps the `TOOL_ERROR: ` prefix the model
+    /// has always seen.
+    pub fn error(msg: impl std::fmt::Display) -> Self {
+        Self {
+            success: false,
+            ..Self::text(format!("TOOL_ERROR: {}", msg))
+        }
+    }
+
+    /// Attach the exit code of the process that produced this output.
+    pub fn with_exit_code(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+}
+
+/// Who triggered a tool call — recorded in the tool-call audit trail.
+/// Both fields are `None` for calls from MCP clients or the ADK bridge.
+#[derive(Debug, Clone, Default)]
+pub struct ToolCaller {
+    pub agent_id: Option<String>,
+    pub session_id: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -139,6 +181,10 @@ pub fn list_available_tools() -> Vec<ToolInfo> {
             name: "execute_command",
             category: "filesystem",
         },
+        ToolInfo {
+            name: "run_tests",
+            category: "filesystem",
+        },
         ToolInfo {
             name: "read_file",
             category: "filesystem",
@@ -155,6 +201,14 @@ pub fn list_available_tools() -> Vec<ToolInfo> {
             name: "delete_file",
             category: "filesystem",
         },
+        ToolInfo {
+            name: "copy_file",
+            category: "filesystem",
+        },
+        ToolInfo {
+            name: "create_directory",
+            category: "filesystem",
+        },
         ToolInfo {
             name: "list_directory",
             category: "filesystem",
@@ -175,6 +229,10 @@ pub fn list_available_tools() -> Vec<ToolInfo> {
             name: "find_file",
             category: "filesystem",
         },
+        ToolInfo {
+            name: "file_stat",
+            category: "filesystem",
+        },
         ToolInfo {
             name: "diff_files",
             category: "filesystem",
@@ -214,6 +272,10 @@ pub fn list_available_tools() -> Vec<ToolInfo> {
             name: "git_diff",
             category: "git",
         },
+        ToolInfo {
+            name: "git_show",
+            category: "git",
+        },
         ToolInfo {
             name: "git_branch",
             category: "git",
@@ -222,6 +284,14 @@ pub fn list_available_tools() -> Vec<ToolInfo> {
             name: "git_commit",
             category: "git",
         },
+        ToolInfo {
+            name: "git_stash",
+            category: "git",
+        },
+        ToolInfo {
+            name: "git_stash_pop",
+            category: "git",
+        },
         // GitHub tools
         ToolInfo {
             name: "github_list_repos",
@@ -306,16 +376,65 @@ pub fn list_available_tools() -> Vec<ToolInfo> {
 
 /// Central dispatcher — routes tool call to the appropriate handler.
 /// Returns `ToolOutput` supporting text + optional multimodal data (Gemini 3).
+/// Calls to `audit::AUDITED_TOOLS` are written to the audit log with `caller`
+/// before they run, then updated with the outcome.
 pub async fn execute_tool(
     name: &str,
     args: &Value,
     state: &AppState,
     working_directory: &str,
+    caller: &ToolCaller,
 ) -> Result<ToolOutput, String> {
     let tool_start = std::time::Instant::now();
     tracing::debug!("Tool '{}' started", name);
 
-    let result = match name {
+    let audit = if crate::audit::AUDITED_TOOLS.contains(&name) {
+        crate::audit::ToolCallAudit::start(
+            &state.db,
+            name,
+            args,
+            caller.agent_id.as_deref(),
+            caller.session_id.as_deref(),
+        )
+        .await
+    } else {
+        None
+    };
+
+    let result = dispatch_tool(name, args, state, working_directory).await;
+
+    let elapsed = tool_start.elapsed();
+    let result = result.map(|mut output| {
+        output.duration_ms = elapsed.as_millis() as u64;
+        output
+    });
+    match &result {
+        Ok(_) => tracing::debug!("Tool '{}' completed in {:.2}s", name, elapsed.as_secs_f64()),
+        Err(e) => tracing::warn!(
+            "Tool '{}' failed in {:.2}s: {}",
+            name,
+            elapsed.as_secs_f64(),
+            e
+        ),
+    }
+    if let Some(audit) = audit {
+        audit
+            .finish(
+                result.as_ref().is_ok_and(|o| o.success),
+                elapsed.as_millis() as u64,
+            )
+            .await;
+    }
+    result
+}
+
+async fn dispatch_tool(
+    name: &str,
+    args: &Value,
+    state: &AppState,
+    working_directory: &str,
+) -> Result<ToolOutput, String> {
+    match name {
         "execute_command" => {
             let command = args["command"]
                 .as_str()
@@ -329,9 +448,19 @@ pub async fn execute_tool(
                     Some(working_directory)
                 }
             });
-            tool_execute_command(command, effective_wd, state)
-                .await
-                .map(ToolOutput::text)
+            tool_execute_command(command, effective_wd, state).await
+        }
+        "run_tests" => {
+            let framework = args["framework"]
+                .as_str()
+                .ok_or("Missing required argument: framework")?;
+            let wd = match args["working_directory"].as_str().filter(|d| !d.is_empty()) {
+                Some(dir) => resolve_path(dir, working_directory),
+                None if !working_directory.is_empty() => working_directory.to_string(),
+                None => return Err("Missing required argument: working_directory".into()),
+            };
+            let verbose = args["verbose"].as_bool().unwrap_or(false);
+            test_runner::tool_run_tests(framework, &wd, verbose, state).await
         }
         "read_file" => {
             let path = args["path"]
@@ -348,7 +477,7 @@ pub async fn execute_tool(
             let content = args["content"]
                 .as_str()
                 .ok_or("Missing required argument: content")?;
-            tool_write_file(&resolved, content)
+            tool_write_file(&resolved, content, max_write_bytes(state).await)
                 .await
                 .map(ToolOutput::text)
         }
@@ -363,7 +492,7 @@ pub async fn execute_tool(
             let new_text = args["new_text"]
                 .as_str()
                 .ok_or("Missing required argument: new_text")?;
-            tool_edit_file(&resolved, old_text, new_text)
+            tool_edit_file(&resolved, old_text, new_text, max_write_bytes(state).await)
                 .await
                 .map(ToolOutput::text)
         }
@@ -374,13 +503,37 @@ pub async fn execute_tool(
             let resolved = resolve_path(path, working_directory);
             tool_delete_file(&resolved).await.map(ToolOutput::text)
         }
+        "copy_file" => {
+            let src = args["src"]
+                .as_str()
+                .ok_or("Missing required argument: src")?;
+            let dst = args["dst"]
+                .as_str()
+                .ok_or("Missing required argument: dst")?;
+            let overwrite = args["overwrite"].as_bool().unwrap_or(false);
+            tool_copy_file(
+                &resolve_path(src, working_directory),
+                &resolve_path(dst, working_directory),
+                overwrite,
+            )
+            .await
+            .map(ToolOutput::text)
+        }
+        "create_directory" => {
+            let path = args["path"]
+                .as_str()
+                .ok_or("Missing required argument: path")?;
+            let resolved = resolve_path(path, working_directory);
+            tool_create_directory(&resolved).await.map(ToolOutput::text)
+        }
         "list_directory" => {
             let path = args["path"]
                 .as_str()
                 .ok_or("Missing required argument: path")?;
             let resolved = resolve_path(path, working_directory);
             let show_hidden = args["show_hidden"].as_bool().unwrap_or(false);
-            tool_list_directory(&resolved, show_hidden)
+            let gitignore = GitignoreFilter::enabled(args);
+            tool_list_directory(&resolved, show_hidden, gitignore)
                 .await
                 .map(ToolOutput::text)
         }
@@ -393,10 +546,18 @@ pub async fn execute_tool(
                 .as_str()
                 .ok_or("Missing required argument: pattern")?;
             let extensions = args["file_extensions"].as_str();
-            let offset = args["offset"].as_u64().unwrap_or(0) as usize;
-            let limit = args["limit"].as_u64().unwrap_or(80) as usize;
-            let multiline = args["multiline"].as_bool().unwrap_or(false);
-            tool_search_files(&resolved, pattern, extensions, offset, limit, multiline)
+            let filter = SearchPathFilter::from_args(args)?;
+            let opts = SearchOptions {
+                offset: args["offset"].as_u64().unwrap_or(0) as usize,
+                limit: args["limit"].as_u64().unwrap_or(80) as usize,
+                multiline: args["multiline"].as_bool().unwrap_or(false),
+                context_before: (args["context_before"].as_u64().unwrap_or(0) as usize)
+                    .min(MAX_SEARCH_CONTEXT),
+                context_after: (args["context_after"].as_u64().unwrap_or(0) as usize)
+                    .min(MAX_SEARCH_CONTEXT),
+            };
+            let gitignore = GitignoreFilter::enabled(args);
+            tool_search_files(&resolved, pattern, extensions, &filter, gitignore, &opts)
                 .await
                 .map(ToolOutput::text)
         }
@@ -433,10 +594,19 @@ pub async fn execute_tool(
             let pattern = args["pattern"]
                 .as_str()
                 .ok_or("Missing required argument: pattern")?;
-            tool_find_file(&resolved, pattern)
+            let contains = args["contains"].as_str().filter(|c| !c.is_empty());
+            let gitignore = GitignoreFilter::enabled(args);
+            tool_find_file(&resolved, pattern, contains, gitignore)
                 .await
                 .map(ToolOutput::text)
         }
+        "file_stat" => {
+            let path = args["path"]
+                .as_str()
+                .ok_or("Missing required argument: path")?;
+            let resolved = resolve_path(path, working_directory);
+            tool_file_stat(&resolved).await.map(ToolOutput::text)
+        }
         "diff_files" => {
             let path_a = args["path_a"]
                 .as_str()
@@ -446,7 +616,19 @@ pub async fn execute_tool(
                 .as_str()
                 .ok_or("Missing required argument: path_b")?;
             let resolved_b = resolve_path(path_b, working_directory);
-            tool_diff_files(&resolved_a, &resolved_b)
+            let opts = DiffOptions {
+                max_lines: (args["max_lines"]
+                    .as_u64()
+                    .unwrap_or(DEFAULT_DIFF_LINES as u64) as usize)
+                    .clamp(1, MAX_DIFF_LINES),
+                context_lines: (args["context_lines"]
+                    .as_u64()
+                    .unwrap_or(DEFAULT_DIFF_CONTEXT as u64)
+                    as usize)
+                    .min(MAX_DIFF_CONTEXT),
+                hunks_only: args["hunks_only"].as_bool().unwrap_or(false),
+            };
+            tool_diff_files(&resolved_a, &resolved_b, &opts)
                 .await
                 .map(ToolOutput::text)
         }
@@ -456,18 +638,39 @@ pub async fn execute_tool(
                 .ok_or("Missing required argument: path")?;
             let resolved = resolve_path(path, working_directory);
             let page_range = args["page_range"].as_str();
-            tool_read_pdf(&resolved, page_range, state)
-                .await
-                .map(ToolOutput::text)
+            match args["mode"].as_str() {
+                None | Some("text") => tool_read_pdf(&resolved, page_range, state)
+                    .await
+                    .map(ToolOutput::text),
+                Some("outline") => tool_read_pdf_outline(&resolved, state)
+                    .await
+                    .map(ToolOutput::text),
+                Some(other) => Err(format!(
+                    "Unknown read_pdf mode '{}' (expected 'text' or 'outline')",
+                    other
+                )),
+            }
         }
         "analyze_image" => {
-            let path = args["path"]
-                .as_str()
-                .ok_or("Missing required argument: path")?;
-            let resolved = resolve_path(path, working_directory);
             let prompt = args["prompt"].as_str();
             let extract_text = args["extract_text"].as_bool();
-            tool_analyze_image(&resolved, prompt, extract_text, state).await
+            if let Some(paths) = args["paths"].as_array() {
+                let resolved: Vec<String> = paths
+                    .iter()
+                    .map(|p| {
+                        p.as_str()
+                            .map(|p| resolve_path(p, working_directory))
+                            .ok_or("paths must be an array of strings")
+                    })
+                    .collect::<Result<_, _>>()?;
+                tool_analyze_images(&resolved, prompt, extract_text, state).await
+            } else {
+                let path = args["path"]
+                    .as_str()
+                    .ok_or("Missing required argument: path (or paths)")?;
+                let resolved = resolve_path(path, working_directory);
+                tool_analyze_image(&resolved, prompt, extract_text, state).await
+            }
         }
         "ocr_document" => {
             let path = args["path"]
@@ -475,11 +678,14 @@ pub async fn execute_tool(
                 .ok_or("Missing required argument: path")?;
             let resolved = resolve_path(path, working_directory);
             let prompt = args["prompt"].as_str();
-            tool_ocr_document(&resolved, prompt, state)
+            let language = args["language"].as_str();
+            tool_ocr_document(&resolved, prompt, language, state)
                 .await
                 .map(ToolOutput::text)
         }
-        "fetch_webpage" => web_scraping::tool_fetch_webpage(args, &state.client).await,
+        "fetch_webpage" => {
+            web_scraping::tool_fetch_webpage(args, &state.client, &state.web_cache).await
+        }
         "crawl_website" => web_scraping::tool_crawl_website(args, &state.client).await,
         // ── Git tools ──
         "git_status" => {
@@ -506,8 +712,29 @@ pub async fn execute_tool(
                 .as_str()
                 .ok_or("Missing required argument: repo_path")?;
             let resolved = resolve_path(repo, working_directory);
-            let target = args["target"].as_str();
-            git_tools::tool_git_diff(&resolved, target)
+            let mode = git_tools::DiffMode::from_args(
+                args["mode"].as_str(),
+                args["target"].as_str(),
+                args["from"].as_str(),
+                args["to"].as_str(),
+            )?;
+            let max_lines = (args["max_lines"]
+                .as_u64()
+                .unwrap_or(DEFAULT_DIFF_LINES as u64) as usize)
+                .clamp(1, MAX_DIFF_LINES);
+            git_tools::tool_git_diff(&resolved, &mode, args["path"].as_str(), max_lines)
+                .await
+                .map(ToolOutput::text)
+        }
+        "git_show" => {
+            let repo = args["repo_path"]
+                .as_str()
+                .ok_or("Missing required argument: repo_path")?;
+            let resolved = resolve_path(repo, working_directory);
+            let rev = args["ref"]
+                .as_str()
+                .ok_or("Missing required argument: ref")?;
+            git_tools::tool_git_show(&resolved, rev, args["path"].as_str())
                 .await
                 .map(ToolOutput::text)
         }
@@ -530,7 +757,28 @@ pub async fn execute_tool(
                 .as_str()
                 .ok_or("Missing required argument: message")?;
             let files = args["files"].as_str();
-            git_tools::tool_git_commit(&resolved, message, files)
+            let dry_run = args["dry_run"].as_bool().unwrap_or(false);
+            git_tools::tool_git_commit(&resolved, message, files, dry_run)
+                .await
+                .map(ToolOutput::text)
+        }
+        "git_stash" => {
+            let repo = args["repo_path"]
+                .as_str()
+                .ok_or("Missing required argument: repo_path")?;
+            let resolved = resolve_path(repo, working_directory);
+            let message = args["message"].as_str();
+            git_tools::tool_git_stash(&resolved, message)
+                .await
+                .map(ToolOutput::text)
+        }
+        "git_stash_pop" => {
+            let repo = args["repo_path"]
+                .as_str()
+                .ok_or("Missing required argument: repo_path")?;
+            let resolved = resolve_path(repo, working_directory);
+            let stash = args["stash"].as_str();
+            git_tools::tool_git_stash_pop(&resolved, stash)
                 .await
                 .map(ToolOutput::text)
         }
@@ -598,46 +846,91 @@ pub async fn execute_tool(
             .map(ToolOutput::text)
             .map_err(|e| format!("MCP tool error: {}", e)),
         // ── MCP meta tools ──
-        "list_mcp_tools" => {
-            let tools = state.mcp_client.list_all_tools().await;
-            let info: Vec<Value> = tools
-                .iter()
-                .map(|t| {
-                    json!({
-                        "name": t.prefixed_name,
-                        "server": t.server_name,
-                        "description": t.description,
-                    })
-                })
-                .collect();
-            Ok(ToolOutput::text(
-                serde_json::to_string_pretty(&info).unwrap_or_else(|_| "[]".to_string()),
-            ))
-        }
+        "list_mcp_tools" => tool_list_mcp_tools(state).await.map(ToolOutput::text),
         "execute_mcp_tool" => {
-            let tool_name = args["tool_name"].as_str().ok_or("Missing tool_name")?;
             let tool_args = args.get("arguments").cloned().unwrap_or(json!({}));
-            state
-                .mcp_client
-                .call_tool(tool_name, &tool_args)
-                .await
+            let result = match (args["server"].as_str(), args["tool"].as_str()) {
+                (Some(server), Some(tool)) => {
+                    state
+                        .mcp_client
+                        .call_server_tool(server, tool, &tool_args)
+                        .await
+                }
+                _ => {
+                    // Legacy form: a single prefixed `tool_name` (mcp_{server}_{tool})
+                    let tool_name = args["tool_name"]
+                        .as_str()
+                        .ok_or("Missing required arguments: server and tool")?;
+                    state.mcp_client.call_tool(tool_name, &tool_args).await
+                }
+            };
+            result
                 .map(ToolOutput::text)
                 .map_err(|e| format!("MCP tool error: {}", e))
         }
         _ => Err(format!("Unknown tool: {}", name)),
-    };
+    }
+}
 
-    let elapsed = tool_start.elapsed();
-    match &result {
-        Ok(_) => tracing::debug!("Tool '{}' completed in {:.2}s", name, elapsed.as_secs_f64()),
-        Err(e) => tracing::warn!(
-            "Tool '{}' failed in {:.2}s: {}",
-            name,
-            elapsed.as_secs_f64(),
-            e
-        ),
+// ---------------------------------------------------------------------------
+// list_mcp_tools
+// ---------------------------------------------------------------------------
+
+/// Max description length per tool in the MCP catalog.
+const MCP_CATALOG_DESC_CHARS: usize = 140;
+
+/// Compact catalog of MCP tools from `gh_mcp_discovered_tools`, grouped by
+/// server, with each server's live connection status.
+async fn tool_list_mcp_tools(state: &AppState) -> Result<String, String> {
+    let servers = crate::mcp::config::list_mcp_servers(&state.db)
+        .await
+        .map_err(|e| format!("Failed to load MCP servers: {}", e))?;
+    let tools = crate::mcp::config::list_all_discovered_tools(&state.db)
+        .await
+        .map_err(|e| format!("Failed to load MCP tools: {}", e))?;
+    let connected: std::collections::HashSet<String> = state
+        .mcp_client
+        .list_all_tools()
+        .await
+        .into_iter()
+        .map(|t| t.server_id)
+        .collect();
+
+    if tools.is_empty() {
+        return Ok("No MCP tools discovered. Connect an MCP server first.".to_string());
     }
-    result
+
+    let mut out = format!(
+        "{} MCP tool(s). Call with execute_mcp_tool {{ server, tool, arguments }}.\n",
+        tools.len()
+    );
+    for server in &servers {
+        let server_tools: Vec<_> = tools.iter().filter(|t| t.server_id == server.id).collect();
+        if server_tools.is_empty() {
+            continue;
+        }
+        let status = if connected.contains(&server.id) {
+            "connected"
+        } else {
+            "offline"
+        };
+        out.push_str(&format!("\n## {} ({})\n", server.name, status));
+        for t in server_tools {
+            let desc = t.description.as_deref().unwrap_or("").replace('\n', " ");
+            let desc = if desc.chars().count() > MCP_CATALOG_DESC_CHARS {
+                let cut: String = desc.chars().take(MCP_CATALOG_DESC_CHARS).collect();
+                format!("{}...", cut.trim_end())
+            } else {
+                desc
+            };
+            if desc.is_empty() {
+                out.push_str(&format!("- {}\n", t.tool_name));
+            } else {
+                out.push_str(&format!("- {} — {}\n", t.tool_name, desc));
+            }
+        }
+    }
+    Ok(out.trim_end().to_string())
 }
 
 // ---------------------------------------------------------------------------
@@ -648,12 +941,80 @@ async fn tool_execute_command(
     command: &str,
     working_directory: Option<&str>,
     state: &AppState,
-) -> Result<String, String> {
-    let lower = command.to_lowercase();
-    for pattern in BLOCKED_PATTERNS {
-        if lower.contains(pattern) {
-            return Err(format!("Blocked dangerous command pattern: {}", pattern));
+) -> Result<ToolOutput, String> {
+    let output = run_policed_command(command, working_directory, state).await?;
+
+    let mut result = String::new();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !stdout.is_empty() {
+        result.push_str(&stdout);
+    }
+    if !stderr.is_empty() {
+        if !result.is_empty() {
+            result.push_str("\n--- stderr ---\n");
+        }
+        result.push_str(&stderr);
+    }
+
+    if result.is_empty() {
+        result = format!(
+            "Command completed with exit code: {}",
+            output.status.code().unwrap_or(-1)
+        );
+    }
+
+    // Truncate if too large
+    if result.len() > MAX_COMMAND_OUTPUT {
+        result.truncate(MAX_COMMAND_OUTPUT);
+        result.push_str("\n... [output truncated at 50KB]");
+    }
+
+    let code = output.status.code().unwrap_or(-1);
+    let text = if output.status.success() {
+        result
+    } else {
+        format!("[exit code: {}]\n{}", code, result)
+    };
+    Ok(ToolOutput::text(text).with_exit_code(code))
+}
+
+/// Run `command` under the settings-driven command policy: allowlist mode,
+/// the denylists, and the Docker sandbox when enabled. Shared by
+/// `execute_command` and `run_tests`.
+async fn run_policed_command(
+    command: &str,
+    working_directory: Option<&str>,
+    state: &AppState,
+) -> Result<std::process::Output, String> {
+    let (use_sandbox, command_allowlist, extra_blocked) =
+        sqlx::query_as::<_, (bool, Vec<String>, Vec<String>)>(
+            "SELECT use_docker_sandbox, command_allowlist, extra_blocked_patterns \
+             FROM gh_settings WHERE id = 1",
+        )
+        .fetch_one(&state.db)
+        .await
+        // Fail closed: without the policy we can't tell what is allowed
+        .map_err(|e| format!("Command rejected: cannot read command policy: {}", e))?;
+
+    if let Err(rejection) = command_policy::check_command(
+        command,
+        &command_allowlist,
+        BLOCKED_PATTERNS,
+        &extra_blocked,
+    ) {
+        if let command_policy::Rejection::NotAllowed(reason) = &rejection {
+            crate::audit::log_audit(
+                &state.db,
+                "execute_command_rejected",
+                json!({ "command": command, "reason": reason }),
+                None,
+            )
+            .await;
         }
+        return Err(rejection.to_string());
     }
 
     // Validate and resolve working directory
@@ -670,13 +1031,6 @@ async fn tool_execute_command(
         None
     };
 
-    // Check sandbox setting
-    let use_sandbox =
-        sqlx::query_scalar::<_, bool>("SELECT use_docker_sandbox FROM gh_settings WHERE id = 1")
-            .fetch_one(&state.db)
-            .await
-            .unwrap_or(false);
-
     let output_res = if use_sandbox {
         // Run in Docker — mount working dir (or cwd) to /app
         let mount_dir = cwd
@@ -712,44 +1066,9 @@ async fn tool_execute_command(
         tokio::time::timeout(COMMAND_TIMEOUT, run_command(command, cwd.as_deref())).await
     };
 
-    let output = output_res
+    output_res
         .map_err(|_| format!("Command timed out after {}s", COMMAND_TIMEOUT.as_secs()))?
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
-
-    let mut result = String::new();
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    if !stdout.is_empty() {
-        result.push_str(&stdout);
-    }
-    if !stderr.is_empty() {
-        if !result.is_empty() {
-            result.push_str("\n--- stderr ---\n");
-        }
-        result.push_str(&stderr);
-    }
-
-    if result.is_empty() {
-        result = format!(
-            "Command completed with exit code: {}",
-            output.status.code().unwrap_or(-1)
-        );
-    }
-
-    // Truncate if too large
-    if result.len() > MAX_COMMAND_OUTPUT {
-        result.truncate(MAX_COMMAND_OUTPUT);
-        result.push_str("\n... [output truncated at 50KB]");
-    }
-
-    if output.status.success() {
-        Ok(result)
-    } else {
-        let code = output.status.code().unwrap_or(-1);
-        Ok(format!("[exit code: {}]\n{}", code, result))
-    }
+        .map_err(|e| format!("Failed to execute command: {}", e))
 }
 
 async fn run_command(
@@ -780,7 +1099,10 @@ async fn tool_read_file(path: &str) -> Result<String, String> {
         .await
         .map_err(|e| format!("Cannot read file '{}': {}", e.path, e.reason))?;
 
-    let mut result = ctx.content;
+    let mut result = match ctx.encoding {
+        Some(encoding) => format!("[Decoded from {} to UTF-8]\n{}", encoding, ctx.content),
+        None => ctx.content,
+    };
     if ctx.truncated {
         result.push_str("\n... [file truncated]");
     }
@@ -791,8 +1113,19 @@ async fn tool_read_file(path: &str) -> Result<String, String> {
 // write_file
 // ---------------------------------------------------------------------------
 
-async fn tool_write_file(path: &str, content: &str) -> Result<String, String> {
-    crate::files::write_file(path, content)
+/// Write cap from the `max_write_bytes` setting, shared by write_file and edit_file.
+pub(crate) async fn max_write_bytes(state: &AppState) -> usize {
+    state
+        .settings()
+        .await
+        .ok()
+        .and_then(|s| usize::try_from(s.max_write_bytes).ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(crate::files::DEFAULT_MAX_WRITE_BYTES)
+}
+
+async fn tool_write_file(path: &str, content: &str, max_bytes: usize) -> Result<String, String> {
+    crate::files::write_file(path, content, max_bytes)
         .await
         .map_err(|e| format!("Cannot write file '{}': {}", e.path, e.reason))
 }
@@ -803,7 +1136,13 @@ async fn tool_write_file(path: &str, content: &str) -> Result<String, String> {
 
 /// Edit a file by replacing a specific text section.
 /// Safer than write_file for modifications — only changes the targeted section.
-async fn tool_edit_file(path: &str, old_text: &str, new_text: &str) -> Result<String, String> {
+/// The edited content is held to the same `max_bytes` cap as write_file.
+async fn tool_edit_file(
+    path: &str,
+    old_text: &str,
+    new_text: &str,
+    max_bytes: usize,
+) -> Result<String, String> {
     let p = std::path::Path::new(path);
 
     if let Err(e) = crate::files::validate_write_path(path) {
@@ -847,6 +1186,8 @@ async fn tool_edit_file(path: &str, old_text: &str, new_text: &str) -> Result<St
                 if end_line <= content_lines.len() {
                     let original_section = content_lines[start_line..end_line].join("\n");
                     let new_content = content.replacen(&original_section, new_text, 1);
+                    crate::files::check_write_size(path, new_content.len(), max_bytes)
+                        .map_err(|e| format!("Cannot edit file '{}': {}", e.path, e.reason))?;
                     tokio::fs::write(p, &new_content)
                         .await
                         .map_err(|e| format!("Failed to write file: {}", e))?;
@@ -874,6 +1215,8 @@ async fn tool_edit_file(path: &str, old_text: &str, new_text: &str) -> Result<St
     }
 
     let new_content = content.replacen(old_text, new_text, 1);
+    crate::files::check_write_size(path, new_content.len(), max_bytes)
+        .map_err(|e| format!("Cannot edit file '{}': {}", e.path, e.reason))?;
     tokio::fs::write(p, &new_content)
         .await
         .map_err(|e| format!("Failed to write file: {}", e))?;
@@ -915,38 +1258,142 @@ async fn tool_delete_file(path: &str) -> Result<String, String> {
 }
 
 // ---------------------------------------------------------------------------
-// list_directory
+// copy_file
 // ---------------------------------------------------------------------------
 
-async fn tool_list_directory(path: &str, show_hidden: bool) -> Result<String, String> {
-    let entries = crate::files::list_directory(path, show_hidden)
-        .await
-        .map_err(|e| format!("Cannot list '{}': {}", e.path, e.reason))?;
-
-    if entries.is_empty() {
-        return Ok("(empty directory)".to_string());
+/// Copy a single file. Parent directories of `dst` are created as needed;
+/// an existing `dst` is only replaced when `overwrite` is set.
+async fn tool_copy_file(src: &str, dst: &str, overwrite: bool) -> Result<String, String> {
+    let src_path = crate::files::validate_write_path(src)
+        .map_err(|e| format!("Source rejected: {}", e.reason))?;
+    if !src_path.is_file() {
+        return Err(format!("Source is not a file: {}", src));
     }
 
-    // Parallel line counting via JoinSet
-    let mut line_count_set = tokio::task::JoinSet::new();
-    for entry in &entries {
-        if !entry.is_dir
-            && entry.size_bytes < 1024 * 1024
-            && crate::files::is_text_file(std::path::Path::new(&entry.path))
-        {
-            let path_owned = entry.path.clone();
-            line_count_set.spawn(async move {
-                let count = count_lines(&path_owned).await;
-                (path_owned, count)
-            });
-        }
+    let dst_raw = std::path::Path::new(dst);
+    if dst_raw.is_dir() {
+        return Err(format!(
+            "Destination is a directory: {} (pass the full target file path)",
+            dst
+        ));
     }
-    let mut line_counts = std::collections::HashMap::new();
-    while let Some(Ok((path_key, count))) = line_count_set.join_next().await {
-        line_counts.insert(path_key, count);
+    if dst_raw.exists() && !overwrite {
+        return Err(format!(
+            "Destination already exists: {} (set overwrite=true to replace it)",
+            dst
+        ));
     }
 
-    let mut lines = Vec::with_capacity(entries.len());
+    // Parent must exist before validation can canonicalize it (same as write_file)
+    if let Some(parent) = dst_raw
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty() && !p.exists())
+    {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Cannot create parent directory: {}", e))?;
+    }
+    let dst_path = crate::files::validate_write_path(dst)
+        .map_err(|e| format!("Destination rejected: {}", e.reason))?;
+    if dst_path == src_path {
+        return Err("Source and destination are the same file".to_string());
+    }
+
+    let bytes = tokio::fs::copy(&src_path, &dst_path)
+        .await
+        .map_err(|e| format!("Failed to copy file: {}", e))?;
+
+    Ok(format!(
+        "Copied {} -> {} ({} bytes)",
+        src_path.display(),
+        dst_path.display(),
+        bytes
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// create_directory
+// ---------------------------------------------------------------------------
+
+/// Create a directory and any missing parents. Succeeds if it already exists.
+async fn tool_create_directory(path: &str) -> Result<String, String> {
+    let p = std::path::Path::new(path);
+    if p.is_dir() {
+        let canonical = crate::files::validate_write_path(path)
+            .map_err(|e| format!("Path rejected: {}", e.reason))?;
+        return Ok(format!("Directory already exists: {}", canonical.display()));
+    }
+    if p.exists() {
+        return Err(format!("A file already exists at {}", path));
+    }
+
+    if let Some(parent) = p
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty() && !p.exists())
+    {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Cannot create parent directory: {}", e))?;
+    }
+    let canonical = crate::files::validate_write_path(path)
+        .map_err(|e| format!("Path rejected: {}", e.reason))?;
+
+    match tokio::fs::create_dir(&canonical).await {
+        Ok(()) => Ok(format!("Created directory {}", canonical.display())),
+        // Lost a race with a concurrent creator — still idempotent
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && canonical.is_dir() => {
+            Ok(format!("Directory already exists: {}", canonical.display()))
+        }
+        Err(e) => Err(format!("Failed to create directory: {}", e)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// list_directory
+// ---------------------------------------------------------------------------
+
+async fn tool_list_directory(
+    path: &str,
+    show_hidden: bool,
+    respect_gitignore: bool,
+) -> Result<String, String> {
+    let mut entries = crate::files::list_directory(path, show_hidden)
+        .await
+        .map_err(|e| format!("Cannot list '{}': {}", e.path, e.reason))?;
+
+    // Entry paths are canonical, so match against the canonical directory
+    if respect_gitignore
+        && let Some(gitignore) = std::fs::canonicalize(path)
+            .ok()
+            .and_then(|dir| GitignoreFilter::for_root(&dir))
+    {
+        entries.retain(|e| !gitignore.is_ignored(std::path::Path::new(&e.path), e.is_dir));
+    }
+
+    if entries.is_empty() {
+        return Ok("(empty directory)".to_string());
+    }
+
+    // Parallel line counting via JoinSet
+    let mut line_count_set = tokio::task::JoinSet::new();
+    for entry in &entries {
+        if !entry.is_dir
+            && entry.size_bytes < 1024 * 1024
+            && crate::files::is_text_file(std::path::Path::new(&entry.path))
+        {
+            let path_owned = entry.path.clone();
+            line_count_set.spawn(async move {
+                let count = count_lines(&path_owned).await;
+                (path_owned, count)
+            });
+        }
+    }
+    let mut line_counts = std::collections::HashMap::new();
+    while let Some(Ok((path_key, count))) = line_count_set.join_next().await {
+        line_counts.insert(path_key, count);
+    }
+
+    let mut lines = Vec::with_capacity(entries.len());
     for entry in &entries {
         if entry.is_dir {
             lines.push(format!("  [DIR]  {}/", entry.name));
@@ -1003,9 +1450,22 @@ fn format_size(bytes: u64) -> String {
 /// Max search results to return.
 const MAX_SEARCH_RESULTS: usize = 150;
 
+/// Output budget for one page of results — long lines can make even a
+/// modest `limit` enormous.
+const SEARCH_OUTPUT_BUDGET: usize = 64 * 1024;
+
+/// Longest line shown in results (#15).
+const SEARCH_LINE_MAX: usize = 500;
+
+/// Bytes kept before the match when a long matching line is windowed.
+const SEARCH_LINE_LEAD: usize = 120;
+
 /// Max directory depth for recursive search.
 const MAX_SEARCH_DEPTH: usize = 12;
 
+/// Max `context_before` / `context_after` lines per hit.
+const MAX_SEARCH_CONTEXT: usize = 5;
+
 /// Directories to skip during search.
 const SKIP_DIRS: &[&str] = &[
     "node_modules",
@@ -1022,14 +1482,198 @@ const SKIP_DIRS: &[&str] = &[
     ".turbo",
 ];
 
+/// Include/exclude glob filter for `search_files`, matched against the path
+/// relative to the search root (with `/` separators) and the bare file name.
+struct SearchPathFilter {
+    include: Vec<glob::Pattern>,
+    /// `None` keeps the built-in `SKIP_DIRS` + hidden-directory exclusion.
+    exclude: Option<Vec<glob::Pattern>>,
+}
+
+impl SearchPathFilter {
+    fn from_args(args: &Value) -> Result<Self, String> {
+        let include = Self::parse_globs(&args["include_globs"])?.unwrap_or_default();
+        let exclude = Self::parse_globs(&args["exclude_globs"])?;
+        Ok(Self { include, exclude })
+    }
+
+    fn parse_globs(value: &Value) -> Result<Option<Vec<glob::Pattern>>, String> {
+        let Some(arr) = value.as_array() else {
+            return Ok(None);
+        };
+        arr.iter()
+            .filter_map(|v| v.as_str())
+            .map(|g| {
+                glob::Pattern::new(g).map_err(|e| format!("Invalid glob pattern '{}': {}", g, e))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+
+    fn matches(patterns: &[glob::Pattern], rel: &str, name: &str) -> bool {
+        patterns.iter().any(|p| p.matches(rel) || p.matches(name))
+    }
+
+    fn is_excluded(&self, rel: &str, name: &str, is_dir: bool) -> bool {
+        match &self.exclude {
+            Some(patterns) => Self::matches(patterns, rel, name),
+            None => (is_dir && name.starts_with('.')) || SKIP_DIRS.contains(&name),
+        }
+    }
+
+    fn is_included(&self, rel: &str, name: &str) -> bool {
+        self.include.is_empty() || Self::matches(&self.include, rel, name)
+    }
+}
+
+/// Pagination and output-shape options for `search_files`.
+struct SearchOptions {
+    offset: usize,
+    limit: usize,
+    multiline: bool,
+    context_before: usize,
+    context_after: usize,
+}
+
+/// A single `search_files` result. Pagination counts entries, so each
+/// `Hit` is one distinct match even when its context overlaps a neighbour.
+enum SearchResult {
+    Text(String),
+    /// Line-mode match with surrounding context: `(1-based line, text)`.
+    Hit {
+        path: String,
+        match_line: usize,
+        lines: Vec<(usize, String)>,
+    },
+}
+
+/// Truncate very long lines (#15: 500 chars).
+fn truncate_search_line(line: &str) -> String {
+    if line.len() > SEARCH_LINE_MAX {
+        let end = line
+            .char_indices()
+            .take_while(|(i, _)| *i < SEARCH_LINE_MAX)
+            .last()
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(SEARCH_LINE_MAX.min(line.len()));
+        format!("{}...", &line[..end])
+    } else {
+        line.to_string()
+    }
+}
+
+/// Like `truncate_search_line`, but keeps a window around the first match so
+/// a hit far into a long (e.g. minified) line is still visible.
+fn window_search_line(line: &str, re: &Regex) -> String {
+    let match_start = re.find(line).map(|m| m.start()).unwrap_or(0);
+    if line.len() <= SEARCH_LINE_MAX || match_start < SEARCH_LINE_LEAD {
+        return truncate_search_line(line);
+    }
+    let boundary = |mut i: usize| {
+        while !line.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    let start = boundary(match_start - SEARCH_LINE_LEAD);
+    let end = boundary((start + SEARCH_LINE_MAX).min(line.len()));
+    let tail = if end < line.len() { "..." } else { "" };
+    format!("...{}{}", &line[start..end], tail)
+}
+
+/// How many results of `page` fit in `budget` bytes of output (at least one,
+/// so a single huge hit is still shown).
+fn fit_search_page(page: &[SearchResult], budget: usize) -> usize {
+    let mut used = 0;
+    for (i, result) in page.iter().enumerate() {
+        used += match result {
+            SearchResult::Text(text) => text.len() + 1,
+            SearchResult::Hit { path, lines, .. } => {
+                path.len() + lines.iter().map(|(_, l)| l.len() + 10).sum::<usize>()
+            }
+        };
+        if used > budget {
+            return i.max(1);
+        }
+    }
+    page.len()
+}
+
+/// Render a page of results, merging context hits in the same file whose
+/// windows overlap or touch so no line is printed twice.
+fn render_search_page(page: &[SearchResult]) -> String {
+    // (path, lines as (line_no, text, is_match))
+    type Group<'a> = (&'a str, Vec<(usize, &'a str, bool)>);
+
+    fn flush(group: &mut Option<Group<'_>>, out: &mut Vec<String>) {
+        if let Some((path, lines)) = group.take() {
+            let first = lines.first().map(|l| l.0).unwrap_or(0);
+            let last = lines.last().map(|l| l.0).unwrap_or(0);
+            let body: Vec<String> = lines
+                .iter()
+                .map(|(n, text, is_match)| {
+                    format!("{} {:>4} | {}", if *is_match { ">" } else { " " }, n, text)
+                })
+                .collect();
+            out.push(format!("{}:{}-{}:\n{}", path, first, last, body.join("\n")));
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut group: Option<Group<'_>> = None;
+
+    for result in page {
+        match result {
+            SearchResult::Text(text) => {
+                flush(&mut group, &mut out);
+                out.push(text.clone());
+            }
+            SearchResult::Hit {
+                path,
+                match_line,
+                lines,
+            } => {
+                let first = lines.first().map(|l| l.0).unwrap_or(*match_line);
+                let mergeable = matches!(&group, Some((p, g))
+                    if *p == path.as_str() && g.last().is_some_and(|l| l.0 + 1 >= first));
+                if !mergeable {
+                    flush(&mut group, &mut out);
+                    group = Some((path.as_str(), Vec::new()));
+                }
+                if let Some((_, g)) = group.as_mut() {
+                    let last = g.last().map(|l| l.0).unwrap_or(0);
+                    for (n, text) in lines {
+                        if *n > last {
+                            g.push((*n, text.as_str(), false));
+                        }
+                    }
+                    if let Some(l) = g.iter_mut().find(|l| l.0 == *match_line) {
+                        l.2 = true;
+                    }
+                }
+            }
+        }
+    }
+    flush(&mut group, &mut out);
+    out.join("\n")
+}
+
 async fn tool_search_files(
     path: &str,
     pattern: &str,
     extensions: Option<&str>,
-    offset: usize,
-    limit: usize,
-    multiline: bool,
+    filter: &SearchPathFilter,
+    respect_gitignore: bool,
+    opts: &SearchOptions,
 ) -> Result<String, String> {
+    let SearchOptions {
+        offset,
+        limit,
+        multiline,
+        context_before,
+        context_after,
+    } = *opts;
+
     let dir = std::path::Path::new(path);
     if !dir.is_dir() {
         return Err(format!("'{}' is not a directory", path));
@@ -1054,6 +1698,9 @@ async fn tool_search_files(
     const MAX_RESULT_BYTES: usize = 5 * 1024 * 1024; // 5MB
     const MAX_STACK_SIZE: usize = 10000;
     let mut files_searched: usize = 0;
+    let mut gitignore = respect_gitignore
+        .then(|| GitignoreFilter::for_root(dir))
+        .flatten();
     let mut stack: Vec<(std::path::PathBuf, usize)> = vec![(dir.to_path_buf(), 0)];
 
     while let Some((current_dir, depth)) = stack.pop() {
@@ -1068,6 +1715,11 @@ async fn tool_search_files(
             Ok(e) => e,
             Err(_) => continue,
         };
+        if depth > 0
+            && let Some(gitignore) = gitignore.as_mut()
+        {
+            gitignore.enter_dir(&current_dir);
+        }
 
         while let Ok(Some(entry)) = entries.next_entry().await {
             if all_results.len() >= MAX_SEARCH_RESULTS {
@@ -1076,20 +1728,31 @@ async fn tool_search_files(
 
             let entry_path = entry.path();
             let name = entry.file_name().to_string_lossy().to_string();
-
-            // Skip hidden and ignored directories
-            if name.starts_with('.') && entry_path.is_dir() {
-                continue;
-            }
-            if SKIP_DIRS.contains(&name.as_str()) {
+            let rel = entry_path
+                .strip_prefix(dir)
+                .unwrap_or(&entry_path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let is_dir = entry_path.is_dir();
+
+            // Skip excluded paths (defaults: hidden + SKIP_DIRS) and gitignored ones
+            if filter.is_excluded(&rel, &name, is_dir)
+                || gitignore
+                    .as_ref()
+                    .is_some_and(|g| g.is_ignored(&entry_path, is_dir))
+            {
                 continue;
             }
 
-            if entry_path.is_dir() {
+            if is_dir {
                 if stack.len() < MAX_STACK_SIZE {
                     stack.push((entry_path, depth + 1));
                 }
             } else if entry_path.is_file() {
+                if !filter.is_included(&rel, &name) {
+                    continue;
+                }
+
                 let ext = entry_path
                     .extension()
                     .and_then(|e| e.to_str())
@@ -1137,12 +1800,50 @@ async fn tool_search_files(
                             );
                             cumulative_result_bytes += result_str.len();
                             if cumulative_result_bytes > MAX_RESULT_BYTES {
-                                all_results
-                                    .push("... [results truncated due to size limit]".to_string());
+                                all_results.push(SearchResult::Text(
+                                    "... [results truncated due to size limit]".to_string(),
+                                ));
                                 size_limit_hit = true;
                                 break;
                             }
-                            all_results.push(result_str);
+                            all_results.push(SearchResult::Text(result_str));
+                        }
+                    } else if context_before > 0 || context_after > 0 {
+                        // Line mode with grep-style -B/-A context
+                        let lines: Vec<&str> = content.lines().collect();
+                        for (idx, line) in lines.iter().enumerate() {
+                            if all_results.len() >= MAX_SEARCH_RESULTS {
+                                break;
+                            }
+                            if re.is_match(line) {
+                                let start = idx.saturating_sub(context_before);
+                                let end = (idx + context_after + 1).min(lines.len());
+                                let ctx: Vec<(usize, String)> = (start..end)
+                                    .map(|i| {
+                                        let text = lines[i].trim_end();
+                                        let text = if i == idx {
+                                            window_search_line(text, &re)
+                                        } else {
+                                            truncate_search_line(text)
+                                        };
+                                        (i + 1, text)
+                                    })
+                                    .collect();
+                                cumulative_result_bytes +=
+                                    ctx.iter().map(|(_, l)| l.len() + 8).sum::<usize>();
+                                if cumulative_result_bytes > MAX_RESULT_BYTES {
+                                    all_results.push(SearchResult::Text(
+                                        "... [results truncated due to size limit]".to_string(),
+                                    ));
+                                    size_limit_hit = true;
+                                    break;
+                                }
+                                all_results.push(SearchResult::Hit {
+                                    path: entry_path.display().to_string(),
+                                    match_line: idx + 1,
+                                    lines: ctx,
+                                });
+                            }
                         }
                     } else {
                         // Line-by-line mode (default, faster)
@@ -1151,19 +1852,7 @@ async fn tool_search_files(
                                 break;
                             }
                             if re.is_match(line) {
-                                let trimmed = line.trim();
-                                // Truncate very long lines (#15: 500 chars)
-                                let display = if trimmed.len() > 500 {
-                                    let end = trimmed
-                                        .char_indices()
-                                        .take_while(|(i, _)| *i < 500)
-                                        .last()
-                                        .map(|(i, c)| i + c.len_utf8())
-                                        .unwrap_or(500.min(trimmed.len()));
-                                    format!("{}...", &trimmed[..end])
-                                } else {
-                                    trimmed.to_string()
-                                };
+                                let display = window_search_line(line.trim(), &re);
                                 let result_str = format!(
                                     "{}:{}:  {}",
                                     entry_path.display(),
@@ -1172,13 +1861,13 @@ async fn tool_search_files(
                                 );
                                 cumulative_result_bytes += result_str.len();
                                 if cumulative_result_bytes > MAX_RESULT_BYTES {
-                                    all_results.push(
+                                    all_results.push(SearchResult::Text(
                                         "... [results truncated due to size limit]".to_string(),
-                                    );
+                                    ));
                                     size_limit_hit = true;
                                     break;
                                 }
-                                all_results.push(result_str);
+                                all_results.push(SearchResult::Text(result_str));
                             }
                         }
                     }
@@ -1199,15 +1888,23 @@ async fn tool_search_files(
             pattern, path, files_searched
         ))
     } else {
-        // Apply pagination
-        let page: Vec<&String> = all_results.iter().skip(offset).take(limit).collect();
-        let shown_start = offset + 1;
-        let shown_end = (offset + page.len()).min(total);
-        let page_str: String = page
-            .iter()
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
+        // Apply pagination, then the output budget
+        let page = &all_results[offset.min(total)..offset.saturating_add(limit).min(total)];
+        let kept = fit_search_page(page, SEARCH_OUTPUT_BUDGET);
+        let omitted = page.len() - kept;
+        let page = &page[..kept];
+        let shown_start = offset.saturating_add(1);
+        let shown_end = offset.saturating_add(page.len()).min(total);
+        let mut page_str = render_search_page(page);
+        if omitted > 0 {
+            page_str.push_str(&format!(
+                "\n\n[{} more matches omitted — output exceeded {} KB. Narrow the pattern or \
+                 continue with offset={}; the results above are NOT complete.]",
+                omitted,
+                SEARCH_OUTPUT_BUDGET / 1024,
+                offset.saturating_add(kept)
+            ));
+        }
 
         let truncated = if total >= MAX_SEARCH_RESULTS {
             format!(" (capped at {} total results)", MAX_SEARCH_RESULTS)
@@ -1227,6 +1924,15 @@ async fn tool_search_files(
 // ---------------------------------------------------------------------------
 
 async fn tool_get_code_structure(path: &str) -> Result<String, String> {
+    let extension = crate::analysis::file_extension(path);
+    if !crate::analysis::is_supported_extension(&extension) {
+        return Ok(format!(
+            "Unsupported language for '{}' (supported extensions: {}). Use read_file or read_file_section instead.",
+            path,
+            crate::analysis::SUPPORTED_EXTENSIONS.join(", ")
+        ));
+    }
+
     // Read file (reuse context reader for safety/limits)
     let ctx = crate::files::read_file_for_context(path)
         .await
@@ -1234,7 +1940,7 @@ async fn tool_get_code_structure(path: &str) -> Result<String, String> {
 
     // Analyze (tree-sitter first, regex fallback)
     if let Some(structure) =
-        crate::analysis::analyze_file_async(ctx.path.clone(), ctx.content.clone()).await
+        crate::analysis::analyze_file_async(ctx.path.clone(), extension, ctx.content.clone()).await
     {
         let mut out = format!("### Code Structure: {}\n", ctx.path);
         if structure.symbols.is_empty() {
@@ -1319,13 +2025,36 @@ async fn tool_read_file_section(
 /// Max results for find_file.
 const MAX_FIND_RESULTS: usize = 50;
 
-/// Find files by glob pattern (simple wildcard matching).
-async fn tool_find_file(path: &str, pattern: &str) -> Result<String, String> {
+/// Max name-matched files whose content `find_file` scans for `contains`.
+const MAX_FIND_CONTENT_SCANS: usize = 500;
+
+/// A `find_file` hit: path, size, and the first `(line, text)` matching `contains`.
+type FoundFile = (String, u64, Option<(usize, String)>);
+
+/// Find files by glob pattern (simple wildcard matching). With `contains`,
+/// keep only text files with a line matching it (regex, or literal if invalid).
+async fn tool_find_file(
+    path: &str,
+    pattern: &str,
+    contains: Option<&str>,
+    respect_gitignore: bool,
+) -> Result<String, String> {
     let dir = std::path::Path::new(path);
     if !dir.is_dir() {
         return Err(format!("'{}' is not a directory", path));
     }
 
+    // Same regex-then-literal fallback as search_files
+    let content_re = contains
+        .map(|c| {
+            Regex::new(&format!("(?i){}", c))
+                .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(c))))
+                .map_err(|e| format!("Invalid contains pattern '{}': {}", c, e))
+        })
+        .transpose()?;
+    let mut files_scanned: usize = 0;
+    let mut scan_capped = false;
+
     // Convert glob pattern to regex: * -> .*, ? -> ., escape the rest
     let mut regex_str = String::from("(?i)^");
     for ch in pattern.chars() {
@@ -1344,11 +2073,14 @@ async fn tool_find_file(path: &str, pattern: &str) -> Result<String, String> {
     let re =
         Regex::new(&regex_str).map_err(|e| format!("Invalid glob pattern '{}': {}", pattern, e))?;
 
-    let mut results: Vec<(String, u64)> = Vec::new();
+    let mut results: Vec<FoundFile> = Vec::new();
+    let mut gitignore = respect_gitignore
+        .then(|| GitignoreFilter::for_root(dir))
+        .flatten();
     let mut stack: Vec<(std::path::PathBuf, usize)> = vec![(dir.to_path_buf(), 0)];
 
     while let Some((current_dir, depth)) = stack.pop() {
-        if depth > MAX_SEARCH_DEPTH || results.len() >= MAX_FIND_RESULTS {
+        if depth > MAX_SEARCH_DEPTH || results.len() >= MAX_FIND_RESULTS || scan_capped {
             break;
         }
 
@@ -1356,9 +2088,14 @@ async fn tool_find_file(path: &str, pattern: &str) -> Result<String, String> {
             Ok(e) => e,
             Err(_) => continue,
         };
+        if depth > 0
+            && let Some(gitignore) = gitignore.as_mut()
+        {
+            gitignore.enter_dir(&current_dir);
+        }
 
         while let Ok(Some(entry)) = entries.next_entry().await {
-            if results.len() >= MAX_FIND_RESULTS {
+            if results.len() >= MAX_FIND_RESULTS || scan_capped {
                 break;
             }
 
@@ -1372,45 +2109,204 @@ async fn tool_find_file(path: &str, pattern: &str) -> Result<String, String> {
             if SKIP_DIRS.contains(&name.as_str()) {
                 continue;
             }
+            let is_dir = entry_path.is_dir();
+            if gitignore
+                .as_ref()
+                .is_some_and(|g| g.is_ignored(&entry_path, is_dir))
+            {
+                continue;
+            }
 
-            if entry_path.is_dir() {
+            if is_dir {
                 stack.push((entry_path, depth + 1));
             } else if entry_path.is_file() && re.is_match(&name) {
                 let size = entry.metadata().await.map(|m| m.len()).unwrap_or(0);
-                results.push((entry_path.to_string_lossy().to_string(), size));
+                let first_match = match &content_re {
+                    None => None,
+                    Some(content_re) => {
+                        // Only text files are scanned, like search_files
+                        if !crate::files::is_text_file(&entry_path) {
+                            continue;
+                        }
+                        if files_scanned >= MAX_FIND_CONTENT_SCANS {
+                            scan_capped = true;
+                            break;
+                        }
+                        files_scanned += 1;
+                        let Ok(content) = tokio::fs::read_to_string(&entry_path).await else {
+                            continue;
+                        };
+                        let Some(hit) = content
+                            .lines()
+                            .enumerate()
+                            .find(|(_, line)| content_re.is_match(line))
+                            .map(|(idx, line)| (idx + 1, truncate_search_line(line.trim())))
+                        else {
+                            continue;
+                        };
+                        Some(hit)
+                    }
+                };
+                results.push((entry_path.to_string_lossy().to_string(), size, first_match));
             }
         }
     }
 
+    let criteria = match contains {
+        Some(c) => format!("'{}' containing '{}'", pattern, c),
+        None => format!("'{}'", pattern),
+    };
+    let scan_note = if scan_capped {
+        format!(
+            "\n[content scan stopped after {} files — narrow the glob pattern or path]",
+            MAX_FIND_CONTENT_SCANS
+        )
+    } else {
+        String::new()
+    };
+
     if results.is_empty() {
         Ok(format!(
-            "No files matching '{}' found in '{}'",
-            pattern, path
+            "No files matching {} found in '{}'{}",
+            criteria, path, scan_note
         ))
     } else {
         let mut lines = Vec::with_capacity(results.len());
-        for (file_path, size) in &results {
-            lines.push(format!("  {} ({})", file_path, format_size(*size)));
+        for (file_path, size, first_match) in &results {
+            match first_match {
+                Some((line_no, text)) => lines.push(format!(
+                    "  {}:{} ({}):  {}",
+                    file_path,
+                    line_no,
+                    format_size(*size),
+                    text
+                )),
+                None => lines.push(format!("  {} ({})", file_path, format_size(*size))),
+            }
         }
         Ok(format!(
-            "Found {} file(s) matching '{}' in {}:\n{}",
+            "Found {} file(s) matching {} in {}:\n{}{}",
             results.len(),
-            pattern,
+            criteria,
             path,
-            lines.join("\n")
+            lines.join("\n"),
+            scan_note
         ))
     }
 }
 
+// ---------------------------------------------------------------------------
+// file_stat
+// ---------------------------------------------------------------------------
+
+/// Files up to this size get an exact line count; larger ones are extrapolated
+/// from a sample so stat stays cheap on huge generated files.
+const STAT_EXACT_LINES_LIMIT: u64 = 2 * 1024 * 1024;
+const STAT_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// Metadata for a path as JSON text — never reads more than a sample of the file.
+async fn tool_file_stat(path: &str) -> Result<String, String> {
+    let meta = match tokio::fs::metadata(path).await {
+        Ok(m) => m,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(json!({ "path": path, "exists": false }).to_string());
+        }
+        Err(e) => return Err(format!("Cannot stat '{}': {}", path, e)),
+    };
+
+    let modified = meta
+        .modified()
+        .ok()
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
+    let line_count_estimate = if meta.is_file() {
+        estimate_line_count(path, meta.len()).await
+    } else {
+        None
+    };
+
+    Ok(json!({
+        "path": path,
+        "exists": true,
+        "is_dir": meta.is_dir(),
+        "size_bytes": meta.len(),
+        "modified": modified,
+        "line_count_estimate": line_count_estimate,
+    })
+    .to_string())
+}
+
+/// Exact newline count for small files, sample-based extrapolation for large
+/// ones. `None` for binary content (NUL byte in the sample) or read errors.
+async fn estimate_line_count(path: &str, size: u64) -> Option<u64> {
+    use tokio::io::AsyncReadExt;
+
+    if size == 0 {
+        return Some(0);
+    }
+    let mut file = tokio::fs::File::open(path).await.ok()?;
+    if size <= STAT_EXACT_LINES_LIMIT {
+        let mut buf = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut buf).await.ok()?;
+        if buf.contains(&0) {
+            return None;
+        }
+        let newlines = buf.iter().filter(|&&b| b == b'\n').count() as u64;
+        // Last line without a trailing newline still counts
+        return Some(newlines + u64::from(!buf.ends_with(b"\n")));
+    }
+
+    let mut sample = vec![0u8; STAT_SAMPLE_BYTES];
+    let n = file.read(&mut sample).await.ok()?;
+    let sample = &sample[..n];
+    if sample.is_empty() || sample.contains(&0) {
+        return None;
+    }
+    let newlines = sample.iter().filter(|&&b| b == b'\n').count() as u64;
+    Some((newlines * size / sample.len() as u64).max(1))
+}
+
 // ---------------------------------------------------------------------------
 // diff_files (#20)
 // ---------------------------------------------------------------------------
 
-/// Max diff output lines.
-const MAX_DIFF_LINES: usize = 200;
+/// Default / max diff output lines (headers excluded).
+const DEFAULT_DIFF_LINES: usize = 200;
+const MAX_DIFF_LINES: usize = 2000;
+/// Default unchanged lines shown around each change in `hunks_only` mode.
+const DEFAULT_DIFF_CONTEXT: usize = 3;
+const MAX_DIFF_CONTEXT: usize = 100;
+
+/// Output-shape options for `diff_files`.
+struct DiffOptions {
+    max_lines: usize,
+    context_lines: usize,
+    /// Emit `@@` hunks only, omitting unchanged regions between them.
+    hunks_only: bool,
+}
+
+#[derive(Clone, Copy)]
+enum DiffOp<'a> {
+    Same(&'a str),
+    Del(&'a str),
+    Add(&'a str),
+}
+
+impl DiffOp<'_> {
+    fn is_change(&self) -> bool {
+        !matches!(self, DiffOp::Same(_))
+    }
+
+    fn render(&self) -> String {
+        match self {
+            DiffOp::Same(l) => format!(" {}", l),
+            DiffOp::Del(l) => format!("-{}", l),
+            DiffOp::Add(l) => format!("+{}", l),
+        }
+    }
+}
 
-/// Simple line-by-line diff between two files (unified-style output).
-async fn tool_diff_files(path_a: &str, path_b: &str) -> Result<String, String> {
+/// Line-by-line diff between two files (unified-style output).
+async fn tool_diff_files(path_a: &str, path_b: &str, opts: &DiffOptions) -> Result<String, String> {
     let p_a = std::path::Path::new(path_a);
     let p_b = std::path::Path::new(path_b);
 
@@ -1430,97 +2326,179 @@ async fn tool_diff_files(path_a: &str, path_b: &str) -> Result<String, String> {
 
     let lines_a: Vec<&str> = content_a.lines().collect();
     let lines_b: Vec<&str> = content_b.lines().collect();
+    let ops = diff_ops(&lines_a, &lines_b);
 
-    // Simple LCS-based diff
-    let mut diff_output = Vec::new();
-    diff_output.push(format!("--- {}", path_a));
-    diff_output.push(format!("+++ {}", path_b));
+    Ok(render_diff(path_a, path_b, &ops, opts))
+}
 
+/// Simple look-ahead diff producing an edit script of same/deleted/added lines.
+fn diff_ops<'a>(lines_a: &[&'a str], lines_b: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let mut ops = Vec::new();
     let (mut i, mut j) = (0usize, 0usize);
     while i < lines_a.len() || j < lines_b.len() {
-        if diff_output.len() > MAX_DIFF_LINES + 2 {
-            diff_output.push(format!("... [truncated at {} diff lines]", MAX_DIFF_LINES));
-            break;
-        }
-
         if i < lines_a.len() && j < lines_b.len() && lines_a[i] == lines_b[j] {
             // Context line (identical)
-            diff_output.push(format!(" {}", lines_a[i]));
+            ops.push(DiffOp::Same(lines_a[i]));
             i += 1;
             j += 1;
-        } else {
-            // Look ahead in B for current A line (detect deletion vs replacement)
-            let b_ahead = lines_b
-                .iter()
-                .skip(j)
-                .take(5)
-                .position(|l| i < lines_a.len() && *l == lines_a[i]);
-            let a_ahead = lines_a
-                .iter()
-                .skip(i)
-                .take(5)
-                .position(|l| j < lines_b.len() && *l == lines_b[j]);
-
-            match (a_ahead, b_ahead) {
-                (Some(a_off), Some(b_off)) if a_off <= b_off => {
-                    // Lines added in B before the match point
-                    for k in 0..a_off {
-                        if i + k < lines_a.len() {
-                            diff_output.push(format!("-{}", lines_a[i + k]));
-                        }
-                    }
-                    i += a_off;
-                }
-                (Some(_), Some(b_off)) => {
-                    // Lines added in B
-                    for k in 0..b_off {
-                        if j + k < lines_b.len() {
-                            diff_output.push(format!("+{}", lines_b[j + k]));
-                        }
-                    }
-                    j += b_off;
+            continue;
+        }
+
+        // Look ahead in B for current A line (detect deletion vs replacement)
+        let b_ahead = lines_b
+            .iter()
+            .skip(j)
+            .take(5)
+            .position(|l| i < lines_a.len() && *l == lines_a[i]);
+        let a_ahead = lines_a
+            .iter()
+            .skip(i)
+            .take(5)
+            .position(|l| j < lines_b.len() && *l == lines_b[j]);
+
+        match (a_ahead, b_ahead) {
+            // Lines removed from A before the match point
+            (Some(a_off), Some(b_off)) if a_off <= b_off => {
+                ops.extend(lines_a[i..i + a_off].iter().map(|l| DiffOp::Del(l)));
+                i += a_off;
+            }
+            // Lines added in B before the match point
+            (_, Some(b_off)) => {
+                ops.extend(lines_b[j..j + b_off].iter().map(|l| DiffOp::Add(l)));
+                j += b_off;
+            }
+            (Some(a_off), None) => {
+                ops.extend(lines_a[i..i + a_off].iter().map(|l| DiffOp::Del(l)));
+                i += a_off;
+            }
+            (None, None) => {
+                // No match found — both lines differ
+                if i < lines_a.len() {
+                    ops.push(DiffOp::Del(lines_a[i]));
+                    i += 1;
                 }
-                (None, Some(b_off)) => {
-                    for k in 0..b_off {
-                        if j + k < lines_b.len() {
-                            diff_output.push(format!("+{}", lines_b[j + k]));
-                        }
-                    }
-                    j += b_off;
+                if j < lines_b.len() {
+                    ops.push(DiffOp::Add(lines_b[j]));
+                    j += 1;
                 }
-                (Some(a_off), None) => {
-                    for k in 0..a_off {
-                        if i + k < lines_a.len() {
-                            diff_output.push(format!("-{}", lines_a[i + k]));
-                        }
-                    }
-                    i += a_off;
+            }
+        }
+    }
+    ops
+}
+
+/// Group changes into hunks (ranges of op indices) padded with `context`
+/// unchanged lines; hunks whose context would overlap are merged.
+fn diff_hunks(ops: &[DiffOp], context: usize) -> Vec<std::ops::Range<usize>> {
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (idx, _) in ops.iter().enumerate().filter(|(_, op)| op.is_change()) {
+        let start = idx.saturating_sub(context);
+        let end = idx.saturating_add(context).saturating_add(1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+fn render_diff(path_a: &str, path_b: &str, ops: &[DiffOp], opts: &DiffOptions) -> String {
+    let hunks = diff_hunks(ops, opts.context_lines);
+    let mut out = vec![format!("--- {}", path_a), format!("+++ {}", path_b)];
+    let mut emitted = 0usize;
+    // Op index at which output was cut off (None = everything shown)
+    let mut cut: Option<usize> = None;
+
+    if opts.hunks_only {
+        // 1-based line numbers in A/B at the start of each op
+        let mut pos = Vec::with_capacity(ops.len());
+        let (mut a, mut b) = (1usize, 1usize);
+        for op in ops {
+            pos.push((a, b));
+            match op {
+                DiffOp::Same(_) => {
+                    a += 1;
+                    b += 1;
                 }
-                (None, None) => {
-                    // No match found — both lines differ
-                    if i < lines_a.len() {
-                        diff_output.push(format!("-{}", lines_a[i]));
-                        i += 1;
-                    }
-                    if j < lines_b.len() {
-                        diff_output.push(format!("+{}", lines_b[j]));
-                        j += 1;
-                    }
+                DiffOp::Del(_) => a += 1,
+                DiffOp::Add(_) => b += 1,
+            }
+        }
+
+        'hunks: for hunk in &hunks {
+            if emitted >= opts.max_lines {
+                cut = Some(hunk.start);
+                break;
+            }
+            let slice = &ops[hunk.clone()];
+            let a_len = slice
+                .iter()
+                .filter(|op| !matches!(op, DiffOp::Add(_)))
+                .count();
+            let b_len = slice
+                .iter()
+                .filter(|op| !matches!(op, DiffOp::Del(_)))
+                .count();
+            let (a_start, b_start) = pos[hunk.start];
+            out.push(format!(
+                "@@ -{},{} +{},{} @@",
+                if a_len == 0 { a_start - 1 } else { a_start },
+                a_len,
+                if b_len == 0 { b_start - 1 } else { b_start },
+                b_len
+            ));
+            emitted += 1;
+            for idx in hunk.clone() {
+                if emitted >= opts.max_lines {
+                    cut = Some(idx);
+                    break 'hunks;
                 }
+                out.push(ops[idx].render());
+                emitted += 1;
+            }
+        }
+    } else {
+        for (idx, op) in ops.iter().enumerate() {
+            if emitted >= opts.max_lines {
+                cut = Some(idx);
+                break;
             }
+            out.push(op.render());
+            emitted += 1;
         }
     }
 
-    let changed = diff_output
-        .iter()
-        .filter(|l| l.starts_with('+') || l.starts_with('-'))
-        .count()
-        - 2; // minus header lines
-    Ok(format!(
-        "{}\n\n{} changed line(s)",
-        diff_output.join("\n"),
-        changed.max(0)
-    ))
+    let changed = ops.iter().filter(|op| op.is_change()).count();
+    if let Some(cut) = cut {
+        let omitted_changes = ops[cut..].iter().filter(|op| op.is_change()).count();
+        let omitted_hunks = hunks
+            .iter()
+            .filter(|h| {
+                ops[cut.clamp(h.start, h.end)..h.end]
+                    .iter()
+                    .any(DiffOp::is_change)
+            })
+            .count();
+        if omitted_changes > 0 {
+            out.push(format!(
+                "... [truncated at {} lines — {} more changed hunk(s) ({} changed line(s)) omitted. \
+                 The files still differ past this point: narrow the comparison or raise max_lines]",
+                opts.max_lines, omitted_hunks, omitted_changes
+            ));
+        } else {
+            out.push(format!(
+                "... [truncated at {} lines — no further changes]",
+                opts.max_lines
+            ));
+        }
+    }
+
+    format!(
+        "{}\n\n{} changed line(s) in {} hunk(s)",
+        out.join("\n"),
+        changed,
+        hunks.len()
+    )
 }
 
 // ---------------------------------------------------------------------------
@@ -1536,13 +2514,12 @@ const MAX_TOOL_OUTPUT_CHARS: usize = 6000;
 /// Minimum alphanumeric characters to consider extraction successful.
 const MIN_ALPHA_THRESHOLD: usize = 20;
 
-/// Read and extract text from a PDF file.
-/// Falls back to Gemini Vision OCR when pdf-extract yields empty/garbage text.
-async fn tool_read_pdf(
-    path: &str,
-    page_range: Option<&str>,
-    state: &AppState,
-) -> Result<String, String> {
+/// Longest per-page snippet in `read_pdf` outline mode.
+const PDF_OUTLINE_SNIPPET_CHARS: usize = 80;
+
+/// Read a PDF from disk after checking its extension and size.
+/// Returns the bytes and the file name.
+async fn load_pdf(path: &str) -> Result<(Vec<u8>, String), String> {
     let file_path = std::path::Path::new(path);
 
     if !file_path.exists() {
@@ -1572,21 +2549,43 @@ async fn tool_read_pdf(
     let filename = file_path
         .file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or("unknown.pdf");
+        .unwrap_or("unknown.pdf")
+        .to_string();
+    Ok((bytes, filename))
+}
 
-    // Extract text (blocking — pdf-extract is synchronous, with 60s timeout)
-    let bytes_arc = std::sync::Arc::new(bytes);
-    let bytes_ref = std::sync::Arc::clone(&bytes_arc);
-    let text = tokio::time::timeout(
+/// Run a pdf-extract function off the async runtime (it is synchronous),
+/// with a 60s timeout.
+async fn run_pdf_extract<T: Send + 'static>(
+    bytes: std::sync::Arc<Vec<u8>>,
+    extract: fn(&[u8]) -> Result<T, pdf_extract::OutputError>,
+) -> Result<T, String> {
+    tokio::time::timeout(
         std::time::Duration::from_secs(60),
         tokio::task::spawn_blocking(move || {
-            pdf_extract::extract_text_from_mem(&bytes_ref)
-                .map_err(|e| format!("PDF extraction failed: {}", e))
+            extract(&bytes).map_err(|e| format!("PDF extraction failed: {}", e))
         }),
     )
     .await
     .map_err(|_| "PDF extraction timed out after 60s".to_string())?
-    .map_err(|e| format!("Task join error: {}", e))??;
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Read and extract text from a PDF file.
+/// Falls back to Gemini Vision OCR when pdf-extract yields empty/garbage text.
+async fn tool_read_pdf(
+    path: &str,
+    page_range: Option<&str>,
+    state: &AppState,
+) -> Result<String, String> {
+    let (bytes, filename) = load_pdf(path).await?;
+
+    let bytes_arc = std::sync::Arc::new(bytes);
+    let text = run_pdf_extract(
+        std::sync::Arc::clone(&bytes_arc),
+        pdf_extract::extract_text_from_mem,
+    )
+    .await?;
 
     // Check if extraction yielded meaningful text
     let alpha_count = text.chars().filter(|c| c.is_alphanumeric()).count();
@@ -1598,7 +2597,7 @@ async fn tool_read_pdf(
             alpha_count
         );
         let b64 = base64::engine::general_purpose::STANDARD.encode(&*bytes_arc);
-        let ocr_text = crate::ocr::ocr_pdf_text(state, &b64, page_range).await?;
+        let ocr_text = crate::ocr::ocr_pdf_text(state, &b64, page_range, None).await?;
 
         let mut output = format!("### PDF (OCR): {} (Vision API)\n\n", filename);
         if ocr_text.len() + output.len() > MAX_TOOL_OUTPUT_CHARS {
@@ -1647,6 +2646,92 @@ async fn tool_read_pdf(
     Ok(output)
 }
 
+/// `read_pdf` outline mode: one line per page with its first line of text
+/// and character count, so the model can pick a `page_range` to read.
+/// Pages without embedded text get a Vision OCR snippet instead, marked as such.
+async fn tool_read_pdf_outline(path: &str, state: &AppState) -> Result<String, String> {
+    let (bytes, filename) = load_pdf(path).await?;
+    let bytes_arc = std::sync::Arc::new(bytes);
+    let pages = run_pdf_extract(
+        std::sync::Arc::clone(&bytes_arc),
+        pdf_extract::extract_text_from_mem_by_pages,
+    )
+    .await?;
+
+    let scanned: Vec<usize> = pages
+        .iter()
+        .enumerate()
+        .filter(|(_, text)| pdf_page_snippet(text).is_none())
+        .map(|(i, _)| i + 1)
+        .collect();
+    let mut ocr_snippets = std::collections::HashMap::new();
+    let mut ocr_failed = false;
+    if !scanned.is_empty() {
+        let b64 = base64::engine::general_purpose::STANDARD.encode(&*bytes_arc);
+        match crate::ocr::ocr_pdf_page_headings(state, &b64, &scanned).await {
+            Ok(headings) => ocr_snippets.extend(headings),
+            Err(e) => {
+                tracing::warn!("read_pdf outline: OCR of pages without text failed: {}", e);
+                ocr_failed = true;
+            }
+        }
+    }
+
+    let mut output = format!("### PDF outline: {} ({} pages)\n\n", filename, pages.len());
+    for (i, text) in pages.iter().enumerate() {
+        let page = i + 1;
+        let line = match pdf_page_snippet(text) {
+            Some(snippet) => format!(
+                "Page {} ({} chars): {}",
+                page,
+                text.trim().chars().count(),
+                snippet
+            ),
+            None => match ocr_snippets.get(&page) {
+                Some(snippet) => format!(
+                    "Page {} (no embedded text, OCR): {}",
+                    page,
+                    truncate_snippet(snippet)
+                ),
+                None => format!("Page {} (no embedded text)", page),
+            },
+        };
+        if output.len() + line.len() + 40 > MAX_TOOL_OUTPUT_CHARS {
+            output.push_str("[... truncated ...]\n");
+            break;
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    if ocr_failed {
+        output.push_str("\nVision OCR of pages without embedded text failed.\n");
+    }
+    output.push_str("\nRead specific pages with mode=\"text\" and page_range.");
+    Ok(output)
+}
+
+/// First non-empty line of a page's extracted text, shortened for the
+/// outline. `None` when the page has no alphanumeric text at all.
+fn pdf_page_snippet(text: &str) -> Option<String> {
+    if !text.chars().any(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    text.lines()
+        .map(str::trim)
+        .find(|l| l.chars().any(|c| c.is_alphanumeric()))
+        .map(truncate_snippet)
+}
+
+fn truncate_snippet(line: &str) -> String {
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > PDF_OUTLINE_SNIPPET_CHARS {
+        let cut: String = line.chars().take(PDF_OUTLINE_SNIPPET_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        line
+    }
+}
+
 /// Parse a page range string like "1-5" or "3" into (start, end) 1-indexed.
 fn parse_pdf_page_range(range: &str, total: usize) -> Result<(usize, usize), String> {
     let range = range.trim();
@@ -1676,9 +2761,6 @@ fn parse_pdf_page_range(range: &str, total: usize) -> Result<(usize, usize), Str
 // analyze_image — Gemini Vision API with OCR mode
 // ---------------------------------------------------------------------------
 
-/// Allowed image extensions.
-const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif"];
-
 /// Maximum image file size (10 MB — Gemini limit).
 const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
 
@@ -1694,15 +2776,30 @@ If the text is handwritten, transcribe it as accurately as possible.\n\
 If there are tables, format them using markdown table syntax.\n\
 Return ONLY the extracted text, no descriptions or commentary.";
 
-/// Analyze an image using Gemini Vision API.
-/// When `extract_text` is true, performs OCR instead of description.
-/// Returns ToolOutput with text + optional inline_data for Gemini multimodal responses.
-async fn tool_analyze_image(
-    path: &str,
-    prompt: Option<&str>,
-    extract_text: Option<bool>,
-    state: &AppState,
-) -> Result<ToolOutput, String> {
+/// Most images sent in one `analyze_image` batch.
+const MAX_BATCH_IMAGES: usize = 8;
+
+/// Total size of the images in one batch request.
+const MAX_BATCH_TOTAL_SIZE: u64 = 20 * 1024 * 1024;
+
+const DESCRIBE_PROMPT: &str = "Describe this image in detail. Include any text, objects, people, colors, layout, and notable features.";
+
+const BATCH_DESCRIBE_PROMPT: &str = "\
+Analyze these images together. Describe each one, referring to it by its number \
+(Image 1, Image 2, ...), then compare them: what they share, how they differ, \
+and anything notable across the set.";
+
+/// An image file checked against the format and size limits, not yet read.
+struct ImageFile {
+    path: String,
+    filename: String,
+    /// Lowercase extension, which selects the `image_format` handling.
+    ext: String,
+    size: u64,
+}
+
+/// Check that `path` exists and has a supported image extension.
+async fn stat_image(path: &str) -> Result<ImageFile, String> {
     let file_path = std::path::Path::new(path);
 
     if !file_path.exists() {
@@ -1714,67 +2811,115 @@ async fn tool_analyze_image(
         .and_then(|e| e.to_str())
         .map(|e| e.to_lowercase())
         .unwrap_or_default();
-    if !IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+    if !image_format::is_supported(&ext) {
         return Err(format!(
             "Not a supported image format: {}. Supported: {:?}",
-            path, IMAGE_EXTENSIONS
+            path,
+            image_format::supported_extensions()
         ));
     }
 
     let metadata = tokio::fs::metadata(file_path)
         .await
         .map_err(|e| format!("Cannot read metadata: {}", e))?;
-    if metadata.len() > MAX_IMAGE_SIZE {
-        return Err(format!(
-            "Image too large: {} bytes (max {} MB)",
-            metadata.len(),
-            MAX_IMAGE_SIZE / (1024 * 1024)
-        ));
+
+    Ok(ImageFile {
+        path: path.to_string(),
+        filename: file_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("image")
+            .to_string(),
+        ext,
+        size: metadata.len(),
+    })
+}
+
+/// An image read from disk, transcoded if Gemini can't take it as-is, and
+/// base64-encoded for an `inlineData` part.
+struct LoadedImage {
+    b64: String,
+    mime_type: &'static str,
+    /// `BMP→PNG, 1920×1080` when the file was transcoded.
+    conversion: Option<String>,
+}
+
+impl LoadedImage {
+    /// `image/png`, or `BMP→PNG, 1920×1080` for a transcoded file.
+    fn describe_format(&self) -> &str {
+        self.conversion.as_deref().unwrap_or(self.mime_type)
     }
+}
 
-    let bytes = tokio::fs::read(file_path)
+async fn load_image(path: &str, ext: &str) -> Result<LoadedImage, String> {
+    let bytes = tokio::fs::read(path)
         .await
         .map_err(|e| format!("Cannot read image: {}", e))?;
-    let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
-
-    let mime_type = match ext.as_str() {
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "webp" => "image/webp",
-        "gif" => "image/gif",
-        _ => "application/octet-stream",
-    };
+    let ext = ext.to_string();
+    let image = tokio::task::spawn_blocking(move || image_format::normalize(&ext, bytes))
+        .await
+        .map_err(|e| format!("Image conversion failed: {}", e))??;
+    if image.bytes.len() as u64 > MAX_IMAGE_SIZE {
+        return Err(format!(
+            "Converted image too large: {} bytes (max {} MB)",
+            image.bytes.len(),
+            MAX_IMAGE_SIZE / (1024 * 1024)
+        ));
+    }
+    Ok(LoadedImage {
+        b64: base64::engine::general_purpose::STANDARD.encode(&image.bytes),
+        mime_type: image.mime_type,
+        conversion: image.conversion,
+    })
+}
 
-    let analysis_prompt = if extract_text.unwrap_or(false) {
-        prompt.unwrap_or(OCR_PROMPT)
-    } else {
-        prompt.unwrap_or(
-            "Describe this image in detail. Include any text, objects, people, colors, layout, and notable features.",
-        )
-    };
+/// Split a batch into images to send and `(filename, reason)` for images
+/// skipped because they exceed `MAX_IMAGE_SIZE` or would push the request
+/// past `MAX_BATCH_TOTAL_SIZE`. Order is preserved.
+fn select_batch_images(images: Vec<ImageFile>) -> (Vec<ImageFile>, Vec<(String, String)>) {
+    let mut kept = Vec::new();
+    let mut skipped = Vec::new();
+    let mut total = 0;
+    for image in images {
+        if image.size > MAX_IMAGE_SIZE {
+            skipped.push((
+                image.filename,
+                format!(
+                    "{} bytes exceeds the {} MB per-image limit",
+                    image.size,
+                    MAX_IMAGE_SIZE / (1024 * 1024)
+                ),
+            ));
+        } else if total + image.size > MAX_BATCH_TOTAL_SIZE {
+            skipped.push((
+                image.filename,
+                format!(
+                    "{} bytes would exceed the {} MB total request limit",
+                    image.size,
+                    MAX_BATCH_TOTAL_SIZE / (1024 * 1024)
+                ),
+            ));
+        } else {
+            total += image.size;
+            kept.push(image);
+        }
+    }
+    (kept, skipped)
+}
 
+/// Send `parts` to Gemini Vision and return the first text part of the answer.
+async fn gemini_vision(parts: Vec<Value>, state: &AppState) -> Result<String, String> {
     // Get credential via oauth module
     let (credential, is_oauth) = crate::oauth::get_google_credential(state)
         .await
         .ok_or_else(|| "No Google API credential configured".to_string())?;
 
-    let url =
-        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent";
+    let url = state
+        .providers
+        .gemini_model("gemini-2.0-flash", "generateContent");
 
     let request_body = serde_json::json!({
-        "contents": [{
-            "parts": [
-                {
-                    "inlineData": {
-                        "mimeType": mime_type,
-                        "data": b64
-                    }
-                },
-                {
-                    "text": analysis_prompt
-                }
-            ]
-        }],
+        "contents": [{ "parts": parts }],
         "generationConfig": {
             "temperature": 1.0,  // Gemini 3: ALWAYS 1.0
             "maxOutputTokens": 4096
@@ -1784,10 +2929,12 @@ async fn tool_analyze_image(
     let builder = state.client.post(url).json(&request_body);
     let builder = crate::oauth::apply_google_auth(builder, &credential, is_oauth);
 
-    let response = builder
-        .send()
-        .await
-        .map_err(|e| format!("Gemini API request failed: {}", e))?;
+    let response = builder.send().await.map_err(|e| {
+        format!(
+            "Gemini API request failed: {}",
+            crate::redact::redact_secrets(&e.to_string())
+        )
+    })?;
 
     let status = response.status();
     let body: Value = response
@@ -1817,11 +2964,40 @@ async fn tool_analyze_image(
     if text.is_empty() {
         return Err("Gemini returned empty result".to_string());
     }
+    Ok(text)
+}
+
+/// Analyze an image using Gemini Vision API.
+/// When `extract_text` is true, performs OCR instead of description.
+/// Returns ToolOutput with text + optional inline_data for Gemini multimodal responses.
+async fn tool_analyze_image(
+    path: &str,
+    prompt: Option<&str>,
+    extract_text: Option<bool>,
+    state: &AppState,
+) -> Result<ToolOutput, String> {
+    let image = stat_image(path).await?;
+    if image.size > MAX_IMAGE_SIZE {
+        return Err(format!(
+            "Image too large: {} bytes (max {} MB)",
+            image.size,
+            MAX_IMAGE_SIZE / (1024 * 1024)
+        ));
+    }
+    let loaded = load_image(&image.path, &image.ext).await?;
+
+    let analysis_prompt = if extract_text.unwrap_or(false) {
+        prompt.unwrap_or(OCR_PROMPT)
+    } else {
+        prompt.unwrap_or(DESCRIBE_PROMPT)
+    };
+
+    let parts = vec![
+        serde_json::json!({ "inlineData": { "mimeType": loaded.mime_type, "data": &loaded.b64 } }),
+        serde_json::json!({ "text": analysis_prompt }),
+    ];
+    let text = gemini_vision(parts, state).await?;
 
-    let filename = file_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("image");
     let label = if extract_text.unwrap_or(false) {
         "OCR"
     } else {
@@ -1830,31 +3006,118 @@ async fn tool_analyze_image(
     let output_text = format!(
         "### {}: {} ({}, {} bytes)\n\n{}",
         label,
-        filename,
-        mime_type,
-        metadata.len(),
+        image.filename,
+        loaded.describe_format(),
+        image.size,
         text
     );
 
     // Return text + inline_data for Gemini multimodal function responses
     Ok(ToolOutput {
-        text: output_text,
         inline_data: Some(InlineData {
-            mime_type: mime_type.to_string(),
-            data: b64,
+            mime_type: loaded.mime_type.to_string(),
+            data: loaded.b64,
         }),
+        ..ToolOutput::text(output_text)
     })
 }
 
+/// Analyze several images in one Gemini Vision request with a single prompt.
+/// Oversized images are skipped and listed in the output; the call fails only
+/// when none are left to send.
+async fn tool_analyze_images(
+    paths: &[String],
+    prompt: Option<&str>,
+    extract_text: Option<bool>,
+    state: &AppState,
+) -> Result<ToolOutput, String> {
+    if paths.is_empty() {
+        return Err("paths must list at least one image".to_string());
+    }
+    if paths.len() > MAX_BATCH_IMAGES {
+        return Err(format!(
+            "Too many images: {} (max {} per call)",
+            paths.len(),
+            MAX_BATCH_IMAGES
+        ));
+    }
+
+    let mut images = Vec::with_capacity(paths.len());
+    for path in paths {
+        images.push(stat_image(path).await?);
+    }
+    let (images, skipped) = select_batch_images(images);
+    let skipped_note = skipped
+        .iter()
+        .map(|(name, reason)| format!("- {}: {}", name, reason))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if images.is_empty() {
+        return Err(format!(
+            "All images were skipped for size:\n{}",
+            skipped_note
+        ));
+    }
+
+    let mut parts = Vec::with_capacity(images.len() * 2 + 1);
+    let mut formats = Vec::with_capacity(images.len());
+    for (i, image) in images.iter().enumerate() {
+        let loaded = load_image(&image.path, &image.ext).await?;
+        parts.push(serde_json::json!({ "text": format!("Image {}: {}", i + 1, image.filename) }));
+        parts.push(serde_json::json!({
+            "inlineData": { "mimeType": loaded.mime_type, "data": &loaded.b64 }
+        }));
+        formats.push(loaded.describe_format().to_string());
+    }
+    let analysis_prompt = if extract_text.unwrap_or(false) {
+        prompt.map(str::to_string).unwrap_or_else(|| {
+            format!(
+                "{}\nDo this for every image, under a heading with its number (Image 1, Image 2, ...).",
+                OCR_PROMPT
+            )
+        })
+    } else {
+        prompt.unwrap_or(BATCH_DESCRIBE_PROMPT).to_string()
+    };
+    parts.push(serde_json::json!({ "text": analysis_prompt }));
+    let text = gemini_vision(parts, state).await?;
+
+    let label = if extract_text.unwrap_or(false) {
+        "OCR"
+    } else {
+        "Image Analysis"
+    };
+    let names = images
+        .iter()
+        .enumerate()
+        .zip(&formats)
+        .map(|((i, image), format)| {
+            format!(
+                "{}. {} ({}, {} bytes)",
+                i + 1,
+                image.filename,
+                format,
+                image.size
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut output_text = format!("### {}: {} images\n{}\n\n", label, images.len(), names);
+    if !skipped.is_empty() {
+        output_text.push_str(&format!("Skipped for size:\n{}\n\n", skipped_note));
+    }
+    output_text.push_str(&text);
+    Ok(ToolOutput::text(output_text))
+}
+
 // ---------------------------------------------------------------------------
 // ocr_document — dedicated OCR tool with markdown table preservation
 // ---------------------------------------------------------------------------
 
-const OCR_DOCUMENT_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "pdf"];
-
 async fn tool_ocr_document(
     path: &str,
     custom_prompt: Option<&str>,
+    language: Option<&str>,
     state: &AppState,
 ) -> Result<String, String> {
     let file_path = std::path::Path::new(path);
@@ -1869,10 +3132,12 @@ async fn tool_ocr_document(
         .map(|e| e.to_lowercase())
         .unwrap_or_default();
 
-    if !OCR_DOCUMENT_EXTENSIONS.contains(&ext.as_str()) {
+    if ext != "pdf" && !image_format::is_supported(&ext) {
+        let mut supported = image_format::supported_extensions();
+        supported.push("pdf");
         return Err(format!(
             "Unsupported file type: .{}. Supported: {:?}",
-            ext, OCR_DOCUMENT_EXTENSIONS
+            ext, supported
         ));
     }
 
@@ -1886,26 +3151,20 @@ async fn tool_ocr_document(
         ));
     }
 
-    let bytes = tokio::fs::read(file_path)
-        .await
-        .map_err(|e| format!("Cannot read file: {}", e))?;
-    let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
-
-    let mime_type = match ext.as_str() {
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "webp" => "image/webp",
-        "gif" => "image/gif",
-        "pdf" => "application/pdf",
-        _ => "application/octet-stream",
-    };
-
     // OCR functions use the default OCR_PROMPT which already preserves tables as markdown
     let _ = custom_prompt; // reserved for future custom prompt support
-    let text = if ext == "pdf" {
-        crate::ocr::ocr_pdf_text(state, &b64, None).await?
+    let (text, format) = if ext == "pdf" {
+        let bytes = tokio::fs::read(file_path)
+            .await
+            .map_err(|e| format!("Cannot read file: {}", e))?;
+        let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        let text = crate::ocr::ocr_pdf_text(state, &b64, None, language).await?;
+        (text, "application/pdf".to_string())
     } else {
-        crate::ocr::ocr_image_text(state, &b64, mime_type).await?
+        let loaded = load_image(path, &ext).await?;
+        let text =
+            crate::ocr::ocr_image_text(state, &loaded.b64, loaded.mime_type, language).await?;
+        (text, loaded.describe_format().to_string())
     };
 
     let filename = file_path
@@ -1915,7 +3174,7 @@ async fn tool_ocr_document(
     Ok(format!(
         "### OCR: {} ({}, {} bytes)\n\n{}",
         filename,
-        mime_type,
+        format,
         metadata.len(),
         text
     ))
@@ -2013,3 +3272,170 @@ async fn tool_generate_image(
         prompt
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(name: &str, size: u64) -> ImageFile {
+        ImageFile {
+            path: format!("/tmp/{}", name),
+            filename: name.to_string(),
+            ext: "png".into(),
+            size,
+        }
+    }
+
+    #[test]
+    fn window_search_line_keeps_match_in_long_lines() {
+        let re = Regex::new("(?i)needle").unwrap();
+        let line = format!("{}needle{}", "a".repeat(2000), "b".repeat(2000));
+        let shown = window_search_line(&line, &re);
+        assert!(shown.contains("needle"));
+        assert!(shown.starts_with("...") && shown.ends_with("..."));
+        assert!(shown.len() <= SEARCH_LINE_MAX + 6);
+        // Short lines and early matches are left to plain truncation
+        assert_eq!(window_search_line("a needle", &re), "a needle");
+    }
+
+    #[test]
+    fn fit_search_page_stops_at_byte_budget() {
+        let page: Vec<SearchResult> = (0..10)
+            .map(|_| SearchResult::Text("x".repeat(99)))
+            .collect();
+        assert_eq!(fit_search_page(&page, 450), 4);
+        assert_eq!(fit_search_page(&page, 10_000), 10);
+        // A single oversized result is still returned
+        assert_eq!(fit_search_page(&page, 10), 1);
+    }
+
+    #[tokio::test]
+    async fn search_files_pagination_saturates_huge_offsets() {
+        let dir = std::env::temp_dir().join(format!("gh-search-page-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "needle\n").unwrap();
+        let filter = SearchPathFilter::from_args(&json!({})).unwrap();
+        let opts = SearchOptions {
+            offset: usize::MAX,
+            limit: usize::MAX,
+            multiline: false,
+            context_before: 0,
+            context_after: 0,
+        };
+        let out = tool_search_files(dir.to_str().unwrap(), "needle", None, &filter, false, &opts)
+            .await
+            .unwrap();
+        assert!(out.contains("of 1 total"), "{}", out);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn find_file_contains_filters_by_content() {
+        let dir = std::env::temp_dir().join(format!("gh-find-contains-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.rs"), "fn main() {}\nlet needle = 1;\n").unwrap();
+        std::fs::write(dir.join("b.rs"), "fn other() {}\n").unwrap();
+        std::fs::write(dir.join("sub").join("c.txt"), "needle\n").unwrap();
+        let root = dir.to_str().unwrap();
+
+        let out = tool_find_file(root, "*.rs", Some("needle"), false)
+            .await
+            .unwrap();
+        assert!(
+            out.starts_with("Found 1 file(s) matching '*.rs' containing 'needle'"),
+            "{}",
+            out
+        );
+        assert!(out.contains("a.rs:2"), "{}", out);
+        assert!(out.contains("let needle = 1;"), "{}", out);
+        assert!(!out.contains("b.rs") && !out.contains("c.txt"), "{}", out);
+
+        let out = tool_find_file(root, "*.rs", None, false).await.unwrap();
+        assert!(out.contains("a.rs") && out.contains("b.rs"), "{}", out);
+
+        let out = tool_find_file(root, "*.rs", Some("absent"), false)
+            .await
+            .unwrap();
+        assert!(out.starts_with("No files matching"), "{}", out);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn file_stat_reports_type_size_and_lines() {
+        let dir = std::env::temp_dir().join(format!("gh-file-stat-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "one\ntwo\nthree").unwrap();
+        std::fs::write(dir.join("bin"), [0u8, 1, 2]).unwrap();
+
+        let stat = |p: std::path::PathBuf| async move {
+            let out = tool_file_stat(p.to_str().unwrap()).await.unwrap();
+            serde_json::from_str::<Value>(&out).unwrap()
+        };
+        let v = stat(file).await;
+        assert_eq!(v["exists"], true);
+        assert_eq!(v["is_dir"], false);
+        assert_eq!(v["size_bytes"], 13);
+        assert_eq!(v["line_count_estimate"], 3);
+        assert!(v["modified"].is_string());
+
+        let v = stat(dir.clone()).await;
+        assert_eq!(v["is_dir"], true);
+        assert!(v["line_count_estimate"].is_null());
+
+        assert!(stat(dir.join("bin")).await["line_count_estimate"].is_null());
+        assert_eq!(stat(dir.join("missing")).await["exists"], false);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn diff_hunks_saturate_huge_context() {
+        let ops = [
+            DiffOp::Same("a"),
+            DiffOp::Del("b"),
+            DiffOp::Add("c"),
+            DiffOp::Same("d"),
+        ];
+        assert_eq!(diff_hunks(&ops, usize::MAX), vec![0..4]);
+        assert_eq!(diff_hunks(&ops, 0), vec![1..3]);
+        let opts = DiffOptions {
+            max_lines: DEFAULT_DIFF_LINES,
+            context_lines: usize::MAX,
+            hunks_only: true,
+        };
+        assert!(render_diff("a.txt", "b.txt", &ops, &opts).contains("+c"));
+    }
+
+    #[test]
+    fn pdf_outline_snippet_takes_first_text_line() {
+        assert_eq!(
+            pdf_page_snippet("\n  \n  1.  Introduction \nBody text").as_deref(),
+            Some("1. Introduction")
+        );
+        assert_eq!(pdf_page_snippet(" \n--- \n"), None);
+        let long = "x".repeat(200);
+        assert_eq!(
+            pdf_page_snippet(&long).unwrap().chars().count(),
+            PDF_OUTLINE_SNIPPET_CHARS + 1
+        );
+    }
+
+    #[test]
+    fn batch_skips_oversized_images_and_respects_total_limit() {
+        const MB: u64 = 1024 * 1024;
+        let (kept, skipped) = select_batch_images(vec![
+            image("a.png", 8 * MB),
+            image("huge.png", 11 * MB),
+            image("b.png", 9 * MB),
+            image("c.png", 4 * MB),
+            image("d.png", 3 * MB),
+        ]);
+        let kept: Vec<&str> = kept.iter().map(|i| i.filename.as_str()).collect();
+        assert_eq!(kept, ["a.png", "b.png", "d.png"]);
+        let skipped: Vec<&str> = skipped.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(skipped, ["huge.png", "c.png"]);
+    }
+}
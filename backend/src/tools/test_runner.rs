@@ -0,0 +1,351 @@
+// tools/test_runner.rs
+//! `run_tests` tool — runs a project's test suite and returns a compact,
+//! structured summary instead of the raw runner output.
+//!
+//! Supported frameworks: `cargo` (cargo test), `jest` (npx jest), `pytest`.
+
+use serde::Serialize;
+use serde_json::json;
+
+use super::{MAX_COMMAND_OUTPUT, ToolOutput, run_policed_command};
+use crate::state::AppState;
+
+/// Max characters kept per failure message.
+const MAX_FAILURE_MESSAGE: usize = 800;
+
+/// Max failures listed in the summary.
+const MAX_FAILURES: usize = 30;
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct TestFailure {
+    pub name: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct TestSummary {
+    pub passed: u64,
+    pub failed: u64,
+    pub failures: Vec<TestFailure>,
+}
+
+/// Fixed runner command for `framework`.
+fn framework_command(framework: &str) -> Result<&'static str, String> {
+    match framework {
+        "cargo" => Ok("cargo test --no-fail-fast"),
+        "jest" => Ok("npx jest --ci"),
+        "pytest" => Ok("python -m pytest -rf"),
+        other => Err(format!(
+            "Unsupported test framework '{}' (expected cargo, jest or pytest)",
+            other
+        )),
+    }
+}
+
+/// Run the test suite for `framework` in `working_directory` and summarize it.
+/// Test runs execute project code, so they go through the same command
+/// policy and sandbox as `execute_command`.
+pub async fn tool_run_tests(
+    framework: &str,
+    working_directory: &str,
+    verbose: bool,
+    state: &AppState,
+) -> Result<ToolOutput, String> {
+    let command = framework_command(framework)?;
+    let output = run_policed_command(command, Some(working_directory), state).await?;
+
+    let mut raw = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.is_empty() {
+        raw.push('\n');
+        raw.push_str(&stderr);
+    }
+
+    let summary = match framework {
+        "cargo" => parse_cargo(&raw),
+        "jest" => parse_jest(&raw),
+        _ => parse_pytest(&raw),
+    };
+
+    let exit_code = output.status.code().unwrap_or(-1);
+    let mut result = json!({
+        "framework": framework,
+        "command": command,
+        "exit_code": exit_code,
+        "passed": summary.passed,
+        "failed": summary.failed,
+        "failures": summary.failures,
+    });
+    if verbose {
+        if raw.len() > MAX_COMMAND_OUTPUT {
+            raw.truncate(char_boundary(&raw, MAX_COMMAND_OUTPUT));
+            raw.push_str("\n... [output truncated at 50KB]");
+        }
+        result["raw_output"] = json!(raw);
+    }
+
+    Ok(ToolOutput::text(result.to_string()).with_exit_code(exit_code))
+}
+
+/// Largest char boundary in `s` that is `<= max`.
+fn char_boundary(s: &str, max: usize) -> usize {
+    let mut end = max.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+fn clip_message(s: &str) -> String {
+    let s = s.trim();
+    if s.len() > MAX_FAILURE_MESSAGE {
+        format!("{}...", &s[..char_boundary(s, MAX_FAILURE_MESSAGE)])
+    } else {
+        s.to_string()
+    }
+}
+
+/// Parse the first integer preceding `label` (e.g. "3 passed") in `line`.
+fn count_before(line: &str, label: &str) -> Option<u64> {
+    let idx = line.find(label)?;
+    line[..idx].split_whitespace().last()?.parse().ok()
+}
+
+/// Close the failure block being collected (if any) and record it.
+fn finish_failure(current: &mut Option<(String, Vec<&str>)>, summary: &mut TestSummary) {
+    if let Some((name, lines)) = current.take()
+        && summary.failures.len() < MAX_FAILURES
+    {
+        summary.failures.push(TestFailure {
+            name,
+            message: clip_message(&lines.join("\n")),
+        });
+    }
+}
+
+/// `cargo test` prints one `test result:` line per test binary and a
+/// `---- name stdout ----` section with the panic message per failure.
+pub(crate) fn parse_cargo(output: &str) -> TestSummary {
+    let mut summary = TestSummary::default();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("test result:") {
+            summary.passed += count_before(rest, " passed").unwrap_or(0);
+            summary.failed += count_before(rest, " failed").unwrap_or(0);
+        }
+
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            finish_failure(&mut current, &mut summary);
+            current = Some((name.to_string(), Vec::new()));
+        } else if line.trim() == "failures:" || line.starts_with("test result:") {
+            finish_failure(&mut current, &mut summary);
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    finish_failure(&mut current, &mut summary);
+    summary
+}
+
+/// Jest reports `Tests: 1 failed, 5 passed, 6 total` and a `● Suite › test`
+/// block per failure.
+pub(crate) fn parse_jest(output: &str) -> TestSummary {
+    let mut summary = TestSummary::default();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("Tests:") {
+            summary.passed = count_before(rest, " passed").unwrap_or(0);
+            summary.failed = count_before(rest, " failed").unwrap_or(0);
+            finish_failure(&mut current, &mut summary);
+        } else if let Some(name) = trimmed.strip_prefix("● ") {
+            finish_failure(&mut current, &mut summary);
+            current = Some((name.to_string(), Vec::new()));
+        } else if trimmed.starts_with("Test Suites:") || is_jest_suite_header(line) {
+            finish_failure(&mut current, &mut summary);
+        } else if let Some((_, lines)) = current.as_mut()
+            && !trimmed.is_empty()
+        {
+            lines.push(trimmed);
+        }
+    }
+    finish_failure(&mut current, &mut summary);
+    summary
+}
+
+/// Unindented `PASS path` / `FAIL path` line that starts each suite's report.
+fn is_jest_suite_header(line: &str) -> bool {
+    line.strip_prefix("PASS ")
+        .or_else(|| line.strip_prefix("FAIL "))
+        .is_some_and(|path| !path.trim().is_empty())
+}
+
+/// pytest (`-rf`) ends with `FAILED path::test - message` lines and a
+/// `=== 1 failed, 3 passed in 0.12s ===` totals line.
+pub(crate) fn parse_pytest(output: &str) -> TestSummary {
+    let mut summary = TestSummary::default();
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("FAILED ") {
+            if summary.failures.len() < MAX_FAILURES {
+                let (name, message) = rest.split_once(" - ").unwrap_or((rest, ""));
+                summary.failures.push(TestFailure {
+                    name: name.trim().to_string(),
+                    message: clip_message(message),
+                });
+            }
+        } else if trimmed.starts_with('=') && trimmed.contains(" in ") {
+            let inner = trimmed.trim_matches('=');
+            if let Some(p) = count_before(inner, " passed") {
+                summary.passed = p;
+            }
+            if let Some(f) = count_before(inner, " failed") {
+                summary.failed = f;
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_output_is_summarized() {
+        let out = "\
+running 3 tests
+test a::ok ... ok
+test a::bad ... FAILED
+test a::ok2 ... ok
+
+failures:
+
+---- a::bad stdout ----
+
+thread 'a::bad' panicked at src/a.rs:10:5:
+assertion `left == right` failed
+  left: 1
+ right: 2
+
+failures:
+    a::bad
+
+test result: FAILED. 2 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+
+running 4 tests
+test result: ok. 4 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let s = parse_cargo(out);
+        assert_eq!(s.passed, 6);
+        assert_eq!(s.failed, 1);
+        assert_eq!(s.failures.len(), 1);
+        assert_eq!(s.failures[0].name, "a::bad");
+        assert!(
+            s.failures[0]
+                .message
+                .contains("assertion `left == right` failed")
+        );
+    }
+
+    #[test]
+    fn jest_output_is_summarized() {
+        let out = "\
+FAIL src/sum.test.js
+  ● math › adds numbers
+
+    expect(received).toBe(expected)
+
+    Expected: 3
+    Received: 4
+
+PASS src/other.test.js
+
+Test Suites: 1 failed, 1 passed, 2 total
+Tests:       1 failed, 5 passed, 6 total
+";
+        let s = parse_jest(out);
+        assert_eq!(s.passed, 5);
+        assert_eq!(s.failed, 1);
+        assert_eq!(s.failures[0].name, "math › adds numbers");
+        assert!(s.failures[0].message.contains("Expected: 3"));
+        assert!(!s.failures[0].message.contains("Test Suites"));
+    }
+
+    #[test]
+    fn runner_commands_are_subject_to_the_allowlist() {
+        use crate::tools::BLOCKED_PATTERNS;
+        use crate::tools::command_policy::{Rejection, check_command};
+
+        let allow = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        for (framework, runner) in [("cargo", "cargo"), ("jest", "npx"), ("pytest", "python")] {
+            let command = framework_command(framework).unwrap();
+            assert!(matches!(
+                check_command(command, &allow(&["git"]), BLOCKED_PATTERNS, &[]),
+                Err(Rejection::NotAllowed(_))
+            ));
+            assert!(check_command(command, &allow(&[runner]), BLOCKED_PATTERNS, &[]).is_ok());
+        }
+        assert!(matches!(
+            check_command(
+                "cargo test --no-fail-fast",
+                &[],
+                &[],
+                &allow(&["cargo test"])
+            ),
+            Err(Rejection::Blocked(_))
+        ));
+        assert!(framework_command("mocha").is_err());
+    }
+
+    #[test]
+    fn jest_failure_blocks_end_at_the_next_suite_header() {
+        let out = "\
+FAIL src/a.test.js
+  ● a › first
+
+    Expected: 1
+
+FAIL src/b.test.js
+  ● b › second
+
+    Expected: 2
+PASS src/c.test.js
+
+Tests:       2 failed, 1 passed, 3 total
+";
+        let s = parse_jest(out);
+        assert_eq!(s.failures.len(), 2);
+        assert_eq!(s.failures[0].name, "a › first");
+        assert!(!s.failures[0].message.contains("src/b.test.js"));
+        assert_eq!(s.failures[1].name, "b › second");
+        assert!(s.failures[1].message.contains("Expected: 2"));
+        assert!(!s.failures[1].message.contains("src/c.test.js"));
+    }
+
+    #[test]
+    fn pytest_output_is_summarized() {
+        let out = "\
+tests/test_x.py .F.                                                      [100%]
+=========================== short test summary info ============================
+FAILED tests/test_x.py::test_foo - AssertionError: assert 1 == 2
+========================= 1 failed, 2 passed in 0.12s ==========================
+";
+        let s = parse_pytest(out);
+        assert_eq!(s.passed, 2);
+        assert_eq!(s.failed, 1);
+        assert_eq!(
+            s.failures[0],
+            TestFailure {
+                name: "tests/test_x.py::test_foo".to_string(),
+                message: "AssertionError: assert 1 == 2".to_string(),
+            }
+        );
+    }
+}
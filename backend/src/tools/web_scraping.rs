@@ -4,10 +4,14 @@
 //! Provides `fetch_webpage` and `crawl_website` tools with SSRF prevention,
 //! robots.txt compliance, sitemap seeding, concurrent crawling, content
 //! deduplication (SHA-256), enhanced HTML→markdown extraction, metadata,
-//! retry with exponential backoff, and JSON/text output formats.
+//! retry with exponential backoff, and JSON/text output formats. Crawls cut
+//! short by the page or time limit return a stateless `resume_token`;
+//! `fetch_webpage` results are kept in a short-lived LRU (`WebPageCache`).
 
+use base64::Engine;
 use regex::Regex;
 use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet, VecDeque};
@@ -31,6 +35,8 @@ const MAX_CONCURRENT: usize = 5;
 const MAX_TOTAL_CRAWL_SECS: u64 = 180;
 const MAX_RETRY_ATTEMPTS: u32 = 3;
 const WEB_USER_AGENT: &str = "Jaskier-Bot/1.0 (AI Agent Tool)";
+const DEFAULT_WEB_CACHE_ENTRIES: usize = 100;
+const DEFAULT_WEB_CACHE_TTL_SECS: u64 = 300;
 
 const TRACKING_PARAMS: &[&str] = &[
     "utm_source",
@@ -57,6 +63,7 @@ const SKIP_EXTENSIONS: &[&str] = &[
 // Types
 // ---------------------------------------------------------------------------
 
+#[derive(Clone)]
 struct WebFetchResult {
     url: String,
     title: String,
@@ -66,7 +73,7 @@ struct WebFetchResult {
     content_hash: String,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct WebPageMetadata {
     description: String,
     og_title: String,
@@ -107,6 +114,119 @@ struct WebPageResult {
     links: Vec<WebCategorizedLink>,
 }
 
+/// LRU cache of extracted `fetch_webpage` results, shared through `AppState`.
+/// Uses `std::sync::Mutex` (not tokio) — same pattern as `LogRingBuffer` in `state.rs`.
+pub struct WebPageCache {
+    entries: std::sync::Mutex<HashMap<String, WebCacheEntry>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+struct WebCacheEntry {
+    result: WebFetchResult,
+    stored_at: Instant,
+    last_used: Instant,
+}
+
+impl WebPageCache {
+    /// `capacity == 0` disables caching.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: std::sync::Mutex::new(HashMap::new()),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// Sized from `WEB_CACHE_MAX_ENTRIES` (default 100, 0 = off) and
+    /// `WEB_CACHE_TTL_SECS` (default 300).
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("WEB_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_WEB_CACHE_ENTRIES);
+        let ttl_secs = std::env::var("WEB_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_WEB_CACHE_TTL_SECS);
+        Self::new(capacity, Duration::from_secs(ttl_secs))
+    }
+
+    /// Drop every cached page (admin cache flush).
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clear();
+    }
+
+    fn get(&self, key: &str) -> Option<WebFetchResult> {
+        let mut entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+        let entry = entries.get_mut(key)?;
+        if entry.stored_at.elapsed() > self.ttl {
+            entries.remove(key);
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some(entry.result.clone())
+    }
+
+    fn insert(&self, key: String, result: WebFetchResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|p| p.into_inner());
+        entries.retain(|_, e| e.stored_at.elapsed() <= self.ttl);
+        if entries.len() >= self.capacity
+            && !entries.contains_key(&key)
+            && let Some(lru) = entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+        {
+            entries.remove(&lru);
+        }
+        let now = Instant::now();
+        entries.insert(
+            key,
+            WebCacheEntry {
+                result,
+                stored_at: now,
+                last_used: now,
+            },
+        );
+    }
+}
+
+/// Crawl state carried between `crawl_website` calls inside `resume_token`.
+/// Nothing is stored server-side; short field names keep the token compact.
+#[derive(Serialize, Deserialize)]
+struct CrawlCheckpoint {
+    #[serde(rename = "u")]
+    start_url: String,
+    #[serde(rename = "q")]
+    queue: Vec<(String, u32)>,
+    #[serde(rename = "v")]
+    visited: Vec<String>,
+    #[serde(rename = "h")]
+    content_hashes: Vec<String>,
+}
+
+impl CrawlCheckpoint {
+    fn encode(&self) -> String {
+        let bytes = serde_json::to_vec(self).unwrap_or_default();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn decode(token: &str) -> Result<Self, String> {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token.trim())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| "Invalid resume_token — pass it back exactly as returned".to_string())
+    }
+}
+
 struct WebExtractionOptions {
     include_links: bool,
     include_metadata: bool,
@@ -772,6 +892,7 @@ fn web_truncate_text(text: &str, max_len: usize) -> String {
 fn web_format_fetch_output(
     result: &WebFetchResult,
     opts: &WebExtractionOptions,
+    cache_hit: bool,
     as_json: bool,
 ) -> String {
     if as_json {
@@ -789,6 +910,7 @@ fn web_format_fetch_output(
             "title": result.title,
             "content_hash": result.content_hash,
             "text": web_truncate_text(&result.text, opts.max_text_length),
+            "cache_hit": cache_hit,
         });
         if opts.include_links {
             obj["links"] = json!(links_json);
@@ -809,6 +931,9 @@ fn web_format_fetch_output(
             "## {}\n**URL**: {}\n**Hash**: {}\n\n",
             result.title, result.url, result.content_hash
         );
+        if cache_hit {
+            out.insert_str(out.len() - 1, "**Cache hit**: true\n");
+        }
         if opts.include_metadata {
             let m = &result.metadata;
             if !m.description.is_empty() {
@@ -867,6 +992,7 @@ fn web_format_crawl_output(
     errors: &[String],
     start_url: &str,
     elapsed_secs: f64,
+    resume: Option<(&str, usize)>,
     as_json: bool,
 ) -> String {
     let total_links: usize = results.iter().map(|r| r.links.len()).sum();
@@ -897,6 +1023,8 @@ fn web_format_crawl_output(
             "elapsed_seconds": (elapsed_secs * 10.0).round() / 10.0,
             "pages": pages,
             "crawl_errors": errors,
+            "queued_urls": resume.map_or(0, |(_, queued)| queued),
+            "resume_token": resume.map(|(token, _)| token),
         });
         serde_json::to_string_pretty(&obj).unwrap_or_else(|_| "{}".to_string())
     } else {
@@ -938,6 +1066,13 @@ fn web_format_crawl_output(
                 out.push_str(&format!("- {}\n", e));
             }
         }
+        if let Some((token, queued)) = resume {
+            out.push_str(&format!(
+                "\n---\n### Incomplete\n\nStopped at the page/time limit with {} URL(s) still queued. \
+                 Call crawl_website again with this `resume_token` to continue without re-fetching:\n\n{}\n",
+                queued, token
+            ));
+        }
         out
     }
 }
@@ -949,6 +1084,7 @@ fn web_format_crawl_output(
 pub(crate) async fn tool_fetch_webpage(
     args: &Value,
     client: &reqwest::Client,
+    cache: &WebPageCache,
 ) -> Result<ToolOutput, String> {
     let url = args["url"].as_str().ok_or("Missing 'url'")?;
     let extract_links = args["extract_links"].as_bool().unwrap_or(true);
@@ -956,6 +1092,7 @@ pub(crate) async fn tool_fetch_webpage(
     let include_images = args["include_images"].as_bool().unwrap_or(false);
     let output_format = args["output_format"].as_str().unwrap_or("text");
     let max_text_length = args["max_text_length"].as_u64().unwrap_or(0) as usize;
+    let no_cache = args["no_cache"].as_bool().unwrap_or(false);
     let custom_headers: HashMap<String, String> = args["headers"]
         .as_object()
         .map(|m| {
@@ -965,8 +1102,6 @@ pub(crate) async fn tool_fetch_webpage(
         })
         .unwrap_or_default();
 
-    let (html, final_url, _status) = web_fetch_with_retry(client, url, &custom_headers).await?;
-
     let opts = WebExtractionOptions {
         include_links: extract_links,
         include_metadata: extract_metadata,
@@ -978,7 +1113,39 @@ pub(crate) async fn tool_fetch_webpage(
         },
     };
 
-    let text = web_extract_text(&html, &opts);
+    // Custom headers (auth, cookies) can change the page, so those fetches skip
+    // the cache. Link/image flags change the extracted text and are part of the key.
+    let cache_key = if no_cache || !custom_headers.is_empty() {
+        None
+    } else {
+        web_validate_url(url).ok().map(|u| {
+            format!(
+                "{}|links={}|images={}",
+                web_normalize_url(&u),
+                extract_links,
+                include_images
+            )
+        })
+    };
+
+    if let Some(key) = &cache_key
+        && let Some(result) = cache.get(key)
+    {
+        let output = web_format_fetch_output(&result, &opts, true, output_format == "json");
+        return Ok(ToolOutput::text(output));
+    }
+
+    let (html, final_url, _status) = web_fetch_with_retry(client, url, &custom_headers).await?;
+
+    // Full text and metadata are extracted so one cached entry serves any
+    // max_text_length / extract_metadata combination.
+    let text = web_extract_text(
+        &html,
+        &WebExtractionOptions {
+            max_text_length: usize::MAX,
+            ..opts
+        },
+    );
     let title_sel = Selector::parse("title").ok().and_then(|sel| {
         Html::parse_document(&html)
             .select(&sel)
@@ -987,11 +1154,7 @@ pub(crate) async fn tool_fetch_webpage(
     });
     let title = title_sel.unwrap_or_default().trim().to_string();
     let content_hash = web_content_hash(&text);
-    let metadata = if extract_metadata {
-        web_extract_metadata(&html, &final_url)
-    } else {
-        WebPageMetadata::default()
-    };
+    let metadata = web_extract_metadata(&html, &final_url);
     let links = if extract_links {
         web_extract_links(&html, &final_url)
     } else {
@@ -1007,7 +1170,10 @@ pub(crate) async fn tool_fetch_webpage(
         content_hash,
     };
 
-    let output = web_format_fetch_output(&result, &opts, output_format == "json");
+    let output = web_format_fetch_output(&result, &opts, false, output_format == "json");
+    if let Some(key) = cache_key {
+        cache.insert(key, result);
+    }
     Ok(ToolOutput::text(output))
 }
 
@@ -1015,7 +1181,21 @@ pub(crate) async fn tool_crawl_website(
     args: &Value,
     client: &reqwest::Client,
 ) -> Result<ToolOutput, String> {
-    let start_url = args["url"].as_str().ok_or("Missing 'url'")?;
+    let checkpoint = match args["resume_token"].as_str() {
+        Some(token) if !token.trim().is_empty() => Some(CrawlCheckpoint::decode(token)?),
+        _ => None,
+    };
+    let start_url = match (&checkpoint, args["url"].as_str()) {
+        (Some(cp), Some(url)) if web_validate_url(url)?.to_string() != cp.start_url => {
+            return Err(format!(
+                "resume_token belongs to a crawl of '{}', not '{}'",
+                cp.start_url, url
+            ));
+        }
+        (Some(cp), _) => cp.start_url.clone(),
+        (None, Some(url)) => url.to_string(),
+        (None, None) => return Err("Missing 'url'".to_string()),
+    };
     let max_depth = (args["max_depth"].as_u64().unwrap_or(1) as u32).min(MAX_CRAWL_DEPTH);
     let max_pages = (args["max_pages"].as_u64().unwrap_or(10) as usize).min(MAX_CRAWL_PAGES);
     let same_domain = args["same_domain_only"].as_bool().unwrap_or(true);
@@ -1049,7 +1229,7 @@ pub(crate) async fn tool_crawl_website(
         })
         .unwrap_or_default();
 
-    let start_parsed = web_validate_url(start_url)?;
+    let start_parsed = web_validate_url(&start_url)?;
     let start_domain = start_parsed.domain().unwrap_or("").to_string();
     let started = Instant::now();
 
@@ -1076,21 +1256,28 @@ pub(crate) async fn tool_crawl_website(
     let mut errors: Vec<String> = Vec::new();
     let mut content_hashes: HashSet<String> = HashSet::new();
 
-    // Sitemap seeding
-    if use_sitemap {
-        let sitemap_urls = web_fetch_sitemap(client, &start_parsed, &robots).await;
-        for su in sitemap_urls {
-            if !path_prefix.is_empty()
-                && let Ok(u) = Url::parse(&su)
-                && !u.path().starts_with(path_prefix)
-            {
-                continue;
+    if let Some(cp) = checkpoint {
+        // Resume: rehydrate state instead of re-seeding
+        visited.extend(cp.visited);
+        queue.extend(cp.queue);
+        content_hashes.extend(cp.content_hashes);
+    } else {
+        // Sitemap seeding
+        if use_sitemap {
+            let sitemap_urls = web_fetch_sitemap(client, &start_parsed, &robots).await;
+            for su in sitemap_urls {
+                if !path_prefix.is_empty()
+                    && let Ok(u) = Url::parse(&su)
+                    && !u.path().starts_with(path_prefix)
+                {
+                    continue;
+                }
+                queue.push_back((su, 0));
             }
-            queue.push_back((su, 0));
         }
-    }
 
-    queue.push_back((start_parsed.to_string(), 0));
+        queue.push_back((start_parsed.to_string(), 0));
+    }
 
     let opts = WebExtractionOptions {
         include_links: true,
@@ -1193,8 +1380,15 @@ pub(crate) async fn tool_crawl_website(
             }
         } else {
             // Sequential fetch
-            for (url, depth) in batch {
+            let mut pending = batch.into_iter();
+            while let Some((url, depth)) = pending.next() {
                 if started.elapsed().as_secs() > max_total_secs || results.len() >= max_pages {
+                    // Hand unfetched URLs back to the queue so a resume picks them up
+                    let unfetched: Vec<_> = std::iter::once((url, depth)).chain(pending).collect();
+                    for (url, depth) in unfetched.into_iter().rev() {
+                        visited.remove(&url);
+                        queue.push_front((url, depth));
+                    }
                     break;
                 }
 
@@ -1259,12 +1453,30 @@ pub(crate) async fn tool_crawl_website(
         }
     }
 
+    // Anything still queued means the page/time limit cut the crawl short
+    queue.retain(|(url, _)| {
+        let normalized = Url::parse(url)
+            .map(|u| web_normalize_url(&u))
+            .unwrap_or_else(|_| url.clone());
+        !visited.contains(&normalized)
+    });
+    let resume_token = (!queue.is_empty()).then(|| {
+        CrawlCheckpoint {
+            start_url: start_parsed.to_string(),
+            queue: queue.iter().cloned().collect(),
+            visited: visited.iter().cloned().collect(),
+            content_hashes: content_hashes.iter().cloned().collect(),
+        }
+        .encode()
+    });
+
     let elapsed = started.elapsed().as_secs_f64();
     let output = web_format_crawl_output(
         &results,
         &errors,
-        start_url,
+        &start_url,
         elapsed,
+        resume_token.as_deref().map(|token| (token, queue.len())),
         output_format == "json",
     );
     Ok(ToolOutput::text(output))
@@ -1307,3 +1519,68 @@ fn web_process_page(
         links,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crawl_checkpoint_round_trips_through_token() {
+        let cp = CrawlCheckpoint {
+            start_url: "https://example.com/docs/".to_string(),
+            queue: vec![("https://example.com/docs/a".to_string(), 2)],
+            visited: vec!["https://example.com/docs/".to_string()],
+            content_hashes: vec!["0123456789abcdef".to_string()],
+        };
+        let token = cp.encode();
+        assert!(!token.contains('='));
+
+        let back = CrawlCheckpoint::decode(&token).unwrap();
+        assert_eq!(back.start_url, cp.start_url);
+        assert_eq!(back.queue, cp.queue);
+        assert_eq!(back.visited, cp.visited);
+        assert_eq!(back.content_hashes, cp.content_hashes);
+    }
+
+    fn page(url: &str) -> WebFetchResult {
+        WebFetchResult {
+            url: url.to_string(),
+            title: String::new(),
+            text: "body".to_string(),
+            metadata: WebPageMetadata::default(),
+            links: Vec::new(),
+            content_hash: web_content_hash("body"),
+        }
+    }
+
+    #[test]
+    fn web_cache_evicts_least_recently_used() {
+        let cache = WebPageCache::new(2, Duration::from_secs(60));
+        cache.insert("a".to_string(), page("a"));
+        cache.insert("b".to_string(), page("b"));
+        assert!(cache.get("a").is_some()); // "b" is now the LRU entry
+        cache.insert("c".to_string(), page("c"));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn web_cache_expires_and_can_be_disabled() {
+        let cache = WebPageCache::new(10, Duration::ZERO);
+        cache.insert("a".to_string(), page("a"));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get("a").is_none());
+
+        let off = WebPageCache::new(0, Duration::from_secs(60));
+        off.insert("a".to_string(), page("a"));
+        assert!(off.get("a").is_none());
+    }
+
+    #[test]
+    fn crawl_checkpoint_rejects_garbage_token() {
+        assert!(CrawlCheckpoint::decode("not a token!").is_err());
+        assert!(CrawlCheckpoint::decode("e30").is_err()); // "{}" — missing fields
+    }
+}
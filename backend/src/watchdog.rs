@@ -2,19 +2,25 @@
 // GeminiHydra v15 — Background watchdog
 //
 // Periodically checks backend health and performs auto-recovery:
-// - DB connectivity ping (SELECT 1)
+// - DB pool ping (SELECT 1) every 15s — repeated failures flip readiness to
+//   503 until connectivity returns
 // - Model cache staleness check + auto-refresh
 // - Browser proxy monitoring with exponential backoff restarts
+// - Webhook alerts (ALERT_WEBHOOK_URL) for DB down, open circuit, high memory
 // - Logs health status for external monitoring
 
 use std::time::Duration;
 
+use crate::alerts::AlertSeverity;
 use crate::model_registry;
 use crate::state::AppState;
 
 const CHECK_INTERVAL: Duration = Duration::from_secs(60);
 const PROXY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
-const DB_PING_TIMEOUT: Duration = Duration::from_secs(5);
+const DB_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(3);
+/// Consecutive failed DB pings before the backend is marked not-ready.
+const DB_FAILURE_THRESHOLD: u32 = 3;
 
 /// Base cooldown seconds for proxy restart (doubles each level).
 const PROXY_RESTART_BASE_COOLDOWN: u64 = 120;
@@ -48,47 +54,160 @@ pub fn spawn(state: AppState) -> tokio::task::JoinHandle<()> {
         });
     }
 
+    // DB pool monitor on its own, shorter interval
+    let db_state = state.clone();
+    tokio::spawn(async move {
+        tracing::info!(
+            "watchdog: DB pool monitor started (interval={}s)",
+            DB_CHECK_INTERVAL.as_secs()
+        );
+
+        // Whether readiness was taken away by this monitor (and must be given back)
+        let mut restore_ready = false;
+        loop {
+            check_db(&db_state, &mut restore_ready).await;
+            tokio::time::sleep(DB_CHECK_INTERVAL).await;
+        }
+    });
+
     tokio::spawn(async move {
         tracing::info!("watchdog: started (interval={}s)", CHECK_INTERVAL.as_secs());
 
         loop {
             tokio::time::sleep(CHECK_INTERVAL).await;
 
-            let db_ok = check_db(&state).await;
-            let cache_ok = check_and_refresh_cache(&state).await;
+            check_alert_conditions(&state).await;
 
-            if db_ok && cache_ok {
+            if check_and_refresh_cache(&state).await {
                 tracing::debug!("watchdog: all checks passed");
             } else {
-                tracing::warn!(
-                    "watchdog: db={} cache={}",
-                    if db_ok { "ok" } else { "FAIL" },
-                    if cache_ok { "ok" } else { "REFRESHED" },
-                );
+                tracing::warn!("watchdog: cache=REFRESHED");
             }
         }
     })
 }
 
-async fn check_db(state: &AppState) -> bool {
-    let result = tokio::time::timeout(
+/// Ping the pool and record the result in `state.db_health`.
+/// After `DB_FAILURE_THRESHOLD` consecutive failures the backend is marked
+/// not-ready; the first successful ping afterwards restores readiness.
+async fn check_db(state: &AppState, restore_ready: &mut bool) {
+    let started = std::time::Instant::now();
+    let result = match tokio::time::timeout(
         DB_PING_TIMEOUT,
         sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(&state.db),
     )
-    .await;
+    .await
+    {
+        Ok(Ok(_)) => Ok(started.elapsed().as_millis() as u64),
+        Ok(Err(e)) => Err(format!("DB ping failed: {}", e)),
+        Err(_) => Err(format!(
+            "DB ping timed out after {}s",
+            DB_PING_TIMEOUT.as_secs()
+        )),
+    };
+
+    let mut health = state.db_health.write().await;
+    health.last_check = Some(chrono::Utc::now().to_rfc3339());
+    health.pool_size = state.db.size();
+    health.pool_idle = state.db.num_idle() as u32;
 
     match result {
-        Ok(Ok(_)) => true,
-        Ok(Err(e)) => {
-            tracing::error!("watchdog: DB ping failed: {}", e);
-            false
+        Ok(latency_ms) => {
+            if health.consecutive_failures >= DB_FAILURE_THRESHOLD {
+                tracing::info!(
+                    alert = "db_pool_recovered",
+                    failures = health.consecutive_failures,
+                    latency_ms,
+                    "watchdog: DB connectivity restored"
+                );
+                if *restore_ready {
+                    state.mark_ready();
+                    *restore_ready = false;
+                }
+                state.alerts.resolve("db_down");
+            }
+            health.healthy = true;
+            health.consecutive_failures = 0;
+            health.latency_ms = Some(latency_ms);
+            health.last_error = None;
         }
-        Err(_) => {
+        Err(err) => {
+            health.healthy = false;
+            health.consecutive_failures += 1;
+            health.latency_ms = None;
             tracing::error!(
-                "watchdog: DB ping timed out after {}s",
-                DB_PING_TIMEOUT.as_secs()
+                "watchdog: {} ({} consecutive)",
+                err,
+                health.consecutive_failures
+            );
+
+            if health.consecutive_failures == DB_FAILURE_THRESHOLD {
+                tracing::error!(
+                    alert = "db_pool_unhealthy",
+                    failures = health.consecutive_failures,
+                    pool_size = health.pool_size,
+                    pool_idle = health.pool_idle,
+                    error = %err,
+                    "watchdog: DB pool unhealthy — taking backend out of rotation"
+                );
+            }
+            if health.consecutive_failures >= DB_FAILURE_THRESHOLD {
+                state.alerts.fire(
+                    &state.client,
+                    "db_down",
+                    AlertSeverity::Critical,
+                    format!(
+                        "Database unreachable for {} consecutive checks: {}",
+                        health.consecutive_failures, err
+                    ),
+                );
+            }
+            // Re-checked every cycle: startup may mark the backend ready mid-outage
+            if health.consecutive_failures >= DB_FAILURE_THRESHOLD && state.is_ready() {
+                state.mark_not_ready();
+                *restore_ready = true;
+            }
+            health.last_error = Some(err);
+        }
+    }
+}
+
+/// Raise webhook alerts for an open Gemini circuit or host memory above
+/// the configured threshold. Debouncing happens in the dispatcher.
+async fn check_alert_conditions(state: &AppState) {
+    if !state.alerts.is_configured() {
+        return;
+    }
+
+    if state.gemini_circuit.is_open() {
+        state.alerts.fire(
+            &state.client,
+            "gemini_circuit_open",
+            AlertSeverity::Critical,
+            "Gemini circuit breaker is OPEN — upstream requests are failing fast",
+        );
+    } else {
+        state.alerts.resolve("gemini_circuit_open");
+    }
+
+    let (used_mb, total_mb) = {
+        let snap = state.system_monitor.read().await;
+        (snap.memory_used_mb, snap.memory_total_mb)
+    };
+    if total_mb > 0.0 {
+        let used_pct = used_mb / total_mb * 100.0;
+        if used_pct >= state.alerts.memory_threshold_pct {
+            state.alerts.fire(
+                &state.client,
+                "memory_high",
+                AlertSeverity::Warning,
+                format!(
+                    "Memory usage at {:.1}% ({:.0}/{:.0} MB, threshold {:.0}%)",
+                    used_pct, used_mb, total_mb, state.alerts.memory_threshold_pct
+                ),
             );
-            false
+        } else {
+            state.alerts.resolve("memory_high");
         }
     }
 }
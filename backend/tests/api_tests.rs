@@ -1,7 +1,7 @@
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use http_body_util::BodyExt;
-use serde_json::Value;
+use serde_json::{Value, json};
 use sqlx::PgPool;
 use tower::ServiceExt;
 
@@ -135,6 +135,40 @@ async fn agents_returns_12_agents() {
     assert_eq!(agents.len(), 12);
 }
 
+#[tokio::test]
+async fn agents_etag_revalidates_with_304() {
+    let state = require_db!();
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri("/api/agents")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let etag = response
+        .headers()
+        .get("etag")
+        .expect("ETag header")
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .uri("/api/agents")
+                .header("if-none-match", &etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["etag"], etag.as_str());
+}
+
 #[tokio::test]
 async fn agents_have_required_fields() {
     let state = require_db!();
@@ -269,6 +303,40 @@ async fn patch_settings_persists_changes() {
     assert!((json["temperature"].as_f64().unwrap() - 0.9).abs() < f64::EPSILON);
 }
 
+#[tokio::test]
+async fn settings_update_reaches_next_prepare_execution() {
+    let state = require_db!();
+    let patch = |style: &'static str| {
+        Request::builder()
+            .method("PATCH")
+            .uri("/api/settings")
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "response_style": style }).to_string()))
+            .unwrap()
+    };
+    let concise_hint = "[STYLE: Be extremely concise.";
+
+    let response = app(state.clone()).oneshot(patch("balanced")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    // Prime the settings cache with the old value.
+    let ctx = geminihydra_backend::context::prepare_execution(
+        &state, "hello", None, None, None, "", false,
+    )
+    .await;
+    assert!(!ctx.final_user_prompt.contains(concise_hint));
+
+    let response = app(state.clone()).oneshot(patch("concise")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let ctx = geminihydra_backend::context::prepare_execution(
+        &state, "hello", None, None, None, "", false,
+    )
+    .await;
+    assert!(ctx.final_user_prompt.contains(concise_hint));
+
+    let response = app(state).oneshot(patch("balanced")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 //  POST /api/settings/reset
 // ═══════════════════════════════════════════════════════════════════════════
@@ -328,6 +396,55 @@ async fn reset_settings_restores_defaults() {
     assert!((json["temperature"].as_f64().unwrap() - 1.0).abs() < f64::EPSILON);
 }
 
+#[tokio::test]
+async fn reset_setting_field_resets_only_that_field() {
+    let state = require_db!();
+    let router = app(state);
+
+    let body = serde_json::json!({ "language": "pl", "history_window": 42 });
+    router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri("/api/settings")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let reset = |field: &str| {
+        Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/settings/{}", field))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = router
+        .clone()
+        .oneshot(reset("history_window"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    let iterations = router
+        .clone()
+        .oneshot(reset("max_iterations"))
+        .await
+        .unwrap();
+    let iterations = body_json(iterations).await;
+    let unknown = router.clone().oneshot(reset("nope")).await.unwrap();
+    router.oneshot(reset("language")).await.unwrap();
+
+    assert_eq!(json["history_window"], 20);
+    // Same default as the gh_settings column
+    assert_eq!(iterations["max_iterations"], 10);
+    assert_eq!(json["language"], "pl");
+    assert_eq!(unknown.status(), StatusCode::BAD_REQUEST);
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 //  GET /api/history
 // ═══════════════════════════════════════════════════════════════════════════
@@ -376,21 +493,1371 @@ async fn clear_history_returns_200() {
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
-//  404 for unknown routes
+//  Agent prompt versions
 // ═══════════════════════════════════════════════════════════════════════════
 
 #[tokio::test]
-async fn unknown_route_returns_404() {
+async fn agent_prompt_update_is_versioned_and_restorable() {
+    let state = require_db!();
+    let router = app(state);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/agents")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut agent = body_json(response).await["agents"][0].clone();
+    let id = agent["id"].as_str().unwrap().to_string();
+    let original = agent["system_prompt"].clone();
+
+    agent["system_prompt"] = serde_json::json!("temporary prompt for versioning test");
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/agents/{}", id))
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&agent).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/agents/{}/prompt-versions", id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let versions = body_json(response).await;
+    let latest = &versions.as_array().unwrap()[0];
+    assert_eq!(latest["system_prompt"], original);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!(
+                    "/api/agents/{}/prompt-versions/{}/restore",
+                    id, latest["version"]
+                ))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/api/agents")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let agents = body_json(response).await;
+    let restored = agents["agents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|a| a["id"] == id.as_str())
+        .unwrap();
+    assert_eq!(restored["system_prompt"], original);
+}
+
+#[tokio::test]
+async fn agent_ratings_returns_histogram() {
+    let state = require_db!();
+    let id = match state.agents.read().await.first() {
+        Some(a) => a.id.clone(),
+        None => return,
+    };
+    let router = app(state);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/agents/{}/ratings", id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["agent_id"], id.as_str());
+    assert_eq!(json["histogram"].as_array().unwrap().len(), 5);
+    assert!(json["weekly"].is_array());
+    assert!(json["recent_low"].is_array());
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/api/agents/no-such-agent/ratings")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn agent_prompt_update_invalidates_prompt_cache() {
+    let state = require_db!();
+    let router = app(state.clone());
+
+    let mut agent = serde_json::to_value(&state.agents.read().await[0]).unwrap();
+    let id = agent["id"].as_str().unwrap().to_string();
+    let original = agent["system_prompt"].clone();
+    let prompt = format!("@{} hello", id);
+
+    // Warm the cache with the current prompt
+    let before = geminihydra_backend::context::prepare_execution(
+        &state, &prompt, None, None, None, "", false,
+    )
+    .await
+    .system_prompt;
+
+    let marker = "prompt-cache-invalidation-marker";
+    agent["system_prompt"] = serde_json::json!(marker);
+    let update = |body: &Value| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/api/agents/{}", id))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(body).unwrap()))
+            .unwrap()
+    };
+    let response = router.clone().oneshot(update(&agent)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let after = geminihydra_backend::context::prepare_execution(
+        &state, &prompt, None, None, None, "", false,
+    )
+    .await
+    .system_prompt;
+
+    agent["system_prompt"] = original;
+    router.oneshot(update(&agent)).await.unwrap();
+
+    assert!(!before.contains(marker));
+    assert!(after.contains(marker));
+}
+
+#[tokio::test]
+async fn agents_reorder_changes_roster_order() {
+    let state = require_db!();
+    let router = app(state.clone());
+    let original: Vec<String> = state
+        .agents
+        .read()
+        .await
+        .iter()
+        .map(|a| a.id.clone())
+        .collect();
+    let reorder = |ids: &[String]| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/agents/reorder")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&serde_json::json!({ "ids": ids })).unwrap(),
+            ))
+            .unwrap()
+    };
+
+    let last = original.last().unwrap().clone();
+    let response = router
+        .clone()
+        .oneshot(reorder(std::slice::from_ref(&last)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let order = body_json(response).await["order"].clone();
+    let roster = state.agents.read().await[0].id.clone();
+
+    let response = router
+        .clone()
+        .oneshot(reorder(&["no-such-agent".to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    router.oneshot(reorder(&original)).await.unwrap();
+
+    assert_eq!(order[0], last.as_str());
+    assert_eq!(order.as_array().unwrap().len(), original.len());
+    assert_eq!(order[1], original[0].as_str());
+    assert_eq!(roster, last);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/tools/execute
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn tools_catalog_lists_schemas() {
     let state = require_db!();
     let response = app(state)
         .oneshot(
             Request::builder()
-                .uri("/api/nonexistent")
+                .uri("/api/tools")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    let read_file = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["name"] == "read_file")
+        .unwrap();
+    assert_eq!(read_file["category"], "filesystem");
+    assert_eq!(read_file["executable"], true);
+    assert_eq!(read_file["parameters"]["required"][0], "path");
+}
+
+#[tokio::test]
+async fn tool_execute_runs_a_native_tool() {
+    let state = require_db!();
+    let router = app(state);
+    let dir = std::env::temp_dir().join(format!("gh-tool-exec-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("hello.txt"), "hi").unwrap();
+    let run = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/tools/execute")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(run(serde_json::json!({
+            "name": "list_directory",
+            "args": { "path": dir.to_string_lossy() }
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    std::fs::remove_dir_all(&dir).ok();
+    assert_eq!(json["success"], true);
+    assert!(json["text"].as_str().unwrap().contains("hello.txt"));
+    assert!(json["duration_ms"].is_u64());
+
+    let response = router
+        .clone()
+        .oneshot(run(
+            serde_json::json!({ "name": "list_directory", "args": {} }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json = body_json(response).await;
+    assert_eq!(json["error"]["code"], "BAD_REQUEST");
+    assert_eq!(json["error"]["message"], "args: missing required 'path'");
+
+    let response = router
+        .clone()
+        .oneshot(run(
+            serde_json::json!({ "name": "list_directory", "args": { "path": 42 } }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json = body_json(response).await;
+    assert!(
+        json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("args.path: expected string")
+    );
+
+    let response = router
+        .oneshot(run(serde_json::json!({ "name": "no_such_tool" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/admin/test-alert
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn test_alert_without_webhook_returns_400() {
+    let mut state = require_db!();
+    state.alerts = std::sync::Arc::new(geminihydra_backend::alerts::AlertDispatcher::new(
+        None,
+        std::time::Duration::from_secs(60),
+        90.0,
+    ));
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/admin/test-alert")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/admin/circuit, POST /api/admin/circuit/reset
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn circuit_reset_force_closes_a_tripped_breaker() {
+    let state = require_db!();
+    let model = state.model_circuit("gemini-test-model").await;
+    for _ in 0..3 {
+        model.record_failure().await;
+    }
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri("/api/admin/circuit")
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    let circuits = json["circuits"].as_array().unwrap();
+    assert_eq!(circuits[0]["provider"], "gemini");
+    let tripped = circuits
+        .iter()
+        .find(|c| c["provider"] == "gemini:gemini-test-model")
+        .unwrap();
+    assert_eq!(tripped["state"], "open");
+    assert_eq!(tripped["trips"], 1);
+
+    let reset = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/admin/circuit/reset")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let response = app(state.clone())
+        .oneshot(reset(r#"{"provider":"gemini:gemini-test-model"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["closed"], json!(["gemini:gemini-test-model"]));
+    assert!(!model.is_open());
 
+    let response = app(state)
+        .oneshot(reset(r#"{"provider":"nope"}"#))
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/admin/cache/clear
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn clear_caches_reports_what_was_cleared() {
+    let state = require_db!();
+    let clear = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/admin/cache/clear")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app(state.clone())
+        .oneshot(clear(r#"{"caches":["settings","prompt"]}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["cleared"], json!(["prompt", "settings"]));
+
+    let response = app(state)
+        .oneshot(clear(r#"{"caches":["tool_defs"]}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/execute — plan-only
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn execute_plan_only_returns_preview_without_gemini() {
+    let state = require_db!();
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/execute")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"prompt":"refactor the database schema","mode":"plan-only"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert!(json["plan"]["agent"].is_string());
+    assert!(json["model"].is_string());
+    assert!(json["system_prompt_chars"].as_u64().unwrap() > 0);
+    assert!(json.get("result").is_none());
+}
+
+#[tokio::test]
+async fn execute_rejects_invalid_session_id() {
+    let state = require_db!();
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/execute")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"prompt":"hi","mode":"plan-only","enable_tools":true,"session_id":"nope"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn ws_plan_only_does_not_lock_session_agent() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let state = require_db!();
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('plan test') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = geminihydra_backend::create_test_router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/execute", addr))
+        .await
+        .unwrap();
+    let execute = json!({
+        "type": "execute",
+        "prompt": "refactor the database schema",
+        "mode": "plan-only",
+        "session_id": session_id.to_string(),
+    });
+    ws.send(Message::text(execute.to_string())).await.unwrap();
+    loop {
+        let msg = ws.next().await.unwrap().unwrap();
+        let json: Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        assert_ne!(json["type"], "error", "{}", json);
+        if json["type"] == "complete" {
+            break;
+        }
+    }
+
+    let agent_id: Option<String> =
+        sqlx::query_scalar("SELECT agent_id FROM gh_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(agent_id, None);
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn execute_idempotency_errors_use_the_error_envelope() {
+    let state = require_db!();
+    let key = uuid::Uuid::new_v4().to_string();
+    let send = |key: &str, prompt: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/execute")
+            .header("content-type", "application/json")
+            .header("idempotency-key", key)
+            .body(Body::from(
+                json!({ "prompt": prompt, "mode": "plan-only" }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app(state.clone())
+        .oneshot(send(&key, "first prompt"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app(state.clone())
+        .oneshot(send(&key, "second prompt"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json = body_json(response).await;
+    assert_eq!(json["error"]["code"], "UNPROCESSABLE_ENTITY");
+    assert!(json["error"]["request_id"].is_string());
+
+    let response = app(state).oneshot(send(" ", "first prompt")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json = body_json(response).await;
+    assert_eq!(json["error"]["code"], "BAD_REQUEST");
+    assert!(
+        json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Idempotency-Key must be")
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/execute/ensemble
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn ensemble_rejects_single_or_unknown_agents() {
+    let state = require_db!();
+    for body in [
+        r#"{"prompt":"hi","agents":["geralt"]}"#,
+        r#"{"prompt":"hi","agents":["geralt","no-such-agent"]}"#,
+    ] {
+        let response = app(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/execute/ensemble")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/sessions/{id}/messages — cursor pagination
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn session_messages_cursor_pagination_walks_newest_first() {
+    let state = require_db!();
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('cursor test') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    for i in 0..3 {
+        sqlx::query(
+            "INSERT INTO gh_chat_messages (session_id, role, content, created_at) \
+             VALUES ($1, 'user', $2, NOW() + make_interval(secs => $3))",
+        )
+        .bind(session_id)
+        .bind(format!("msg {}", i))
+        .bind(i as f64)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+
+    let get = |uri: String| {
+        let state = state.clone();
+        async move {
+            let response = app(state)
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            body_json(response).await
+        }
+    };
+
+    let base = format!("/api/sessions/{}/messages", session_id);
+    let first = get(format!("{}?order=desc&limit=2", base)).await;
+    assert_eq!(first["messages"][0]["content"], "msg 2");
+    assert_eq!(first["has_more"], true);
+    let cursor = first["next_cursor"].as_str().unwrap().to_string();
+
+    let second = get(format!("{}?order=desc&limit=2&after={}", base, cursor)).await;
+    assert_eq!(second["returned"], 1);
+    assert_eq!(second["messages"][0]["content"], "msg 0");
+    assert_eq!(second["has_more"], false);
+    assert!(second["next_cursor"].is_null());
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/sessions/{id}/summarize
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn clear_session_messages_keeps_the_session() {
+    let state = require_db!();
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO gh_sessions (title, agent_id, working_directory) \
+         VALUES ('clear test', 'eskel', '/tmp') RETURNING id",
+    )
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    for content in ["one", "two"] {
+        sqlx::query(
+            "INSERT INTO gh_chat_messages (session_id, role, content) VALUES ($1, 'user', $2)",
+        )
+        .bind(session_id)
+        .bind(content)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+    sqlx::query(
+        "INSERT INTO gh_session_summaries (session_id, summary, message_count, model) \
+         VALUES ($1, 'old summary', 2, 'test')",
+    )
+    .bind(session_id)
+    .execute(&state.db)
+    .await
+    .unwrap();
+
+    let clear = |id: String| {
+        let state = state.clone();
+        async move {
+            app(state)
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/api/sessions/{}/clear-messages", id))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+        }
+    };
+
+    let response = clear(session_id.to_string()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["messages_removed"], 2);
+
+    let (agent_id, working_directory): (Option<String>, String) =
+        sqlx::query_as("SELECT agent_id, working_directory FROM gh_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(agent_id.as_deref(), Some("eskel"));
+    assert_eq!(working_directory, "/tmp");
+    let summaries: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM gh_session_summaries WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(summaries, 0);
+
+    let response = clear(uuid::Uuid::new_v4().to_string()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn summarize_session_without_messages_returns_404() {
+    let state = require_db!();
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('summary test') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/sessions/{}/summarize", session_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  PATCH/DELETE /api/sessions/{id}/model
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn session_model_pin_round_trip() {
+    let state = require_db!();
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO gh_sessions (title) VALUES ('model pin test') RETURNING id",
+    )
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    let uri = format!("/api/sessions/{}/model", session_id);
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri(&uri)
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"model":"gemini-test-pin"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["model_override"], "gemini-test-pin");
+    assert!(json["effective_model"].is_string());
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert!(json["model_override"].is_null());
+    assert_ne!(json["source"], "session");
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn bulk_delete_sessions_requires_a_filter_and_deletes_by_id() {
+    let state = require_db!();
+    let bulk_delete = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/sessions/bulk-delete")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app(state.clone())
+        .oneshot(bulk_delete("{}".into()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO gh_sessions (title) VALUES ('bulk delete test') RETURNING id",
+    )
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO gh_chat_messages (role, content, session_id) VALUES ('user', 'hi', $1)",
+    )
+    .bind(session_id)
+    .execute(&state.db)
+    .await
+    .unwrap();
+
+    let response = app(state.clone())
+        .oneshot(bulk_delete(format!(r#"{{"ids":["{}"]}}"#, session_id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["deleted"], 1);
+    assert_eq!(json["messages_deleted"], 1);
+    assert_eq!(json["has_more"], false);
+}
+
+#[tokio::test]
+async fn delete_session_archives_until_hard_delete() {
+    let state = require_db!();
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('archive test') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    let request = |method: &str, uri: String| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let listed = |json: &Value| {
+        json.as_array()
+            .unwrap()
+            .iter()
+            .any(|s| s["id"] == session_id.to_string())
+    };
+
+    let response = app(state.clone())
+        .oneshot(request("DELETE", format!("/api/sessions/{}", session_id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["status"], "archived");
+    // Archiving again is a no-op
+    let response = app(state.clone())
+        .oneshot(request("DELETE", format!("/api/sessions/{}", session_id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app(state.clone())
+        .oneshot(request("GET", "/api/sessions?limit=500".into()))
+        .await
+        .unwrap();
+    assert!(!listed(&body_json(response).await));
+    let response = app(state.clone())
+        .oneshot(request(
+            "GET",
+            "/api/sessions?limit=500&include_archived=true".into(),
+        ))
+        .await
+        .unwrap();
+    assert!(listed(&body_json(response).await));
+
+    let response = app(state.clone())
+        .oneshot(request(
+            "POST",
+            format!("/api/sessions/{}/restore", session_id),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app(state.clone())
+        .oneshot(request("GET", "/api/sessions?limit=500".into()))
+        .await
+        .unwrap();
+    assert!(listed(&body_json(response).await));
+
+    let response = app(state.clone())
+        .oneshot(request(
+            "DELETE",
+            format!("/api/sessions/{}?hard=true", session_id),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["status"], "deleted");
+    let response = app(state.clone())
+        .oneshot(request(
+            "POST",
+            format!("/api/sessions/{}/restore", session_id),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app(state)
+        .oneshot(request("DELETE", format!("/api/sessions/{}", session_id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn session_tags_filter_the_session_list() {
+    let state = require_db!();
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('tag test') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    let tag = format!("test-tag-{}", session_id);
+
+    for _ in 0..2 {
+        let response = app(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/sessions/{}/tags", session_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({ "tag": format!(" {} ", tag) }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["tags"], json!([tag]));
+    }
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/sessions?tag={}", tag))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = body_json(response).await;
+    let sessions = json.as_array().unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["id"], session_id.to_string());
+    assert_eq!(sessions[0]["tags"], json!([tag]));
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/sessions/{}/tags/{}", session_id, tag))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["tags"], json!([]));
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_tag_adds_respect_the_limit() {
+    let state = require_db!();
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('tag race') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+
+    let adds = (0..40).map(|i| {
+        let state = state.clone();
+        tokio::spawn(async move {
+            app(state)
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/api/sessions/{}/tags", session_id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({ "tag": format!("t{}", i) }).to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        })
+    });
+    let statuses = futures_util::future::join_all(adds).await;
+    let ok = statuses
+        .into_iter()
+        .filter(|s| *s.as_ref().unwrap() == StatusCode::OK)
+        .count();
+    assert_eq!(ok, 20);
+
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM gh_session_tags WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(count, 20);
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Tool-call audit trail
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn write_file_calls_are_audited_without_content() {
+    let state = require_db!();
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let path = std::env::temp_dir().join(format!("gh-audit-{}.txt", session_id));
+    let caller = geminihydra_backend::tools::ToolCaller {
+        agent_id: Some("test-agent".into()),
+        session_id: Some(session_id.clone()),
+    };
+    let args = json!({ "path": path.to_string_lossy(), "content": "secret body" });
+    // Audited whether or not the path passes write validation
+    let _ =
+        geminihydra_backend::tools::execute_tool("write_file", &args, &state, "", &caller).await;
+    let _ = std::fs::remove_file(&path);
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri(format!(
+                    "/api/logs/tool-calls?tool=write_file&session={}",
+                    session_id
+                ))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    let calls = json["tool_calls"].as_array().unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0]["agent_id"], "test-agent");
+    assert!(calls[0]["args"].get("content").is_none());
+
+    sqlx::query("DELETE FROM gh_audit_log WHERE details->>'session_id' = $1")
+        .bind(&session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn copy_file_calls_are_audited_with_source_and_destination() {
+    let state = require_db!();
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let caller = geminihydra_backend::tools::ToolCaller {
+        agent_id: None,
+        session_id: Some(session_id.clone()),
+    };
+    let args = json!({ "src": "/nonexistent/a.txt", "dst": "/nonexistent/b.txt" });
+    let _ = geminihydra_backend::tools::execute_tool("copy_file", &args, &state, "", &caller).await;
+
+    let details: Vec<Value> = sqlx::query_scalar(
+        "SELECT details FROM gh_audit_log WHERE action = 'tool_call' AND details->>'session_id' = $1",
+    )
+    .bind(&session_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap();
+    assert_eq!(details.len(), 1);
+    assert_eq!(details[0]["tool"], "copy_file");
+    assert_eq!(details[0]["args"]["src"], "/nonexistent/a.txt");
+    assert_eq!(details[0]["args"]["dst"], "/nonexistent/b.txt");
+
+    sqlx::query("DELETE FROM gh_audit_log WHERE details->>'session_id' = $1")
+        .bind(&session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn timed_out_command_still_leaves_audit_row() {
+    let state = require_db!();
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let caller = geminihydra_backend::tools::ToolCaller {
+        agent_id: None,
+        session_id: Some(session_id.clone()),
+    };
+    let args = json!({ "command": "sleep 5" });
+    // Callers time out before the tool's own COMMAND_TIMEOUT fires
+    let timed_out = tokio::time::timeout(
+        std::time::Duration::from_millis(300),
+        geminihydra_backend::tools::execute_tool("execute_command", &args, &state, "", &caller),
+    )
+    .await;
+    assert!(timed_out.is_err());
+    // The cancellation update is spawned from the dropped guard
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let details: Vec<Value> = sqlx::query_scalar(
+        "SELECT details FROM gh_audit_log WHERE action = 'tool_call' AND details->>'session_id' = $1",
+    )
+    .bind(&session_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap();
+    assert_eq!(details.len(), 1);
+    assert_eq!(details[0]["tool"], "execute_command");
+    assert_eq!(details[0]["args"]["command"], "sleep 5");
+    assert_eq!(details[0]["status"], "cancelled");
+
+    sqlx::query("DELETE FROM gh_audit_log WHERE details->>'session_id' = $1")
+        .bind(&session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn usage_export_streams_csv_attachment() {
+    let state = require_db!();
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri("/api/logs/usage/export?from=2020-01-01T00:00:00Z&to=2020-01-02T00:00:00Z")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-disposition"]
+            .to_str()
+            .unwrap()
+            .starts_with("attachment;")
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&bytes).starts_with("id,created_at,agent_id,"));
+
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .uri("/api/logs/usage/export?from=2020-01-02T00:00:00Z&to=2020-01-01T00:00:00Z")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  404 for unknown routes
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn unknown_route_returns_404() {
+    let state = require_db!();
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .uri("/api/nonexistent")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn body_limit_is_larger_for_ocr_routes() {
+    let state = require_db!();
+    let big = || {
+        // 12 MB: over the 10 MB default, under the OCR limit
+        let body = vec![b'x'; 12 * 1024 * 1024];
+        (body.len(), Body::from(body))
+    };
+    let post = |uri: &str| {
+        let (len, body) = big();
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("content-length", len)
+            .body(body)
+            .unwrap()
+    };
+
+    let response = app(state.clone())
+        .oneshot(post("/api/execute"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let json = body_json(response).await;
+    assert_eq!(json["error"]["code"], "PAYLOAD_TOO_LARGE");
+
+    // Reaches the OCR handler's JSON extractor, which rejects the syntax
+    let response = app(state).oneshot(post("/api/ocr")).await.unwrap();
+    assert_ne!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn session_stats_count_messages_agents_and_tools() {
+    let state = require_db!();
+    let agent = match state.agents.read().await.first() {
+        Some(a) => a.id.clone(),
+        None => return,
+    };
+    let session_id: uuid::Uuid =
+        sqlx::query_scalar("INSERT INTO gh_sessions (title) VALUES ('stats test') RETURNING id")
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    let assistant = "Looking.\n\n---\n**🔧 Tool:** `read_file`\n```\nok\n```\n---\n\n\
+                     ---\n**🔧 Tool:** `grep_files`\n```\nok\n```\n---\n\n\
+                     ---\n**🔧 Tool:** `read_file`\n```\nok\n```\n---\n\nDone.";
+    for (role, content, agent) in [
+        ("user", "find the bug", Some(agent.as_str())),
+        ("assistant", assistant, Some("routing note, not an agent")),
+    ] {
+        sqlx::query(
+            "INSERT INTO gh_chat_messages (session_id, role, content, agent) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(session_id)
+        .bind(role)
+        .bind(content)
+        .bind(agent)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/sessions/{}/stats", session_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["message_count"], 2);
+    assert_eq!(json["user_messages"], 1);
+    assert_eq!(json["assistant_messages"], 1);
+    assert!(json["first_message_at"].is_string());
+    assert_eq!(json["agents"], json!([{ "agent": agent, "messages": 1 }]));
+    assert_eq!(
+        json["top_tools"],
+        json!([
+            { "tool": "read_file", "calls": 2 },
+            { "tool": "grep_files", "calls": 1 }
+        ])
+    );
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn history_stats_fill_the_window_and_group_by_model() {
+    let state = require_db!();
+    let model = format!("stats-model-{}", uuid::Uuid::new_v4());
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO gh_sessions (title) VALUES ('history stats test') RETURNING id",
+    )
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    for role in ["user", "assistant", "assistant"] {
+        sqlx::query(
+            "INSERT INTO gh_chat_messages (session_id, role, content, model) VALUES ($1, $2, 'hi', $3)",
+        )
+        .bind(session_id)
+        .bind(role)
+        .bind(&model)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri("/api/history/stats?days=7")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = body_json(response).await;
+    assert_eq!(json["days"], 7);
+    let daily = json["daily"].as_array().unwrap();
+    assert_eq!(daily.len(), 7);
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    assert_eq!(daily[6]["date"], today.as_str());
+    assert!(daily[6]["messages"].as_i64().unwrap() >= 3);
+    assert!(json["window_messages"].as_i64().unwrap() <= json["total_messages"].as_i64().unwrap());
+    let models = json["models"].as_array().unwrap();
+    let ours = models
+        .iter()
+        .find(|m| m["model"] == model.as_str())
+        .unwrap();
+    assert_eq!(ours["messages"], 2);
+
+    sqlx::query("DELETE FROM gh_sessions WHERE id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+}
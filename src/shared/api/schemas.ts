@@ -176,6 +176,7 @@ export type SaveApiKeyResponse = z.infer<typeof saveApiKeyResponseSchema>;
 const wsStartMessageSchema = z.object({
   type: z.literal('start'),
   id: z.string(),
+  request_id: z.string().optional(),
   agent: z.string(),
   model: z.string(),
   files_loaded: z.array(z.string()),
@@ -204,6 +205,13 @@ const wsCompleteMessageSchema = z.object({
 
 export type WsCompleteMessage = z.infer<typeof wsCompleteMessageSchema>;
 
+const wsCancelledMessageSchema = z.object({
+  type: z.literal('cancelled'),
+  duration_ms: z.number(),
+});
+
+export type WsCancelledMessage = z.infer<typeof wsCancelledMessageSchema>;
+
 const wsErrorMessageSchema = z.object({
   type: z.literal('error'),
   message: z.string(),
@@ -295,6 +303,7 @@ export const wsServerMessageSchema = z.discriminatedUnion('type', [
   wsToolCallMessageSchema,
   wsToolResultMessageSchema,
   wsCompleteMessageSchema,
+  wsCancelledMessageSchema,
   wsErrorMessageSchema,
   wsPongMessageSchema,
   // ADK Orchestration
@@ -314,6 +323,10 @@ interface WsExecuteMessage {
   mode: string;
   model?: string;
   session_id?: string;
+  /** Per-message generation overrides (settings are left untouched) */
+  temperature?: number;
+  top_p?: number;
+  thinking_level?: 'none' | 'minimal' | 'low' | 'medium' | 'high';
 }
 
 interface WsOrchestrateMessage {
@@ -14,6 +14,7 @@ import { useCallback, useEffect, useRef, useState } from 'react';
 import type {
   WsAgentDelegationMessage,
   WsAgentOutputMessage,
+  WsCancelledMessage,
   WsClientMessage,
   WsCompleteMessage,
   WsOrchestrationStartMessage,
@@ -41,6 +42,7 @@ export interface WsCallbacks {
   onToolCall?: (msg: WsToolCallMessage, sessionId: string | null) => void;
   onToolResult?: (msg: WsToolResultMessage, sessionId: string | null) => void;
   onComplete?: (msg: WsCompleteMessage, sessionId: string | null) => void;
+  onCancelled?: (msg: WsCancelledMessage, sessionId: string | null) => void;
   onError?: (message: string, sessionId: string | null) => void;
   // ADK Orchestration callbacks
   onOrchestrationStart?: (msg: WsOrchestrationStartMessage, sessionId: string | null) => void;
@@ -192,6 +194,14 @@ export function useWebSocketChat(callbacks: WsCallbacks) {
           streamingSessionIdRef.current = null;
           startHeartbeat(); // Resume heartbeat after streaming ends
           break;
+        case 'cancelled':
+          setIsStreaming(false);
+          isStreamingRef.current = false;
+          setStreamingSessionId(null);
+          cbs.onCancelled?.(msg, sid);
+          streamingSessionIdRef.current = null;
+          startHeartbeat();
+          break;
         case 'error':
           setIsStreaming(false);
           isStreamingRef.current = false;